@@ -18,9 +18,10 @@ object Slimg {
 
     // ── Decode ──────────────────────────────────────────
 
+    /** Decode [data]; pixels are rotated upright from EXIF unless [autoOrient] is false. */
     @Throws(SlimgException::class)
-    fun decode(data: ByteArray): DecodeResult =
-        io.clroot.slimg.decode(data)
+    fun decode(data: ByteArray, autoOrient: Boolean = true): DecodeResult =
+        io.clroot.slimg.decode(data, autoOrient)
 
     @Throws(SlimgException::class)
     fun decode(stream: InputStream): DecodeResult =
@@ -31,8 +32,26 @@ object Slimg {
         io.clroot.slimg.decode(buffer)
 
     @Throws(SlimgException::class)
-    fun decodeFile(path: String): DecodeResult =
-        io.clroot.slimg.decodeFile(path)
+    fun decodeFile(path: String, autoOrient: Boolean = true): DecodeResult =
+        io.clroot.slimg.decodeFile(path, autoOrient)
+
+    /** Decode every frame of [data]; still images come back as one frame. */
+    @Throws(SlimgException::class)
+    fun decodeAnimation(data: ByteArray, autoOrient: Boolean = true): AnimationDecodeResult =
+        io.clroot.slimg.decodeAnimation(data, autoOrient)
+
+    @Throws(SlimgException::class)
+    fun decodeAnimationFile(path: String, autoOrient: Boolean = true): AnimationDecodeResult =
+        io.clroot.slimg.decodeAnimationFile(path, autoOrient)
+
+    /** Read the format, size, frame count and metadata of [data] without decoding pixels. */
+    @Throws(SlimgException::class)
+    fun probe(data: ByteArray): ImageInfo =
+        io.clroot.slimg.probe(data)
+
+    @Throws(SlimgException::class)
+    fun probeFile(path: String): ImageInfo =
+        io.clroot.slimg.probeFile(path)
 
     // ── Optimize ────────────────────────────────────────
 
@@ -66,9 +85,54 @@ object Slimg {
         crop: CropMode? = null,
         extend: ExtendMode? = null,
         fillColor: FillColor? = null,
+        lossless: Boolean = false,
+        metadata: MetadataPolicy? = null,
+        color: ColorPolicy? = null,
+        toneMap: ToneMapOperator? = null,
+        resizeFilter: ResizeFilter? = null,
+        withoutEnlargement: Boolean = false,
+        linearLight: LinearLight? = null,
+        premultiplyAlpha: Boolean = true,
     ): PipelineResult = io.clroot.slimg.convert(
         image,
-        PipelineOptions(format, quality.toQuality(), resize, crop, extend, fillColor),
+        PipelineOptions(
+            format, quality.toQuality(), resize, crop, extend, fillColor,
+            lossless = lossless, metadata = metadata, color = color, toneMap = toneMap,
+            resizeFilter = resizeFilter, withoutEnlargement = withoutEnlargement,
+            linearLight = linearLight, premultiplyAlpha = premultiplyAlpha,
+        ),
+    )
+
+    /**
+     * Encode every frame of [animation] into [format], applying the
+     * transformations to each. Throws for still-only formats when there is
+     * more than one frame.
+     */
+    @Throws(SlimgException::class)
+    fun convertAnimation(
+        animation: Animation,
+        format: Format,
+        quality: Int = 80,
+        resize: ResizeMode? = null,
+        crop: CropMode? = null,
+        extend: ExtendMode? = null,
+        fillColor: FillColor? = null,
+        lossless: Boolean = false,
+        metadata: MetadataPolicy? = null,
+        color: ColorPolicy? = null,
+        toneMap: ToneMapOperator? = null,
+        resizeFilter: ResizeFilter? = null,
+        withoutEnlargement: Boolean = false,
+        linearLight: LinearLight? = null,
+        premultiplyAlpha: Boolean = true,
+    ): PipelineResult = io.clroot.slimg.convertAnimation(
+        animation,
+        PipelineOptions(
+            format, quality.toQuality(), resize, crop, extend, fillColor,
+            lossless = lossless, metadata = metadata, color = color, toneMap = toneMap,
+            resizeFilter = resizeFilter, withoutEnlargement = withoutEnlargement,
+            linearLight = linearLight, premultiplyAlpha = premultiplyAlpha,
+        ),
     )
 
     // ── Image Operations ────────────────────────────────
@@ -77,9 +141,22 @@ object Slimg {
     fun crop(image: ImageData, mode: CropMode): ImageData =
         io.clroot.slimg.crop(image, mode)
 
+    /** The region [crop] would take, e.g. to see where [CropMode.Smart] landed. */
+    @Throws(SlimgException::class)
+    fun cropRegion(image: ImageData, mode: CropMode): CropRegion =
+        io.clroot.slimg.cropRegion(image, mode)
+
     @Throws(SlimgException::class)
-    fun resize(image: ImageData, mode: ResizeMode): ImageData =
-        io.clroot.slimg.resize(image, mode)
+    fun resize(
+        image: ImageData,
+        mode: ResizeMode,
+        filter: ResizeFilter? = null,
+        withoutEnlargement: Boolean = false,
+        linearLight: LinearLight? = null,
+        premultiplyAlpha: Boolean = true,
+    ): ImageData = io.clroot.slimg.resize(
+        image, mode, filter, withoutEnlargement, linearLight, premultiplyAlpha,
+    )
 
     @Throws(SlimgException::class)
     fun extend(
@@ -96,6 +173,9 @@ object Slimg {
     fun formatCanEncode(format: Format): Boolean =
         io.clroot.slimg.formatCanEncode(format)
 
+    fun formatSupportsAnimation(format: Format): Boolean =
+        io.clroot.slimg.formatSupportsAnimation(format)
+
     fun formatFromExtension(path: String): Format? =
         io.clroot.slimg.formatFromExtension(path)
 
@@ -21,6 +21,7 @@ class SlimgImage private constructor(
 ) {
     val width: Int get() = imageData.width.toInt()
     val height: Int get() = imageData.height.toInt()
+    val pixelFormat: PixelFormat get() = imageData.pixelFormat ?: PixelFormat.RGBA8
 
     // ── Factory ─────────────────────────────────────────
 
@@ -87,6 +88,32 @@ class SlimgImage private constructor(
     fun resize(scale: Double): SlimgImage =
         transformed(io.clroot.slimg.resize(imageData, ResizeMode.Scale(scale)))
 
+    /**
+     * Fill exactly [width] x [height], cropping whatever overflows (centered).
+     *
+     * ```kotlin
+     * image.cover(256, 256)
+     * ```
+     */
+    @Throws(SlimgException::class)
+    fun cover(width: Int, height: Int): SlimgImage {
+        val mode = ResizeMode.Cover(width.toUInt(), height.toUInt())
+        return transformed(io.clroot.slimg.resize(imageData, mode))
+    }
+
+    /**
+     * Fit within [width] x [height], then pad to exactly that size with [fill].
+     */
+    @Throws(SlimgException::class)
+    fun contain(
+        width: Int,
+        height: Int,
+        fill: FillColor = FillColor.Transparent,
+    ): SlimgImage {
+        val mode = ResizeMode.Contain(width.toUInt(), height.toUInt(), fill)
+        return transformed(io.clroot.slimg.resize(imageData, mode))
+    }
+
     // ── Crop ────────────────────────────────────────────
 
     /**
@@ -99,35 +126,54 @@ class SlimgImage private constructor(
     }
 
     /**
-     * Crop to an aspect ratio (centered).
+     * Crop to an aspect ratio, keeping the part of the image picked by
+     * [gravity] (centered by default).
      *
      * ```kotlin
      * image.crop(aspectRatio = 16 to 9)
+     * image.crop(aspectRatio = 3 to 1, gravity = Gravity.North)
+     * ```
+     */
+    @Throws(SlimgException::class)
+    fun crop(aspectRatio: Pair<Int, Int>, gravity: Gravity? = null): SlimgImage {
+        val mode = CropMode.AspectRatio(aspectRatio.first.toUInt(), aspectRatio.second.toUInt(), gravity)
+        return transformed(io.clroot.slimg.crop(imageData, mode))
+    }
+
+    /**
+     * Crop to an aspect ratio, placing the window over the most detailed
+     * part of the image instead of the centre.
+     *
+     * ```kotlin
+     * image.smartCrop(aspectRatio = 1 to 1)
      * ```
      */
     @Throws(SlimgException::class)
-    fun crop(aspectRatio: Pair<Int, Int>): SlimgImage {
-        val mode = CropMode.AspectRatio(aspectRatio.first.toUInt(), aspectRatio.second.toUInt())
+    fun smartCrop(aspectRatio: Pair<Int, Int>): SlimgImage {
+        val mode = CropMode.Smart(aspectRatio.first.toUInt(), aspectRatio.second.toUInt())
         return transformed(io.clroot.slimg.crop(imageData, mode))
     }
 
     // ── Extend ──────────────────────────────────────────
 
     /**
-     * Extend the canvas to exact pixel dimensions (centered).
+     * Extend the canvas to exact pixel dimensions, placing the image by
+     * [gravity] (centered by default).
      */
     @Throws(SlimgException::class)
     fun extend(
         width: Int,
         height: Int,
         fill: FillColor = FillColor.Transparent,
+        gravity: Gravity? = null,
     ): SlimgImage {
-        val mode = ExtendMode.Size(width.toUInt(), height.toUInt())
+        val mode = ExtendMode.Size(width.toUInt(), height.toUInt(), gravity)
         return transformed(io.clroot.slimg.extend(imageData, mode, fill))
     }
 
     /**
-     * Extend the canvas to fit an aspect ratio (centered).
+     * Extend the canvas to fit an aspect ratio, placing the image by
+     * [gravity] (centered by default).
      *
      * ```kotlin
      * image.extend(aspectRatio = 16 to 9, fill = Slimg.solidColor(255, 255, 255))
@@ -137,8 +183,9 @@ class SlimgImage private constructor(
     fun extend(
         aspectRatio: Pair<Int, Int>,
         fill: FillColor = FillColor.Transparent,
+        gravity: Gravity? = null,
     ): SlimgImage {
-        val mode = ExtendMode.AspectRatio(aspectRatio.first.toUInt(), aspectRatio.second.toUInt())
+        val mode = ExtendMode.AspectRatio(aspectRatio.first.toUInt(), aspectRatio.second.toUInt(), gravity)
         return transformed(io.clroot.slimg.extend(imageData, mode, fill))
     }
 
@@ -110,6 +110,19 @@ class SlimgObjectTest {
         assertEquals(decoded.image.width, decoded.image.height)
     }
 
+    @Test
+    fun `convertAnimation keeps every frame`() {
+        val animation = Animation(
+            listOf(Frame(testImage(16u, 16u), 100u), Frame(testImage(16u, 16u), 50u)),
+            loopCount = 0u,
+        )
+        val result = Slimg.convertAnimation(animation, Format.PNG, resize = ResizeMode.Width(8u))
+        val decoded = Slimg.decodeAnimation(result.data)
+        assertEquals(2, decoded.animation.frames.size)
+        assertEquals(8u, decoded.animation.frames[1].image.width)
+        assertEquals(50u, decoded.animation.frames[1].durationMs)
+    }
+
     // ── Image Operations ────────────────────────────────
 
     @Test
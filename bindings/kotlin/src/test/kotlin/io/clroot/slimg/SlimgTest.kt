@@ -45,6 +45,9 @@ class SlimgTest {
         assertEquals("avif", formatExtension(Format.AVIF))
         assertEquals("jxl", formatExtension(Format.JXL))
         assertEquals("qoi", formatExtension(Format.QOI))
+        assertEquals("gif", formatExtension(Format.GIF))
+        assertEquals("tiff", formatExtension(Format.TIFF))
+        assertEquals("bmp", formatExtension(Format.BMP))
     }
 
     @Test
@@ -55,6 +58,9 @@ class SlimgTest {
         assertTrue(formatCanEncode(Format.AVIF))
         assertTrue(formatCanEncode(Format.JXL))
         assertTrue(formatCanEncode(Format.QOI))
+        assertTrue(formatCanEncode(Format.GIF))
+        assertTrue(formatCanEncode(Format.TIFF))
+        assertTrue(formatCanEncode(Format.BMP))
     }
 
     @Test
@@ -66,11 +72,17 @@ class SlimgTest {
         assertEquals(Format.AVIF, formatFromExtension("image.avif"))
         assertEquals(Format.JXL, formatFromExtension("image.jxl"))
         assertEquals(Format.QOI, formatFromExtension("image.qoi"))
+        assertEquals(Format.GIF, formatFromExtension("image.gif"))
+        assertEquals(Format.TIFF, formatFromExtension("scan.tif"))
+        assertEquals(Format.BMP, formatFromExtension("export.bmp"))
+        assertEquals(Format.TGA, formatFromExtension("texture.tga"))
+        assertEquals(Format.ICO, formatFromExtension("favicon.ico"))
+        assertEquals(Format.PNM, formatFromExtension("frame.ppm"))
     }
 
     @Test
     fun `formatFromExtension returns null for unknown`() {
-        assertNull(formatFromExtension("file.bmp"))
+        assertNull(formatFromExtension("file.psd"))
         assertNull(formatFromExtension("noext"))
     }
 
@@ -3,6 +3,8 @@
 from slimg._types import (
     Format,
     Image,
+    Frame,
+    Animation,
     Result,
     Resize,
     Crop,
@@ -10,8 +12,11 @@ from slimg._types import (
     SlimgError,
     open,
     decode,
+    open_animation,
+    decode_animation,
     convert,
     crop_image as crop,
+    crop_region,
     extend_image as extend,
     resize_image as resize,
     optimize,
@@ -21,6 +26,8 @@ from slimg._types import (
 __all__ = [
     "Format",
     "Image",
+    "Frame",
+    "Animation",
     "Result",
     "Resize",
     "Crop",
@@ -28,8 +35,11 @@ __all__ = [
     "SlimgError",
     "open",
     "decode",
+    "open_animation",
+    "decode_animation",
     "convert",
     "crop",
+    "crop_region",
     "extend",
     "resize",
     "optimize",
@@ -8,7 +8,7 @@ from __future__ import annotations
 
 import builtins
 from enum import Enum
-from typing import Optional, Tuple, Union
+from typing import List, Optional, Tuple, Union
 
 from slimg import _lowlevel
 
@@ -51,6 +51,12 @@ class Format(Enum):
     AVIF = "avif"
     JXL = "jxl"
     QOI = "qoi"
+    GIF = "gif"
+    TIFF = "tiff"
+    BMP = "bmp"
+    TGA = "tga"
+    ICO = "ico"
+    PNM = "pnm"
 
     @property
     def extension(self) -> str:
@@ -94,7 +100,7 @@ class Format(Enum):
         """Accept a ``Format`` enum member or a string and return ``Format``.
 
         Strings are matched case-insensitively; ``'jpg'`` is accepted as an
-        alias for JPEG.
+        alias for JPEG and ``'tif'`` for TIFF.
         """
         if isinstance(fmt, cls):
             return fmt
@@ -108,6 +114,13 @@ class Format(Enum):
                 "avif": cls.AVIF,
                 "jxl": cls.JXL,
                 "qoi": cls.QOI,
+                "gif": cls.GIF,
+                "tiff": cls.TIFF,
+                "tif": cls.TIFF,
+                "bmp": cls.BMP,
+                "tga": cls.TGA,
+                "ico": cls.ICO,
+                "pnm": cls.PNM,
             }
             result = name_map.get(normalised)
             if result is None:
@@ -124,6 +137,12 @@ _FORMAT_TO_LL = {
     Format.AVIF: _lowlevel.Format.AVIF,
     Format.JXL: _lowlevel.Format.JXL,
     Format.QOI: _lowlevel.Format.QOI,
+    Format.GIF: _lowlevel.Format.GIF,
+    Format.TIFF: _lowlevel.Format.TIFF,
+    Format.BMP: _lowlevel.Format.BMP,
+    Format.TGA: _lowlevel.Format.TGA,
+    Format.ICO: _lowlevel.Format.ICO,
+    Format.PNM: _lowlevel.Format.PNM,
 }
 _FORMAT_FROM_LL = {v: k for k, v in _FORMAT_TO_LL.items()}
 
@@ -132,8 +151,21 @@ _FORMAT_FROM_LL = {v: k for k, v in _FORMAT_TO_LL.items()}
 # Image
 # ---------------------------------------------------------------------------
 
+# Pixel format name -> (lowlevel enum, bytes per pixel).
+_PIXEL_FORMATS = {
+    "rgba8": (_lowlevel.PixelFormat.RGBA8, 4),
+    "rgba16": (_lowlevel.PixelFormat.RGBA16, 8),
+    "rgba32f": (_lowlevel.PixelFormat.RGBA32_F, 16),
+}
+_PIXEL_FORMAT_NAMES = {ll: name for name, (ll, _) in _PIXEL_FORMATS.items()}
+
+
 class Image:
-    """Decoded image with RGBA pixel data."""
+    """Decoded image with RGBA pixel data.
+
+    *pixel_format* is ``'rgba8'`` (the default), ``'rgba16'`` or
+    ``'rgba32f'``; wider samples are stored in native byte order.
+    """
 
     def __init__(
         self,
@@ -141,17 +173,29 @@ class Image:
         height: int,
         data: bytes,
         format: Optional[Format] = None,
+        *,
+        pixel_format: str = "rgba8",
     ):
-        expected = width * height * 4
+        try:
+            _, bytes_per_pixel = _PIXEL_FORMATS[pixel_format.lower()]
+        except KeyError:
+            raise ValueError(
+                f"Invalid pixel format: {pixel_format!r}. "
+                "Use 'rgba8', 'rgba16', or 'rgba32f'."
+            ) from None
+        expected = width * height * bytes_per_pixel
         if len(data) != expected:
             raise ValueError(
                 f"Buffer size mismatch: expected {expected} bytes "
-                f"({width}x{height} RGBA), got {len(data)}"
+                f"({width}x{height} {pixel_format.upper()}), got {len(data)}"
             )
         self._width = width
         self._height = height
         self._data = data
         self._format = format
+        self._pixel_format = pixel_format.lower()
+        # EXIF / XMP / ICC carried over from the decoded file, if any.
+        self._metadata: Optional[_lowlevel.Metadata] = None
 
     @property
     def width(self) -> int:
@@ -169,18 +213,30 @@ class Image:
     def format(self) -> Optional[Format]:
         return self._format
 
+    @property
+    def pixel_format(self) -> str:
+        return self._pixel_format
+
     # -- internal helpers ----------------------------------------------------
 
     def _to_lowlevel(self) -> _lowlevel.ImageData:
         return _lowlevel.ImageData(
             width=self._width, height=self._height, data=self._data,
+            metadata=self._metadata,
+            pixel_format=_PIXEL_FORMATS[self._pixel_format][0],
         )
 
     @classmethod
     def _from_lowlevel(
         cls, img: _lowlevel.ImageData, fmt: Optional[Format] = None,
     ) -> Image:
-        return cls(width=img.width, height=img.height, data=img.data, format=fmt)
+        pixel_format = _PIXEL_FORMAT_NAMES.get(img.pixel_format, "rgba8")
+        image = cls(
+            width=img.width, height=img.height, data=img.data, format=fmt,
+            pixel_format=pixel_format,
+        )
+        image._metadata = img.metadata
+        return image
 
     @classmethod
     def _from_raw(cls, width: int, height: int, data: bytes) -> Image:
@@ -188,6 +244,122 @@ class Image:
         return cls(width=width, height=height, data=data)
 
 
+# ---------------------------------------------------------------------------
+# Animation
+# ---------------------------------------------------------------------------
+
+_DISPOSALS = {
+    "none": _lowlevel.Disposal.NONE,
+    "background": _lowlevel.Disposal.BACKGROUND,
+    "previous": _lowlevel.Disposal.PREVIOUS,
+}
+_DISPOSAL_NAMES = {ll: name for name, ll in _DISPOSALS.items()}
+
+
+class Frame:
+    """One full-canvas frame of an :class:`Animation`.
+
+    *disposal* is ``'none'`` (the default), ``'background'`` or
+    ``'previous'``.
+    """
+
+    def __init__(self, image: Image, duration_ms: int, disposal: str = "none"):
+        if disposal.lower() not in _DISPOSALS:
+            raise ValueError(
+                f"Invalid disposal: {disposal!r}. "
+                "Use 'none', 'background', or 'previous'."
+            )
+        self._image = image
+        self._duration_ms = duration_ms
+        self._disposal = disposal.lower()
+
+    @property
+    def image(self) -> Image:
+        return self._image
+
+    @property
+    def duration_ms(self) -> int:
+        return self._duration_ms
+
+    @property
+    def disposal(self) -> str:
+        return self._disposal
+
+    def _to_lowlevel(self) -> _lowlevel.Frame:
+        return _lowlevel.Frame(
+            image=self._image._to_lowlevel(),
+            duration_ms=self._duration_ms,
+            disposal=_DISPOSALS[self._disposal],
+        )
+
+
+class Animation:
+    """A sequence of equally sized frames.
+
+    *loop_count* is how many times the animation plays; ``0`` loops
+    forever.
+    """
+
+    def __init__(
+        self,
+        frames: List[Frame],
+        loop_count: int = 0,
+        format: Optional[Format] = None,
+    ):
+        if not frames:
+            raise ValueError("An animation needs at least one frame")
+        self._frames = list(frames)
+        self._loop_count = loop_count
+        self._format = format
+
+    @property
+    def frames(self) -> List[Frame]:
+        return self._frames
+
+    @property
+    def loop_count(self) -> int:
+        return self._loop_count
+
+    @property
+    def format(self) -> Optional[Format]:
+        return self._format
+
+    @property
+    def width(self) -> int:
+        return self._frames[0].image.width
+
+    @property
+    def height(self) -> int:
+        return self._frames[0].image.height
+
+    @property
+    def is_animated(self) -> bool:
+        """Whether there is more than one frame."""
+        return len(self._frames) > 1
+
+    # -- internal helpers ----------------------------------------------------
+
+    def _to_lowlevel(self) -> _lowlevel.Animation:
+        return _lowlevel.Animation(
+            frames=[frame._to_lowlevel() for frame in self._frames],
+            loop_count=self._loop_count,
+        )
+
+    @classmethod
+    def _from_lowlevel(
+        cls, anim: _lowlevel.Animation, fmt: Optional[Format] = None,
+    ) -> Animation:
+        frames = [
+            Frame(
+                Image._from_lowlevel(frame.image, fmt),
+                frame.duration_ms,
+                _DISPOSAL_NAMES.get(frame.disposal, "none"),
+            )
+            for frame in anim.frames
+        ]
+        return cls(frames, loop_count=anim.loop_count, format=fmt)
+
+
 # ---------------------------------------------------------------------------
 # Result
 # ---------------------------------------------------------------------------
@@ -245,6 +417,24 @@ class Resize:
         """Scale by *factor* (e.g. 0.5 = half size, 2.0 = double)."""
         return _lowlevel.ResizeMode.SCALE(factor=factor)
 
+    @staticmethod
+    def cover(width: int, height: int):
+        """Fill exactly *width* x *height*, cropping the overflow."""
+        return _lowlevel.ResizeMode.COVER(width=width, height=height)
+
+    @staticmethod
+    def contain(width: int, height: int, fill=None):
+        """Fit within *width* x *height*, then pad to that size with *fill*
+        (transparent by default)."""
+        return _lowlevel.ResizeMode.CONTAIN(
+            width=width, height=height, fill=_resolve_fill(fill)
+        )
+
+    @staticmethod
+    def max_pixels(max_pixels: int):
+        """Shrink to at most *max_pixels* pixels; smaller images are kept."""
+        return _lowlevel.ResizeMode.MAX_PIXELS(max_pixels=max_pixels)
+
 
 class Crop:
     """Factory for lowlevel ``CropMode`` variants."""
@@ -255,23 +445,35 @@ class Crop:
         return _lowlevel.CropMode.REGION(x=x, y=y, width=width, height=height)
 
     @staticmethod
-    def aspect_ratio(width: int, height: int):
-        """Centre-crop to the given aspect ratio."""
-        return _lowlevel.CropMode.ASPECT_RATIO(width=width, height=height)
+    def aspect_ratio(width: int, height: int, gravity=None):
+        """Crop to the given aspect ratio, keeping the part named by
+        *gravity* (centre by default)."""
+        return _lowlevel.CropMode.ASPECT_RATIO(
+            width=width, height=height, gravity=_resolve_gravity(gravity)
+        )
+
+    @staticmethod
+    def smart(width: int, height: int):
+        """Crop to the given aspect ratio over the most detailed region."""
+        return _lowlevel.CropMode.SMART(width=width, height=height)
 
 
 class Extend:
     """Factory for lowlevel ``ExtendMode`` variants."""
 
     @staticmethod
-    def aspect_ratio(width: int, height: int):
+    def aspect_ratio(width: int, height: int, gravity=None):
         """Extend (pad) canvas to fit the given aspect ratio."""
-        return _lowlevel.ExtendMode.ASPECT_RATIO(width=width, height=height)
+        return _lowlevel.ExtendMode.ASPECT_RATIO(
+            width=width, height=height, gravity=_resolve_gravity(gravity)
+        )
 
     @staticmethod
-    def size(width: int, height: int):
+    def size(width: int, height: int, gravity=None):
         """Extend (pad) canvas to an exact pixel *width* x *height*."""
-        return _lowlevel.ExtendMode.SIZE(width=width, height=height)
+        return _lowlevel.ExtendMode.SIZE(
+            width=width, height=height, gravity=_resolve_gravity(gravity)
+        )
 
 
 # ---------------------------------------------------------------------------
@@ -318,30 +520,201 @@ def _resolve_fill(
     )
 
 
+_METADATA_POLICIES = {
+    "keep": _lowlevel.MetadataPolicy.KEEP,
+    "strip": _lowlevel.MetadataPolicy.STRIP,
+    "safe": _lowlevel.MetadataPolicy.SAFE,
+}
+
+
+def _resolve_metadata(
+    metadata: Optional[str],
+) -> Optional[_lowlevel.MetadataPolicy]:
+    """Convert a metadata policy name to a lowlevel ``MetadataPolicy``."""
+    if metadata is None:
+        return None
+    try:
+        return _METADATA_POLICIES[metadata.lower()]
+    except KeyError:
+        raise ValueError(
+            f"Invalid metadata policy: {metadata!r}. "
+            "Use 'keep', 'strip', or 'safe'."
+        ) from None
+
+
+_COLOR_POLICIES = {
+    "preserve": _lowlevel.ColorPolicy.PRESERVE,
+    "srgb": _lowlevel.ColorPolicy.SRGB,
+}
+
+
+def _resolve_color(
+    color: Optional[str],
+) -> Optional[_lowlevel.ColorPolicy]:
+    """Convert a colour policy name to a lowlevel ``ColorPolicy``."""
+    if color is None:
+        return None
+    try:
+        return _COLOR_POLICIES[color.lower()]
+    except KeyError:
+        raise ValueError(
+            f"Invalid color policy: {color!r}. "
+            "Use 'preserve' or 'srgb'."
+        ) from None
+
+
+_TONE_MAP_OPERATORS = {
+    "clip": _lowlevel.ToneMapOperator.CLIP,
+    "reinhard": _lowlevel.ToneMapOperator.REINHARD,
+    "hable": _lowlevel.ToneMapOperator.HABLE,
+}
+
+
+def _resolve_tone_map(
+    tone_map: Optional[str],
+) -> Optional[_lowlevel.ToneMapOperator]:
+    """Convert an operator name to a lowlevel ``ToneMapOperator``."""
+    if tone_map is None:
+        return None
+    try:
+        return _TONE_MAP_OPERATORS[tone_map.lower()]
+    except KeyError:
+        raise ValueError(
+            f"Invalid tone map operator: {tone_map!r}. "
+            "Use 'clip', 'reinhard', or 'hable'."
+        ) from None
+
+
+_RESIZE_FILTERS = {
+    "nearest": _lowlevel.ResizeFilter.NEAREST,
+    "bilinear": _lowlevel.ResizeFilter.BILINEAR,
+    "catmull-rom": _lowlevel.ResizeFilter.CATMULL_ROM,
+    "mitchell": _lowlevel.ResizeFilter.MITCHELL,
+    "lanczos3": _lowlevel.ResizeFilter.LANCZOS3,
+}
+
+
+def _resolve_filter(
+    filter: Optional[str],
+) -> Optional[_lowlevel.ResizeFilter]:
+    """Convert a filter name to a lowlevel ``ResizeFilter``."""
+    if filter is None:
+        return None
+    try:
+        return _RESIZE_FILTERS[filter.lower()]
+    except KeyError:
+        raise ValueError(
+            f"Invalid resize filter: {filter!r}. "
+            "Use 'nearest', 'bilinear', 'catmull-rom', 'mitchell', or 'lanczos3'."
+        ) from None
+
+
+_LINEAR_LIGHT = {
+    "auto": _lowlevel.LinearLight.AUTO,
+    "always": _lowlevel.LinearLight.ALWAYS,
+    "never": _lowlevel.LinearLight.NEVER,
+}
+
+
+def _resolve_linear_light(
+    linear_light: Optional[str],
+) -> Optional[_lowlevel.LinearLight]:
+    """Convert a linear-light setting to a lowlevel ``LinearLight``."""
+    if linear_light is None:
+        return None
+    try:
+        return _LINEAR_LIGHT[linear_light.lower()]
+    except KeyError:
+        raise ValueError(
+            f"Invalid linear light setting: {linear_light!r}. "
+            "Use 'auto', 'always', or 'never'."
+        ) from None
+
+
+_GRAVITIES = {
+    "center": _lowlevel.Gravity.CENTER,
+    "north": _lowlevel.Gravity.NORTH,
+    "northeast": _lowlevel.Gravity.NORTH_EAST,
+    "east": _lowlevel.Gravity.EAST,
+    "southeast": _lowlevel.Gravity.SOUTH_EAST,
+    "south": _lowlevel.Gravity.SOUTH,
+    "southwest": _lowlevel.Gravity.SOUTH_WEST,
+    "west": _lowlevel.Gravity.WEST,
+    "northwest": _lowlevel.Gravity.NORTH_WEST,
+}
+
+
+def _resolve_gravity(
+    gravity: Union[None, str, Tuple[float, float]],
+) -> Optional[_lowlevel.Gravity]:
+    """Convert a compass name or an ``(x, y)`` focal point to a lowlevel
+    ``Gravity``."""
+    if gravity is None:
+        return None
+    if isinstance(gravity, tuple) and len(gravity) == 2:
+        x, y = gravity
+        return _lowlevel.Gravity.FOCAL(x=float(x), y=float(y))
+    if isinstance(gravity, str) and gravity.lower() in _GRAVITIES:
+        return _GRAVITIES[gravity.lower()]()
+    raise ValueError(
+        f"Invalid gravity: {gravity!r}. "
+        "Use 'center', a compass direction such as 'north' or 'southwest', "
+        "or an (x, y) focal point."
+    )
+
+
 # ---------------------------------------------------------------------------
 # Public functions
 # ---------------------------------------------------------------------------
 
-def open(path: str) -> Image:
+def open(path: str, *, auto_orient: bool = True) -> Image:
     """Read an image file from disk and decode it.
 
+    The pixels are rotated upright according to the EXIF orientation
+    unless *auto_orient* is ``False``.
+
     This shadows Python's built-in ``open``; the built-in is still
     available via ``builtins.open``.
     """
-    result = _lowlevel.decode_file(path)
+    result = _lowlevel.decode_file(path, auto_orient=auto_orient)
     fmt = Format._from_lowlevel(result.format)
     return Image._from_lowlevel(result.image, fmt)
 
 
-def decode(data: bytes) -> Image:
-    """Decode image bytes (format is auto-detected from magic bytes)."""
-    result = _lowlevel.decode(data)
+def decode(data: bytes, *, auto_orient: bool = True) -> Image:
+    """Decode image bytes (format is auto-detected from magic bytes).
+
+    The pixels are rotated upright according to the EXIF orientation
+    unless *auto_orient* is ``False``.
+    """
+    result = _lowlevel.decode(data, auto_orient=auto_orient)
     fmt = Format._from_lowlevel(result.format)
     return Image._from_lowlevel(result.image, fmt)
 
 
+def open_animation(path: str, *, auto_orient: bool = True) -> Animation:
+    """Read an image file from disk and decode every frame.
+
+    Still images come back as a one-frame ``Animation``.
+    """
+    result = _lowlevel.decode_animation_file(path, auto_orient=auto_orient)
+    fmt = Format._from_lowlevel(result.format)
+    return Animation._from_lowlevel(result.animation, fmt)
+
+
+def decode_animation(data: bytes, *, auto_orient: bool = True) -> Animation:
+    """Decode every frame of animated GIF, WebP, AVIF, PNG (APNG) or
+    JPEG XL bytes, or every page of a multi-page TIFF.
+
+    Still images come back as a one-frame ``Animation``.
+    """
+    result = _lowlevel.decode_animation(data, auto_orient=auto_orient)
+    fmt = Format._from_lowlevel(result.format)
+    return Animation._from_lowlevel(result.animation, fmt)
+
+
 def convert(
-    image: Image,
+    image: Union[Image, Animation],
     format: Union[Format, str],
     quality: int = 80,
     *,
@@ -349,10 +722,22 @@ def convert(
     crop=None,
     extend=None,
     fill=None,
+    lossless: bool = False,
+    metadata: Optional[str] = None,
+    color: Optional[str] = None,
+    tone_map: Optional[str] = None,
+    resize_filter: Optional[str] = None,
+    without_enlargement: bool = False,
+    linear_light: Optional[str] = None,
+    premultiply_alpha: bool = True,
 ) -> Result:
     """Encode *image* in the target *format*, optionally applying
     crop / extend / resize in a single pipeline.
 
+    *image* may also be an ``Animation``; every frame is transformed and
+    the timing is kept. Converting more than one frame to a still-only
+    format (JPEG, QOI) raises ``SlimgError``.
+
     *format* may be a ``Format`` enum member or a string such as
     ``'png'``, ``'webp'``, ``'jpg'``, etc.
 
@@ -361,6 +746,25 @@ def convert(
 
     *fill* accepts ``'transparent'``, ``(r, g, b)``, or
     ``(r, g, b, a)``.  Defaults to transparent when *extend* is set.
+
+    With *lossless* the output decodes to exactly the input pixels; this
+    raises ``SlimgError`` for formats that cannot guarantee it.
+
+    *metadata* is ``'keep'``, ``'strip'`` or ``'safe'`` (the default),
+    which keeps the ICC profile and copyright/orientation EXIF tags only.
+
+    *color* is ``'preserve'`` (the default), which embeds the source ICC
+    profile where the format allows it, or ``'srgb'``, which converts the
+    pixels to sRGB and drops the profile.
+
+    HDR (PQ/HLG) images stay HDR when written as AVIF or JPEG XL. Other
+    formats, or ``color='srgb'``, tone-map them to SDR with *tone_map*:
+    ``'clip'``, ``'reinhard'`` (the default) or ``'hable'``.
+
+    *resize_filter* picks the resampling filter used by *resize*; see
+    ``resize``. With *without_enlargement*, images already smaller than
+    the resize target are not scaled up. *linear_light* and
+    *premultiply_alpha* are passed on to ``resize`` as well.
     """
     _validate_quality(quality)
     fmt = Format._resolve(format)
@@ -375,58 +779,114 @@ def convert(
         crop=crop,
         extend=extend,
         fill_color=fill_color,
+        lossless=lossless,
+        metadata=_resolve_metadata(metadata),
+        color=_resolve_color(color),
+        tone_map=_resolve_tone_map(tone_map),
+        resize_filter=_resolve_filter(resize_filter),
+        without_enlargement=without_enlargement,
+        linear_light=_resolve_linear_light(linear_light),
+        premultiply_alpha=premultiply_alpha,
     )
-    result = _lowlevel.convert(image._to_lowlevel(), opts)
+    if isinstance(image, Animation):
+        result = _lowlevel.convert_animation(image._to_lowlevel(), opts)
+    else:
+        result = _lowlevel.convert(image._to_lowlevel(), opts)
     return Result(data=result.data, format=Format._from_lowlevel(result.format))
 
 
+def _crop_mode(region, aspect_ratio, smart: bool, gravity):
+    """Build a lowlevel ``CropMode`` from the ``crop`` keyword arguments."""
+    if region is not None and aspect_ratio is not None:
+        raise ValueError("Specify either region or aspect_ratio, not both")
+    if smart and aspect_ratio is None:
+        raise ValueError("smart needs aspect_ratio")
+    if gravity is not None and (aspect_ratio is None or smart):
+        raise ValueError("gravity needs aspect_ratio and cannot be combined with smart")
+    if region is not None:
+        x, y, w, h = region
+        return _lowlevel.CropMode.REGION(x=x, y=y, width=w, height=h)
+    if aspect_ratio is not None:
+        w, h = aspect_ratio
+        if smart:
+            return _lowlevel.CropMode.SMART(width=w, height=h)
+        return _lowlevel.CropMode.ASPECT_RATIO(
+            width=w, height=h, gravity=_resolve_gravity(gravity)
+        )
+    raise ValueError("Specify region or aspect_ratio")
+
+
 def crop_image(
     image: Image,
     *,
     region: Optional[Tuple[int, int, int, int]] = None,
     aspect_ratio: Optional[Tuple[int, int]] = None,
+    smart: bool = False,
+    gravity: Union[None, str, Tuple[float, float]] = None,
 ) -> Image:
     """Crop *image*.
 
     Provide exactly one of:
     - ``region=(x, y, width, height)``
     - ``aspect_ratio=(width, height)``
+
+    With *smart*, the *aspect_ratio* window is placed over the most
+    detailed part of the image instead of the centre. *gravity* places it
+    by hand instead: ``"north"``, ``"southwest"`` and the other compass
+    directions keep that edge or corner, and an ``(x, y)`` focal point in
+    fractions of the image is kept as close to the middle as possible.
     """
-    if region is not None and aspect_ratio is not None:
-        raise ValueError("Specify either region or aspect_ratio, not both")
-    if region is not None:
-        x, y, w, h = region
-        mode = _lowlevel.CropMode.REGION(x=x, y=y, width=w, height=h)
-    elif aspect_ratio is not None:
-        w, h = aspect_ratio
-        mode = _lowlevel.CropMode.ASPECT_RATIO(width=w, height=h)
-    else:
-        raise ValueError("Specify region or aspect_ratio")
+    mode = _crop_mode(region, aspect_ratio, smart, gravity)
     result = _lowlevel.crop(image._to_lowlevel(), mode)
     return Image._from_lowlevel(result, image.format)
 
 
+def crop_region(
+    image: Image,
+    *,
+    region: Optional[Tuple[int, int, int, int]] = None,
+    aspect_ratio: Optional[Tuple[int, int]] = None,
+    smart: bool = False,
+    gravity: Union[None, str, Tuple[float, float]] = None,
+) -> Tuple[int, int, int, int]:
+    """The ``(x, y, width, height)`` that ``crop`` would take from *image*
+    with the same arguments.
+    """
+    mode = _crop_mode(region, aspect_ratio, smart, gravity)
+    r = _lowlevel.crop_region(image._to_lowlevel(), mode)
+    return (r.x, r.y, r.width, r.height)
+
+
 def extend_image(
     image: Image,
     *,
     aspect_ratio: Optional[Tuple[int, int]] = None,
     size: Optional[Tuple[int, int]] = None,
     fill: Union[str, Tuple[int, int, int], Tuple[int, int, int, int]] = "transparent",
+    gravity: Union[None, str, Tuple[float, float]] = None,
 ) -> Image:
     """Extend (pad) *image*.
 
     Provide exactly one of:
     - ``aspect_ratio=(width, height)``
     - ``size=(width, height)``
+
+    *gravity* places the image on the canvas (centred by default), e.g.
+    ``"north"`` to pad only below, or an ``(x, y)`` focal point of the
+    image to move to the middle.
     """
     if aspect_ratio is not None and size is not None:
         raise ValueError("Specify either aspect_ratio or size, not both")
     if aspect_ratio is not None:
         w, h = aspect_ratio
-        mode = _lowlevel.ExtendMode.ASPECT_RATIO(width=w, height=h)
+        mode = _lowlevel.ExtendMode.ASPECT_RATIO(
+            width=w, height=h, gravity=_resolve_gravity(gravity)
+        )
     elif size is not None:
         w, h = size
-        mode = _lowlevel.ExtendMode.SIZE(width=w, height=h)
+        mode = _lowlevel.ExtendMode.SIZE(
+            width=w, height=h, gravity=_resolve_gravity(gravity)
+        )
     else:
         raise ValueError("Specify aspect_ratio or size")
     fill_color = _resolve_fill(fill)
@@ -442,10 +902,31 @@ def resize_image(
     exact: Optional[Tuple[int, int]] = None,
     fit: Optional[Tuple[int, int]] = None,
     scale: Optional[float] = None,
+    cover: Optional[Tuple[int, int]] = None,
+    contain: Optional[Tuple[int, int]] = None,
+    max_pixels: Optional[int] = None,
+    fill=None,
+    filter: Optional[str] = None,
+    without_enlargement: bool = False,
+    linear_light: Optional[str] = None,
+    premultiply_alpha: bool = True,
 ) -> Image:
     """Resize *image*.
 
     Provide exactly one keyword argument to specify the resize mode.
+    *cover* fills the size and crops the overflow; *contain* fits within
+    it and pads with *fill* (transparent by default); *max_pixels* shrinks
+    to at most that many pixels.
+
+    *filter* is ``'nearest'``, ``'bilinear'``, ``'catmull-rom'``,
+    ``'mitchell'`` or ``'lanczos3'`` (the default). With
+    *without_enlargement*, an image smaller than the target keeps its size.
+
+    *linear_light* is ``'auto'`` (the default), which filters downscales
+    in linear light so fine detail keeps its brightness, ``'always'`` or
+    ``'never'``. Alpha is premultiplied while filtering so transparent
+    pixels do not darken cut-out edges; pass ``premultiply_alpha=False``
+    to filter it like any other channel.
     """
     modes = [
         (k, v)
@@ -455,11 +936,17 @@ def resize_image(
             ("exact", exact),
             ("fit", fit),
             ("scale", scale),
+            ("cover", cover),
+            ("contain", contain),
+            ("max_pixels", max_pixels),
         ]
         if v is not None
     ]
     if len(modes) != 1:
-        raise ValueError("Specify exactly one of: width, height, exact, fit, scale")
+        raise ValueError(
+            "Specify exactly one of: width, height, exact, fit, scale, "
+            "cover, contain, max_pixels"
+        )
 
     name, value = modes[0]
     if name == "width":
@@ -472,8 +959,21 @@ def resize_image(
         resize_mode = _lowlevel.ResizeMode.FIT(max_width=value[0], max_height=value[1])
     elif name == "scale":
         resize_mode = _lowlevel.ResizeMode.SCALE(factor=value)
-
-    result = _lowlevel.resize(image._to_lowlevel(), resize_mode)
+    elif name == "cover":
+        resize_mode = Resize.cover(*value)
+    elif name == "contain":
+        resize_mode = Resize.contain(*value, fill=fill)
+    elif name == "max_pixels":
+        resize_mode = Resize.max_pixels(value)
+
+    result = _lowlevel.resize(
+        image._to_lowlevel(),
+        resize_mode,
+        _resolve_filter(filter),
+        without_enlargement,
+        _resolve_linear_light(linear_light),
+        premultiply_alpha,
+    )
     return Image._from_lowlevel(result, image.format)
 
 
@@ -0,0 +1,65 @@
+import pytest
+import slimg
+from conftest import create_test_image
+
+
+def two_frame_animation() -> slimg.Animation:
+    frames = [
+        slimg.Frame(create_test_image(10, 8), 120),
+        slimg.Frame(create_test_image(10, 8), 80, disposal="background"),
+    ]
+    return slimg.Animation(frames, loop_count=2)
+
+
+class TestAnimation:
+    def test_png_roundtrip_keeps_frames(self):
+        result = slimg.convert(two_frame_animation(), format="png")
+        animation = slimg.decode_animation(result.data)
+        assert animation.format == slimg.Format.PNG
+        assert animation.is_animated
+        assert animation.loop_count == 2
+        assert [f.duration_ms for f in animation.frames] == [120, 80]
+
+    def test_gif_roundtrip_keeps_frames(self):
+        result = slimg.convert(two_frame_animation(), format="gif")
+        animation = slimg.decode_animation(result.data)
+        assert animation.format == slimg.Format.GIF
+        assert [f.duration_ms for f in animation.frames] == [120, 80]
+
+    def test_resize_applies_to_every_frame(self):
+        result = slimg.convert(
+            two_frame_animation(),
+            format="webp",
+            lossless=True,
+            resize=slimg.Resize.width(5),
+        )
+        animation = slimg.decode_animation(result.data)
+        assert len(animation.frames) == 2
+        for frame in animation.frames:
+            assert (frame.image.width, frame.image.height) == (5, 4)
+
+    def test_still_image_is_one_frame(self, sample_image):
+        result = slimg.convert(sample_image, format="png")
+        animation = slimg.decode_animation(result.data)
+        assert not animation.is_animated
+        assert (animation.width, animation.height) == (10, 8)
+
+    def test_open_animation(self, tmp_path):
+        path = str(tmp_path / "anim.png")
+        slimg.convert(two_frame_animation(), format="png").save(path)
+        animation = slimg.open_animation(path)
+        assert len(animation.frames) == 2
+
+    def test_still_only_format_raises(self):
+        with pytest.raises(slimg.SlimgError):
+            slimg.convert(two_frame_animation(), format="jpeg")
+
+
+class TestAnimationValidation:
+    def test_empty_frames(self):
+        with pytest.raises(ValueError, match="at least one frame"):
+            slimg.Animation([])
+
+    def test_unknown_disposal(self, sample_image):
+        with pytest.raises(ValueError, match="disposal"):
+            slimg.Frame(sample_image, 100, disposal="clear")
@@ -28,6 +28,18 @@ class TestConvert:
         assert result.data[0] == 0xFF
         assert result.data[1] == 0xD8
 
+    def test_to_gif(self, sample_image):
+        result = slimg.convert(sample_image, format="gif", quality=80)
+        assert result.format == slimg.Format.GIF
+        assert result.data[:6] == b"GIF89a"
+
+    def test_tiff_roundtrip_is_lossless(self, sample_image):
+        result = slimg.convert(sample_image, format="tiff")
+        assert result.format == slimg.Format.TIFF
+        decoded = slimg.decode(result.data)
+        assert decoded.format == slimg.Format.TIFF
+        assert decoded.data == sample_image.data
+
     def test_with_resize(self, sample_image):
         result = slimg.convert(
             sample_image,
@@ -64,6 +76,21 @@ class TestConvert:
         decoded = slimg.decode(result.data)
         assert decoded.width == decoded.height  # square after extend
 
+    def test_metadata_policy_accepted(self, sample_image):
+        for policy in ("keep", "strip", "safe"):
+            result = slimg.convert(sample_image, format="png", metadata=policy)
+            assert result.format == slimg.Format.PNG
+
+    def test_color_policy_accepted(self, sample_image):
+        for policy in ("preserve", "srgb"):
+            result = slimg.convert(sample_image, format="jpeg", color=policy)
+            assert result.format == slimg.Format.JPEG
+
+    def test_tone_map_accepted(self, sample_image):
+        for operator in ("clip", "reinhard", "hable"):
+            result = slimg.convert(sample_image, format="png", tone_map=operator)
+            assert result.format == slimg.Format.PNG
+
 
 class TestConvertValidation:
     def test_quality_too_high(self, sample_image):
@@ -80,7 +107,19 @@ class TestConvertValidation:
 
     def test_unknown_format_string(self, sample_image):
         with pytest.raises(ValueError, match="Unknown format"):
-            slimg.convert(sample_image, format="bmp", quality=80)
+            slimg.convert(sample_image, format="psd", quality=80)
+
+    def test_unknown_metadata_policy(self, sample_image):
+        with pytest.raises(ValueError, match="metadata policy"):
+            slimg.convert(sample_image, format="png", metadata="all")
+
+    def test_unknown_color_policy(self, sample_image):
+        with pytest.raises(ValueError, match="color policy"):
+            slimg.convert(sample_image, format="png", color="p3")
+
+    def test_unknown_tone_map(self, sample_image):
+        with pytest.raises(ValueError, match="tone map"):
+            slimg.convert(sample_image, format="png", tone_map="aces")
 
 
 class TestResultSave:
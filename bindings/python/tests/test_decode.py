@@ -69,3 +69,16 @@ class TestImage:
     def test_empty_buffer_raises(self):
         with pytest.raises(ValueError, match="Buffer size mismatch"):
             slimg.Image(width=1, height=1, data=b"")
+
+    def test_sixteen_bit_image_roundtrips_through_png(self):
+        data = bytes(range(4 * 2 * 8))  # 8 bytes per pixel
+        img = slimg.Image(4, 2, data, pixel_format="rgba16")
+        assert img.pixel_format == "rgba16"
+
+        decoded = slimg.decode(slimg.convert(img, format="png").data)
+        assert decoded.pixel_format == "rgba16"
+        assert decoded.data == data
+
+    def test_pixel_format_checks_buffer_size(self):
+        with pytest.raises(ValueError, match="RGBA16"):
+            slimg.Image(2, 2, bytes(16), pixel_format="rgba16")
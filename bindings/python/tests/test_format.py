@@ -21,6 +21,12 @@ class TestFormatExtension:
     def test_qoi(self):
         assert slimg.Format.QOI.extension == "qoi"
 
+    def test_gif(self):
+        assert slimg.Format.GIF.extension == "gif"
+
+    def test_tiff(self):
+        assert slimg.Format.TIFF.extension == "tiff"
+
 
 class TestFormatCanEncode:
     def test_jpeg_can_encode(self):
@@ -52,8 +58,18 @@ class TestFormatFromPath:
     def test_avif(self):
         assert slimg.Format.from_path("image.avif") == slimg.Format.AVIF
 
+    def test_gif(self):
+        assert slimg.Format.from_path("anim.gif") == slimg.Format.GIF
+
+    def test_design_exports(self):
+        assert slimg.Format.from_path("scan.tif") == slimg.Format.TIFF
+        assert slimg.Format.from_path("export.bmp") == slimg.Format.BMP
+        assert slimg.Format.from_path("texture.tga") == slimg.Format.TGA
+        assert slimg.Format.from_path("pointer.cur") == slimg.Format.ICO
+        assert slimg.Format.from_path("frame.pgm") == slimg.Format.PNM
+
     def test_unknown_returns_none(self):
-        assert slimg.Format.from_path("file.bmp") is None
+        assert slimg.Format.from_path("file.psd") is None
 
     def test_no_extension_returns_none(self):
         assert slimg.Format.from_path("noext") is None
@@ -68,6 +84,15 @@ class TestFormatFromBytes:
         header = bytes([0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A])
         assert slimg.Format.from_bytes(header) == slimg.Format.PNG
 
+    def test_gif_magic(self):
+        assert slimg.Format.from_bytes(b"GIF89a") == slimg.Format.GIF
+
+    def test_tiff_magic(self):
+        assert slimg.Format.from_bytes(b"II*\x00\x08\x00\x00\x00") == slimg.Format.TIFF
+
+    def test_bmp_magic(self):
+        assert slimg.Format.from_bytes(b"BM" + bytes(12)) == slimg.Format.BMP
+
     def test_unknown_returns_none(self):
         assert slimg.Format.from_bytes(bytes([0x00, 0x00, 0x00, 0x00])) is None
 
@@ -82,12 +107,15 @@ class TestFormatResolve:
     def test_string_jpg_alias(self):
         assert slimg.Format._resolve("jpg") == slimg.Format.JPEG
 
+    def test_string_tif_alias(self):
+        assert slimg.Format._resolve("tif") == slimg.Format.TIFF
+
     def test_enum_passthrough(self):
         assert slimg.Format._resolve(slimg.Format.WEBP) == slimg.Format.WEBP
 
     def test_unknown_string_raises(self):
         with pytest.raises(ValueError, match="Unknown format"):
-            slimg.Format._resolve("bmp")
+            slimg.Format._resolve("psd")
 
     def test_wrong_type_raises(self):
         with pytest.raises(TypeError):
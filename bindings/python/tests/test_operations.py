@@ -36,6 +36,27 @@ class TestCrop:
         with pytest.raises(ValueError, match="region or aspect_ratio"):
             slimg.crop(sample_image)
 
+    def test_smart_takes_the_reported_region(self, sample_image):
+        x, y, w, h = slimg.crop_region(sample_image, aspect_ratio=(1, 1), smart=True)
+        assert (w, h) == (8, 8)
+        smart = slimg.crop(sample_image, aspect_ratio=(1, 1), smart=True)
+        assert smart.data == slimg.crop(sample_image, region=(x, y, w, h)).data
+
+    def test_gravity_keeps_the_named_edge(self, sample_image):
+        cropped = slimg.crop(sample_image, aspect_ratio=(1, 1), gravity="east")
+        assert pixel_at(cropped, 0, 0) == (0, 2, 0xFF, 0xFF)
+        assert slimg.crop_region(
+            sample_image, aspect_ratio=(1, 1), gravity=(0.0, 0.5)
+        ) == (0, 0, 8, 8)
+
+    def test_invalid_gravity_raises(self, sample_image):
+        with pytest.raises(ValueError):
+            slimg.crop(sample_image, aspect_ratio=(1, 1), gravity="up")
+
+    def test_smart_without_aspect_ratio_raises(self, sample_image):
+        with pytest.raises(ValueError, match="smart needs aspect_ratio"):
+            slimg.crop(sample_image, region=(0, 0, 5, 4), smart=True)
+
 
 class TestExtend:
     def test_aspect_ratio_square_from_landscape(self, sample_image):
@@ -73,6 +94,12 @@ class TestExtend:
         extended = slimg.extend(img, aspect_ratio=(1, 1), fill="transparent")
         assert extended.data == img.data
 
+    def test_gravity_places_the_image(self):
+        img = create_test_image(4, 4)
+        extended = slimg.extend(img, size=(6, 6), fill="transparent", gravity="northwest")
+        assert pixel_at(extended, 0, 0) == (0, 0, 0xFF, 0xFF)
+        assert pixel_at(extended, 5, 5) == (0, 0, 0, 0)
+
     def test_smaller_size_raises(self, sample_image):
         with pytest.raises(slimg.SlimgError):
             slimg.extend(sample_image, size=(5, 8), fill="transparent")
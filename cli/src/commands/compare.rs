@@ -0,0 +1,135 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::{Args, ValueEnum};
+use slimg_core::metrics::{self, Scores};
+use slimg_core::{DecodeOptions, Format, PipelineOptions, convert, decode_file_with};
+
+use super::safe_write;
+
+#[derive(Debug, Args)]
+pub struct CompareArgs {
+    /// Reference image, usually the original
+    pub reference: PathBuf,
+
+    /// Image to score against the reference
+    pub distorted: PathBuf,
+
+    /// Write an image highlighting the differing pixels to this path
+    #[arg(long)]
+    pub heatmap: Option<PathBuf>,
+
+    /// Fail when --metric is worse than this value
+    #[arg(long)]
+    pub threshold: Option<f64>,
+
+    /// Metric checked against --threshold
+    #[arg(long, value_enum, default_value_t = MetricArg::Dssim, requires = "threshold")]
+    pub metric: MetricArg,
+
+    /// Keep pixels as stored instead of applying the EXIF orientation
+    #[arg(long)]
+    pub no_auto_orient: bool,
+}
+
+/// Metric argument for `--metric`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MetricArg {
+    /// Peak signal-to-noise ratio in dB (higher is better)
+    Psnr,
+    /// Structural similarity (1 is identical)
+    Ssim,
+    /// DSSIM (0 is identical)
+    Dssim,
+    /// SSIMULACRA2 (100 is identical)
+    Ssimulacra2,
+    /// Largest per-channel difference, 0-255
+    MaxError,
+}
+
+impl MetricArg {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Psnr => "PSNR",
+            Self::Ssim => "SSIM",
+            Self::Dssim => "DSSIM",
+            Self::Ssimulacra2 => "SSIMULACRA2",
+            Self::MaxError => "max error",
+        }
+    }
+
+    fn value(self, scores: &Scores) -> f64 {
+        match self {
+            Self::Psnr => scores.psnr,
+            Self::Ssim => scores.ssim,
+            Self::Dssim => scores.dssim,
+            Self::Ssimulacra2 => scores.ssimulacra2,
+            Self::MaxError => f64::from(scores.max_error),
+        }
+    }
+
+    /// Whether `value` is worse than `threshold` for this metric.
+    fn fails(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Self::Psnr | Self::Ssim | Self::Ssimulacra2 => value < threshold,
+            Self::Dssim | Self::MaxError => value > threshold,
+        }
+    }
+}
+
+pub fn run(args: CompareArgs) -> anyhow::Result<()> {
+    let decode_options = DecodeOptions {
+        auto_orient: !args.no_auto_orient,
+        ..Default::default()
+    };
+    let (reference, _) = decode_file_with(&args.reference, &decode_options)
+        .with_context(|| format!("{}", args.reference.display()))?;
+    let (distorted, _) = decode_file_with(&args.distorted, &decode_options)
+        .with_context(|| format!("{}", args.distorted.display()))?;
+
+    let scores = metrics::compare(&reference, &distorted)?;
+    println!("PSNR         {:.2} dB", scores.psnr);
+    println!("SSIM         {:.5}", scores.ssim);
+    println!("DSSIM        {:.5}", scores.dssim);
+    println!("SSIMULACRA2  {:.2}", scores.ssimulacra2);
+    println!("Max error    {}", scores.max_error);
+
+    if let Some(path) = &args.heatmap {
+        let format = Format::from_extension(path)
+            .with_context(|| format!("cannot tell the heatmap format from {}", path.display()))?;
+        let image = metrics::heatmap(&reference, &distorted)?;
+        let options = PipelineOptions {
+            lossless: format.supports_lossless(),
+            ..PipelineOptions::new(format)
+        };
+        let result = convert(&image, &options)?;
+        safe_write(path, &result.data, true)?;
+        println!("Heatmap      {}", path.display());
+    }
+
+    if let Some(threshold) = args.threshold {
+        let value = args.metric.value(&scores);
+        if args.metric.fails(value, threshold) {
+            anyhow::bail!(
+                "{} {value} is worse than the threshold {threshold}",
+                args.metric.name()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn threshold_direction_follows_the_metric() {
+        assert!(MetricArg::Dssim.fails(0.01, 0.001));
+        assert!(!MetricArg::Dssim.fails(0.0005, 0.001));
+        assert!(MetricArg::Ssimulacra2.fails(70.0, 90.0));
+        assert!(!MetricArg::Psnr.fails(45.0, 40.0));
+        assert!(!MetricArg::MaxError.fails(3.0, 3.0));
+    }
+}
@@ -1,12 +1,17 @@
 use std::path::PathBuf;
 
 use anyhow::Context;
-use clap::Args;
+use clap::{Args, ValueEnum};
 use rayon::prelude::*;
-use slimg_core::{PipelineOptions, convert, decode_file, output_path};
+use slimg_core::{
+    AutoFormat, DecodeOptions, Format, PipelineOptions, PipelineResult, SizeLimit,
+    convert_animation, decode_animation_with, never_larger, output_path, transcode_jpeg,
+};
 
 use super::{
-    ErrorCollector, FormatArg, collect_files, configure_thread_pool, make_progress_bar, safe_write,
+    CodecArgs, ColorArg, ErrorCollector, FormatArg, MetadataArg, ToneMapArg, collect_files,
+    configure_thread_pool, make_progress_bar, parse_byte_size, quality_target, safe_write,
+    search_note,
 };
 
 #[derive(Debug, Args)]
@@ -14,14 +19,66 @@ pub struct ConvertArgs {
     /// Input file or directory
     pub input: PathBuf,
 
-    /// Output format
-    #[arg(short, long)]
-    pub format: FormatArg,
+    /// Output format, or `auto` to keep the smallest of --candidates
+    #[arg(short, long, value_parser = parse_target)]
+    pub format: TargetArg,
+
+    /// Formats `--format auto` chooses between (default: webp,avif,jxl)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub candidates: Vec<FormatArg>,
 
     /// Encoding quality (0-100)
     #[arg(short, long, default_value_t = 80)]
     pub quality: u8,
 
+    /// Encode losslessly (PNG, WebP, JXL, QOI only)
+    #[arg(long)]
+    pub lossless: bool,
+
+    /// Recompress JPEGs into reversible JXL, or restore them with `--format jpeg`
+    #[arg(long, conflicts_with_all = ["lossless", "target_ssimulacra2", "target_dssim"])]
+    pub lossless_jpeg: bool,
+
+    /// Largest output size (e.g. 150KB, 1.5MB); the quality is lowered to fit
+    #[arg(long, value_parser = parse_byte_size, conflicts_with = "lossless_jpeg")]
+    pub max_size: Option<u64>,
+
+    /// Shrink images that exceed --max-size even at the lowest quality
+    #[arg(long, requires = "max_size")]
+    pub downscale_to_fit: bool,
+
+    /// Pick the lowest quality scoring at least this SSIMULACRA2 (90 is visually lossless)
+    #[arg(long, conflicts_with_all = ["lossless", "target_dssim"])]
+    pub target_ssimulacra2: Option<f64>,
+
+    /// Pick the lowest quality scoring at most this DSSIM (0 is identical)
+    #[arg(long, conflicts_with = "lossless")]
+    pub target_dssim: Option<f64>,
+
+    /// Metadata to keep in the output
+    #[arg(long, value_enum, default_value_t = MetadataArg::Safe)]
+    pub metadata: MetadataArg,
+
+    /// How to handle the source ICC colour profile
+    #[arg(long, value_enum, default_value_t = ColorArg::Preserve)]
+    pub color_profile: ColorArg,
+
+    /// Tone-mapping operator for HDR sources written to SDR formats
+    #[arg(long, value_enum, default_value_t = ToneMapArg::Reinhard)]
+    pub tone_map: ToneMapArg,
+
+    /// Keep pixels as stored instead of applying the EXIF orientation
+    #[arg(long)]
+    pub no_auto_orient: bool,
+
+    /// Skip files whose converted output would be no smaller than the input,
+    /// unless the input holds metadata that --metadata drops
+    #[arg(long)]
+    pub never_larger: bool,
+
+    #[command(flatten)]
+    pub codec: CodecArgs,
+
     /// Output path (file or directory)
     #[arg(short, long)]
     pub output: Option<PathBuf>,
@@ -35,8 +92,63 @@ pub struct ConvertArgs {
     pub jobs: Option<usize>,
 }
 
+/// Value of `--format`: a fixed format, or `auto`.
+#[derive(Debug, Clone, Copy)]
+pub enum TargetArg {
+    Auto,
+    Format(FormatArg),
+}
+
+fn parse_target(s: &str) -> Result<TargetArg, String> {
+    if s.trim().eq_ignore_ascii_case("auto") {
+        return Ok(TargetArg::Auto);
+    }
+    FormatArg::from_str(s.trim(), true)
+        .map(TargetArg::Format)
+        .map_err(|_| {
+            let names: Vec<_> = FormatArg::value_variants()
+                .iter()
+                .filter_map(|v| v.to_possible_value())
+                .map(|v| v.get_name().to_string())
+                .collect();
+            format!("unknown format '{s}' (expected auto, {})", names.join(", "))
+        })
+}
+
+/// Extra summary lines naming each losing `--format auto` candidate.
+fn rejection_lines(result: &PipelineResult) -> String {
+    result
+        .rejected
+        .iter()
+        .map(|r| format!("\n  {}: {}", r.format.extension(), r.reason))
+        .collect()
+}
+
 pub fn run(args: ConvertArgs) -> anyhow::Result<()> {
-    let target_format = args.format.into_format();
+    let (target_format, auto_format) = match args.format {
+        TargetArg::Format(format) if args.candidates.is_empty() => (format.into_format(), None),
+        TargetArg::Format(_) => anyhow::bail!("--candidates needs --format auto"),
+        TargetArg::Auto => {
+            let auto = if args.candidates.is_empty() {
+                AutoFormat::default()
+            } else {
+                AutoFormat {
+                    candidates: args.candidates.iter().map(|f| f.into_format()).collect(),
+                }
+            };
+            (auto.candidates[0], Some(auto))
+        }
+    };
+
+    if args.lossless && auto_format.is_none() && !target_format.supports_lossless() {
+        anyhow::bail!("cannot encode {} losslessly", target_format.extension());
+    }
+    if args.lossless_jpeg
+        && (auto_format.is_some() || !matches!(target_format, Format::Jxl | Format::Jpeg))
+    {
+        anyhow::bail!("--lossless-jpeg needs --format jxl or --format jpeg");
+    }
+
     let files = collect_files(&args.input, args.recursive)?;
 
     if files.is_empty() {
@@ -46,12 +158,24 @@ pub fn run(args: ConvertArgs) -> anyhow::Result<()> {
     configure_thread_pool(args.jobs)?;
 
     let options = PipelineOptions {
-        format: target_format,
         quality: args.quality,
-        resize: None,
-        crop: None,
-        extend: None,
-        fill_color: None,
+        lossless: args.lossless,
+        metadata: args.metadata.into_policy(),
+        color: args.color_profile.into_policy(),
+        tone_map: args.tone_map.into_operator(),
+        codec: args.codec.to_options(),
+        max_size: args.max_size.map(|max_bytes| SizeLimit {
+            max_bytes,
+            downscale: args.downscale_to_fit,
+        }),
+        quality_target: quality_target(args.target_ssimulacra2, args.target_dssim),
+        auto_format,
+        ..PipelineOptions::new(target_format)
+    };
+
+    let decode_options = DecodeOptions {
+        auto_orient: !args.no_auto_orient,
+        ..Default::default()
     };
 
     let pb = make_progress_bar(files.len());
@@ -59,13 +183,32 @@ pub fn run(args: ConvertArgs) -> anyhow::Result<()> {
 
     files.par_iter().for_each(|file| {
         let result: anyhow::Result<()> = (|| {
-            let original_size = std::fs::metadata(file)?.len();
-            let (image, _src_format) =
-                decode_file(file).with_context(|| format!("{}", file.display()))?;
-            let result =
-                convert(&image, &options).with_context(|| format!("{}", file.display()))?;
+            let data = std::fs::read(file)?;
+            let original_size = data.len() as u64;
+            let result = if args.lossless_jpeg {
+                transcode_jpeg(&data, target_format, &options.codec)
+            } else {
+                decode_animation_with(&data, &decode_options)
+                    .and_then(|(animation, _)| convert_animation(&animation, &options))
+            }
+            .and_then(|result| {
+                if args.never_larger {
+                    never_larger(&data, result, options.metadata)
+                } else {
+                    Ok(result)
+                }
+            })
+            .with_context(|| format!("{}", file.display()))?;
+            if result.kept_original {
+                pb.println(format!("{} -> skipped (would grow)", file.display()));
+                return Ok(());
+            }
 
-            let out = output_path(file, target_format, args.output.as_deref());
+            let mut out = output_path(file, result.format, args.output.as_deref());
+            if options.auto_format.is_some() {
+                // An explicit output file takes the winner's extension too.
+                out.set_extension(result.format.extension());
+            }
             safe_write(&out, &result.data, false)?;
 
             let new_size = result.data.len() as u64;
@@ -76,12 +219,14 @@ pub fn run(args: ConvertArgs) -> anyhow::Result<()> {
             };
 
             pb.println(format!(
-                "{} -> {} ({} -> {} bytes, {:.1}%)",
+                "{} -> {} ({} -> {} bytes, {:.1}%{}){}",
                 file.display(),
                 out.display(),
                 original_size,
                 new_size,
                 ratio,
+                search_note(&result, args.max_size, options.quality_target),
+                rejection_lines(&result),
             ));
 
             Ok(())
@@ -102,3 +247,23 @@ pub fn run(args: ConvertArgs) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_target_accepts_auto_and_formats() {
+        assert!(matches!(parse_target("auto"), Ok(TargetArg::Auto)));
+        assert!(matches!(
+            parse_target("WebP"),
+            Ok(TargetArg::Format(FormatArg::Webp))
+        ));
+    }
+
+    #[test]
+    fn parse_target_lists_choices_on_error() {
+        let err = parse_target("bogus").unwrap_err();
+        assert!(err.contains("auto, jpeg, png"), "{err}");
+    }
+}
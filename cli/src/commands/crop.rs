@@ -3,10 +3,14 @@ use std::path::PathBuf;
 use anyhow::Context;
 use clap::Args;
 use rayon::prelude::*;
-use slimg_core::{CropMode, PipelineOptions, convert, decode_file, output_path};
+use slimg_core::{
+    CropMode, DecodeOptions, Gravity, PipelineOptions, convert_animation, crop,
+    decode_animation_file_with, output_path,
+};
 
 use super::{
-    ErrorCollector, FormatArg, collect_files, configure_thread_pool, make_progress_bar, safe_write,
+    CodecArgs, ColorArg, ErrorCollector, FormatArg, MetadataArg, ToneMapArg, collect_files,
+    configure_thread_pool, make_progress_bar, parse_gravity, safe_write,
 };
 
 #[derive(Debug, Args)]
@@ -22,6 +26,23 @@ pub struct CropArgs {
     #[arg(long, value_parser = parse_aspect, conflicts_with = "region")]
     pub aspect: Option<(u32, u32)>,
 
+    /// Place the --aspect window over the most detailed part of the image
+    /// instead of the centre
+    #[arg(long, requires = "aspect", conflicts_with = "region")]
+    pub smart: bool,
+
+    /// Part of the image the --aspect window keeps: center, north, northeast,
+    /// east, southeast, south, southwest, west, northwest, or a focal point
+    /// x,y as fractions of the image (e.g. 0.3,0.25)
+    #[arg(
+        long,
+        value_parser = parse_gravity,
+        default_value = "center",
+        requires = "aspect",
+        conflicts_with_all = ["region", "smart"]
+    )]
+    pub gravity: Gravity,
+
     /// Output format (defaults to input format)
     #[arg(short, long)]
     pub format: Option<FormatArg>,
@@ -30,6 +51,29 @@ pub struct CropArgs {
     #[arg(short, long, default_value_t = 80)]
     pub quality: u8,
 
+    /// Encode losslessly (PNG, WebP, JXL, QOI only)
+    #[arg(long)]
+    pub lossless: bool,
+
+    /// Metadata to keep in the output
+    #[arg(long, value_enum, default_value_t = MetadataArg::Safe)]
+    pub metadata: MetadataArg,
+
+    /// How to handle the source ICC colour profile
+    #[arg(long, value_enum, default_value_t = ColorArg::Preserve)]
+    pub color_profile: ColorArg,
+
+    /// Tone-mapping operator for HDR sources written to SDR formats
+    #[arg(long, value_enum, default_value_t = ToneMapArg::Reinhard)]
+    pub tone_map: ToneMapArg,
+
+    /// Keep pixels as stored instead of applying the EXIF orientation
+    #[arg(long)]
+    pub no_auto_orient: bool,
+
+    #[command(flatten)]
+    pub codec: CodecArgs,
+
     /// Output path (file or directory)
     #[arg(short, long)]
     pub output: Option<PathBuf>,
@@ -91,7 +135,15 @@ fn build_crop_mode(args: &CropArgs) -> anyhow::Result<CropMode> {
             width: w,
             height: h,
         }),
-        (None, Some((w, h))) => Ok(CropMode::AspectRatio { width: w, height: h }),
+        (None, Some((w, h))) if args.smart => Ok(CropMode::Smart {
+            width: w,
+            height: h,
+        }),
+        (None, Some((w, h))) => Ok(CropMode::AspectRatio {
+            width: w,
+            height: h,
+            gravity: args.gravity,
+        }),
         _ => anyhow::bail!("specify exactly one of --region or --aspect"),
     }
 }
@@ -106,14 +158,19 @@ pub fn run(args: CropArgs) -> anyhow::Result<()> {
 
     configure_thread_pool(args.jobs)?;
 
+    let decode_options = DecodeOptions {
+        auto_orient: !args.no_auto_orient,
+        ..Default::default()
+    };
+
     let pb = make_progress_bar(files.len());
     let errors = ErrorCollector::new();
 
     files.par_iter().for_each(|file| {
         let result: anyhow::Result<()> = (|| {
             let original_size = std::fs::metadata(file)?.len();
-            let (image, src_format) =
-                decode_file(file).with_context(|| format!("{}", file.display()))?;
+            let (animation, src_format) = decode_animation_file_with(file, &decode_options)
+                .with_context(|| format!("{}", file.display()))?;
 
             let target_format = args.format.map(|f| f.into_format()).unwrap_or(src_format);
 
@@ -121,17 +178,36 @@ pub fn run(args: CropArgs) -> anyhow::Result<()> {
                 anyhow::bail!("cannot encode to {} format", target_format.extension());
             }
 
+            // Resolve a smart crop here so the chosen region can be reported.
+            let (mode, placed) = match &crop_mode {
+                CropMode::Smart { .. } => {
+                    let (x, y, width, height) =
+                        crop::crop_region(&animation.frames[0].image, &crop_mode)
+                            .with_context(|| format!("{}", file.display()))?;
+                    let region = CropMode::Region {
+                        x,
+                        y,
+                        width,
+                        height,
+                    };
+                    (region, format!(" [{x},{y},{width},{height}]"))
+                }
+                mode => (mode.clone(), String::new()),
+            };
+
             let options = PipelineOptions {
-                format: target_format,
                 quality: args.quality,
-                resize: None,
-                crop: Some(crop_mode.clone()),
-                extend: None,
-                fill_color: None,
+                lossless: args.lossless,
+                metadata: args.metadata.into_policy(),
+                color: args.color_profile.into_policy(),
+                tone_map: args.tone_map.into_operator(),
+                crop: Some(mode),
+                codec: args.codec.to_options(),
+                ..PipelineOptions::new(target_format)
             };
 
-            let result =
-                convert(&image, &options).with_context(|| format!("{}", file.display()))?;
+            let result = convert_animation(&animation, &options)
+                .with_context(|| format!("{}", file.display()))?;
 
             let out = output_path(file, target_format, args.output.as_deref());
             safe_write(&out, &result.data, args.overwrite)?;
@@ -144,7 +220,7 @@ pub fn run(args: CropArgs) -> anyhow::Result<()> {
             };
 
             pb.println(format!(
-                "{} -> {} ({} -> {} bytes, {:.1}%)",
+                "{}{placed} -> {} ({} -> {} bytes, {:.1}%)",
                 file.display(),
                 out.display(),
                 original_size,
@@ -174,6 +250,7 @@ pub fn run(args: CropArgs) -> anyhow::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use clap::Parser;
 
     #[test]
     fn parse_region_valid() {
@@ -214,4 +291,54 @@ mod tests {
     fn parse_aspect_zero() {
         assert!(parse_aspect("0:9").is_err());
     }
+
+    #[derive(Parser)]
+    struct CropCli {
+        #[command(flatten)]
+        args: CropArgs,
+    }
+
+    fn parse_mode(args: &[&str]) -> anyhow::Result<CropMode> {
+        let argv = ["slimg", "photo.jpg"]
+            .into_iter()
+            .chain(args.iter().copied());
+        let cli = CropCli::try_parse_from(argv)?;
+        build_crop_mode(&cli.args)
+    }
+
+    #[test]
+    fn smart_needs_an_aspect() {
+        assert_eq!(
+            parse_mode(&["--aspect", "1:1", "--smart"]).unwrap(),
+            CropMode::Smart {
+                width: 1,
+                height: 1
+            }
+        );
+        assert!(parse_mode(&["--smart"]).is_err());
+        assert!(parse_mode(&["--region", "0,0,1,1", "--smart"]).is_err());
+    }
+
+    #[test]
+    fn gravity_places_the_aspect_window() {
+        assert_eq!(
+            parse_mode(&["--aspect", "1:1", "--gravity", "north"]).unwrap(),
+            CropMode::AspectRatio {
+                width: 1,
+                height: 1,
+                gravity: Gravity::North
+            }
+        );
+        assert_eq!(
+            parse_mode(&["--aspect", "1:1"]).unwrap(),
+            CropMode::AspectRatio {
+                width: 1,
+                height: 1,
+                gravity: Gravity::Center
+            }
+        );
+        assert!(parse_mode(&["--gravity", "north"]).is_err());
+        assert!(parse_mode(&["--region", "0,0,1,1", "--gravity", "north"]).is_err());
+        assert!(parse_mode(&["--aspect", "1:1", "--smart", "--gravity", "north"]).is_err());
+    }
 }
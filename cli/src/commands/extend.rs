@@ -3,11 +3,14 @@ use std::path::PathBuf;
 use anyhow::Context;
 use clap::Args;
 use rayon::prelude::*;
-use slimg_core::{ExtendMode, FillColor, PipelineOptions, convert, decode_file, output_path};
+use slimg_core::{
+    DecodeOptions, ExtendMode, FillColor, Gravity, PipelineOptions, convert_animation,
+    decode_animation_file_with, output_path,
+};
 
 use super::{
-    ErrorCollector, FormatArg, collect_files, configure_thread_pool, make_progress_bar,
-    parse_size, safe_write,
+    CodecArgs, ColorArg, ErrorCollector, FormatArg, MetadataArg, ToneMapArg, collect_files,
+    configure_thread_pool, make_progress_bar, parse_gravity, parse_size, safe_write,
 };
 
 #[derive(Debug, Args)]
@@ -24,6 +27,12 @@ pub struct ExtendArgs {
     #[arg(long, value_parser = parse_size, conflicts_with = "aspect")]
     pub size: Option<(u32, u32)>,
 
+    /// Where to place the image on the canvas: center, north, northeast,
+    /// east, southeast, south, southwest, west, northwest, or a focal point
+    /// x,y as fractions of the image to put in the middle (e.g. 0.3,0.25)
+    #[arg(long, value_parser = parse_gravity, default_value = "center")]
+    pub gravity: Gravity,
+
     /// Fill color as hex (e.g. '#FFFFFF', '000000'). Default: white.
     #[arg(long, conflicts_with = "transparent")]
     pub color: Option<String>,
@@ -40,6 +49,29 @@ pub struct ExtendArgs {
     #[arg(short, long, default_value_t = 80)]
     pub quality: u8,
 
+    /// Encode losslessly (PNG, WebP, JXL, QOI only)
+    #[arg(long)]
+    pub lossless: bool,
+
+    /// Metadata to keep in the output
+    #[arg(long, value_enum, default_value_t = MetadataArg::Safe)]
+    pub metadata: MetadataArg,
+
+    /// How to handle the source ICC colour profile
+    #[arg(long, value_enum, default_value_t = ColorArg::Preserve)]
+    pub color_profile: ColorArg,
+
+    /// Tone-mapping operator for HDR sources written to SDR formats
+    #[arg(long, value_enum, default_value_t = ToneMapArg::Reinhard)]
+    pub tone_map: ToneMapArg,
+
+    /// Keep pixels as stored instead of applying the EXIF orientation
+    #[arg(long)]
+    pub no_auto_orient: bool,
+
+    #[command(flatten)]
+    pub codec: CodecArgs,
+
     /// Output path (file or directory)
     #[arg(short, long)]
     pub output: Option<PathBuf>,
@@ -57,7 +89,7 @@ pub struct ExtendArgs {
     pub overwrite: bool,
 }
 
-fn parse_hex_color(s: &str) -> anyhow::Result<[u8; 4]> {
+pub(crate) fn parse_hex_color(s: &str) -> anyhow::Result<[u8; 4]> {
     let s = s.trim_start_matches('#');
     if s.len() != 6 && s.len() != 8 {
         anyhow::bail!("expected 6 or 8 hex digits (e.g. 'FF0000' or 'FF0000FF')");
@@ -75,14 +107,26 @@ fn parse_hex_color(s: &str) -> anyhow::Result<[u8; 4]> {
 
 fn build_extend_mode(args: &ExtendArgs) -> anyhow::Result<ExtendMode> {
     match (args.aspect, args.size) {
-        (Some((w, h)), None) => Ok(ExtendMode::AspectRatio { width: w, height: h }),
-        (None, Some((w, h))) => Ok(ExtendMode::Size { width: w, height: h }),
+        (Some((w, h)), None) => Ok(ExtendMode::AspectRatio {
+            width: w,
+            height: h,
+            gravity: args.gravity,
+        }),
+        (None, Some((w, h))) => Ok(ExtendMode::Size {
+            width: w,
+            height: h,
+            gravity: args.gravity,
+        }),
         _ => anyhow::bail!("specify exactly one of --aspect or --size"),
     }
 }
 
-fn build_fill_color(args: &ExtendArgs, format: slimg_core::Format) -> anyhow::Result<FillColor> {
-    if args.transparent {
+pub(crate) fn build_fill_color(
+    color: Option<&str>,
+    transparent: bool,
+    format: slimg_core::Format,
+) -> anyhow::Result<FillColor> {
+    if transparent {
         if format == slimg_core::Format::Jpeg {
             eprintln!(
                 "warning: JPEG does not support transparency, using white background"
@@ -92,7 +136,7 @@ fn build_fill_color(args: &ExtendArgs, format: slimg_core::Format) -> anyhow::Re
         return Ok(FillColor::Transparent);
     }
 
-    match &args.color {
+    match color {
         Some(hex) => Ok(FillColor::Solid(parse_hex_color(hex)?)),
         None => Ok(FillColor::Solid([255, 255, 255, 255])),
     }
@@ -108,14 +152,19 @@ pub fn run(args: ExtendArgs) -> anyhow::Result<()> {
 
     configure_thread_pool(args.jobs)?;
 
+    let decode_options = DecodeOptions {
+        auto_orient: !args.no_auto_orient,
+        ..Default::default()
+    };
+
     let pb = make_progress_bar(files.len());
     let errors = ErrorCollector::new();
 
     files.par_iter().for_each(|file| {
         let result: anyhow::Result<()> = (|| {
             let original_size = std::fs::metadata(file)?.len();
-            let (image, src_format) =
-                decode_file(file).with_context(|| format!("{}", file.display()))?;
+            let (animation, src_format) = decode_animation_file_with(file, &decode_options)
+                .with_context(|| format!("{}", file.display()))?;
 
             let target_format = args.format.map(|f| f.into_format()).unwrap_or(src_format);
 
@@ -123,19 +172,22 @@ pub fn run(args: ExtendArgs) -> anyhow::Result<()> {
                 anyhow::bail!("cannot encode to {} format", target_format.extension());
             }
 
-            let fill = build_fill_color(&args, target_format)?;
+            let fill = build_fill_color(args.color.as_deref(), args.transparent, target_format)?;
 
             let options = PipelineOptions {
-                format: target_format,
                 quality: args.quality,
-                resize: None,
-                crop: None,
+                lossless: args.lossless,
+                metadata: args.metadata.into_policy(),
+                color: args.color_profile.into_policy(),
+                tone_map: args.tone_map.into_operator(),
                 extend: Some(extend_mode.clone()),
                 fill_color: Some(fill),
+                codec: args.codec.to_options(),
+                ..PipelineOptions::new(target_format)
             };
 
-            let result =
-                convert(&image, &options).with_context(|| format!("{}", file.display()))?;
+            let result = convert_animation(&animation, &options)
+                .with_context(|| format!("{}", file.display()))?;
 
             let out = output_path(file, target_format, args.output.as_deref());
             safe_write(&out, &result.data, args.overwrite)?;
@@ -178,6 +230,41 @@ pub fn run(args: ExtendArgs) -> anyhow::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct ExtendCli {
+        #[command(flatten)]
+        args: ExtendArgs,
+    }
+
+    fn parse_mode(args: &[&str]) -> anyhow::Result<ExtendMode> {
+        let argv = ["slimg", "photo.jpg"]
+            .into_iter()
+            .chain(args.iter().copied());
+        let cli = ExtendCli::try_parse_from(argv)?;
+        build_extend_mode(&cli.args)
+    }
+
+    #[test]
+    fn gravity_applies_to_both_modes() {
+        assert_eq!(
+            parse_mode(&["--aspect", "1:1"]).unwrap(),
+            ExtendMode::AspectRatio {
+                width: 1,
+                height: 1,
+                gravity: Gravity::Center
+            }
+        );
+        assert_eq!(
+            parse_mode(&["--size", "800x600", "--gravity", "southwest"]).unwrap(),
+            ExtendMode::Size {
+                width: 800,
+                height: 600,
+                gravity: Gravity::SouthWest
+            }
+        );
+    }
 
     #[test]
     fn parse_hex_color_6_digits() {
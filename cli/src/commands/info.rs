@@ -0,0 +1,201 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use clap::Args;
+use serde_json::{Value, json};
+use slimg_core::{ColorPrimaries, HdrMetadata, ImageInfo, Orientation, TransferFunction, probe};
+
+use super::collect_files;
+
+#[derive(Debug, Args)]
+pub struct InfoArgs {
+    /// Input file or directory
+    pub input: PathBuf,
+
+    /// Print one JSON object per file instead of a table
+    #[arg(long)]
+    pub json: bool,
+
+    /// Process subdirectories recursively
+    #[arg(long)]
+    pub recursive: bool,
+}
+
+pub fn run(args: InfoArgs) -> anyhow::Result<()> {
+    let files = collect_files(&args.input, args.recursive)?;
+    if files.is_empty() {
+        anyhow::bail!("no image files found in {}", args.input.display());
+    }
+
+    let mut failed = 0;
+    for (i, file) in files.iter().enumerate() {
+        let info = fs::read(file)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| Ok((data.len(), probe(&data)?)))
+            .with_context(|| format!("{}", file.display()));
+        let (size, info) = match info {
+            Ok(info) => info,
+            Err(e) => {
+                eprintln!("{e:#}");
+                failed += 1;
+                continue;
+            }
+        };
+
+        if args.json {
+            println!("{}", to_json(file, size, &info));
+        } else {
+            if i > 0 {
+                println!();
+            }
+            print_table(file, size, &info);
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{failed} file(s) could not be read");
+    }
+    Ok(())
+}
+
+fn print_table(path: &Path, size: usize, info: &ImageInfo) {
+    let (width, height) = info.display_dimensions();
+    let stored = if (width, height) == (info.width, info.height) {
+        String::new()
+    } else {
+        format!(" (stored {}x{})", info.width, info.height)
+    };
+    let metadata = &info.metadata;
+
+    println!("{}", path.display());
+    println!("  Format       {}", info.format.extension());
+    println!("  File size    {size} bytes");
+    println!("  Dimensions   {width}x{height}{stored}");
+    println!("  Bit depth    {}", info.bit_depth);
+    println!(
+        "  Alpha        {}",
+        if info.has_alpha { "yes" } else { "no" }
+    );
+    println!("  Frames       {}", info.frame_count);
+    println!(
+        "  Orientation  {} (EXIF {})",
+        orientation_name(info.orientation),
+        info.orientation.to_exif()
+    );
+    println!("  ICC profile  {}", byte_count(metadata.icc_bytes));
+    println!("  EXIF         {}", byte_count(metadata.exif_bytes));
+    println!("  XMP          {}", byte_count(metadata.xmp_bytes));
+    if let Some(hdr) = &metadata.hdr {
+        println!("  HDR          {}", hdr_description(hdr));
+    }
+}
+
+fn to_json(path: &Path, size: usize, info: &ImageInfo) -> Value {
+    let (width, height) = info.display_dimensions();
+    let metadata = &info.metadata;
+    let hdr = metadata.hdr.map(|hdr| {
+        json!({
+            "transfer": transfer_name(hdr.transfer),
+            "primaries": primaries_name(hdr.primaries),
+            "max_cll": hdr.content_light_level.map(|level| level.max_cll),
+            "max_fall": hdr.content_light_level.map(|level| level.max_fall),
+        })
+    });
+    json!({
+        "path": path.display().to_string(),
+        "format": info.format.extension(),
+        "file_size": size,
+        "width": width,
+        "height": height,
+        "stored_width": info.width,
+        "stored_height": info.height,
+        "bit_depth": info.bit_depth,
+        "has_alpha": info.has_alpha,
+        "frame_count": info.frame_count,
+        "orientation": info.orientation.to_exif(),
+        "has_color_profile": info.has_color_profile(),
+        "icc_bytes": metadata.icc_bytes,
+        "exif_bytes": metadata.exif_bytes,
+        "xmp_bytes": metadata.xmp_bytes,
+        "hdr": hdr,
+    })
+}
+
+fn byte_count(bytes: Option<usize>) -> String {
+    bytes.map_or_else(|| "none".to_string(), |n| format!("{n} bytes"))
+}
+
+fn orientation_name(orientation: Orientation) -> &'static str {
+    match orientation {
+        Orientation::Normal => "normal",
+        Orientation::FlipHorizontal => "flip horizontal",
+        Orientation::Rotate180 => "rotate 180",
+        Orientation::FlipVertical => "flip vertical",
+        Orientation::Transpose => "transpose",
+        Orientation::Rotate90 => "rotate 90",
+        Orientation::Transverse => "transverse",
+        Orientation::Rotate270 => "rotate 270",
+    }
+}
+
+fn transfer_name(transfer: TransferFunction) -> &'static str {
+    match transfer {
+        TransferFunction::Pq => "pq",
+        TransferFunction::Hlg => "hlg",
+    }
+}
+
+fn primaries_name(primaries: ColorPrimaries) -> &'static str {
+    match primaries {
+        ColorPrimaries::Bt709 => "bt709",
+        ColorPrimaries::DisplayP3 => "display-p3",
+        ColorPrimaries::Bt2020 => "bt2020",
+    }
+}
+
+fn hdr_description(hdr: &HdrMetadata) -> String {
+    let mut description = format!(
+        "{}, {}",
+        transfer_name(hdr.transfer),
+        primaries_name(hdr.primaries)
+    );
+    if let Some(level) = hdr.content_light_level {
+        description.push_str(&format!(", MaxCLL {} nits", level.max_cll));
+    }
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slimg_core::{Format, MetadataSummary};
+
+    #[test]
+    fn json_reports_upright_and_stored_dimensions() {
+        let info = ImageInfo {
+            format: Format::Jpeg,
+            width: 400,
+            height: 300,
+            bit_depth: 8,
+            has_alpha: false,
+            frame_count: 1,
+            orientation: Orientation::Rotate90,
+            metadata: MetadataSummary {
+                icc_bytes: Some(3144),
+                ..Default::default()
+            },
+        };
+
+        let value = to_json(Path::new("photo.jpg"), 1000, &info);
+
+        assert_eq!(value["format"], "jpg");
+        assert_eq!(value["width"], 300);
+        assert_eq!(value["height"], 400);
+        assert_eq!(value["stored_width"], 400);
+        assert_eq!(value["orientation"], 6);
+        assert_eq!(value["has_color_profile"], true);
+        assert_eq!(value["exif_bytes"], Value::Null);
+        assert_eq!(value["hdr"], Value::Null);
+    }
+}
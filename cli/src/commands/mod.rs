@@ -1,6 +1,8 @@
+pub mod compare;
 pub mod convert;
 pub mod crop;
 pub mod extend;
+pub mod info;
 pub mod optimize;
 pub mod resize;
 
@@ -8,9 +10,13 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
-use clap::ValueEnum;
+use clap::{Args, ValueEnum};
 use indicatif::{ProgressBar, ProgressStyle};
-use slimg_core::Format;
+use slimg_core::{
+    ChromaSubsampling, CodecOptions, ColorPolicy, Format, Gravity, LinearLight, MetadataPolicy,
+    PipelineResult, PngQuantizeOptions, QoiColorspace, QualityTarget, ResizeFilter,
+    ToneMapOperator,
+};
 
 /// Image format argument for CLI.
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -21,6 +27,12 @@ pub enum FormatArg {
     Avif,
     Jxl,
     Qoi,
+    Gif,
+    Tiff,
+    Bmp,
+    Tga,
+    Ico,
+    Pnm,
 }
 
 impl FormatArg {
@@ -32,12 +44,280 @@ impl FormatArg {
             Self::Avif => Format::Avif,
             Self::Jxl => Format::Jxl,
             Self::Qoi => Format::Qoi,
+            Self::Gif => Format::Gif,
+            Self::Tiff => Format::Tiff,
+            Self::Bmp => Format::Bmp,
+            Self::Tga => Format::Tga,
+            Self::Ico => Format::Ico,
+            Self::Pnm => Format::Pnm,
         }
     }
 }
 
+/// JPEG chroma subsampling argument for CLI.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SubsamplingArg {
+    #[value(name = "420")]
+    S420,
+    #[value(name = "422")]
+    S422,
+    #[value(name = "444")]
+    S444,
+}
+
+impl SubsamplingArg {
+    pub fn into_subsampling(self) -> ChromaSubsampling {
+        match self {
+            Self::S420 => ChromaSubsampling::Yuv420,
+            Self::S422 => ChromaSubsampling::Yuv422,
+            Self::S444 => ChromaSubsampling::Yuv444,
+        }
+    }
+}
+
+/// Metadata policy argument for CLI.
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum MetadataArg {
+    /// Keep EXIF, XMP and ICC as-is
+    Keep,
+    /// Drop all metadata
+    Strip,
+    /// Keep ICC and EXIF copyright/artist/orientation; drop GPS and XMP
+    #[default]
+    Safe,
+}
+
+impl MetadataArg {
+    pub fn into_policy(self) -> MetadataPolicy {
+        match self {
+            Self::Keep => MetadataPolicy::Keep,
+            Self::Strip => MetadataPolicy::Strip,
+            Self::Safe => MetadataPolicy::Safe,
+        }
+    }
+}
+
+/// Colour profile policy argument for CLI.
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum ColorArg {
+    /// Embed the source ICC profile; convert to sRGB only when it would be lost
+    #[default]
+    Preserve,
+    /// Convert pixels to sRGB and drop the source profile
+    Srgb,
+}
+
+impl ColorArg {
+    pub fn into_policy(self) -> ColorPolicy {
+        match self {
+            Self::Preserve => ColorPolicy::Preserve,
+            Self::Srgb => ColorPolicy::Srgb,
+        }
+    }
+}
+
+/// Tone-mapping operator argument for CLI.
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum ToneMapArg {
+    /// Hard-clip highlights above SDR white
+    Clip,
+    /// Extended Reinhard on luminance
+    #[default]
+    Reinhard,
+    /// Hable's filmic curve
+    Hable,
+}
+
+impl ToneMapArg {
+    pub fn into_operator(self) -> ToneMapOperator {
+        match self {
+            Self::Clip => ToneMapOperator::Clip,
+            Self::Reinhard => ToneMapOperator::Reinhard,
+            Self::Hable => ToneMapOperator::Hable,
+        }
+    }
+}
+
+/// Resampling filter argument for CLI.
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum FilterArg {
+    /// Nearest neighbour; keeps pixel art crisp
+    Nearest,
+    /// Bilinear interpolation
+    Bilinear,
+    /// Catmull-Rom bicubic
+    CatmullRom,
+    /// Mitchell-Netravali bicubic; less ringing than Catmull-Rom
+    Mitchell,
+    /// Three-lobe Lanczos; the sharpest
+    #[default]
+    Lanczos3,
+}
+
+impl FilterArg {
+    pub fn into_filter(self) -> ResizeFilter {
+        match self {
+            Self::Nearest => ResizeFilter::Nearest,
+            Self::Bilinear => ResizeFilter::Bilinear,
+            Self::CatmullRom => ResizeFilter::CatmullRom,
+            Self::Mitchell => ResizeFilter::Mitchell,
+            Self::Lanczos3 => ResizeFilter::Lanczos3,
+        }
+    }
+}
+
+/// Linear-light resizing argument for CLI.
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum LinearLightArg {
+    /// Linear light when scaling to half size or less
+    #[default]
+    Auto,
+    /// Always resize in linear light
+    Always,
+    /// Resize the gamma-encoded values as stored
+    Never,
+}
+
+impl LinearLightArg {
+    pub fn into_linear_light(self) -> LinearLight {
+        match self {
+            Self::Auto => LinearLight::Auto,
+            Self::Always => LinearLight::Always,
+            Self::Never => LinearLight::Never,
+        }
+    }
+}
+
+/// Per-format encoder flags shared by every command that encodes.
+#[derive(Debug, Clone, Args)]
+#[command(next_help_heading = "Codec options")]
+pub struct CodecArgs {
+    /// JPEG: write a baseline (non-progressive) file
+    #[arg(long)]
+    pub jpeg_baseline: bool,
+
+    /// JPEG: chroma subsampling
+    #[arg(long, value_enum, default_value = "420")]
+    pub jpeg_subsampling: SubsamplingArg,
+
+    /// JPEG: smoothing factor (0-100)
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub jpeg_smoothing: u8,
+
+    /// PNG: OxiPNG optimization level (0-6, defaults to one derived from quality)
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=6))]
+    pub png_level: Option<u8>,
+
+    /// PNG: write an interlaced (Adam7) file
+    #[arg(long)]
+    pub png_interlace: bool,
+
+    /// PNG: allow changing the colour of fully transparent pixels
+    #[arg(long)]
+    pub png_optimize_alpha: bool,
+
+    /// PNG: quantize to a palette (lossy); --quality sets the target
+    #[arg(long)]
+    pub png_lossy: bool,
+
+    /// PNG: maximum palette size for --png-lossy (2-256)
+    #[arg(long, default_value_t = 256, value_parser = clap::value_parser!(u16).range(2..=256))]
+    pub png_colors: u16,
+
+    /// PNG: dithering strength for --png-lossy (0.0-1.0)
+    #[arg(long, default_value_t = 1.0, value_parser = parse_unit_interval)]
+    pub png_dither: f32,
+
+    /// PNG: keep the lossless output if --png-lossy scores below this (0-100)
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub png_min_quality: u8,
+
+    /// WebP: compression method (0-6, higher is slower but smaller)
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u8).range(0..=6))]
+    pub webp_method: u8,
+
+    /// WebP: alpha plane quality (0-100)
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub webp_alpha_quality: u8,
+
+    /// WebP: use the sharper (slower) RGB to YUV conversion
+    #[arg(long)]
+    pub webp_sharp_yuv: bool,
+
+    /// AVIF: encoder speed (1-10, lower is slower but smaller)
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u8).range(1..=10))]
+    pub avif_speed: u8,
+
+    /// AVIF: alpha plane quality (1-100, defaults to the encoder's choice)
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub avif_alpha_quality: Option<u8>,
+
+    /// JXL: encoder effort (1-10, higher is slower but smaller)
+    #[arg(long, default_value_t = 7, value_parser = clap::value_parser!(u8).range(1..=10))]
+    pub jxl_effort: u8,
+
+    /// JXL: decoding speed tier (0-4, higher decodes faster)
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=4))]
+    pub jxl_decoding_speed: u8,
+
+    /// QOI: tag the output as linear instead of sRGB
+    #[arg(long)]
+    pub qoi_linear: bool,
+
+    /// GIF: maximum size of the shared palette (2-256)
+    #[arg(long, default_value_t = 256, value_parser = clap::value_parser!(u16).range(2..=256))]
+    pub gif_colors: u16,
+
+    /// GIF: dithering strength (0.0-1.0)
+    #[arg(long, default_value_t = 1.0, value_parser = parse_unit_interval)]
+    pub gif_dither: f32,
+}
+
+impl CodecArgs {
+    pub fn to_options(&self) -> CodecOptions {
+        let mut opts = CodecOptions::default();
+
+        opts.jpeg.progressive = !self.jpeg_baseline;
+        opts.jpeg.chroma_subsampling = self.jpeg_subsampling.into_subsampling();
+        opts.jpeg.smoothing = self.jpeg_smoothing;
+
+        opts.png.optimization_level = self.png_level;
+        opts.png.interlace = self.png_interlace;
+        opts.png.optimize_alpha = self.png_optimize_alpha;
+        if self.png_lossy {
+            opts.png.quantize = Some(PngQuantizeOptions {
+                max_colors: self.png_colors,
+                dithering: self.png_dither,
+                min_quality: self.png_min_quality,
+            });
+        }
+
+        opts.webp.method = self.webp_method;
+        opts.webp.alpha_quality = self.webp_alpha_quality;
+        opts.webp.sharp_yuv = self.webp_sharp_yuv;
+
+        opts.avif.speed = self.avif_speed;
+        opts.avif.alpha_quality = self.avif_alpha_quality;
+
+        opts.jxl.effort = self.jxl_effort;
+        opts.jxl.decoding_speed = self.jxl_decoding_speed;
+
+        if self.qoi_linear {
+            opts.qoi.colorspace = QoiColorspace::Linear;
+        }
+
+        opts.gif.max_colors = self.gif_colors;
+        opts.gif.dithering = self.gif_dither;
+
+        opts
+    }
+}
+
 /// Known image file extensions that slimg can process.
-const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "avif", "jxl", "qoi"];
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "webp", "avif", "jxl", "qoi", "gif", "tif", "tiff", "bmp", "dib", "tga",
+    "ico", "cur", "pnm", "pbm", "pgm", "ppm", "pam",
+];
 
 /// Collect image files from a path.
 ///
@@ -108,6 +388,41 @@ pub(crate) fn safe_write(path: &Path, data: &[u8], overwrite: bool) -> anyhow::R
     Ok(())
 }
 
+/// The quality target picked by `--target-ssimulacra2` or `--target-dssim`.
+pub(crate) fn quality_target(
+    ssimulacra2: Option<f64>,
+    dssim: Option<f64>,
+) -> Option<QualityTarget> {
+    ssimulacra2
+        .map(QualityTarget::Ssimulacra2)
+        .or(dssim.map(QualityTarget::Dssim))
+}
+
+/// Suffix for the per-file summary naming the quality a `--max-size` or
+/// quality-target search settled on, and the score it reached.
+pub(crate) fn search_note(
+    result: &PipelineResult,
+    max_size: Option<u64>,
+    target: Option<QualityTarget>,
+) -> String {
+    let mut note = String::new();
+    if (max_size.is_some() || target.is_some())
+        && let Some(quality) = result.quality
+    {
+        note.push_str(&format!(", quality {quality}"));
+    }
+    match (target, result.score) {
+        (Some(QualityTarget::Ssimulacra2(_)), Some(score)) => {
+            note.push_str(&format!(", SSIMULACRA2 {score:.1}"));
+        }
+        (Some(QualityTarget::Dssim(_)), Some(score)) => {
+            note.push_str(&format!(", DSSIM {score:.5}"));
+        }
+        _ => {}
+    }
+    note
+}
+
 /// Collector for errors that occur during batch processing.
 /// Thread-safe — can be shared across rayon workers.
 pub(crate) struct ErrorCollector {
@@ -144,6 +459,46 @@ impl ErrorCollector {
     }
 }
 
+/// Parse a fraction in the range 0.0-1.0.
+pub(crate) fn parse_unit_interval(s: &str) -> std::result::Result<f32, String> {
+    let v: f32 = s
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid number: '{s}'"))?;
+    if !(0.0..=1.0).contains(&v) {
+        return Err("value must be between 0.0 and 1.0".to_string());
+    }
+    Ok(v)
+}
+
+/// Parse a byte count with an optional unit (e.g. "150KB", "1.5MB", "64KiB").
+/// KB and MB are decimal; KiB and MiB are binary.
+pub(crate) fn parse_byte_size(s: &str) -> std::result::Result<u64, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1000,
+        "kib" => 1024,
+        "m" | "mb" => 1000 * 1000,
+        "mib" => 1024 * 1024,
+        _ => {
+            return Err(format!(
+                "unknown size unit: '{unit}' (use B, KB, KiB, MB or MiB)"
+            ));
+        }
+    };
+    let number: f64 = number.parse().map_err(|_| format!("invalid size: '{s}'"))?;
+    let bytes = (number * multiplier as f64).round() as u64;
+    if bytes == 0 {
+        return Err("size must be non-zero".to_string());
+    }
+    Ok(bytes)
+}
+
 /// Parse "WxH" size string (e.g. "1920x1080").
 pub(crate) fn parse_size(s: &str) -> std::result::Result<(u32, u32), String> {
     let parts: Vec<&str> = s.split('x').collect();
@@ -164,6 +519,37 @@ pub(crate) fn parse_size(s: &str) -> std::result::Result<(u32, u32), String> {
     Ok((w, h))
 }
 
+/// Parse a gravity: a compass name (e.g. "north", "southeast", "center") or a
+/// focal point "x,y" given as fractions of the image (e.g. "0.3,0.25").
+pub(crate) fn parse_gravity(s: &str) -> std::result::Result<Gravity, String> {
+    let gravity = match s.trim().to_ascii_lowercase().as_str() {
+        "center" | "centre" => Gravity::Center,
+        "north" => Gravity::North,
+        "northeast" => Gravity::NorthEast,
+        "east" => Gravity::East,
+        "southeast" => Gravity::SouthEast,
+        "south" => Gravity::South,
+        "southwest" => Gravity::SouthWest,
+        "west" => Gravity::West,
+        "northwest" => Gravity::NorthWest,
+        _ => {
+            let Some((x, y)) = s.split_once(',') else {
+                return Err(format!(
+                    "unknown gravity: '{s}' (use a compass direction such as north or \
+                     southwest, center, or a focal point x,y)"
+                ));
+            };
+            let x = parse_unit_interval(x).map_err(|e| format!("focal x: {e}"))?;
+            let y = parse_unit_interval(y).map_err(|e| format!("focal y: {e}"))?;
+            Gravity::Focal {
+                x: x as f64,
+                y: y as f64,
+            }
+        }
+    };
+    Ok(gravity)
+}
+
 fn collect_dir(dir: &Path, recursive: bool, out: &mut Vec<PathBuf>) -> anyhow::Result<()> {
     for entry in std::fs::read_dir(dir)? {
         let entry = entry?;
@@ -184,9 +570,96 @@ fn collect_dir(dir: &Path, recursive: bool, out: &mut Vec<PathBuf>) -> anyhow::R
 #[cfg(test)]
 mod tests {
     use super::*;
+    use clap::Parser;
     use std::fs;
     use tempfile::TempDir;
 
+    #[derive(Parser)]
+    struct CodecCli {
+        #[command(flatten)]
+        codec: CodecArgs,
+    }
+
+    fn parse_codec(args: &[&str]) -> CodecOptions {
+        let argv = std::iter::once("slimg").chain(args.iter().copied());
+        CodecCli::parse_from(argv).codec.to_options()
+    }
+
+    // ── CodecArgs ───────────────────────────────────────────
+
+    #[test]
+    fn codec_args_default_matches_core_defaults() {
+        assert_eq!(parse_codec(&[]), CodecOptions::default());
+    }
+
+    #[test]
+    fn codec_args_map_to_options() {
+        let opts = parse_codec(&[
+            "--jpeg-baseline",
+            "--jpeg-subsampling",
+            "444",
+            "--png-level",
+            "3",
+            "--webp-method",
+            "6",
+            "--avif-speed",
+            "2",
+            "--jxl-effort",
+            "9",
+            "--qoi-linear",
+        ]);
+
+        assert!(!opts.jpeg.progressive);
+        assert_eq!(opts.jpeg.chroma_subsampling, ChromaSubsampling::Yuv444);
+        assert_eq!(opts.png.optimization_level, Some(3));
+        assert_eq!(opts.webp.method, 6);
+        assert_eq!(opts.avif.speed, 2);
+        assert_eq!(opts.jxl.effort, 9);
+        assert_eq!(opts.qoi.colorspace, QoiColorspace::Linear);
+        assert_eq!(opts.png.quantize, None);
+    }
+
+    #[test]
+    fn codec_args_png_lossy() {
+        let opts = parse_codec(&["--png-lossy", "--png-colors", "64", "--png-dither", "0.5"]);
+
+        let quantize = opts.png.quantize.expect("quantize should be set");
+        assert_eq!(quantize.max_colors, 64);
+        assert_eq!(quantize.dithering, 0.5);
+        assert_eq!(quantize.min_quality, 0);
+    }
+
+    #[test]
+    fn codec_args_gif_palette() {
+        let opts = parse_codec(&["--gif-colors", "32", "--gif-dither", "0"]);
+
+        assert_eq!(opts.gif.max_colors, 32);
+        assert_eq!(opts.gif.dithering, 0.0);
+    }
+
+    #[test]
+    fn codec_args_reject_out_of_range() {
+        let argv = ["slimg", "--webp-method", "7"];
+        assert!(CodecCli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn metadata_arg_default_matches_core_default() {
+        assert_eq!(MetadataArg::default().into_policy(), MetadataPolicy::default());
+        assert_eq!(MetadataArg::Strip.into_policy(), MetadataPolicy::Strip);
+    }
+
+    #[test]
+    fn color_arg_default_matches_core_default() {
+        assert_eq!(ColorArg::default().into_policy(), ColorPolicy::default());
+        assert_eq!(ColorArg::Srgb.into_policy(), ColorPolicy::Srgb);
+    }
+
+    #[test]
+    fn tone_map_arg_default_matches_core_default() {
+        assert_eq!(ToneMapArg::default().into_operator(), ToneMapOperator::default());
+    }
+
     // ── safe_write ──────────────────────────────────────────
 
     #[test]
@@ -320,12 +793,16 @@ mod tests {
         fs::write(dir.path().join("a.jpg"), b"").unwrap();
         fs::write(dir.path().join("b.txt"), b"").unwrap();
         fs::write(dir.path().join("c.png"), b"").unwrap();
+        fs::write(dir.path().join("d.gif"), b"").unwrap();
+        fs::write(dir.path().join("e.TIF"), b"").unwrap();
+        fs::write(dir.path().join("f.bmp"), b"").unwrap();
+        fs::write(dir.path().join("g.psd"), b"").unwrap();
 
         let files = collect_files(dir.path(), false).unwrap();
-        assert_eq!(files.len(), 2);
+        assert_eq!(files.len(), 5);
         assert!(files.iter().all(|f| {
             let ext = f.extension().unwrap().to_str().unwrap();
-            ext == "jpg" || ext == "png"
+            ["jpg", "png", "gif", "TIF", "bmp"].contains(&ext)
         }));
     }
 
@@ -344,6 +821,60 @@ mod tests {
         assert_eq!(recursive.len(), 2);
     }
 
+    // ── parse_unit_interval ───────────────────────────────────
+
+    #[test]
+    fn parse_unit_interval_valid() {
+        assert_eq!(parse_unit_interval("0.75"), Ok(0.75));
+    }
+
+    #[test]
+    fn parse_unit_interval_out_of_range() {
+        assert!(parse_unit_interval("1.5").is_err());
+    }
+
+    // ── parse_byte_size ───────────────────────────────────────
+
+    #[test]
+    fn parse_byte_size_units() {
+        assert_eq!(parse_byte_size("2048"), Ok(2048));
+        assert_eq!(parse_byte_size("150KB"), Ok(150_000));
+        assert_eq!(parse_byte_size("150 kb"), Ok(150_000));
+        assert_eq!(parse_byte_size("64KiB"), Ok(65_536));
+        assert_eq!(parse_byte_size("1.5MB"), Ok(1_500_000));
+    }
+
+    #[test]
+    fn parse_byte_size_invalid() {
+        assert!(parse_byte_size("150GB").is_err());
+        assert!(parse_byte_size("KB").is_err());
+        assert!(parse_byte_size("0").is_err());
+    }
+
+    // ── search_note ───────────────────────────────────────────
+
+    #[test]
+    fn search_note_names_quality_and_score() {
+        let result = PipelineResult {
+            data: Vec::new(),
+            format: Format::Jpeg,
+            width: 1,
+            height: 1,
+            quality: Some(62),
+            score: Some(80.44),
+            rejected: Vec::new(),
+            kept_original: false,
+        };
+        let target = quality_target(Some(80.0), None);
+
+        assert_eq!(search_note(&result, None, None), "");
+        assert_eq!(search_note(&result, Some(1000), None), ", quality 62");
+        assert_eq!(
+            search_note(&result, None, target),
+            ", quality 62, SSIMULACRA2 80.4"
+        );
+    }
+
     // ── parse_size ────────────────────────────────────────────
 
     #[test]
@@ -370,4 +901,28 @@ mod tests {
     fn parse_size_invalid_number() {
         assert!(parse_size("abcx100").is_err());
     }
+
+    // ── parse_gravity ─────────────────────────────────────────
+
+    #[test]
+    fn parse_gravity_names() {
+        assert_eq!(parse_gravity("north"), Ok(Gravity::North));
+        assert_eq!(parse_gravity("SouthEast"), Ok(Gravity::SouthEast));
+        assert_eq!(parse_gravity("centre"), Ok(Gravity::Center));
+    }
+
+    #[test]
+    fn parse_gravity_focal_point() {
+        assert_eq!(
+            parse_gravity("0.25, 0.5"),
+            Ok(Gravity::Focal { x: 0.25, y: 0.5 })
+        );
+    }
+
+    #[test]
+    fn parse_gravity_invalid() {
+        assert!(parse_gravity("up").is_err());
+        assert!(parse_gravity("1.5,0.5").is_err());
+        assert!(parse_gravity("0.5").is_err());
+    }
 }
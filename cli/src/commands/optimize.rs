@@ -2,9 +2,13 @@ use std::path::PathBuf;
 
 use clap::Args;
 use rayon::prelude::*;
-use slimg_core::{optimize, output_path};
+use slimg_core::{EncodeOptions, OptimizeOptions, SizeLimit, optimize_with, output_path};
 
-use super::{ErrorCollector, collect_files, configure_thread_pool, make_progress_bar, safe_write};
+use super::{
+    CodecArgs, ColorArg, ErrorCollector, MetadataArg, ToneMapArg, collect_files,
+    configure_thread_pool, make_progress_bar, parse_byte_size, quality_target, safe_write,
+    search_note,
+};
 
 #[derive(Debug, Args)]
 pub struct OptimizeArgs {
@@ -15,6 +19,41 @@ pub struct OptimizeArgs {
     #[arg(short, long, default_value_t = 80)]
     pub quality: u8,
 
+    /// Encode losslessly (PNG, WebP, JXL, QOI; JPEGs are rewritten without re-encoding)
+    #[arg(long)]
+    pub lossless: bool,
+
+    /// Largest output size (e.g. 150KB, 1.5MB); the quality is lowered to fit
+    #[arg(long, value_parser = parse_byte_size)]
+    pub max_size: Option<u64>,
+
+    /// Shrink images that exceed --max-size even at the lowest quality
+    #[arg(long, requires = "max_size")]
+    pub downscale_to_fit: bool,
+
+    /// Pick the lowest quality scoring at least this SSIMULACRA2 (90 is visually lossless)
+    #[arg(long, conflicts_with_all = ["lossless", "target_dssim"])]
+    pub target_ssimulacra2: Option<f64>,
+
+    /// Pick the lowest quality scoring at most this DSSIM (0 is identical)
+    #[arg(long, conflicts_with = "lossless")]
+    pub target_dssim: Option<f64>,
+
+    /// Metadata to keep in the output
+    #[arg(long, value_enum, default_value_t = MetadataArg::Safe)]
+    pub metadata: MetadataArg,
+
+    /// How to handle the source ICC colour profile
+    #[arg(long, value_enum, default_value_t = ColorArg::Preserve)]
+    pub color_profile: ColorArg,
+
+    /// Tone-mapping operator for HDR sources written to SDR formats
+    #[arg(long, value_enum, default_value_t = ToneMapArg::Reinhard)]
+    pub tone_map: ToneMapArg,
+
+    #[command(flatten)]
+    pub codec: CodecArgs,
+
     /// Output path (file or directory)
     #[arg(short, long)]
     pub output: Option<PathBuf>,
@@ -41,6 +80,24 @@ pub fn run(args: OptimizeArgs) -> anyhow::Result<()> {
 
     configure_thread_pool(args.jobs)?;
 
+    let options = OptimizeOptions {
+        encode: EncodeOptions {
+            quality: args.quality,
+            lossless: args.lossless,
+            metadata: args.metadata.into_policy(),
+            color: args.color_profile.into_policy(),
+            tone_map: args.tone_map.into_operator(),
+            codec: args.codec.to_options(),
+        },
+        max_size: args.max_size.map(|max_bytes| SizeLimit {
+            max_bytes,
+            downscale: args.downscale_to_fit,
+        }),
+        quality_target: quality_target(args.target_ssimulacra2, args.target_dssim),
+        never_larger: true,
+        ..Default::default()
+    };
+
     let pb = make_progress_bar(files.len());
     let errors = ErrorCollector::new();
 
@@ -49,7 +106,11 @@ pub fn run(args: OptimizeArgs) -> anyhow::Result<()> {
             let original_data = std::fs::read(file)?;
             let original_size = original_data.len() as u64;
 
-            let result = optimize(&original_data, args.quality)?;
+            let result = optimize_with(&original_data, &options)?;
+            if result.kept_original {
+                pb.println(format!("{} -> skipped (would grow)", file.display()));
+                return Ok(());
+            }
             let new_size = result.data.len() as u64;
 
             let out = if args.overwrite {
@@ -58,31 +119,23 @@ pub fn run(args: OptimizeArgs) -> anyhow::Result<()> {
                 output_path(file, result.format, args.output.as_deref())
             };
 
-            if new_size < original_size || args.overwrite {
-                safe_write(&out, &result.data, args.overwrite)?;
-
-                let ratio = if original_size > 0 {
-                    (new_size as f64 / original_size as f64) * 100.0
-                } else {
-                    0.0
-                };
-
-                pb.println(format!(
-                    "{} -> {} ({} -> {} bytes, {:.1}%)",
-                    file.display(),
-                    out.display(),
-                    original_size,
-                    new_size,
-                    ratio,
-                ));
+            safe_write(&out, &result.data, args.overwrite)?;
+
+            let ratio = if original_size > 0 {
+                (new_size as f64 / original_size as f64) * 100.0
             } else {
-                pb.println(format!(
-                    "{} -> skipped (optimized size {} >= original {})",
-                    file.display(),
-                    new_size,
-                    original_size,
-                ));
-            }
+                0.0
+            };
+
+            pb.println(format!(
+                "{} -> {} ({} -> {} bytes, {:.1}%{})",
+                file.display(),
+                out.display(),
+                original_size,
+                new_size,
+                ratio,
+                search_note(&result, args.max_size, options.quality_target),
+            ));
 
             Ok(())
         })();
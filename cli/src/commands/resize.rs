@@ -1,9 +1,13 @@
 use std::path::PathBuf;
 
 use clap::Args;
-use slimg_core::{PipelineOptions, ResizeMode, convert, decode_file, output_path};
+use slimg_core::{
+    DecodeOptions, Format, PipelineOptions, ResizeMode, ResizeOptions, convert_animation,
+    decode_animation_file_with, output_path,
+};
 
-use super::FormatArg;
+use super::extend::build_fill_color;
+use super::{CodecArgs, ColorArg, FilterArg, FormatArg, LinearLightArg, MetadataArg, ToneMapArg};
 
 #[derive(Debug, Args)]
 pub struct ResizeArgs {
@@ -22,6 +26,42 @@ pub struct ResizeArgs {
     #[arg(long)]
     pub scale: Option<f64>,
 
+    /// Shrink to at most this many megapixels (e.g. 12)
+    #[arg(long, conflicts_with_all = ["width", "height", "scale"])]
+    pub max_megapixels: Option<f64>,
+
+    /// Fill --width x --height exactly, cropping the overflow
+    #[arg(long, requires_all = ["width", "height"], conflicts_with = "contain")]
+    pub cover: bool,
+
+    /// Fit within --width x --height, then pad to exactly that size
+    #[arg(long, requires_all = ["width", "height"])]
+    pub contain: bool,
+
+    /// Padding color for --contain as hex (e.g. '#FFFFFF'). Default: white.
+    #[arg(long, requires = "contain", conflicts_with = "transparent")]
+    pub color: Option<String>,
+
+    /// Pad --contain with transparency (for formats with alpha support)
+    #[arg(long, requires = "contain")]
+    pub transparent: bool,
+
+    /// Never scale up images smaller than the target
+    #[arg(long)]
+    pub without_enlargement: bool,
+
+    /// Resampling filter
+    #[arg(long, value_enum, default_value_t = FilterArg::Lanczos3)]
+    pub filter: FilterArg,
+
+    /// When to resize in linear light rather than on gamma-encoded values
+    #[arg(long, value_enum, default_value_t = LinearLightArg::Auto)]
+    pub linear_light: LinearLightArg,
+
+    /// Resize colour and alpha independently instead of premultiplying
+    #[arg(long)]
+    pub no_premultiply: bool,
+
     /// Output format (defaults to input format)
     #[arg(short, long)]
     pub format: Option<FormatArg>,
@@ -30,27 +70,70 @@ pub struct ResizeArgs {
     #[arg(short, long, default_value_t = 80)]
     pub quality: u8,
 
+    /// Encode losslessly (PNG, WebP, JXL, QOI only)
+    #[arg(long)]
+    pub lossless: bool,
+
+    /// Metadata to keep in the output
+    #[arg(long, value_enum, default_value_t = MetadataArg::Safe)]
+    pub metadata: MetadataArg,
+
+    /// How to handle the source ICC colour profile
+    #[arg(long, value_enum, default_value_t = ColorArg::Preserve)]
+    pub color_profile: ColorArg,
+
+    /// Tone-mapping operator for HDR sources written to SDR formats
+    #[arg(long, value_enum, default_value_t = ToneMapArg::Reinhard)]
+    pub tone_map: ToneMapArg,
+
+    /// Keep pixels as stored instead of applying the EXIF orientation
+    #[arg(long)]
+    pub no_auto_orient: bool,
+
+    #[command(flatten)]
+    pub codec: CodecArgs,
+
     /// Output path
     #[arg(short, long)]
     pub output: Option<PathBuf>,
 }
 
-pub fn run(args: ResizeArgs) -> anyhow::Result<()> {
-    let resize_mode = match (args.width, args.height, args.scale) {
-        (Some(w), Some(h), None) => ResizeMode::Fit(w, h),
-        (Some(w), None, None) => ResizeMode::Width(w),
-        (None, Some(h), None) => ResizeMode::Height(h),
-        (None, None, Some(s)) => ResizeMode::Scale(s),
-        (None, None, None) => {
-            anyhow::bail!("specify at least one of --width, --height, or --scale");
+fn build_resize_mode(args: &ResizeArgs, format: Format) -> anyhow::Result<ResizeMode> {
+    let mode = match (args.width, args.height, args.scale, args.max_megapixels) {
+        (Some(w), Some(h), None, None) if args.cover => ResizeMode::Cover(w, h),
+        (Some(w), Some(h), None, None) if args.contain => {
+            let fill = build_fill_color(args.color.as_deref(), args.transparent, format)?;
+            ResizeMode::Contain(w, h, fill)
+        }
+        (Some(w), Some(h), None, None) => ResizeMode::Fit(w, h),
+        (Some(w), None, None, None) => ResizeMode::Width(w),
+        (None, Some(h), None, None) => ResizeMode::Height(h),
+        (None, None, Some(s), None) => ResizeMode::Scale(s),
+        (None, None, None, Some(megapixels)) => {
+            if megapixels <= 0.0 {
+                anyhow::bail!("--max-megapixels must be positive");
+            }
+            ResizeMode::MaxPixels((megapixels * 1_000_000.0) as u64)
+        }
+        (None, None, None, None) => {
+            anyhow::bail!(
+                "specify at least one of --width, --height, --scale, or --max-megapixels"
+            );
         }
         _ => {
             anyhow::bail!("--scale cannot be combined with --width or --height");
         }
     };
+    Ok(mode)
+}
 
+pub fn run(args: ResizeArgs) -> anyhow::Result<()> {
     let original_size = std::fs::metadata(&args.input)?.len();
-    let (image, src_format) = decode_file(&args.input)?;
+    let decode_options = DecodeOptions {
+        auto_orient: !args.no_auto_orient,
+        ..Default::default()
+    };
+    let (animation, src_format) = decode_animation_file_with(&args.input, &decode_options)?;
 
     let target_format = args.format.map(|f| f.into_format()).unwrap_or(src_format);
 
@@ -58,16 +141,30 @@ pub fn run(args: ResizeArgs) -> anyhow::Result<()> {
         anyhow::bail!("cannot encode to {} format", target_format.extension());
     }
 
+    if args.lossless && !target_format.supports_lossless() {
+        anyhow::bail!("cannot encode {} losslessly", target_format.extension());
+    }
+
+    let resize_mode = build_resize_mode(&args, target_format)?;
+
     let options = PipelineOptions {
-        format: target_format,
         quality: args.quality,
+        lossless: args.lossless,
+        metadata: args.metadata.into_policy(),
+        color: args.color_profile.into_policy(),
+        tone_map: args.tone_map.into_operator(),
         resize: Some(resize_mode),
-        crop: None,
-        extend: None,
-        fill_color: None,
+        resize_options: ResizeOptions {
+            filter: args.filter.into_filter(),
+            without_enlargement: args.without_enlargement,
+            linear_light: args.linear_light.into_linear_light(),
+            premultiply_alpha: !args.no_premultiply,
+        },
+        codec: args.codec.to_options(),
+        ..PipelineOptions::new(target_format)
     };
 
-    let result = convert(&image, &options)?;
+    let result = convert_animation(&animation, &options)?;
 
     let out = output_path(&args.input, target_format, args.output.as_deref());
     if let Some(parent) = out.parent() {
@@ -93,3 +190,48 @@ pub fn run(args: ResizeArgs) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use slimg_core::FillColor;
+
+    #[derive(Parser)]
+    struct ResizeCli {
+        #[command(flatten)]
+        args: ResizeArgs,
+    }
+
+    fn parse_mode(args: &[&str]) -> anyhow::Result<ResizeMode> {
+        let argv = ["slimg", "photo.jpg"].into_iter().chain(args.iter().copied());
+        let cli = ResizeCli::try_parse_from(argv)?;
+        build_resize_mode(&cli.args, Format::Png)
+    }
+
+    #[test]
+    fn cover_and_contain_take_both_sides() {
+        let size = ["--width", "200", "--height", "100"];
+        let with = |flags: &[&str]| parse_mode(&[&size[..], flags].concat());
+
+        assert_eq!(with(&[]).unwrap(), ResizeMode::Fit(200, 100));
+        assert_eq!(with(&["--cover"]).unwrap(), ResizeMode::Cover(200, 100));
+        assert_eq!(
+            with(&["--contain", "--transparent"]).unwrap(),
+            ResizeMode::Contain(200, 100, FillColor::Transparent)
+        );
+        assert!(with(&["--cover", "--contain"]).is_err());
+        assert!(parse_mode(&["--width", "200", "--cover"]).is_err());
+        assert!(parse_mode(&["--width", "200", "--color", "000000"]).is_err());
+    }
+
+    #[test]
+    fn max_megapixels_stands_alone() {
+        assert_eq!(
+            parse_mode(&["--max-megapixels", "1.5"]).unwrap(),
+            ResizeMode::MaxPixels(1_500_000)
+        );
+        assert!(parse_mode(&["--max-megapixels", "2", "--width", "10"]).is_err());
+        assert!(parse_mode(&["--max-megapixels", "0"]).is_err());
+    }
+}
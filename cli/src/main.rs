@@ -24,6 +24,10 @@ enum Commands {
     Crop(commands::crop::CropArgs),
     /// Extend image by adding padding with optional format conversion
     Extend(commands::extend::ExtendArgs),
+    /// Score an image against a reference with PSNR, SSIM, DSSIM and SSIMULACRA2
+    Compare(commands::compare::CompareArgs),
+    /// Show format, dimensions and metadata read from the file headers
+    Info(commands::info::InfoArgs),
     /// Generate shell completions
     Completions {
         /// Shell to generate completions for
@@ -40,6 +44,8 @@ fn main() -> anyhow::Result<()> {
         Commands::Resize(args) => commands::resize::run(args),
         Commands::Crop(args) => commands::crop::run(args),
         Commands::Extend(args) => commands::extend::run(args),
+        Commands::Compare(args) => commands::compare::run(args),
+        Commands::Info(args) => commands::info::run(args),
         Commands::Completions { shell } => {
             clap_complete::generate(shell, &mut Cli::command(), "slimg", &mut io::stdout());
             Ok(())
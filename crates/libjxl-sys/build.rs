@@ -89,17 +89,27 @@ fn run_bindgen(src_include: &Path, install_include: &Path, out_file: &Path) {
         .clang_arg(format!("-I{}", src_include.display()))
         .clang_arg(format!("-I{}", install_include.display()))
         .clang_arg(format!("--target={target}"))
+        // Keep in sync with .github/workflows/build-libjxl-prebuilt.yml. The
+        // prebuilt bindings are fetched by crate version, so any change here
+        // needs a version bump and a new libjxl-prebuilt release.
         // Encoder functions
         .allowlist_function("JxlEncoderCreate")
         .allowlist_function("JxlEncoderDestroy")
         .allowlist_function("JxlEncoderReset")
         .allowlist_function("JxlEncoderSetBasicInfo")
         .allowlist_function("JxlEncoderSetColorEncoding")
+        .allowlist_function("JxlEncoderSetICCProfile")
         .allowlist_function("JxlEncoderFrameSettingsCreate")
         .allowlist_function("JxlEncoderSetFrameDistance")
         .allowlist_function("JxlEncoderSetFrameLossless")
         .allowlist_function("JxlEncoderFrameSettingsSetOption")
         .allowlist_function("JxlEncoderAddImageFrame")
+        .allowlist_function("JxlEncoderInitFrameHeader")
+        .allowlist_function("JxlEncoderSetFrameHeader")
+        .allowlist_function("JxlEncoderUseBoxes")
+        .allowlist_function("JxlEncoderAddBox")
+        .allowlist_function("JxlEncoderAddJPEGFrame")
+        .allowlist_function("JxlEncoderStoreJPEGMetadata")
         .allowlist_function("JxlEncoderCloseInput")
         .allowlist_function("JxlEncoderProcessOutput")
         .allowlist_function("JxlEncoderDistanceFromQuality")
@@ -113,9 +123,20 @@ fn run_bindgen(src_include: &Path, install_include: &Path, out_file: &Path) {
         .allowlist_function("JxlDecoderCloseInput")
         .allowlist_function("JxlDecoderProcessInput")
         .allowlist_function("JxlDecoderGetBasicInfo")
+        .allowlist_function("JxlDecoderGetColorAsEncodedProfile")
+        .allowlist_function("JxlDecoderGetICCProfileSize")
+        .allowlist_function("JxlDecoderGetColorAsICCProfile")
+        .allowlist_function("JxlDecoderSetDecompressBoxes")
+        .allowlist_function("JxlDecoderGetBoxType")
+        .allowlist_function("JxlDecoderSetBoxBuffer")
+        .allowlist_function("JxlDecoderReleaseBoxBuffer")
         .allowlist_function("JxlDecoderImageOutBufferSize")
         .allowlist_function("JxlDecoderSetImageOutBuffer")
         .allowlist_function("JxlDecoderReleaseInput")
+        .allowlist_function("JxlDecoderSetKeepOrientation")
+        .allowlist_function("JxlDecoderGetFrameHeader")
+        .allowlist_function("JxlDecoderSetJPEGBuffer")
+        .allowlist_function("JxlDecoderReleaseJPEGBuffer")
         // Encoder types
         .allowlist_type("JxlEncoderStatus")
         .allowlist_type("JxlEncoderFrameSettingId")
@@ -130,6 +151,9 @@ fn run_bindgen(src_include: &Path, install_include: &Path, out_file: &Path) {
         .allowlist_type("JxlDataType")
         .allowlist_type("JxlEndianness")
         .allowlist_type("JxlColorEncoding")
+        .allowlist_type("JxlColorProfileTarget")
+        .allowlist_type("JxlBoxType")
+        .allowlist_type("JxlFrameHeader")
         .generate()
         .expect("failed to generate libjxl bindings")
         .write_to_file(out_file)
@@ -1,9 +1,16 @@
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use image::imageops::FilterType;
+use image::{DynamicImage, RgbaImage};
 use slimg_core::codec::{get_codec, EncodeOptions};
-use slimg_core::resize::resize;
-use slimg_core::{convert, optimize, Format, ImageData, PipelineOptions, ResizeMode};
+use slimg_core::resize::{resize, resize_with};
+use slimg_core::{
+    convert, optimize, FillColor, Format, ImageData, LinearLight, PipelineOptions, ResizeFilter,
+    ResizeMode, ResizeOptions,
+};
 
 const BENCH_IMAGE_SIZE: u32 = 512;
+/// Large enough for the resampling itself, not the setup, to dominate.
+const LARGE_IMAGE_SIZE: u32 = 2048;
 
 fn generate_test_image(width: u32, height: u32) -> ImageData {
     let mut data = vec![0u8; (width * height * 4) as usize];
@@ -22,7 +29,10 @@ fn generate_test_image(width: u32, height: u32) -> ImageData {
 /// Pre-encode a test image in the given format and return the encoded bytes.
 fn pre_encode(image: &ImageData, format: Format, quality: u8) -> Vec<u8> {
     let codec = get_codec(format);
-    let options = EncodeOptions { quality };
+    let options = EncodeOptions {
+        quality,
+        ..Default::default()
+    };
     codec.encode(image, &options).unwrap()
 }
 
@@ -46,14 +56,7 @@ fn bench_convert(c: &mut Criterion) {
         let codec = get_codec(*src_format);
         let decoded = codec.decode(&encoded).unwrap();
 
-        let options = PipelineOptions {
-            format: *dst_format,
-            quality: 80,
-            resize: None,
-            crop: None,
-            extend: None,
-            fill_color: None,
-        };
+        let options = PipelineOptions::new(*dst_format);
 
         group.bench_with_input(
             BenchmarkId::from_parameter(name),
@@ -83,11 +86,12 @@ fn bench_optimize(c: &mut Criterion) {
         let encoded = pre_encode(&image, *format, 90);
 
         group.throughput(Throughput::Bytes(encoded.len() as u64));
+        let options = EncodeOptions::default();
         group.bench_with_input(
             BenchmarkId::from_parameter(name),
             &encoded,
             |b, data| {
-                b.iter(|| optimize(data, 80).unwrap());
+                b.iter(|| optimize(data, &options).unwrap());
             },
         );
     }
@@ -104,6 +108,8 @@ fn bench_resize(c: &mut Criterion) {
         ("exact_256x256", ResizeMode::Exact(256, 256)),
         ("scale_0.5", ResizeMode::Scale(0.5)),
         ("fit_256x256", ResizeMode::Fit(256, 256)),
+        ("cover_256x128", ResizeMode::Cover(256, 128)),
+        ("contain_256x128", ResizeMode::Contain(256, 128, FillColor::Transparent)),
     ];
 
     let pixel_count = (BENCH_IMAGE_SIZE as u64) * (BENCH_IMAGE_SIZE as u64);
@@ -123,5 +129,79 @@ fn bench_resize(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_convert, bench_optimize, bench_resize);
+fn bench_resize_filter(c: &mut Criterion) {
+    let image = generate_test_image(LARGE_IMAGE_SIZE, LARGE_IMAGE_SIZE);
+    let mode = ResizeMode::Scale(0.5);
+
+    let filters = [
+        ("nearest", ResizeFilter::Nearest),
+        ("bilinear", ResizeFilter::Bilinear),
+        ("catmull_rom", ResizeFilter::CatmullRom),
+        ("mitchell", ResizeFilter::Mitchell),
+        ("lanczos3", ResizeFilter::Lanczos3),
+    ];
+
+    let pixel_count = (LARGE_IMAGE_SIZE as u64) * (LARGE_IMAGE_SIZE as u64);
+    let mut group = c.benchmark_group("resize_filter");
+    group.throughput(Throughput::Elements(pixel_count));
+
+    for (name, filter) in filters {
+        let options = ResizeOptions {
+            filter,
+            ..Default::default()
+        };
+        group.bench_with_input(BenchmarkId::from_parameter(name), &image, |b, image| {
+            b.iter(|| resize_with(image, &mode, &options).unwrap());
+        });
+    }
+
+    // Filtering the encoded values skips the linear-light round trip.
+    let options = ResizeOptions {
+        linear_light: LinearLight::Never,
+        ..Default::default()
+    };
+    group.bench_with_input(
+        BenchmarkId::from_parameter("lanczos3_encoded"),
+        &image,
+        |b, image| {
+            b.iter(|| resize_with(image, &mode, &options).unwrap());
+        },
+    );
+
+    // A slight downscale stays on the encoded values by default.
+    let options = ResizeOptions::default();
+    group.bench_with_input(
+        BenchmarkId::from_parameter("lanczos3_slight"),
+        &image,
+        |b, image| {
+            b.iter(|| resize_with(image, &ResizeMode::Scale(0.8), &options).unwrap());
+        },
+    );
+
+    // The previous backend: copy into a DynamicImage, resize with the image
+    // crate, copy back out. Compare against `lanczos3` above.
+    group.bench_with_input(
+        BenchmarkId::from_parameter("image_crate_lanczos3"),
+        &image,
+        |b, image| {
+            let (width, height) = (image.width / 2, image.height / 2);
+            b.iter(|| {
+                let rgba = RgbaImage::from_raw(image.width, image.height, image.data.clone());
+                let dynamic = DynamicImage::ImageRgba8(rgba.unwrap());
+                let resized = dynamic.resize(width, height, FilterType::Lanczos3);
+                ImageData::new(width, height, resized.to_rgba8().into_raw())
+            });
+        },
+    );
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_convert,
+    bench_optimize,
+    bench_resize,
+    bench_resize_filter
+);
 criterion_main!(benches);
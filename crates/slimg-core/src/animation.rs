@@ -0,0 +1,192 @@
+use crate::codec::ImageData;
+use crate::error::{Error, Result};
+use crate::metadata::Metadata;
+
+/// What a player does with a frame once its duration is over.
+///
+/// Frames in an [`Animation`] are already composited, so disposal does not
+/// change how a frame looks. It is kept so formats that store it can write
+/// it back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Disposal {
+    /// Leave the frame on the canvas.
+    #[default]
+    None,
+    /// Clear the frame's area to transparent.
+    Background,
+    /// Restore the canvas to its state before the frame.
+    Previous,
+}
+
+/// A single frame of an [`Animation`].
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// The whole canvas as it looks while this frame is shown.
+    pub image: ImageData,
+    /// How long the frame is shown, in milliseconds.
+    pub duration_ms: u32,
+    /// Disposal requested by the source file.
+    pub disposal: Disposal,
+}
+
+impl Frame {
+    /// A frame shown for `duration_ms` with no disposal.
+    pub fn new(image: ImageData, duration_ms: u32) -> Self {
+        Self {
+            image,
+            duration_ms,
+            disposal: Disposal::None,
+        }
+    }
+}
+
+/// A sequence of full-canvas frames.
+///
+/// Every frame has the same dimensions and pixel format. Each frame's image
+/// carries the file's metadata; encoders write the metadata of the first.
+#[derive(Debug, Clone)]
+pub struct Animation {
+    /// The frames in display order.
+    pub frames: Vec<Frame>,
+    /// How many times the animation plays. `0` loops forever.
+    pub loop_count: u32,
+}
+
+impl Animation {
+    /// A one-frame animation holding a still image.
+    pub fn from_still(image: ImageData) -> Self {
+        Self {
+            frames: vec![Frame::new(image, 0)],
+            loop_count: 0,
+        }
+    }
+
+    /// Whether there is more than one frame.
+    pub fn is_animated(&self) -> bool {
+        self.frames.len() > 1
+    }
+
+    /// Canvas width in pixels.
+    pub fn width(&self) -> u32 {
+        self.frames.first().map_or(0, |f| f.image.width)
+    }
+
+    /// Canvas height in pixels.
+    pub fn height(&self) -> u32 {
+        self.frames.first().map_or(0, |f| f.image.height)
+    }
+
+    /// Metadata of the first frame.
+    pub fn metadata(&self) -> Option<&Metadata> {
+        self.frames.first().map(|f| &f.image.metadata)
+    }
+
+    /// Total display time of one loop in milliseconds.
+    pub fn duration_ms(&self) -> u64 {
+        self.frames.iter().map(|f| u64::from(f.duration_ms)).sum()
+    }
+
+    /// The first frame's image, dropping the rest.
+    pub fn into_first_frame(self) -> Option<ImageData> {
+        self.frames.into_iter().next().map(|f| f.image)
+    }
+
+    /// Apply `f` to every frame's image, keeping timing and disposal.
+    pub fn try_map_frames(
+        &self,
+        mut f: impl FnMut(&ImageData) -> Result<ImageData>,
+    ) -> Result<Animation> {
+        let frames = self
+            .frames
+            .iter()
+            .map(|frame| {
+                Ok(Frame {
+                    image: f(&frame.image)?,
+                    duration_ms: frame.duration_ms,
+                    disposal: frame.disposal,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Animation {
+            frames,
+            loop_count: self.loop_count,
+        })
+    }
+
+    /// Check that there is at least one frame and that every frame matches
+    /// the first in size and pixel format.
+    pub(crate) fn validate(&self) -> Result<()> {
+        let Some(first) = self.frames.first() else {
+            return Err(Error::Encode("animation has no frames".to_string()));
+        };
+        let first = &first.image;
+        for (i, frame) in self.frames.iter().enumerate().skip(1) {
+            let image = &frame.image;
+            if (image.width, image.height) != (first.width, first.height) {
+                return Err(Error::Encode(format!(
+                    "frame {i} is {}x{}, expected {}x{}",
+                    image.width, image.height, first.width, first.height
+                )));
+            }
+            if image.pixel_format != first.pixel_format {
+                return Err(Error::Encode(format!(
+                    "frame {i} is {:?}, expected {:?}",
+                    image.pixel_format, first.pixel_format
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(width: u32, height: u32, value: u8, duration_ms: u32) -> Frame {
+        let data = vec![value; (width * height * 4) as usize];
+        Frame::new(ImageData::new(width, height, data), duration_ms)
+    }
+
+    #[test]
+    fn still_image_is_a_single_frame() {
+        let animation = Animation::from_still(ImageData::new(3, 2, vec![0; 24]));
+
+        assert!(!animation.is_animated());
+        assert_eq!((animation.width(), animation.height()), (3, 2));
+        assert!(animation.validate().is_ok());
+    }
+
+    #[test]
+    fn try_map_frames_keeps_timing() {
+        let mut animation = Animation {
+            frames: vec![frame(4, 4, 10, 100), frame(4, 4, 20, 250)],
+            loop_count: 3,
+        };
+        animation.frames[1].disposal = Disposal::Previous;
+
+        let mapped = animation
+            .try_map_frames(|image| Ok(ImageData::new(1, 1, image.data[..4].to_vec())))
+            .unwrap();
+
+        assert_eq!(mapped.loop_count, 3);
+        assert_eq!(mapped.duration_ms(), 350);
+        assert_eq!(mapped.frames[1].disposal, Disposal::Previous);
+        assert_eq!(mapped.frames[1].image.data, vec![20; 4]);
+    }
+
+    #[test]
+    fn validate_rejects_mismatched_frames() {
+        let empty = Animation {
+            frames: Vec::new(),
+            loop_count: 0,
+        };
+        assert!(empty.validate().is_err());
+
+        let mixed = Animation {
+            frames: vec![frame(4, 4, 0, 100), frame(2, 4, 0, 100)],
+            loop_count: 0,
+        };
+        assert!(mixed.validate().is_err());
+    }
+}
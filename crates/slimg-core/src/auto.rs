@@ -0,0 +1,183 @@
+use std::fmt;
+
+use crate::error::{Error, Result};
+use crate::format::Format;
+use crate::pipeline::PipelineResult;
+
+/// Pick the output format by encoding several and keeping the smallest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoFormat {
+    /// Formats to try, in order of preference when sizes tie. Repeats are
+    /// ignored.
+    pub candidates: Vec<Format>,
+}
+
+impl Default for AutoFormat {
+    /// WebP, AVIF and JPEG XL.
+    fn default() -> Self {
+        Self {
+            candidates: vec![Format::WebP, Format::Avif, Format::Jxl],
+        }
+    }
+}
+
+impl AutoFormat {
+    /// The candidates with repeats removed, or an error when there are none.
+    pub(crate) fn formats(&self) -> Result<Vec<Format>> {
+        let mut formats = Vec::with_capacity(self.candidates.len());
+        for &format in &self.candidates {
+            if !formats.contains(&format) {
+                formats.push(format);
+            }
+        }
+        if formats.is_empty() {
+            return Err(Error::Encode(
+                "no candidate formats to choose from".to_string(),
+            ));
+        }
+        Ok(formats)
+    }
+}
+
+/// A candidate format that lost to the one in [`PipelineResult::format`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rejected {
+    pub format: Format,
+    pub reason: Rejection,
+}
+
+/// Why a candidate format lost.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rejection {
+    /// It encoded, but to more bytes than the winner.
+    Larger { bytes: u64 },
+    /// It could not be encoded, for example because the format cannot hold
+    /// an animation or fit the size limit. Holds the error message.
+    Failed(String),
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Larger { bytes } => write!(f, "larger ({bytes} bytes)"),
+            Self::Failed(message) => write!(f, "failed: {message}"),
+        }
+    }
+}
+
+/// Keep the smallest successful encode, listing why every other candidate
+/// lost. Ties go to the earlier candidate. When nothing encoded, the first
+/// candidate's error is returned.
+pub(crate) fn choose(outcomes: Vec<(Format, Result<PipelineResult>)>) -> Result<PipelineResult> {
+    let winner = outcomes
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (_, outcome))| Some((i, outcome.as_ref().ok()?.data.len())))
+        .min_by_key(|&(i, len)| (len, i))
+        .map(|(i, _)| i);
+    let Some(winner) = winner else {
+        let mut errors = outcomes
+            .into_iter()
+            .filter_map(|(_, outcome)| outcome.err());
+        return Err(errors.next().expect("at least one candidate"));
+    };
+
+    let mut rejected = Vec::with_capacity(outcomes.len() - 1);
+    let mut chosen = None;
+    for (i, (format, outcome)) in outcomes.into_iter().enumerate() {
+        match outcome {
+            Ok(result) if i == winner => chosen = Some(result),
+            Ok(result) => rejected.push(Rejected {
+                format,
+                reason: Rejection::Larger {
+                    bytes: result.data.len() as u64,
+                },
+            }),
+            Err(e) => rejected.push(Rejected {
+                format,
+                reason: Rejection::Failed(e.to_string()),
+            }),
+        }
+    }
+    let mut chosen = chosen.expect("winner is one of the outcomes");
+    chosen.rejected = rejected;
+    Ok(chosen)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(format: Format, len: usize) -> (Format, Result<PipelineResult>) {
+        let result = PipelineResult {
+            data: vec![0; len],
+            format,
+            width: 1,
+            height: 1,
+            quality: Some(80),
+            score: None,
+            rejected: Vec::new(),
+            kept_original: false,
+        };
+        (format, Ok(result))
+    }
+
+    #[test]
+    fn smallest_candidate_wins_and_the_rest_say_why() {
+        let outcomes = vec![
+            encoded(Format::WebP, 300),
+            (Format::Jxl, Err(Error::Encode("no encoder".to_string()))),
+            encoded(Format::Avif, 200),
+        ];
+
+        let result = choose(outcomes).unwrap();
+
+        assert_eq!(result.format, Format::Avif);
+        assert_eq!(
+            result.rejected,
+            vec![
+                Rejected {
+                    format: Format::WebP,
+                    reason: Rejection::Larger { bytes: 300 },
+                },
+                Rejected {
+                    format: Format::Jxl,
+                    reason: Rejection::Failed("encode error: no encoder".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ties_go_to_the_earlier_candidate() {
+        let outcomes = vec![encoded(Format::Avif, 100), encoded(Format::WebP, 100)];
+
+        assert_eq!(choose(outcomes).unwrap().format, Format::Avif);
+    }
+
+    #[test]
+    fn all_failures_return_the_first_error() {
+        let outcomes = vec![
+            (Format::Qoi, Err(Error::AnimationNotSupported(Format::Qoi))),
+            (
+                Format::Jpeg,
+                Err(Error::AnimationNotSupported(Format::Jpeg)),
+            ),
+        ];
+
+        assert!(matches!(
+            choose(outcomes),
+            Err(Error::AnimationNotSupported(Format::Qoi))
+        ));
+    }
+
+    #[test]
+    fn repeated_candidates_are_tried_once() {
+        let auto = AutoFormat {
+            candidates: vec![Format::WebP, Format::Avif, Format::WebP],
+        };
+
+        assert_eq!(auto.formats().unwrap(), vec![Format::WebP, Format::Avif]);
+        assert!(AutoFormat { candidates: vec![] }.formats().is_err());
+    }
+}
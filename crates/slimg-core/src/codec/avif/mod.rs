@@ -0,0 +1,844 @@
+mod sequence;
+
+use avif_serialize::Aviffy;
+use avif_serialize::constants as isobmff;
+use imgref::Img;
+use mp4parse::{AvifContext, ImageMirror, ImageRotation, ParseStrictness};
+use rav1e::prelude as av1;
+use ravif::{EncodedImage, MatrixCoefficients, PixelRange};
+use rgb::RGBA8;
+
+use crate::animation::Animation;
+use crate::color::prepare_for_encode;
+use crate::error::{Error, Result};
+use crate::format::Format;
+use crate::hdr::{ColorPrimaries, ContentLightLevel, HdrMetadata, TransferFunction};
+use crate::limits::DecodeLimits;
+use crate::metadata::Metadata;
+use crate::orientation::Orientation;
+
+use super::{Codec, EncodeOptions, ImageData, ImageInfo, PixelFormat, decode_with_image};
+
+/// AVIF encoder settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AvifOptions {
+    /// Encoder speed (1..=10). Lower is slower but smaller.
+    pub speed: u8,
+    /// Quality of the alpha plane (1..=100). `None` keeps the encoder default.
+    pub alpha_quality: Option<u8>,
+}
+
+impl Default for AvifOptions {
+    fn default() -> Self {
+        Self {
+            speed: 6,
+            alpha_quality: None,
+        }
+    }
+}
+
+/// AVIF codec backed by ravif for encoding and the `image` crate for decoding.
+/// HDR images are encoded with rav1e directly, since ravif always signals
+/// sRGB. Image sequences are muxed here and decoded with dav1d.
+pub struct AvifCodec;
+
+impl Codec for AvifCodec {
+    fn format(&self) -> Format {
+        Format::Avif
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<ImageData> {
+        let mut image = decode_with_image(data, image::ImageFormat::Avif)
+            .map_err(|e| Error::Decode(format!("avif decode: {e}")))?;
+        image.metadata.orientation = container_orientation(data);
+        image.metadata.hdr = container_hdr(data);
+        Ok(image)
+    }
+
+    fn probe(&self, data: &[u8]) -> Result<ImageInfo> {
+        let ctx = mp4parse::read_avif(&mut &data[..], ParseStrictness::Normal)
+            .map_err(|e| Error::Decode(format!("avif header: {e:?}")))?;
+        let (size, bit_depth) = primary_layout(data)
+            .ok_or_else(|| Error::Decode("avif header: no ispe or av1C property".to_string()))?;
+        let metadata = Metadata {
+            icc: ctx
+                .icc_colour_information()
+                .and_then(|icc| icc.ok())
+                .map(<[u8]>::to_vec),
+            orientation: orientation_of(&ctx),
+            hdr: container_hdr(data),
+            ..Default::default()
+        };
+        let frame_count = sequence::frame_count(&ctx).unwrap_or(1).max(1);
+        Ok(ImageInfo {
+            frame_count: frame_count.min(u32::MAX as usize) as u32,
+            ..ImageInfo::still(
+                Format::Avif,
+                size,
+                bit_depth,
+                ctx.alpha_item_is_present(),
+                &metadata,
+            )
+        })
+    }
+
+    fn encode(&self, image: &ImageData, options: &EncodeOptions) -> Result<Vec<u8>> {
+        if options.lossless {
+            return Err(Error::LosslessNotSupported(Format::Avif));
+        }
+
+        // ravif cannot embed an ICC profile.
+        let image = &*prepare_for_encode(image, options, Format::Avif);
+        let avif = options.codec.avif;
+
+        if let Some(hdr) = image.metadata.hdr {
+            return encode_hdr(image, &hdr, options);
+        }
+
+        let mut encoder = ravif::Encoder::new()
+            .with_quality(options.quality as f32)
+            .with_speed(avif.speed.clamp(1, 10));
+        if let Some(alpha_quality) = avif.alpha_quality {
+            encoder = encoder.with_alpha_quality(alpha_quality.clamp(1, 100) as f32);
+        }
+        // ravif can only embed EXIF; XMP is dropped.
+        if let Some(exif) = image.metadata.filter(options.metadata).exif {
+            encoder = encoder.with_exif(exif);
+        }
+
+        let encoded = match image.pixel_format {
+            PixelFormat::Rgba8 => encode_rgba8(&encoder, image),
+            _ => encode_10_bit(&encoder, image),
+        }
+        .map_err(|e| Error::Encode(format!("ravif encode: {e}")))?;
+
+        Ok(encoded.avif_file)
+    }
+
+    fn decode_animation(&self, data: &[u8]) -> Result<Animation> {
+        self.decode_animation_limited(data, &DecodeLimits::default())
+    }
+
+    /// A sequence is checked against `limits` while it is decoded, rather
+    /// than by a separate probe.
+    fn decode_animation_limited(&self, data: &[u8], limits: &DecodeLimits) -> Result<Animation> {
+        let Some(mut animation) = sequence::decode(data, limits)? else {
+            return self.decode_limited(data, limits).map(Animation::from_still);
+        };
+        let metadata = container_metadata(data);
+        for frame in &mut animation.frames {
+            frame.image.metadata = metadata.clone();
+        }
+        Ok(animation)
+    }
+
+    fn encode_animation(&self, animation: &Animation, options: &EncodeOptions) -> Result<Vec<u8>> {
+        animation.validate()?;
+        match animation.frames.as_slice() {
+            [frame] => self.encode(&frame.image, options),
+            _ if options.lossless => Err(Error::LosslessNotSupported(Format::Avif)),
+            _ => sequence::encode(animation, options),
+        }
+    }
+}
+
+/// Metadata of the primary image without decoding its pixels.
+fn container_metadata(data: &[u8]) -> Metadata {
+    let mut metadata =
+        image::ImageReader::with_format(std::io::Cursor::new(data), image::ImageFormat::Avif)
+            .into_decoder()
+            .map(|mut decoder| Metadata::from_decoder(&mut decoder))
+            .unwrap_or_default();
+    metadata.orientation = container_orientation(data);
+    metadata.hdr = container_hdr(data);
+    metadata
+}
+
+fn encode_rgba8(
+    encoder: &ravif::Encoder,
+    image: &ImageData,
+) -> std::result::Result<EncodedImage, ravif::Error> {
+    let pixels: Vec<RGBA8> = image
+        .data
+        .chunks_exact(4)
+        .map(|px| RGBA8::new(px[0], px[1], px[2], px[3]))
+        .collect();
+    let buffer = Img::new(pixels.as_slice(), image.width as usize, image.height as usize);
+    encoder.encode_rgba(buffer)
+}
+
+/// BT.601 luma coefficients for red, green and blue, as ravif uses them.
+const BT601: [f32; 3] = [0.299, 0.587, 0.114];
+
+/// BT.2020 non-constant luminance coefficients, used for HDR output.
+const BT2020: [f32; 3] = [0.2627, 0.6780, 0.0593];
+
+/// Encode a high bit depth image straight into 10-bit full-range YCbCr,
+/// instead of letting ravif widen an 8-bit copy.
+fn encode_10_bit(
+    encoder: &ravif::Encoder,
+    image: &ImageData,
+) -> std::result::Result<EncodedImage, ravif::Error> {
+    let (planes, alpha) = ycbcr(image, BT601, 10);
+    encoder.encode_raw_planes_10_bit(
+        image.width as usize,
+        image.height as usize,
+        planes,
+        alpha,
+        PixelRange::Full,
+        MatrixCoefficients::BT601,
+    )
+}
+
+/// Convert to full-range YCbCr of `bit_depth` bits with the given luma
+/// coefficients, plus an alpha plane unless the image is opaque.
+fn ycbcr(image: &ImageData, luma: [f32; 3], bit_depth: u8) -> (Vec<[u16; 3]>, Option<Vec<u16>>) {
+    let samples = image.as_pixel_format(PixelFormat::Rgba16).rgba16_samples();
+    let max = ((1u32 << bit_depth) - 1) as f32;
+    let mid = (1u32 << (bit_depth - 1)) as f32;
+    let scale = |s: u16| s as f32 * (max / 65535.0);
+
+    let planes = samples
+        .chunks_exact(4)
+        .map(|px| {
+            let (r, g, b) = (scale(px[0]), scale(px[1]), scale(px[2]));
+            let y = luma[0] * r + luma[1] * g + luma[2] * b;
+            let cb = (b - y) * (0.5 / (1.0 - luma[2])) + mid;
+            let cr = (r - y) * (0.5 / (1.0 - luma[0])) + mid;
+            [y, cb, cr].map(|v| v.round().clamp(0.0, max) as u16)
+        })
+        .collect();
+    let alpha = samples
+        .chunks_exact(4)
+        .any(|px| px[3] != u16::MAX)
+        .then(|| {
+            samples
+                .chunks_exact(4)
+                .map(|px| scale(px[3]).round() as u16)
+                .collect()
+        });
+    (planes, alpha)
+}
+
+/// Encode PQ/HLG pixels as 10-bit BT.2020 YCbCr, signalling the transfer
+/// function and primaries in both the AV1 sequence header and the `colr`
+/// box, and the content light level in `clli`.
+fn encode_hdr(image: &ImageData, hdr: &HdrMetadata, options: &EncodeOptions) -> Result<Vec<u8>> {
+    let avif = options.codec.avif;
+    let width = image.width as usize;
+    let height = image.height as usize;
+    let speed = avif.speed.clamp(1, 10);
+    let quantizer = quality_to_quantizer(options.quality as f32);
+    let alpha_quantizer =
+        quality_to_quantizer(avif.alpha_quality.unwrap_or(options.quality).clamp(1, 100) as f32);
+
+    let (primaries, transfer) = hdr_signalling(hdr);
+
+    let (ycbcr, alpha) = ycbcr(image, BT2020, 10);
+    let color_config = av1::EncoderConfig {
+        width,
+        height,
+        bit_depth: 10,
+        chroma_sampling: av1::ChromaSampling::Cs444,
+        pixel_range: av1::PixelRange::Full,
+        color_description: Some(hdr_color_description(hdr)),
+        content_light: hdr_content_light(hdr),
+        still_picture: true,
+        quantizer: quantizer as usize,
+        min_quantizer: quantizer,
+        ..av1::EncoderConfig::with_speed_preset(speed)
+    };
+    let planes: [Vec<u16>; 3] = std::array::from_fn(|c| ycbcr.iter().map(|px| px[c]).collect());
+    let color = encode_still(color_config, &planes)?;
+
+    let alpha = match alpha {
+        Some(alpha) => {
+            let alpha_config = av1::EncoderConfig {
+                width,
+                height,
+                bit_depth: 10,
+                chroma_sampling: av1::ChromaSampling::Cs400,
+                pixel_range: av1::PixelRange::Full,
+                still_picture: true,
+                quantizer: alpha_quantizer as usize,
+                min_quantizer: alpha_quantizer,
+                ..av1::EncoderConfig::with_speed_preset(speed)
+            };
+            Some(encode_still(alpha_config, &[alpha])?)
+        }
+        None => None,
+    };
+
+    let mut aviffy = Aviffy::new();
+    aviffy
+        .set_color_primaries(primaries)
+        .set_transfer_characteristics(transfer)
+        .set_matrix_coefficients(isobmff::MatrixCoefficients::Bt2020Ncl)
+        .set_full_color_range(true);
+    if let Some(level) = hdr.content_light_level {
+        aviffy.set_content_light_level(level.max_cll, level.max_fall);
+    }
+    // Like the ravif path, only EXIF can be embedded.
+    if let Some(exif) = image.metadata.filter(options.metadata).exif {
+        aviffy.set_exif(exif);
+    }
+    Ok(aviffy.to_vec(&color, alpha.as_deref(), image.width, image.height, 10))
+}
+
+/// CICP primaries and transfer function for `hdr`, as written to `colr`.
+fn hdr_signalling(
+    hdr: &HdrMetadata,
+) -> (isobmff::ColorPrimaries, isobmff::TransferCharacteristics) {
+    let primaries = match hdr.primaries {
+        ColorPrimaries::Bt709 => isobmff::ColorPrimaries::Bt709,
+        ColorPrimaries::DisplayP3 => isobmff::ColorPrimaries::DisplayP3,
+        ColorPrimaries::Bt2020 => isobmff::ColorPrimaries::Bt2020,
+    };
+    let transfer = match hdr.transfer {
+        TransferFunction::Pq => isobmff::TransferCharacteristics::Smpte2084,
+        TransferFunction::Hlg => isobmff::TransferCharacteristics::Hlg,
+    };
+    (primaries, transfer)
+}
+
+/// The same signalling for the AV1 sequence header.
+fn hdr_color_description(hdr: &HdrMetadata) -> av1::ColorDescription {
+    av1::ColorDescription {
+        color_primaries: match hdr.primaries {
+            ColorPrimaries::Bt709 => av1::ColorPrimaries::BT709,
+            ColorPrimaries::DisplayP3 => av1::ColorPrimaries::SMPTE432,
+            ColorPrimaries::Bt2020 => av1::ColorPrimaries::BT2020,
+        },
+        transfer_characteristics: match hdr.transfer {
+            TransferFunction::Pq => av1::TransferCharacteristics::SMPTE2084,
+            TransferFunction::Hlg => av1::TransferCharacteristics::HLG,
+        },
+        matrix_coefficients: av1::MatrixCoefficients::BT2020NCL,
+    }
+}
+
+fn hdr_content_light(hdr: &HdrMetadata) -> Option<av1::ContentLight> {
+    hdr.content_light_level.map(|level| av1::ContentLight {
+        max_content_light_level: level.max_cll,
+        max_frame_average_light_level: level.max_fall,
+    })
+}
+
+/// Map 0..=100 quality to an AV1 quantizer the same way ravif does.
+fn quality_to_quantizer(quality: f32) -> u8 {
+    let q = quality / 100.0;
+    let x = if q >= 0.82 {
+        (1.0 - q) * 2.6
+    } else if q > 0.25 {
+        q.mul_add(-0.5, 1.0 - 0.125)
+    } else {
+        1.0 - q
+    };
+    (x * 255.0).round() as u8
+}
+
+/// Encode one still frame from row-major 10-bit `planes`.
+fn encode_still(config: av1::EncoderConfig, planes: &[Vec<u16>]) -> Result<Vec<u8>> {
+    let packets = encode_av1::<u16>(config, &[planes])?;
+    Ok(packets
+        .into_iter()
+        .filter(|packet| packet.frame_type == av1::FrameType::KEY)
+        .flat_map(|packet| packet.data)
+        .collect())
+}
+
+/// Encode `frames` of row-major planes, returning one packet per frame in
+/// display order. Samples are cast to `T`, so they must fit the configured
+/// bit depth.
+fn encode_av1<T: av1::Pixel>(
+    config: av1::EncoderConfig,
+    frames: &[&[Vec<u16>]],
+) -> Result<Vec<av1::Packet<T>>> {
+    let width = config.width;
+    let mut ctx: av1::Context<T> = av1::Config::new()
+        .with_encoder_config(config)
+        .new_context()
+        .map_err(|e| Error::Encode(format!("rav1e config: {e}")))?;
+
+    let mut out = Vec::with_capacity(frames.len());
+    let mut receive = |ctx: &mut av1::Context<T>| loop {
+        match ctx.receive_packet() {
+            Ok(packet) => out.push(packet),
+            Err(av1::EncoderStatus::Encoded) => {}
+            Err(av1::EncoderStatus::NeedMoreData | av1::EncoderStatus::LimitReached) => {
+                return Ok(());
+            }
+            Err(e) => return Err(Error::Encode(format!("rav1e encode: {e}"))),
+        }
+    };
+
+    for planes in frames {
+        let mut frame = ctx.new_frame();
+        for (plane, samples) in frame.planes.iter_mut().zip(planes.iter()) {
+            let mut slice = plane.mut_slice(Default::default());
+            for (row, src) in slice.rows_iter_mut().zip(samples.chunks_exact(width)) {
+                for (dst, &sample) in row[..width].iter_mut().zip(src) {
+                    *dst = T::cast_from(sample);
+                }
+            }
+        }
+        ctx.send_frame(frame)
+            .map_err(|e| Error::Encode(format!("rav1e encode: {e}")))?;
+        receive(&mut ctx)?;
+    }
+    ctx.flush();
+    receive(&mut ctx)?;
+    Ok(out)
+}
+
+/// HDR signalling from the primary image's `colr` (nclx) and `clli`
+/// properties, or `None` for SDR transfer functions.
+fn container_hdr(data: &[u8]) -> Option<HdrMetadata> {
+    let ipco = item_properties(data)?;
+
+    let be16 = |b: &[u8]| u16::from_be_bytes([b[0], b[1]]);
+    let mut hdr = None;
+    let mut content_light_level = None;
+    for (kind, body) in boxes(ipco) {
+        match kind {
+            b"colr" if body.len() >= 10 && body.starts_with(b"nclx") => {
+                hdr = hdr.or(HdrMetadata::from_cicp(be16(&body[4..]), be16(&body[6..])));
+            }
+            b"clli" if body.len() >= 4 => {
+                content_light_level = Some(ContentLightLevel {
+                    max_cll: be16(body),
+                    max_fall: be16(&body[2..]),
+                });
+            }
+            _ => {}
+        }
+    }
+    hdr.map(|hdr| HdrMetadata {
+        content_light_level,
+        ..hdr
+    })
+}
+
+/// Size from the first `ispe` property and bit depth from the first `av1C`,
+/// which belong to the primary image.
+fn primary_layout(data: &[u8]) -> Option<((u32, u32), u8)> {
+    let ipco = item_properties(data)?;
+
+    let be32 = |b: &[u8]| u32::from_be_bytes([b[0], b[1], b[2], b[3]]);
+    let mut size = None;
+    let mut bit_depth = None;
+    for (kind, body) in boxes(ipco) {
+        match kind {
+            // A full box: version and flags, then width and height.
+            b"ispe" if body.len() >= 12 && size.is_none() => {
+                size = Some((be32(&body[4..]), be32(&body[8..])));
+            }
+            // high_bitdepth and twelve_bit flags of the sequence header.
+            b"av1C" if body.len() >= 3 && bit_depth.is_none() => {
+                bit_depth = Some(match (body[2] & 0x40 != 0, body[2] & 0x20 != 0) {
+                    (true, true) => 12,
+                    (true, false) => 10,
+                    _ => 8,
+                });
+            }
+            _ => {}
+        }
+    }
+    Some((size?, bit_depth?))
+}
+
+/// Payload of the `ipco` box holding the item properties.
+fn item_properties(data: &[u8]) -> Option<&[u8]> {
+    let (_, meta) = boxes(data).find(|(kind, _)| *kind == b"meta")?;
+    // `meta` is a full box: skip its version and flags.
+    let (_, iprp) = boxes(meta.get(4..)?).find(|(kind, _)| *kind == b"iprp")?;
+    let (_, ipco) = boxes(iprp).find(|(kind, _)| *kind == b"ipco")?;
+    Some(ipco)
+}
+
+/// Iterate over the ISOBMFF boxes in `data` as `(type, payload)` pairs,
+/// stopping at the first malformed header.
+fn boxes(mut data: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    std::iter::from_fn(move || {
+        if data.len() < 8 {
+            return None;
+        }
+        let kind = &data[4..8];
+        let (header, size) = match u32::from_be_bytes([data[0], data[1], data[2], data[3]]) {
+            0 => (8, data.len() as u64),
+            1 if data.len() >= 16 => (16, u64::from_be_bytes(data[8..16].try_into().ok()?)),
+            size => (8, size as u64),
+        };
+        if size < header as u64 || size > data.len() as u64 {
+            return None;
+        }
+        let (current, rest) = data.split_at(size as usize);
+        data = rest;
+        Some((kind, &current[header..]))
+    })
+}
+
+/// Orientation from the `irot` and `imir` properties of the primary item.
+fn container_orientation(data: &[u8]) -> Orientation {
+    match mp4parse::read_avif(&mut &data[..], ParseStrictness::Normal) {
+        Ok(ctx) => orientation_of(&ctx),
+        Err(_) => Orientation::Normal,
+    }
+}
+
+/// Orientation from the primary item properties of a parsed container.
+fn orientation_of(ctx: &AvifContext) -> Orientation {
+    let rotation = ctx.image_rotation().unwrap_or(ImageRotation::D0);
+    // SAFETY: the pointer is either null or points into `ctx`, which is alive.
+    let mirror = ctx
+        .image_mirror_ptr()
+        .ok()
+        .and_then(|ptr| unsafe { ptr.as_ref() });
+    orientation_from_transforms(rotation, mirror)
+}
+
+/// HEIF applies `irot` (anti-clockwise) first, then `imir`.
+fn orientation_from_transforms(
+    rotation: ImageRotation,
+    mirror: Option<&ImageMirror>,
+) -> Orientation {
+    let rotated = match rotation {
+        ImageRotation::D0 => Orientation::Normal,
+        ImageRotation::D90 => Orientation::Rotate270,
+        ImageRotation::D180 => Orientation::Rotate180,
+        ImageRotation::D270 => Orientation::Rotate90,
+    };
+    match mirror {
+        None => rotated,
+        Some(ImageMirror::LeftRight) => rotated.then(Orientation::FlipHorizontal),
+        Some(ImageMirror::TopBottom) => rotated.then(Orientation::FlipVertical),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::Frame;
+    use crate::limits::Limit;
+    use crate::metadata::MetadataPolicy;
+
+    fn create_test_image(width: u32, height: u32) -> ImageData {
+        let size = (width * height * 4) as usize;
+        let mut data = vec![0u8; size];
+        for y in 0..height {
+            for x in 0..width {
+                let i = ((y * width + x) * 4) as usize;
+                data[i] = (x * 255 / width) as u8; // R
+                data[i + 1] = (y * 255 / height) as u8; // G
+                data[i + 2] = 128; // B
+                data[i + 3] = 255; // A
+            }
+        }
+        ImageData::new(width, height, data)
+    }
+
+    #[test]
+    fn encode_produces_valid_avif() {
+        let codec = AvifCodec;
+        let image = create_test_image(64, 48);
+        let options = EncodeOptions {
+            quality: 80,
+            ..Default::default()
+        };
+
+        let encoded = codec.encode(&image, &options).expect("encode failed");
+
+        // Verify AVIF container: bytes 4-7 should be "ftyp"
+        assert!(
+            encoded.len() >= 8,
+            "encoded data too short: {} bytes",
+            encoded.len()
+        );
+        assert_eq!(&encoded[4..8], b"ftyp", "missing AVIF ftyp box");
+    }
+
+    #[test]
+    fn encode_and_decode_roundtrip() {
+        let codec = AvifCodec;
+        let original = create_test_image(64, 48);
+        let options = EncodeOptions {
+            quality: 80,
+            ..Default::default()
+        };
+
+        let encoded = codec.encode(&original, &options).expect("encode failed");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.width, original.width);
+        assert_eq!(decoded.height, original.height);
+        assert_eq!(
+            decoded.data.len(),
+            (decoded.width * decoded.height) as usize * decoded.pixel_format.bytes_per_pixel()
+        );
+    }
+
+    #[test]
+    fn sixteen_bit_input_keeps_more_than_eight_bits() {
+        let codec = AvifCodec;
+        // A gentle ramp whose steps are finer than 8-bit resolution.
+        let samples: Vec<u16> = (0..32 * 32)
+            .flat_map(|i| {
+                let v = 20_000 + (i % 32) as u16 * 64;
+                [v, v, v, u16::MAX]
+            })
+            .collect();
+        let original = ImageData::from_rgba16(32, 32, samples);
+        let options = EncodeOptions {
+            quality: 100,
+            ..Default::default()
+        };
+
+        let encoded = codec.encode(&original, &options).expect("encode failed");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.pixel_format, PixelFormat::Rgba16);
+        let distinct: std::collections::BTreeSet<u16> = decoded
+            .rgba16_samples()
+            .chunks_exact(4)
+            .take(32)
+            .map(|px| px[0])
+            .collect();
+        // 32 steps of 64 span fewer than 8 distinct 8-bit levels.
+        assert!(distinct.len() > 8, "only {} levels survived", distinct.len());
+    }
+
+    #[test]
+    fn hdr_signalling_roundtrips() {
+        let codec = AvifCodec;
+        let hdr = HdrMetadata {
+            transfer: TransferFunction::Pq,
+            primaries: ColorPrimaries::Bt2020,
+            content_light_level: Some(ContentLightLevel {
+                max_cll: 1000,
+                max_fall: 400,
+            }),
+        };
+        let samples: Vec<u16> = (0..16 * 16)
+            .flat_map(|i| [40_000, 30_000 + i as u16 * 8, 20_000, u16::MAX])
+            .collect();
+        let original = ImageData::from_rgba16(16, 16, samples.clone()).with_metadata(Metadata {
+            hdr: Some(hdr),
+            ..Default::default()
+        });
+        let options = EncodeOptions {
+            quality: 90,
+            ..Default::default()
+        };
+
+        let encoded = codec.encode(&original, &options).expect("encode failed");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.metadata.hdr, Some(hdr));
+        assert_eq!(decoded.pixel_format, PixelFormat::Rgba16);
+        let px = decoded.rgba16_samples();
+        // The YCbCr round trip is approximate, as on the SDR path.
+        for (got, want) in px.iter().zip(&samples).take(4) {
+            assert!(got.abs_diff(*want) < 2048, "{got} vs {want}");
+        }
+    }
+
+    #[test]
+    fn sdr_file_has_no_hdr_metadata() {
+        let codec = AvifCodec;
+        let encoded = codec
+            .encode(&create_test_image(8, 8), &EncodeOptions::default())
+            .expect("encode failed");
+        assert_eq!(container_hdr(&encoded), None);
+    }
+
+    #[test]
+    fn lossless_is_rejected() {
+        let codec = AvifCodec;
+        let image = create_test_image(8, 8);
+        let options = EncodeOptions {
+            lossless: true,
+            ..Default::default()
+        };
+
+        let result = codec.encode(&image, &options);
+        assert!(matches!(
+            result,
+            Err(Error::LosslessNotSupported(Format::Avif))
+        ));
+    }
+
+    #[test]
+    fn exif_is_embedded() {
+        let codec = AvifCodec;
+        let image = create_test_image(16, 16).with_metadata(Metadata {
+            exif: Some(b"MM\0*\0\0\0\x08\0\0\0\0\0\0".to_vec()),
+            ..Default::default()
+        });
+        let options = EncodeOptions {
+            metadata: MetadataPolicy::Keep,
+            ..Default::default()
+        };
+
+        let encoded = codec.encode(&image, &options).expect("encode failed");
+
+        assert!(encoded.windows(4).any(|w| w == b"Exif"), "missing Exif item");
+    }
+
+    fn solid_frame(rgba: [u8; 4], duration_ms: u32) -> Frame {
+        let data = rgba.repeat(32 * 24);
+        Frame::new(ImageData::new(32, 24, data), duration_ms)
+    }
+
+    /// Lossy coding and the YCbCr round trip move each channel a little.
+    fn assert_close(got: &[u8], want: [u8; 4]) {
+        for (got, want) in got.iter().zip(want) {
+            assert!(got.abs_diff(want) <= 16, "{got:?} vs {want:?}");
+        }
+    }
+
+    #[test]
+    fn sequence_roundtrip_keeps_frames_and_timing() {
+        let codec = AvifCodec;
+        let colors = [[200, 40, 40, 255], [40, 200, 40, 255], [40, 40, 200, 255]];
+        let animation = Animation {
+            frames: vec![
+                solid_frame(colors[0], 100),
+                solid_frame(colors[1], 250),
+                solid_frame(colors[2], 40),
+            ],
+            loop_count: 3,
+        };
+        let options = EncodeOptions {
+            quality: 90,
+            ..Default::default()
+        };
+
+        let encoded = codec
+            .encode_animation(&animation, &options)
+            .expect("encode failed");
+        assert_eq!(&encoded[8..12], b"avis");
+        let decoded = codec.decode_animation(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.loop_count, 3);
+        let durations: Vec<u32> = decoded.frames.iter().map(|f| f.duration_ms).collect();
+        assert_eq!(durations, [100, 250, 40]);
+        for (frame, color) in decoded.frames.iter().zip(colors) {
+            assert_eq!(frame.image.pixel_format, PixelFormat::Rgba8);
+            assert_close(&frame.image.data[..4], color);
+        }
+
+        // Still decoders see the first frame.
+        let still = codec.decode(&encoded).expect("still decode failed");
+        assert_eq!((still.width, still.height), (32, 24));
+    }
+
+    #[test]
+    fn translucent_sequence_keeps_alpha() {
+        let codec = AvifCodec;
+        let animation = Animation {
+            frames: vec![
+                solid_frame([255, 255, 255, 255], 50),
+                solid_frame([255, 255, 255, 128], 50),
+            ],
+            loop_count: 0,
+        };
+
+        let encoded = codec
+            .encode_animation(&animation, &EncodeOptions::default())
+            .expect("encode failed");
+        let decoded = codec.decode_animation(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.loop_count, 0);
+        assert_close(&decoded.frames[0].image.data[..4], [255, 255, 255, 255]);
+        assert_close(&decoded.frames[1].image.data[..4], [255, 255, 255, 128]);
+    }
+
+    #[test]
+    fn sequence_stops_at_the_picture_over_the_allocation_limit() {
+        let codec = AvifCodec;
+        let animation = Animation {
+            frames: (0..5)
+                .map(|_| solid_frame([200, 40, 40, 255], 100))
+                .collect(),
+            loop_count: 0,
+        };
+        let encoded = codec
+            .encode_animation(&animation, &EncodeOptions::default())
+            .expect("encode failed");
+        let frame_bytes = 32 * 24 * 4;
+        let limits = DecodeLimits {
+            max_alloc: Some(2 * frame_bytes),
+            ..DecodeLimits::none()
+        };
+
+        // The third picture is rejected before the rest are decoded.
+        assert!(matches!(
+            codec.decode_animation_limited(&encoded, &limits),
+            Err(Error::LimitExceeded {
+                limit: Limit::Alloc,
+                actual,
+                ..
+            }) if actual == 3 * frame_bytes
+        ));
+    }
+
+    #[test]
+    fn probe_reads_layout_from_the_container() {
+        let codec = AvifCodec;
+        let still = codec
+            .encode(&create_test_image(64, 48), &EncodeOptions::default())
+            .expect("encode failed");
+        let info = codec.probe(&still).expect("probe failed");
+        assert_eq!((info.width, info.height), (64, 48));
+        // ravif widens 8-bit input to 10 bits.
+        assert_eq!(info.bit_depth, 10);
+        assert_eq!(info.frame_count, 1);
+        assert!(!info.has_alpha);
+
+        let translucent = ImageData::new(16, 16, [90, 90, 90, 128].repeat(16 * 16));
+        let encoded = codec
+            .encode(&translucent, &EncodeOptions::default())
+            .expect("encode failed");
+        assert!(codec.probe(&encoded).expect("probe failed").has_alpha);
+
+        let animation = Animation {
+            frames: vec![
+                solid_frame([200, 40, 40, 255], 100),
+                solid_frame([40, 200, 40, 255], 100),
+            ],
+            loop_count: 0,
+        };
+        let encoded = codec
+            .encode_animation(&animation, &EncodeOptions::default())
+            .expect("encode failed");
+        let info = codec.probe(&encoded).expect("probe failed");
+        assert_eq!((info.width, info.height), (32, 24));
+        assert_eq!(info.frame_count, 2);
+    }
+
+    #[test]
+    fn container_transforms_map_to_orientation() {
+        let cases = [
+            (ImageRotation::D0, None, Orientation::Normal),
+            (ImageRotation::D90, None, Orientation::Rotate270),
+            (ImageRotation::D270, None, Orientation::Rotate90),
+            (ImageRotation::D180, Some(ImageMirror::LeftRight), Orientation::FlipVertical),
+            (ImageRotation::D0, Some(ImageMirror::TopBottom), Orientation::FlipVertical),
+            (ImageRotation::D90, Some(ImageMirror::LeftRight), Orientation::Transverse),
+            (ImageRotation::D270, Some(ImageMirror::LeftRight), Orientation::Transpose),
+        ];
+        for (rotation, mirror, expected) in cases {
+            assert_eq!(orientation_from_transforms(rotation, mirror.as_ref()), expected);
+        }
+    }
+
+    #[test]
+    fn decode_without_transforms_is_upright() {
+        let codec = AvifCodec;
+        let encoded = codec
+            .encode(&create_test_image(16, 8), &EncodeOptions::default())
+            .expect("encode failed");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+        assert_eq!(decoded.metadata.orientation, Orientation::Normal);
+    }
+}
@@ -0,0 +1,885 @@
+use std::borrow::Cow;
+
+use dav1d::{PixelLayout, PlanarImageComponent, pixel};
+use mp4parse::unstable::{Indice, create_sample_table};
+use mp4parse::{AvifContext, MediaContext, ParseStrictness, Track, TrackType};
+use rav1e::prelude as av1;
+
+use crate::animation::{Animation, Frame};
+use crate::codec::{EncodeOptions, ImageData, PixelFormat};
+use crate::color::prepare_for_encode;
+use crate::error::{Error, Result};
+use crate::format::Format;
+use crate::hdr::ContentLightLevel;
+use crate::limits::DecodeLimits;
+
+use super::{
+    BT601, BT2020, encode_av1, hdr_color_description, hdr_content_light, quality_to_quantizer,
+    ycbcr,
+};
+
+/// BT.709 luma coefficients, for sequences that signal them.
+const BT709: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+/// Auxiliary type URN that marks an alpha plane.
+const ALPHA_URN: &[u8] = b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha\0";
+
+/// Decode every frame of an AVIF image sequence.
+///
+/// Returns `None` when `data` has no sequence track with more than one
+/// sample, so the caller can decode the primary image instead. Frames come
+/// back without metadata. The frame count is checked against `limits`
+/// up front, and each picture as dav1d hands it over.
+pub(super) fn decode(data: &[u8], limits: &DecodeLimits) -> Result<Option<Animation>> {
+    let Ok(ctx) = mp4parse::read_avif(&mut &data[..], ParseStrictness::Normal) else {
+        return Ok(None);
+    };
+    let Some(sequence) = &ctx.sequence else {
+        return Ok(None);
+    };
+    let Some(color) = color_track(sequence) else {
+        return Ok(None);
+    };
+    let table = sample_table(color)?;
+    if table.len() < 2 {
+        return Ok(None);
+    }
+    limits.check_frames(table.len() as u32)?;
+
+    let alpha = color.track_id.and_then(|id| {
+        sequence.tracks.iter().find(|track| {
+            matches!(track.track_type, TrackType::AuxiliaryVideo)
+                && track
+                    .tref
+                    .as_ref()
+                    .is_some_and(|tref| tref.has_auxl_reference(id))
+        })
+    });
+
+    let mut decoded = 0;
+    let pictures = decode_track(data, &table, limits, 4, &mut decoded)?;
+    let alpha_pictures = match alpha {
+        Some(track) => Some(decode_track(
+            data,
+            &sample_table(track)?,
+            limits,
+            1,
+            &mut decoded,
+        )?),
+        None => None,
+    };
+    if pictures.len() != table.len()
+        || alpha_pictures
+            .as_ref()
+            .is_some_and(|alpha| alpha.len() != pictures.len())
+    {
+        return Err(Error::Decode(
+            "avif decode: sequence tracks do not line up".to_string(),
+        ));
+    }
+
+    let timescale = color.timescale.map_or(0, |timescale| timescale.0);
+    let frames = table
+        .iter()
+        .zip(&pictures)
+        .enumerate()
+        .map(|(i, (sample, picture))| {
+            let alpha = alpha_pictures.as_ref().map(|alpha| &alpha[i]);
+            let ticks = (sample.end_composition.0 - sample.start_composition.0).max(0) as u64;
+            let duration_ms = (ticks * 1000 + timescale / 2)
+                .checked_div(timescale)
+                .unwrap_or(0);
+            Frame::new(
+                picture_to_image(picture, alpha),
+                duration_ms.min(u64::from(u32::MAX)) as u32,
+            )
+        })
+        .collect();
+
+    Ok(Some(Animation {
+        frames,
+        loop_count: loop_count(color),
+    }))
+}
+
+/// Number of frames in the sequence track, or `None` when there is none.
+pub(super) fn frame_count(ctx: &AvifContext) -> Option<usize> {
+    let color = color_track(ctx.sequence.as_ref()?)?;
+    sample_table(color).ok().map(|table| table.len())
+}
+
+fn color_track(sequence: &MediaContext) -> Option<&Track> {
+    sequence
+        .tracks
+        .iter()
+        .find(|track| matches!(track.track_type, TrackType::Picture | TrackType::Video))
+}
+
+fn sample_table(track: &Track) -> Result<Vec<Indice>> {
+    create_sample_table(track, 0.into())
+        .map(|table| table.into_iter().collect())
+        .ok_or_else(|| Error::Decode("avif decode: broken sample table".to_string()))
+}
+
+/// Plays per the edit list: a repeating edit fills the track duration, and
+/// an all-ones duration repeats forever. Files without an edit list loop,
+/// as browsers play them.
+fn loop_count(track: &Track) -> u32 {
+    match track.looped {
+        Some(true) => {
+            let duration = track.tkhd.as_ref().map_or(u64::MAX, |tkhd| tkhd.duration);
+            let segment = track.edited_duration.map_or(0, |segment| segment.0);
+            if duration >= u64::from(u32::MAX) || segment == 0 {
+                0
+            } else {
+                duration.div_ceil(segment).min(u64::from(u32::MAX)) as u32
+            }
+        }
+        Some(false) => 1,
+        None => 0,
+    }
+}
+
+/// Decode every sample of one track. Each picture is checked against
+/// `limits` before it is kept: `decoded` is the running total of bytes
+/// held so far, and a picture adds `channels` samples per pixel.
+fn decode_track(
+    data: &[u8],
+    table: &[Indice],
+    limits: &DecodeLimits,
+    channels: u64,
+    decoded: &mut u64,
+) -> Result<Vec<dav1d::Picture>> {
+    let dav1d_error = |e: dav1d::Error| Error::Decode(format!("avif decode: {e}"));
+    let mut settings = dav1d::Settings::new();
+    // One picture out per sample in, so pictures line up with the table.
+    settings.set_max_frame_delay(1);
+    // A frame larger than this could not fit the limits even at 4 bytes a
+    // pixel, so dav1d need not allocate it.
+    let max_pixels = limits
+        .max_pixels
+        .into_iter()
+        .chain(limits.max_alloc.map(|bytes| bytes / 4))
+        .min();
+    if let Some(max_pixels) = max_pixels {
+        settings.set_frame_size_limit(max_pixels.clamp(1, u64::from(u32::MAX)) as u32);
+    }
+    let mut decoder = dav1d::Decoder::with_settings(&settings).map_err(dav1d_error)?;
+
+    let mut pictures = Vec::with_capacity(table.len());
+    let mut take_pictures = |decoder: &mut dav1d::Decoder| loop {
+        match decoder.get_picture() {
+            Ok(picture) => {
+                limits.check_size(picture.width(), picture.height())?;
+                let bytes_per_sample = if picture.bit_depth() > 8 { 2 } else { 1 };
+                *decoded += u64::from(picture.width())
+                    * u64::from(picture.height())
+                    * channels
+                    * bytes_per_sample;
+                limits.check_alloc(*decoded)?;
+                pictures.push(picture);
+            }
+            Err(dav1d::Error::Again) => return Ok(()),
+            Err(e) => return Err(dav1d_error(e)),
+        }
+    };
+    for sample in table {
+        let sample = data
+            .get(sample.start_offset.0 as usize..sample.end_offset.0 as usize)
+            .ok_or_else(|| Error::Decode("avif decode: sample outside the file".to_string()))?;
+        let mut sent = decoder.send_data(sample.to_vec(), None, None, None);
+        while let Err(dav1d::Error::Again) = sent {
+            take_pictures(&mut decoder)?;
+            sent = decoder.send_pending_data();
+        }
+        sent.map_err(dav1d_error)?;
+        take_pictures(&mut decoder)?;
+    }
+    Ok(pictures)
+}
+
+/// Row access to one plane of a decoded picture.
+struct Samples {
+    plane: dav1d::Plane,
+    stride: usize,
+    wide: bool,
+}
+
+impl Samples {
+    fn new(picture: &dav1d::Picture, component: PlanarImageComponent) -> Self {
+        Self {
+            plane: picture.plane(component),
+            stride: picture.stride(component) as usize,
+            wide: picture.bit_depth() > 8,
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> f32 {
+        let row = y * self.stride;
+        if self.wide {
+            let i = row + 2 * x;
+            u16::from_le_bytes([self.plane[i], self.plane[i + 1]]) as f32
+        } else {
+            self.plane[row + x] as f32
+        }
+    }
+}
+
+/// Normalise luma-like and chroma samples of `picture` to 0..=1 and
+/// -0.5..=0.5.
+fn sample_range(picture: &dav1d::Picture) -> (impl Fn(f32) -> f32, impl Fn(f32) -> f32) {
+    let depth = picture.bit_depth() as i32;
+    let max = ((1 << depth) - 1) as f32;
+    let mid = (1 << (depth - 1)) as f32;
+    let scale = (1 << (depth - 8)) as f32;
+    let full = matches!(picture.color_range(), pixel::YUVRange::Full);
+    let luma = move |v: f32| {
+        if full {
+            v / max
+        } else {
+            (v - 16.0 * scale) / (219.0 * scale)
+        }
+    };
+    let chroma = move |v: f32| {
+        if full {
+            (v - mid) / max
+        } else {
+            (v - mid) / (224.0 * scale)
+        }
+    };
+    (luma, chroma)
+}
+
+/// Convert a decoded picture to RGBA, taking alpha from the luma plane of
+/// `alpha`. 8-bit pictures become `Rgba8`, deeper ones `Rgba16`.
+fn picture_to_image(color: &dav1d::Picture, alpha: Option<&dav1d::Picture>) -> ImageData {
+    let width = color.width() as usize;
+    let height = color.height() as usize;
+    let layout = color.pixel_layout();
+    let (shift_x, shift_y) = match layout {
+        PixelLayout::I420 => (1, 1),
+        PixelLayout::I422 => (1, 0),
+        PixelLayout::I400 | PixelLayout::I444 => (0, 0),
+    };
+    let luma = match color.matrix_coefficients() {
+        pixel::MatrixCoefficients::Identity => None,
+        pixel::MatrixCoefficients::BT709 => Some(BT709),
+        pixel::MatrixCoefficients::BT2020NonConstantLuminance
+        | pixel::MatrixCoefficients::BT2020ConstantLuminance => Some(BT2020),
+        _ => Some(BT601),
+    };
+    let (to_luma, to_chroma) = sample_range(color);
+
+    let y_plane = Samples::new(color, PlanarImageComponent::Y);
+    let chroma = (layout != PixelLayout::I400).then(|| {
+        (
+            Samples::new(color, PlanarImageComponent::U),
+            Samples::new(color, PlanarImageComponent::V),
+        )
+    });
+    let alpha = alpha.map(|alpha| {
+        let (to_alpha, _) = sample_range(alpha);
+        (Samples::new(alpha, PlanarImageComponent::Y), to_alpha)
+    });
+
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let luma_sample = y_plane.get(x, y);
+            let [r, g, b] = match (&chroma, luma) {
+                (None, _) => [to_luma(luma_sample); 3],
+                (Some((u, v)), None) => {
+                    let (cx, cy) = (x >> shift_x, y >> shift_y);
+                    [
+                        to_luma(v.get(cx, cy)),
+                        to_luma(luma_sample),
+                        to_luma(u.get(cx, cy)),
+                    ]
+                }
+                (Some((u, v)), Some([kr, kg, kb])) => {
+                    let (cx, cy) = (x >> shift_x, y >> shift_y);
+                    let (cb, cr) = (to_chroma(u.get(cx, cy)), to_chroma(v.get(cx, cy)));
+                    let luma = to_luma(luma_sample);
+                    let r = luma + 2.0 * (1.0 - kr) * cr;
+                    let b = luma + 2.0 * (1.0 - kb) * cb;
+                    [r, (luma - kr * r - kb * b) / kg, b]
+                }
+            };
+            let a = alpha
+                .as_ref()
+                .map_or(1.0, |(plane, to_alpha)| to_alpha(plane.get(x, y)));
+            pixels.extend([r, g, b, a].map(|v| v.clamp(0.0, 1.0)));
+        }
+    }
+
+    if color.bit_depth() > 8 {
+        let samples = pixels
+            .iter()
+            .map(|v| (v * 65535.0).round() as u16)
+            .collect();
+        ImageData::from_rgba16(width as u32, height as u32, samples)
+    } else {
+        let data = pixels.iter().map(|v| (v * 255.0).round() as u8).collect();
+        ImageData::new(width as u32, height as u32, data)
+    }
+}
+
+/// One encoded AV1 temporal unit.
+struct Sample {
+    data: Vec<u8>,
+    sync: bool,
+}
+
+impl<T: av1::Pixel> From<av1::Packet<T>> for Sample {
+    fn from(packet: av1::Packet<T>) -> Self {
+        Self {
+            sync: packet.frame_type == av1::FrameType::KEY,
+            data: packet.data,
+        }
+    }
+}
+
+/// Encode a multi-frame animation as an AVIF image sequence.
+///
+/// The first frame doubles as the primary image, so readers without
+/// sequence support still show it. 8-bit frames are coded at 8 bits and
+/// everything else at 10. Like the still path, only EXIF is embedded.
+pub(super) fn encode(animation: &Animation, options: &EncodeOptions) -> Result<Vec<u8>> {
+    let frames: Vec<Cow<'_, ImageData>> = animation
+        .frames
+        .iter()
+        .map(|frame| prepare_for_encode(&frame.image, options, Format::Avif))
+        .collect();
+    let first = &frames[0];
+    let hdr = first.metadata.hdr;
+    let bit_depth = if hdr.is_none() && first.pixel_format == PixelFormat::Rgba8 {
+        8
+    } else {
+        10
+    };
+    let luma = if hdr.is_some() { BT2020 } else { BT601 };
+
+    let mut color = Vec::with_capacity(frames.len());
+    let mut alpha = Vec::with_capacity(frames.len());
+    for frame in &frames {
+        let (samples, frame_alpha) = ycbcr(frame, luma, bit_depth);
+        let planes: [Vec<u16>; 3] =
+            std::array::from_fn(|c| samples.iter().map(|px| px[c]).collect());
+        color.push(planes);
+        alpha.push(frame_alpha);
+    }
+    // Every frame needs an alpha sample once any frame is translucent.
+    let alpha = alpha.iter().any(Option::is_some).then(|| {
+        let opaque = vec![(1 << bit_depth) - 1; first.width as usize * first.height as usize];
+        alpha
+            .into_iter()
+            .map(|plane| [plane.unwrap_or_else(|| opaque.clone())])
+            .collect::<Vec<_>>()
+    });
+
+    let avif = options.codec.avif;
+    let quantizer = quality_to_quantizer(options.quality as f32);
+    let alpha_quantizer =
+        quality_to_quantizer(avif.alpha_quality.unwrap_or(options.quality).clamp(1, 100) as f32);
+    let base = av1::EncoderConfig {
+        width: first.width as usize,
+        height: first.height as usize,
+        bit_depth: bit_depth as usize,
+        pixel_range: av1::PixelRange::Full,
+        ..av1::EncoderConfig::with_speed_preset(avif.speed.clamp(1, 10))
+    };
+    let color_description = match &hdr {
+        Some(hdr) => hdr_color_description(hdr),
+        None => av1::ColorDescription {
+            color_primaries: av1::ColorPrimaries::BT709,
+            transfer_characteristics: av1::TransferCharacteristics::SRGB,
+            matrix_coefficients: av1::MatrixCoefficients::BT601,
+        },
+    };
+    let color_config = av1::EncoderConfig {
+        chroma_sampling: av1::ChromaSampling::Cs444,
+        color_description: Some(color_description),
+        content_light: hdr.as_ref().and_then(hdr_content_light),
+        quantizer: quantizer as usize,
+        min_quantizer: quantizer,
+        ..base.clone()
+    };
+    let color = encode_samples(color_config, &color)?;
+    let alpha = match alpha {
+        Some(planes) => {
+            let alpha_config = av1::EncoderConfig {
+                chroma_sampling: av1::ChromaSampling::Cs400,
+                quantizer: alpha_quantizer as usize,
+                min_quantizer: alpha_quantizer,
+                ..base
+            };
+            Some(encode_samples(alpha_config, &planes)?)
+        }
+        None => None,
+    };
+
+    let sequence = Sequence {
+        width: first.width,
+        height: first.height,
+        bit_depth,
+        durations: animation.frames.iter().map(|f| f.duration_ms).collect(),
+        loop_count: animation.loop_count,
+        cicp: [
+            color_description.color_primaries as u16,
+            color_description.transfer_characteristics as u16,
+            color_description.matrix_coefficients as u16,
+        ],
+        content_light_level: hdr.and_then(|hdr| hdr.content_light_level),
+        color,
+        alpha,
+        exif: first.metadata.filter(options.metadata).exif,
+    };
+    Ok(sequence.to_vec())
+}
+
+/// Encode one packet per frame at the configured bit depth.
+fn encode_samples<const N: usize>(
+    config: av1::EncoderConfig,
+    frames: &[[Vec<u16>; N]],
+) -> Result<Vec<Sample>> {
+    let frames: Vec<&[Vec<u16>]> = frames.iter().map(|planes| &planes[..]).collect();
+    let samples: Vec<Sample> = if config.bit_depth == 8 {
+        let packets = encode_av1::<u8>(config, &frames)?;
+        packets.into_iter().map(Sample::from).collect()
+    } else {
+        let packets = encode_av1::<u16>(config, &frames)?;
+        packets.into_iter().map(Sample::from).collect()
+    };
+    if samples.len() != frames.len() {
+        return Err(Error::Encode(format!(
+            "rav1e encode: {} packets for {} frames",
+            samples.len(),
+            frames.len()
+        )));
+    }
+    Ok(samples)
+}
+
+/// Everything the `avis` muxer writes. Times are in milliseconds, which is
+/// also the timescale of the movie and both tracks.
+struct Sequence {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    durations: Vec<u32>,
+    loop_count: u32,
+    /// Colour primaries, transfer characteristics and matrix coefficients.
+    cicp: [u16; 3],
+    content_light_level: Option<ContentLightLevel>,
+    color: Vec<Sample>,
+    alpha: Option<Vec<Sample>>,
+    exif: Option<Vec<u8>>,
+}
+
+const COLOR_ID: u16 = 1;
+const ALPHA_ID: u16 = 2;
+const EXIF_ID: u16 = 3;
+
+impl Sequence {
+    fn to_vec(&self) -> Vec<u8> {
+        // Offsets into `mdat` are known only once the boxes before it are
+        // written, and their size does not depend on the offsets.
+        let mdat_start = self.header(0).len() as u32 + 8;
+        let mut out = self.header(mdat_start);
+        write_box(&mut out, b"mdat", |out| {
+            for sample in self.color.iter().chain(self.alpha.iter().flatten()) {
+                out.extend_from_slice(&sample.data);
+            }
+            if let Some(exif) = &self.exif {
+                out.put_u32(0);
+                out.extend_from_slice(exif);
+            }
+        });
+        out
+    }
+
+    fn media_duration(&self) -> u32 {
+        let total: u64 = self.durations.iter().map(|&d| u64::from(d)).sum();
+        total.min(u64::from(u32::MAX - 1)) as u32
+    }
+
+    /// Track and movie duration: the media repeated `loop_count` times, or
+    /// all ones to repeat forever.
+    fn presentation_duration(&self) -> u32 {
+        match self.loop_count {
+            0 => u32::MAX,
+            plays => u64::from(self.media_duration())
+                .saturating_mul(u64::from(plays))
+                .min(u64::from(u32::MAX - 1)) as u32,
+        }
+    }
+
+    fn header(&self, mdat_start: u32) -> Vec<u8> {
+        let color_len: u32 = self.color.iter().map(|s| s.data.len() as u32).sum();
+        let alpha_len: u32 = self
+            .alpha
+            .iter()
+            .flatten()
+            .map(|s| s.data.len() as u32)
+            .sum();
+
+        let mut out = Vec::new();
+        write_box(&mut out, b"ftyp", |out| {
+            out.extend_from_slice(b"avis");
+            out.put_u32(0);
+            for brand in [b"avif", b"avis", b"msf1", b"iso8", b"mif1", b"miaf"] {
+                out.extend_from_slice(brand);
+            }
+        });
+        self.write_meta(&mut out, mdat_start, color_len, alpha_len);
+        write_box(&mut out, b"moov", |out| {
+            write_full_box(out, b"mvhd", 0, 0, |out| {
+                out.put_u32(0); // creation time
+                out.put_u32(0); // modification time
+                out.put_u32(1000);
+                out.put_u32(self.presentation_duration());
+                out.put_u32(0x0001_0000); // rate 1.0
+                out.put_u16(0x0100); // volume 1.0
+                out.extend_from_slice(&[0; 10]);
+                put_unity_matrix(out);
+                out.extend_from_slice(&[0; 24]);
+                out.put_u32(if self.alpha.is_some() { 3 } else { 2 });
+            });
+            self.write_trak(out, 1, &self.color, mdat_start);
+            if let Some(alpha) = &self.alpha {
+                self.write_trak(out, 2, alpha, mdat_start + color_len);
+            }
+        });
+        out
+    }
+
+    /// The primary image item: the first color sample, plus its alpha and
+    /// EXIF items.
+    fn write_meta(&self, out: &mut Vec<u8>, mdat_start: u32, color_len: u32, alpha_len: u32) {
+        let mut items = vec![(COLOR_ID, b"av01", mdat_start, self.color[0].data.len())];
+        if let Some(alpha) = &self.alpha {
+            items.push((
+                ALPHA_ID,
+                b"av01",
+                mdat_start + color_len,
+                alpha[0].data.len(),
+            ));
+        }
+        if let Some(exif) = &self.exif {
+            let offset = mdat_start + color_len + alpha_len;
+            items.push((EXIF_ID, b"Exif", offset, exif.len() + 4));
+        }
+
+        write_full_box(out, b"meta", 0, 0, |out| {
+            write_handler(out, b"pict");
+            write_full_box(out, b"pitm", 0, 0, |out| out.put_u16(COLOR_ID));
+            write_full_box(out, b"iloc", 0, 0, |out| {
+                out.extend_from_slice(&[0x44, 0x00]); // 32-bit offsets and lengths
+                out.put_u16(items.len() as u16);
+                for &(id, _, offset, len) in &items {
+                    out.put_u16(id);
+                    out.put_u16(0); // data reference index
+                    out.put_u16(1); // extent count
+                    out.put_u32(offset);
+                    out.put_u32(len as u32);
+                }
+            });
+            write_full_box(out, b"iinf", 0, 0, |out| {
+                out.put_u16(items.len() as u16);
+                for &(id, kind, _, _) in &items {
+                    write_full_box(out, b"infe", 2, 0, |out| {
+                        out.put_u16(id);
+                        out.put_u16(0); // protection index
+                        out.extend_from_slice(kind);
+                        out.push(0); // empty name
+                    });
+                }
+            });
+            if items.len() > 1 {
+                write_full_box(out, b"iref", 0, 0, |out| {
+                    if self.alpha.is_some() {
+                        write_reference(out, b"auxl", ALPHA_ID, COLOR_ID);
+                    }
+                    if self.exif.is_some() {
+                        write_reference(out, b"cdsc", EXIF_ID, COLOR_ID);
+                    }
+                });
+            }
+            write_box(out, b"iprp", |out| self.write_properties(out));
+        });
+    }
+
+    fn write_properties(&self, out: &mut Vec<u8>) {
+        let mut properties: Vec<Vec<u8>> = Vec::new();
+        let mut add = |write: &dyn Fn(&mut Vec<u8>)| {
+            let mut property = Vec::new();
+            write(&mut property);
+            properties.push(property);
+            properties.len() as u8
+        };
+
+        let ispe = add(&|out| {
+            write_full_box(out, b"ispe", 0, 0, |out| {
+                out.put_u32(self.width);
+                out.put_u32(self.height);
+            })
+        });
+        let pixi = add(&|out| self.write_pixi(out, 3));
+        let av1c = add(&|out| self.write_av1c(out, false));
+        let colr = add(&|out| self.write_colr(out));
+        let mut color = vec![ispe, pixi, 0x80 | av1c, colr];
+        if self.content_light_level.is_some() {
+            color.push(add(&|out| self.write_clli(out)));
+        }
+        let mut associations = vec![(COLOR_ID, color)];
+        if self.alpha.is_some() {
+            let pixi = add(&|out| self.write_pixi(out, 1));
+            let av1c = add(&|out| self.write_av1c(out, true));
+            let auxc = add(&|out| {
+                write_full_box(out, b"auxC", 0, 0, |out| out.extend_from_slice(ALPHA_URN))
+            });
+            associations.push((ALPHA_ID, vec![ispe, pixi, 0x80 | av1c, 0x80 | auxc]));
+        }
+
+        write_box(out, b"ipco", |out| {
+            for property in &properties {
+                out.extend_from_slice(property);
+            }
+        });
+        write_full_box(out, b"ipma", 0, 0, |out| {
+            out.put_u32(associations.len() as u32);
+            for (id, indices) in &associations {
+                out.put_u16(*id);
+                out.push(indices.len() as u8);
+                out.extend_from_slice(indices);
+            }
+        });
+    }
+
+    fn write_trak(&self, out: &mut Vec<u8>, track_id: u32, samples: &[Sample], offset: u32) {
+        let alpha = track_id != 1;
+        write_box(out, b"trak", |out| {
+            // Enabled and in the movie.
+            write_full_box(out, b"tkhd", 0, 3, |out| {
+                out.put_u32(0); // creation time
+                out.put_u32(0); // modification time
+                out.put_u32(track_id);
+                out.put_u32(0);
+                out.put_u32(self.presentation_duration());
+                out.extend_from_slice(&[0; 16]); // reserved, layer, group, volume
+                put_unity_matrix(out);
+                out.put_u32(self.width << 16);
+                out.put_u32(self.height << 16);
+            });
+            if alpha {
+                write_box(out, b"tref", |out| {
+                    write_box(out, b"auxl", |out| out.put_u32(1));
+                });
+            }
+            write_box(out, b"edts", |out| {
+                // Flag 1 repeats the edit for the whole track duration.
+                let repeat = u32::from(self.loop_count != 1);
+                write_full_box(out, b"elst", 0, repeat, |out| {
+                    out.put_u32(1);
+                    out.put_u32(self.media_duration());
+                    out.put_u32(0); // media time
+                    out.put_u32(0x0001_0000); // rate 1.0
+                });
+            });
+            write_box(out, b"mdia", |out| {
+                write_full_box(out, b"mdhd", 0, 0, |out| {
+                    out.put_u32(0); // creation time
+                    out.put_u32(0); // modification time
+                    out.put_u32(1000);
+                    out.put_u32(self.media_duration());
+                    out.put_u16(0x55c4); // "und"
+                    out.put_u16(0);
+                });
+                write_handler(out, if alpha { b"auxv" } else { b"pict" });
+                write_box(out, b"minf", |out| {
+                    write_full_box(out, b"vmhd", 0, 1, |out| out.extend_from_slice(&[0; 8]));
+                    write_box(out, b"dinf", |out| {
+                        write_full_box(out, b"dref", 0, 0, |out| {
+                            out.put_u32(1);
+                            // Flag 1: the media is in this file.
+                            write_full_box(out, b"url ", 0, 1, |_| {});
+                        });
+                    });
+                    write_box(out, b"stbl", |out| {
+                        self.write_sample_tables(out, samples, offset, alpha)
+                    });
+                });
+            });
+        });
+    }
+
+    fn write_sample_tables(&self, out: &mut Vec<u8>, samples: &[Sample], offset: u32, alpha: bool) {
+        write_full_box(out, b"stsd", 0, 0, |out| {
+            out.put_u32(1);
+            write_box(out, b"av01", |out| {
+                out.extend_from_slice(&[0; 6]);
+                out.put_u16(1); // data reference index
+                out.extend_from_slice(&[0; 16]);
+                out.put_u16(self.width as u16);
+                out.put_u16(self.height as u16);
+                out.put_u32(0x0048_0000); // 72 dpi
+                out.put_u32(0x0048_0000);
+                out.put_u32(0);
+                out.put_u16(1); // frame count
+                out.extend_from_slice(&[0; 32]); // compressor name
+                out.put_u16(0x0018); // depth
+                out.put_u16(0xffff);
+                self.write_av1c(out, alpha);
+                if alpha {
+                    write_full_box(out, b"auxi", 0, 0, |out| out.extend_from_slice(ALPHA_URN));
+                } else {
+                    self.write_colr(out);
+                }
+            });
+        });
+        write_full_box(out, b"stts", 0, 0, |out| {
+            let mut runs: Vec<(u32, u32)> = Vec::new();
+            for &duration in &self.durations {
+                match runs.last_mut() {
+                    Some((count, delta)) if *delta == duration => *count += 1,
+                    _ => runs.push((1, duration)),
+                }
+            }
+            out.put_u32(runs.len() as u32);
+            for (count, delta) in runs {
+                out.put_u32(count);
+                out.put_u32(delta);
+            }
+        });
+        // All samples sit in one chunk.
+        write_full_box(out, b"stsc", 0, 0, |out| {
+            out.put_u32(1);
+            out.put_u32(1); // first chunk
+            out.put_u32(samples.len() as u32);
+            out.put_u32(1); // sample description index
+        });
+        write_full_box(out, b"stsz", 0, 0, |out| {
+            out.put_u32(0);
+            out.put_u32(samples.len() as u32);
+            for sample in samples {
+                out.put_u32(sample.data.len() as u32);
+            }
+        });
+        write_full_box(out, b"stco", 0, 0, |out| {
+            out.put_u32(1);
+            out.put_u32(offset);
+        });
+        write_full_box(out, b"stss", 0, 0, |out| {
+            let sync: Vec<u32> = (1..)
+                .zip(samples)
+                .filter(|(_, sample)| sample.sync)
+                .map(|(number, _)| number)
+                .collect();
+            out.put_u32(sync.len() as u32);
+            for number in sync {
+                out.put_u32(number);
+            }
+        });
+    }
+
+    fn write_pixi(&self, out: &mut Vec<u8>, channels: u8) {
+        write_full_box(out, b"pixi", 0, 0, |out| {
+            out.push(channels);
+            out.extend(std::iter::repeat_n(self.bit_depth, channels as usize));
+        });
+    }
+
+    /// Color planes are 4:4:4 (profile 1) and alpha is monochrome (profile
+    /// 0), both at level 31, which places no limits.
+    fn write_av1c(&self, out: &mut Vec<u8>, alpha: bool) {
+        write_box(out, b"av1C", |out| {
+            let high_bitdepth = u8::from(self.bit_depth > 8) << 6;
+            let (profile, layout) = if alpha {
+                (0, 0b1_1100) // monochrome, subsampled in x and y
+            } else {
+                (1, 0)
+            };
+            out.extend_from_slice(&[0x81, profile << 5 | 31, high_bitdepth | layout, 0]);
+        });
+    }
+
+    fn write_colr(&self, out: &mut Vec<u8>) {
+        write_box(out, b"colr", |out| {
+            out.extend_from_slice(b"nclx");
+            for code in self.cicp {
+                out.put_u16(code);
+            }
+            out.push(0x80); // full range
+        });
+    }
+
+    fn write_clli(&self, out: &mut Vec<u8>) {
+        if let Some(level) = self.content_light_level {
+            write_box(out, b"clli", |out| {
+                out.put_u16(level.max_cll);
+                out.put_u16(level.max_fall);
+            });
+        }
+    }
+}
+
+trait PutBe {
+    fn put_u16(&mut self, value: u16);
+    fn put_u32(&mut self, value: u32);
+}
+
+impl PutBe for Vec<u8> {
+    fn put_u16(&mut self, value: u16) {
+        self.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn put_u32(&mut self, value: u32) {
+        self.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+/// Append a box whose payload is written by `body`.
+fn write_box(out: &mut Vec<u8>, kind: &[u8; 4], body: impl FnOnce(&mut Vec<u8>)) {
+    let start = out.len();
+    out.put_u32(0);
+    out.extend_from_slice(kind);
+    body(out);
+    let size = (out.len() - start) as u32;
+    out[start..start + 4].copy_from_slice(&size.to_be_bytes());
+}
+
+fn write_full_box(
+    out: &mut Vec<u8>,
+    kind: &[u8; 4],
+    version: u8,
+    flags: u32,
+    body: impl FnOnce(&mut Vec<u8>),
+) {
+    write_box(out, kind, |out| {
+        out.put_u32(u32::from(version) << 24 | flags);
+        body(out);
+    });
+}
+
+fn write_handler(out: &mut Vec<u8>, handler: &[u8; 4]) {
+    write_full_box(out, b"hdlr", 0, 0, |out| {
+        out.put_u32(0);
+        out.extend_from_slice(handler);
+        out.extend_from_slice(&[0; 12]);
+        out.push(0); // empty name
+    });
+}
+
+fn write_reference(out: &mut Vec<u8>, kind: &[u8; 4], from: u16, to: u16) {
+    write_box(out, kind, |out| {
+        out.put_u16(from);
+        out.put_u16(1);
+        out.put_u16(to);
+    });
+}
+
+fn put_unity_matrix(out: &mut Vec<u8>) {
+    for value in [0x0001_0000, 0, 0, 0, 0x0001_0000, 0, 0, 0, 0x4000_0000] {
+        out.put_u32(value);
+    }
+}
@@ -0,0 +1,86 @@
+use crate::color::prepare_for_encode;
+use crate::error::{Error, Result};
+use crate::format::Format;
+
+use super::{
+    Codec, EncodeOptions, ImageData, ImageInfo, decode_with_image, encode_with_image,
+    probe_with_image,
+};
+
+/// BMP codec backed by the `image` crate. Lossless format — quality is
+/// ignored.
+///
+/// Pixels are written as 8-bit grey, RGB or RGBA, whichever is smallest.
+pub struct BmpCodec;
+
+impl Codec for BmpCodec {
+    fn format(&self) -> Format {
+        Format::Bmp
+    }
+
+    fn quality_affects_size(&self, _options: &EncodeOptions) -> bool {
+        false
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<ImageData> {
+        decode_with_image(data, image::ImageFormat::Bmp)
+            .map_err(|e| Error::Decode(format!("bmp decode: {e}")))
+    }
+
+    fn probe(&self, data: &[u8]) -> Result<ImageInfo> {
+        probe_with_image(data, image::ImageFormat::Bmp, Format::Bmp)
+            .map_err(|e| Error::Decode(format!("bmp header: {e}")))
+    }
+
+    fn encode(&self, image: &ImageData, options: &EncodeOptions) -> Result<Vec<u8>> {
+        let image = prepare_for_encode(image, options, Format::Bmp);
+        encode_with_image(&image, image::ImageFormat::Bmp)
+            .map_err(|e| Error::Encode(format!("bmp encode: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: u32, height: u32, alpha: u8) -> ImageData {
+        let data = (0..width * height)
+            .flat_map(|i| [(i * 7) as u8, (i * 13) as u8, (i * 29) as u8, alpha])
+            .collect();
+        ImageData::new(width, height, data)
+    }
+
+    #[test]
+    fn encode_and_decode_roundtrip() {
+        let original = gradient(13, 7, 255);
+
+        let encoded = BmpCodec
+            .encode(&original, &EncodeOptions::default())
+            .expect("encode failed");
+        assert_eq!(&encoded[..2], b"BM");
+
+        let decoded = BmpCodec.decode(&encoded).expect("decode failed");
+        assert_eq!((decoded.width, decoded.height), (13, 7));
+        assert_eq!(decoded.data, original.data, "BMP should be lossless");
+    }
+
+    #[test]
+    fn alpha_is_kept() {
+        let original = gradient(4, 4, 100);
+
+        let encoded = BmpCodec
+            .encode(&original, &EncodeOptions::default())
+            .expect("encode failed");
+        let decoded = BmpCodec.decode(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.data, original.data);
+    }
+
+    #[test]
+    fn decode_invalid_data() {
+        assert!(matches!(
+            BmpCodec.decode(b"BMnot a bitmap"),
+            Err(Error::Decode(_))
+        ));
+    }
+}
@@ -0,0 +1,587 @@
+use std::borrow::Cow;
+use std::io::Cursor;
+
+use image::codecs::gif::GifDecoder;
+use image::{AnimationDecoder, ImageDecoder};
+
+use crate::animation::{Animation, Disposal, Frame};
+use crate::color::prepare_for_encode;
+use crate::error::{Error, Result};
+use crate::format::Format;
+use crate::limits::DecodeLimits;
+use crate::metadata::Metadata;
+use crate::quantize::{self, QuantizeOptions};
+
+use super::{
+    Codec, EncodeOptions, ImageData, ImageInfo, PixelFormat, decode_with_image, probe_with_image,
+};
+
+/// GIF encoder settings.
+///
+/// Every frame shares one global palette. The encoder picks the smallest
+/// palette that still reaches the requested quality.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GifOptions {
+    /// Maximum palette size (2..=256).
+    pub max_colors: u16,
+    /// Dithering strength, from 0.0 (off) to 1.0 (full Floyd–Steinberg).
+    pub dithering: f32,
+}
+
+impl Default for GifOptions {
+    fn default() -> Self {
+        Self {
+            max_colors: 256,
+            dithering: 1.0,
+        }
+    }
+}
+
+/// GIF codec. Decoding goes through the `image` crate; the encoder quantizes
+/// to a shared palette and stores each frame as the rectangle that changed.
+pub struct GifCodec;
+
+impl Codec for GifCodec {
+    fn format(&self) -> Format {
+        Format::Gif
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<ImageData> {
+        decode_with_image(data, image::ImageFormat::Gif)
+            .map_err(|e| Error::Decode(format!("gif decode: {e}")))
+    }
+
+    fn probe(&self, data: &[u8]) -> Result<ImageInfo> {
+        let info = probe_with_image(data, image::ImageFormat::Gif, Format::Gif)
+            .map_err(|e| Error::Decode(format!("gif header: {e}")))?;
+        let frame_count = gif_control(data).map_or(1, |control| control.disposals.len() as u32);
+        Ok(ImageInfo {
+            frame_count,
+            ..info
+        })
+    }
+
+    fn encode(&self, image: &ImageData, options: &EncodeOptions) -> Result<Vec<u8>> {
+        self.encode_animation(&Animation::from_still(image.clone()), options)
+    }
+
+    fn decode_animation(&self, data: &[u8]) -> Result<Animation> {
+        self.decode_animation_limited(data, &DecodeLimits::default())
+    }
+
+    fn decode_animation_limited(&self, data: &[u8], limits: &DecodeLimits) -> Result<Animation> {
+        let Some(control) = gif_control(data) else {
+            return self.decode_limited(data, limits).map(Animation::from_still);
+        };
+        decode_frames(data, &control, limits)
+    }
+
+    fn encode_animation(&self, animation: &Animation, options: &EncodeOptions) -> Result<Vec<u8>> {
+        if options.lossless {
+            return Err(Error::LosslessNotSupported(Format::Gif));
+        }
+        animation.validate()?;
+        let (width, height) = (animation.width(), animation.height());
+        if width > u32::from(u16::MAX) || height > u32::from(u16::MAX) {
+            return Err(Error::Encode(format!(
+                "gif encode: {width}x{height} exceeds 65535x65535"
+            )));
+        }
+
+        let frames: Vec<_> = animation
+            .frames
+            .iter()
+            .map(|frame| binary_alpha(&prepare_for_encode(&frame.image, options, Format::Gif)))
+            .collect();
+        let palette = Palette::build(&frames, animation.is_animated(), options);
+        encode_gif(animation, &palette, width as u16, height as u16)
+            .map_err(|e| Error::Encode(format!("gif encode: {e}")))
+    }
+}
+
+/// Loop count and frame disposal read from GIF extension blocks.
+struct GifControl {
+    loop_count: u32,
+    disposals: Vec<Disposal>,
+}
+
+/// Walk the GIF blocks for the NETSCAPE loop extension and each frame's
+/// graphic control extension, or `None` when there are fewer than two frames.
+fn gif_control(data: &[u8]) -> Option<GifControl> {
+    let flags = *data.get(10)?;
+    let mut pos = 13 + color_table_len(flags);
+    // Without a NETSCAPE extension the animation plays once.
+    let mut control = GifControl {
+        loop_count: 1,
+        disposals: Vec::new(),
+    };
+    let mut disposal = Disposal::None;
+    loop {
+        match *data.get(pos)? {
+            0x21 => {
+                let body = data.get(pos + 2..)?;
+                match data.get(pos + 1)? {
+                    0xF9 if body.first() == Some(&4) => {
+                        disposal = match (body.get(1)? >> 2) & 0x07 {
+                            2 => Disposal::Background,
+                            3 => Disposal::Previous,
+                            _ => Disposal::None,
+                        };
+                    }
+                    0xFF if body.starts_with(b"\x0BNETSCAPE2.0\x03\x01") => {
+                        let repeats = u16::from_le_bytes([*body.get(14)?, *body.get(15)?]);
+                        // The stored count is repeats after the first play.
+                        control.loop_count = match repeats {
+                            0 => 0,
+                            n => u32::from(n) + 1,
+                        };
+                    }
+                    _ => {}
+                }
+                pos = skip_sub_blocks(data, pos + 2)?;
+            }
+            0x2C => {
+                let flags = *data.get(pos + 9)?;
+                // Descriptor, local colour table, then the LZW code size byte.
+                pos += 10 + color_table_len(flags) + 1;
+                pos = skip_sub_blocks(data, pos)?;
+                control.disposals.push(std::mem::take(&mut disposal));
+            }
+            _ => break,
+        }
+    }
+    (control.disposals.len() > 1).then_some(control)
+}
+
+/// Size in bytes of the colour table announced by a packed `flags` byte.
+fn color_table_len(flags: u8) -> usize {
+    if flags & 0x80 == 0 {
+        0
+    } else {
+        3 << ((flags & 0x07) + 1)
+    }
+}
+
+/// Position just past the sub-block chain starting at `pos`.
+fn skip_sub_blocks(data: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *data.get(pos)? as usize;
+        pos += 1 + len;
+        if len == 0 {
+            return Some(pos);
+        }
+    }
+}
+
+/// Decode every frame, composited onto the full canvas.
+fn decode_frames(data: &[u8], control: &GifControl, limits: &DecodeLimits) -> Result<Animation> {
+    let gif_error = |e: image::ImageError| Error::Decode(format!("gif decode: {e}"));
+    let mut decoder = GifDecoder::new(Cursor::new(data)).map_err(gif_error)?;
+    let metadata = Metadata::from_decoder(&mut decoder);
+    limits.check(&ImageInfo {
+        frame_count: control.disposals.len() as u32,
+        ..ImageInfo::still(Format::Gif, decoder.dimensions(), 8, true, &metadata)
+    })?;
+    let frames = decoder.into_frames().collect_frames().map_err(gif_error)?;
+
+    let frames = frames
+        .into_iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            let (num, den) = frame.delay().numer_denom_ms();
+            let buffer = frame.into_buffer();
+            let image = ImageData::new(buffer.width(), buffer.height(), buffer.into_raw())
+                .with_metadata(metadata.clone());
+            Frame {
+                image,
+                duration_ms: num.checked_div(den).unwrap_or(0),
+                disposal: control.disposals.get(i).copied().unwrap_or_default(),
+            }
+        })
+        .collect();
+    Ok(Animation {
+        frames,
+        loop_count: control.loop_count,
+    })
+}
+
+/// GIF only knows opaque and transparent pixels: round alpha to one or the
+/// other and narrow to 8 bits.
+fn binary_alpha(image: &ImageData) -> ImageData {
+    let data = image
+        .as_pixel_format(PixelFormat::Rgba8)
+        .data
+        .chunks_exact(4)
+        .flat_map(|px| match px[3] {
+            0..=127 => [0, 0, 0, 0],
+            _ => [px[0], px[1], px[2], 255],
+        })
+        .collect();
+    ImageData::new(image.width, image.height, data)
+}
+
+/// The global palette and every frame mapped onto it.
+struct Palette {
+    /// RGB triples for the global colour table.
+    colors: Vec<u8>,
+    /// Index of the transparent entry, if the palette has one.
+    transparent: Option<u8>,
+    /// One index per canvas pixel for each frame.
+    frames: Vec<Vec<u8>>,
+}
+
+impl Palette {
+    /// Quantize all frames together so they share one palette. Animations
+    /// always get a transparent entry, which frame differencing uses for
+    /// pixels that did not change.
+    fn build(frames: &[ImageData], animated: bool, options: &EncodeOptions) -> Self {
+        let settings = options.codec.gif;
+        let mut max_colors = settings.max_colors.clamp(2, 256);
+        if animated {
+            max_colors = max_colors.min(255);
+        }
+        let quantize_options = QuantizeOptions {
+            max_colors,
+            dithering: settings.dithering,
+        };
+        let stacked = stack_frames(frames);
+        let (indexed, _) =
+            quantize::quantize_to_quality(&stacked, &quantize_options, options.quality);
+
+        // Median cut can blend transparent and opaque pixels into one
+        // entry; anything mostly transparent becomes the transparent entry.
+        let mut colors = Vec::with_capacity(indexed.palette.len() * 3);
+        let mut remap = Vec::with_capacity(indexed.palette.len());
+        let mut transparent = None;
+        for entry in &indexed.palette {
+            if entry[3] >= 128 {
+                remap.push((colors.len() / 3) as u8);
+                colors.extend_from_slice(&entry[..3]);
+            } else {
+                let index = *transparent.get_or_insert_with(|| {
+                    colors.extend_from_slice(&[0, 0, 0]);
+                    colors.len() / 3 - 1
+                });
+                remap.push(index as u8);
+            }
+        }
+        if transparent.is_none() && animated {
+            transparent = Some(colors.len() / 3);
+            colors.extend_from_slice(&[0, 0, 0]);
+        }
+
+        let frame_len = (stacked.width * stacked.height) as usize / frames.len();
+        let frames = indexed
+            .indices
+            .chunks_exact(frame_len)
+            .map(|frame| frame.iter().map(|&i| remap[i as usize]).collect())
+            .collect();
+        Self {
+            colors,
+            transparent: transparent.map(|i| i as u8),
+            frames,
+        }
+    }
+}
+
+/// Stack equally sized frames into one tall image for quantization.
+fn stack_frames(frames: &[ImageData]) -> ImageData {
+    let first = &frames[0];
+    let data = frames
+        .iter()
+        .flat_map(|frame| frame.data.iter().copied())
+        .collect();
+    ImageData::new(first.width, first.height * frames.len() as u32, data)
+}
+
+/// Write the GIF. Each frame after the first only stores the rectangle that
+/// differs from the previous one, with unchanged pixels left transparent.
+/// A frame is cleared to the background afterwards when the next one
+/// uncovers transparent pixels, since those cannot be drawn over.
+fn encode_gif(
+    animation: &Animation,
+    palette: &Palette,
+    width: u16,
+    height: u16,
+) -> std::result::Result<Vec<u8>, gif::EncodingError> {
+    let mut out = Vec::new();
+    let mut encoder = gif::Encoder::new(&mut out, width, height, &palette.colors)?;
+    if animation.is_animated() {
+        encoder.set_repeat(match animation.loop_count {
+            0 => gif::Repeat::Infinite,
+            n => gif::Repeat::Finite((n - 1).min(u32::from(u16::MAX)) as u16),
+        })?;
+    }
+
+    let clears: Vec<bool> = palette
+        .frames
+        .windows(2)
+        .map(|pair| match palette.transparent {
+            Some(t) => pair[0]
+                .iter()
+                .zip(&pair[1])
+                .any(|(&a, &b)| b == t && a != t),
+            None => false,
+        })
+        .chain([false])
+        .collect();
+
+    let width = usize::from(width);
+    let mut canvas: Option<&[u8]> = None;
+    for (i, (frame, indices)) in animation.frames.iter().zip(&palette.frames).enumerate() {
+        let (left, top, rect_width, rect_height) = match (canvas, clears[i]) {
+            (Some(canvas), false) => changed_rect(canvas, indices, width),
+            _ => (0, 0, width, indices.len() / width),
+        };
+        let mut buffer = Vec::with_capacity(rect_width * rect_height);
+        for row in top..top + rect_height {
+            let start = row * width + left;
+            for x in start..start + rect_width {
+                buffer.push(match (canvas, palette.transparent) {
+                    (Some(canvas), Some(t)) if canvas[x] == indices[x] => t,
+                    _ => indices[x],
+                });
+            }
+        }
+
+        encoder.write_frame(&gif::Frame {
+            delay: frame_delay(frame.duration_ms),
+            dispose: if clears[i] {
+                gif::DisposalMethod::Background
+            } else {
+                gif::DisposalMethod::Keep
+            },
+            transparent: palette.transparent,
+            left: left as u16,
+            top: top as u16,
+            width: rect_width as u16,
+            height: rect_height as u16,
+            buffer: Cow::Owned(buffer),
+            ..gif::Frame::default()
+        })?;
+        canvas = if clears[i] { None } else { Some(indices) };
+    }
+    encoder.into_inner()?;
+
+    Ok(out)
+}
+
+/// Bounding box `(left, top, width, height)` of the pixels that differ
+/// between `previous` and `next`. Identical frames keep a single pixel.
+fn changed_rect(previous: &[u8], next: &[u8], width: usize) -> (usize, usize, usize, usize) {
+    let (mut left, mut top, mut right, mut bottom) = (usize::MAX, usize::MAX, 0, 0);
+    for (i, (a, b)) in previous.iter().zip(next).enumerate() {
+        if a != b {
+            let (x, y) = (i % width, i / width);
+            left = left.min(x);
+            right = right.max(x);
+            top = top.min(y);
+            bottom = bottom.max(y);
+        }
+    }
+    if left == usize::MAX {
+        return (0, 0, 1, 1);
+    }
+    (left, top, right - left + 1, bottom - top + 1)
+}
+
+/// GIF frame delay in hundredths of a second.
+fn frame_delay(duration_ms: u32) -> u16 {
+    ((duration_ms + 5) / 10).min(u32::from(u16::MAX)) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::Limit;
+
+    fn create_test_image(width: u32, height: u32) -> ImageData {
+        let size = (width * height * 4) as usize;
+        let mut data = vec![0u8; size];
+        for y in 0..height {
+            for x in 0..width {
+                let i = ((y * width + x) * 4) as usize;
+                data[i] = (x * 255 / width) as u8;
+                data[i + 1] = (y * 255 / height) as u8;
+                data[i + 2] = 128;
+                data[i + 3] = 255;
+            }
+        }
+        ImageData::new(width, height, data)
+    }
+
+    fn solid(width: u32, height: u32, rgba: [u8; 4]) -> ImageData {
+        let data = rgba.repeat((width * height) as usize);
+        ImageData::new(width, height, data)
+    }
+
+    #[test]
+    fn encode_and_decode_roundtrip() {
+        let codec = GifCodec;
+        let original = create_test_image(32, 24);
+
+        let encoded = codec
+            .encode(&original, &EncodeOptions::default())
+            .expect("encode failed");
+        assert_eq!(&encoded[..6], b"GIF89a");
+
+        let decoded = codec.decode(&encoded).expect("decode failed");
+        assert_eq!((decoded.width, decoded.height), (32, 24));
+        assert_eq!(decoded.pixel_format, PixelFormat::Rgba8);
+    }
+
+    #[test]
+    fn few_colours_survive_exactly() {
+        let codec = GifCodec;
+        let mut image = solid(8, 8, [200, 30, 60, 255]);
+        image.data[..16].copy_from_slice(&[10, 20, 30, 255].repeat(4));
+
+        let encoded = codec.encode(&image, &EncodeOptions::default()).unwrap();
+        let decoded = codec.decode(&encoded).unwrap();
+
+        assert_eq!(decoded.data, image.data);
+    }
+
+    #[test]
+    fn transparency_roundtrip() {
+        let codec = GifCodec;
+        let mut image = solid(4, 4, [255, 0, 0, 255]);
+        image.data[3] = 0;
+        image.data[7] = 100;
+
+        let encoded = codec.encode(&image, &EncodeOptions::default()).unwrap();
+        let decoded = codec.decode(&encoded).unwrap();
+
+        assert_eq!(decoded.data[3], 0);
+        assert_eq!(decoded.data[7], 0, "alpha below half becomes transparent");
+        assert_eq!(&decoded.data[8..12], &[255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn lossless_is_rejected() {
+        let options = EncodeOptions {
+            lossless: true,
+            ..Default::default()
+        };
+        let result = GifCodec.encode(&create_test_image(4, 4), &options);
+        assert!(matches!(
+            result,
+            Err(Error::LosslessNotSupported(Format::Gif))
+        ));
+    }
+
+    fn sample_animation() -> Animation {
+        let mut second = solid(16, 16, [0, 0, 255, 255]);
+        // Only a 2x2 block changes in the third frame.
+        let mut third = second.clone();
+        for y in 5..7 {
+            for x in 9..11 {
+                let i = (y * 16 + x) * 4;
+                third.data[i..i + 4].copy_from_slice(&[255, 255, 0, 255]);
+            }
+        }
+        // The second frame uncovers a transparent corner.
+        second.data[..4].copy_from_slice(&[0, 0, 0, 0]);
+        third.data[..4].copy_from_slice(&[0, 0, 0, 0]);
+        Animation {
+            frames: vec![
+                Frame::new(solid(16, 16, [255, 0, 0, 255]), 100),
+                Frame::new(second, 250),
+                Frame::new(third, 40),
+            ],
+            loop_count: 3,
+        }
+    }
+
+    #[test]
+    fn animation_roundtrip_keeps_frames_and_timing() {
+        let codec = GifCodec;
+        let animation = sample_animation();
+
+        let encoded = codec
+            .encode_animation(&animation, &EncodeOptions::default())
+            .unwrap();
+        let decoded = codec.decode_animation(&encoded).unwrap();
+
+        assert_eq!(decoded.loop_count, 3);
+        let durations: Vec<u32> = decoded.frames.iter().map(|f| f.duration_ms).collect();
+        assert_eq!(durations, [100, 250, 40]);
+        for (original, frame) in animation.frames.iter().zip(&decoded.frames) {
+            assert_eq!(frame.image.data, original.image.data);
+        }
+    }
+
+    #[test]
+    fn frames_store_only_changed_rect() {
+        let animation = sample_animation();
+        let encoded = GifCodec
+            .encode_animation(&animation, &EncodeOptions::default())
+            .unwrap();
+
+        let mut decoder = gif::DecodeOptions::new()
+            .read_info(Cursor::new(&encoded))
+            .unwrap();
+        let mut rects = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            rects.push((
+                frame.left,
+                frame.top,
+                frame.width,
+                frame.height,
+                frame.dispose,
+            ));
+        }
+
+        // The first frame is cleared because the second uncovers a
+        // transparent pixel; the third only stores the changed block.
+        assert_eq!(rects[0], (0, 0, 16, 16, gif::DisposalMethod::Background));
+        assert_eq!(rects[1], (0, 0, 16, 16, gif::DisposalMethod::Keep));
+        assert_eq!(rects[2], (9, 5, 2, 2, gif::DisposalMethod::Keep));
+    }
+
+    #[test]
+    fn loop_forever_roundtrip() {
+        let mut animation = sample_animation();
+        animation.loop_count = 0;
+        let encoded = GifCodec
+            .encode_animation(&animation, &EncodeOptions::default())
+            .unwrap();
+
+        assert_eq!(GifCodec.decode_animation(&encoded).unwrap().loop_count, 0);
+    }
+
+    #[test]
+    fn animation_is_checked_against_limits_without_a_probe() {
+        let encoded = GifCodec
+            .encode_animation(&sample_animation(), &EncodeOptions::default())
+            .unwrap();
+        let limits = DecodeLimits {
+            max_alloc: Some(3 * 16 * 16 * 4 - 1),
+            ..DecodeLimits::none()
+        };
+
+        assert!(matches!(
+            GifCodec.decode_animation_limited(&encoded, &limits),
+            Err(Error::LimitExceeded {
+                limit: Limit::Alloc,
+                actual: 3072,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn still_gif_decodes_as_one_frame() {
+        let encoded = GifCodec
+            .encode(&create_test_image(8, 8), &EncodeOptions::default())
+            .unwrap();
+
+        let animation = GifCodec.decode_animation(&encoded).unwrap();
+        assert!(!animation.is_animated());
+    }
+
+    #[test]
+    fn decode_invalid_data_returns_error() {
+        assert!(GifCodec.decode(b"GIF89a not really").is_err());
+    }
+}
@@ -0,0 +1,111 @@
+use image::codecs::ico::IcoEncoder;
+use image::{ExtendedColorType, ImageEncoder};
+
+use crate::color::prepare_for_encode;
+use crate::error::{Error, Result};
+use crate::format::Format;
+
+use super::{
+    Codec, EncodeOptions, ImageData, ImageInfo, PixelFormat, decode_with_image, probe_with_image,
+};
+
+/// Largest width or height an icon entry can describe.
+const MAX_ICON_SIZE: u32 = 256;
+
+/// ICO and CUR codec backed by the `image` crate. Lossless format — quality
+/// is ignored.
+///
+/// Decoding picks the largest, deepest entry of the icon. Encoding writes a
+/// single 8-bit RGBA PNG entry, the only PNG layout icons allow, so the image
+/// must be at most 256x256.
+pub struct IcoCodec;
+
+impl Codec for IcoCodec {
+    fn format(&self) -> Format {
+        Format::Ico
+    }
+
+    fn quality_affects_size(&self, _options: &EncodeOptions) -> bool {
+        false
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<ImageData> {
+        decode_with_image(data, image::ImageFormat::Ico)
+            .map_err(|e| Error::Decode(format!("ico decode: {e}")))
+    }
+
+    fn probe(&self, data: &[u8]) -> Result<ImageInfo> {
+        probe_with_image(data, image::ImageFormat::Ico, Format::Ico)
+            .map_err(|e| Error::Decode(format!("ico header: {e}")))
+    }
+
+    fn encode(&self, image: &ImageData, options: &EncodeOptions) -> Result<Vec<u8>> {
+        if image.width > MAX_ICON_SIZE || image.height > MAX_ICON_SIZE {
+            return Err(Error::Encode(format!(
+                "ico encode: {}x{} exceeds {MAX_ICON_SIZE}x{MAX_ICON_SIZE}",
+                image.width, image.height
+            )));
+        }
+        let image = prepare_for_encode(image, options, Format::Ico);
+        let image = image.as_pixel_format(PixelFormat::Rgba8);
+
+        let mut out = Vec::new();
+        IcoEncoder::new(&mut out)
+            .write_image(
+                &image.data,
+                image.width,
+                image.height,
+                ExtendedColorType::Rgba8,
+            )
+            .map_err(|e| Error::Encode(format!("ico encode: {e}")))?;
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_and_decode_roundtrip() {
+        let data = (0..32 * 32u32)
+            .flat_map(|i| [(i * 5) as u8, (i * 11) as u8, 200, (i % 256) as u8])
+            .collect();
+        let original = ImageData::new(32, 32, data);
+
+        let encoded = IcoCodec
+            .encode(&original, &EncodeOptions::default())
+            .expect("encode failed");
+        assert_eq!(Format::from_magic_bytes(&encoded), Some(Format::Ico));
+
+        let decoded = IcoCodec.decode(&encoded).expect("decode failed");
+        assert_eq!((decoded.width, decoded.height), (32, 32));
+        assert_eq!(decoded.data, original.data, "ICO should be lossless");
+    }
+
+    #[test]
+    fn oversized_image_is_rejected() {
+        let image = ImageData::new(257, 1, vec![0; 257 * 4]);
+
+        let result = IcoCodec.encode(&image, &EncodeOptions::default());
+
+        assert!(matches!(result, Err(Error::Encode(_))));
+    }
+
+    #[test]
+    fn decode_cursor() {
+        let icon = IcoCodec
+            .encode(
+                &ImageData::new(2, 2, vec![255; 16]),
+                &EncodeOptions::default(),
+            )
+            .expect("encode failed");
+        // A cursor differs from an icon only in its type and hotspot fields.
+        let mut cursor = icon.clone();
+        cursor[2] = 2;
+        cursor[10..14].copy_from_slice(&[1, 0, 1, 0]);
+
+        let decoded = IcoCodec.decode(&cursor).expect("decode failed");
+        assert_eq!(decoded.data, vec![255; 16]);
+    }
+}
@@ -0,0 +1,493 @@
+mod transform;
+
+use mozjpeg::Marker;
+use mozjpeg::decompress::MarkerData;
+
+use crate::color::prepare_for_encode;
+use crate::error::{Error, Result};
+use crate::format::Format;
+use crate::limits::DecodeLimits;
+use crate::metadata::{Metadata, exif_orientation};
+
+use super::{Codec, EncodeOptions, ImageData, ImageInfo};
+
+pub use transform::{JpegTransform, transform_jpeg};
+
+/// Chroma subsampling used when encoding JPEG.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChromaSubsampling {
+    /// Half horizontal and vertical chroma resolution (MozJPEG default).
+    #[default]
+    Yuv420,
+    /// Half horizontal chroma resolution.
+    Yuv422,
+    /// Full chroma resolution.
+    Yuv444,
+}
+
+impl ChromaSubsampling {
+    /// Pixel block size covered by one chroma sample, as `(width, height)`.
+    fn pixel_size(self) -> (u8, u8) {
+        match self {
+            Self::Yuv420 => (2, 2),
+            Self::Yuv422 => (2, 1),
+            Self::Yuv444 => (1, 1),
+        }
+    }
+}
+
+/// JPEG encoder settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JpegOptions {
+    /// Write a progressive JPEG with optimized scans.
+    pub progressive: bool,
+    /// Compute optimal Huffman tables instead of the standard ones.
+    pub optimize_coding: bool,
+    /// Chroma subsampling mode.
+    pub chroma_subsampling: ChromaSubsampling,
+    /// Input smoothing factor (0..=100, 0 disables smoothing).
+    pub smoothing: u8,
+}
+
+impl Default for JpegOptions {
+    fn default() -> Self {
+        Self {
+            progressive: true,
+            optimize_coding: true,
+            chroma_subsampling: ChromaSubsampling::Yuv420,
+            smoothing: 0,
+        }
+    }
+}
+
+/// JPEG codec backed by MozJPEG.
+pub struct JpegCodec;
+
+impl Codec for JpegCodec {
+    fn format(&self) -> Format {
+        Format::Jpeg
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<ImageData> {
+        self.decode_limited(data, &DecodeLimits::default())
+    }
+
+    /// The header read by the decoder is checked against `limits` before
+    /// any scanline is allocated.
+    fn decode_limited(&self, data: &[u8], limits: &DecodeLimits) -> Result<ImageData> {
+        // mozjpeg uses setjmp/longjmp internally, which translates to panics
+        // in Rust. We must catch those to turn them into proper errors.
+        let data = data.to_vec();
+        let limits = *limits;
+        let result = std::panic::catch_unwind(move || -> Result<ImageData> {
+            let decompress = mozjpeg::Decompress::with_markers(&[Marker::APP(1), Marker::APP(2)])
+                .from_mem(&data)
+                .map_err(|e| Error::Decode(format!("mozjpeg decompress init: {e}")))?;
+
+            let width = decompress.width() as u32;
+            let height = decompress.height() as u32;
+            let metadata = metadata_from_markers(decompress.markers());
+            limits.check(&ImageInfo::still(
+                Format::Jpeg,
+                (width, height),
+                8,
+                false,
+                &metadata,
+            ))?;
+
+            let mut decompressor = decompress
+                .rgba()
+                .map_err(|e| Error::Decode(format!("mozjpeg rgba conversion: {e}")))?;
+
+            let pixels: Vec<[u8; 4]> = decompressor
+                .read_scanlines()
+                .map_err(|e| Error::Decode(format!("mozjpeg read scanlines: {e}")))?;
+
+            decompressor
+                .finish()
+                .map_err(|e| Error::Decode(format!("mozjpeg finish: {e}")))?;
+
+            let rgba_data: Vec<u8> = pixels.into_iter().flatten().collect();
+
+            Ok(ImageData::new(width, height, rgba_data).with_metadata(metadata))
+        });
+
+        match result {
+            Ok(inner) => inner,
+            Err(panic) => {
+                let msg = panic_message(&panic);
+                Err(Error::Decode(format!("mozjpeg panicked: {msg}")))
+            }
+        }
+    }
+
+    fn probe(&self, data: &[u8]) -> Result<ImageInfo> {
+        // Reading the header can longjmp too.
+        let data = data.to_vec();
+        let result = std::panic::catch_unwind(move || -> Result<ImageInfo> {
+            let decompress = mozjpeg::Decompress::with_markers(&[Marker::APP(1), Marker::APP(2)])
+                .from_mem(&data)
+                .map_err(|e| Error::Decode(format!("mozjpeg decompress init: {e}")))?;
+            let metadata = metadata_from_markers(decompress.markers());
+            let size = (decompress.width() as u32, decompress.height() as u32);
+            Ok(ImageInfo::still(Format::Jpeg, size, 8, false, &metadata))
+        });
+
+        match result {
+            Ok(inner) => inner,
+            Err(panic) => {
+                let msg = panic_message(&panic);
+                Err(Error::Decode(format!("mozjpeg panicked: {msg}")))
+            }
+        }
+    }
+
+    fn encode(&self, image: &ImageData, options: &EncodeOptions) -> Result<Vec<u8>> {
+        if options.lossless {
+            return Err(Error::LosslessNotSupported(Format::Jpeg));
+        }
+
+        let image = prepare_for_encode(image, options, Format::Jpeg);
+        let width = image.width;
+        let height = image.height;
+        let rgb_data = image.to_rgb();
+        let quality = options.quality as f32;
+        let jpeg = options.codec.jpeg;
+        let metadata = image.metadata.filter(options.metadata);
+
+        let result = std::panic::catch_unwind(move || -> Result<Vec<u8>> {
+            let mut compress = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_RGB);
+
+            compress.set_size(width as usize, height as usize);
+            compress.set_quality(quality);
+            if jpeg.progressive {
+                compress.set_progressive_mode();
+                compress.set_optimize_scans(true);
+            } else {
+                compress.set_optimize_scans(false);
+            }
+            compress.set_optimize_coding(jpeg.optimize_coding);
+            compress.set_smoothing_factor(jpeg.smoothing.min(100));
+
+            let chroma = jpeg.chroma_subsampling.pixel_size();
+            compress.set_chroma_sampling_pixel_sizes(chroma, chroma);
+
+            let mut compressor = compress
+                .start_compress(Vec::new())
+                .map_err(|e| Error::Encode(format!("mozjpeg compress start: {e}")))?;
+            write_metadata(&metadata, |marker, data| {
+                compressor.write_marker(marker, data)
+            });
+
+            compressor
+                .write_scanlines(&rgb_data)
+                .map_err(|e| Error::Encode(format!("mozjpeg write scanlines: {e}")))?;
+
+            let output = compressor
+                .finish()
+                .map_err(|e| Error::Encode(format!("mozjpeg finish: {e}")))?;
+
+            Ok(output)
+        });
+
+        match result {
+            Ok(inner) => inner,
+            Err(panic) => {
+                let msg = panic_message(&panic);
+                Err(Error::Encode(format!("mozjpeg panicked: {msg}")))
+            }
+        }
+    }
+}
+
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+const XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const ICC_HEADER: &[u8] = b"ICC_PROFILE\0";
+/// Largest payload a single JPEG marker segment can hold.
+const MAX_MARKER_LEN: usize = 65533;
+
+/// Collect EXIF, XMP and ICC data from saved APP1/APP2 segments.
+fn metadata_from_markers<'a>(markers: impl Iterator<Item = MarkerData<'a>>) -> Metadata {
+    let mut metadata = Metadata::default();
+    let mut icc_chunks: Vec<(u8, &[u8])> = Vec::new();
+
+    for marker in markers {
+        match marker.marker {
+            Marker::APP(1) => {
+                if let Some(exif) = marker.data.strip_prefix(EXIF_HEADER) {
+                    metadata.exif.get_or_insert_with(|| exif.to_vec());
+                } else if let Some(xmp) = marker.data.strip_prefix(XMP_HEADER) {
+                    metadata.xmp.get_or_insert_with(|| xmp.to_vec());
+                }
+            }
+            Marker::APP(2) => {
+                // Each ICC segment carries a 1-based sequence number and a total count.
+                match marker.data.strip_prefix(ICC_HEADER) {
+                    Some(chunk) if chunk.len() > 2 => icc_chunks.push((chunk[0], &chunk[2..])),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !icc_chunks.is_empty() {
+        icc_chunks.sort_by_key(|&(seq, _)| seq);
+        metadata.icc = Some(icc_chunks.into_iter().flat_map(|(_, c)| c).copied().collect());
+    }
+    metadata.orientation = metadata
+        .exif
+        .as_deref()
+        .and_then(exif_orientation)
+        .unwrap_or_default();
+    metadata
+}
+
+/// Write EXIF and XMP as APP1 and ICC as APP2 segments through
+/// `write_marker`. EXIF or XMP too large for a single segment is skipped.
+fn write_metadata(metadata: &Metadata, mut write_marker: impl FnMut(Marker, &[u8])) {
+    for (header, payload) in [
+        (EXIF_HEADER, &metadata.exif),
+        (XMP_HEADER, &metadata.xmp),
+    ] {
+        match payload {
+            Some(payload) if header.len() + payload.len() <= MAX_MARKER_LEN => {
+                write_marker(Marker::APP(1), &[header, payload].concat());
+            }
+            _ => {}
+        }
+    }
+    let Some(icc) = metadata.icc.as_deref().filter(|icc| !icc.is_empty()) else {
+        return;
+    };
+    // Each ICC segment carries a 1-based sequence number and a total count.
+    let chunks = icc.chunks(MAX_MARKER_LEN - ICC_HEADER.len() - 2);
+    let count = chunks.len() as u8;
+    for (i, chunk) in chunks.enumerate() {
+        write_marker(
+            Marker::APP(2),
+            &[ICC_HEADER, &[i as u8 + 1, count], chunk].concat(),
+        );
+    }
+}
+
+/// Extract a human-readable message from a `catch_unwind` panic payload.
+fn panic_message(panic: &Box<dyn std::any::Any + Send>) -> String {
+    if let Some(s) = panic.downcast_ref::<&str>() {
+        (*s).to_string()
+    } else if let Some(s) = panic.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::{MetadataPolicy, sample_metadata};
+
+    fn create_test_image(width: u32, height: u32) -> ImageData {
+        let size = (width * height * 4) as usize;
+        let mut data = vec![0u8; size];
+        for y in 0..height {
+            for x in 0..width {
+                let i = ((y * width + x) * 4) as usize;
+                data[i] = (x * 255 / width) as u8; // R
+                data[i + 1] = (y * 255 / height) as u8; // G
+                data[i + 2] = 128; // B
+                data[i + 3] = 255; // A
+            }
+        }
+        ImageData::new(width, height, data)
+    }
+
+    #[test]
+    fn encode_and_decode_roundtrip() {
+        let codec = JpegCodec;
+        let original = create_test_image(64, 48);
+        let options = EncodeOptions {
+            quality: 90,
+            ..Default::default()
+        };
+
+        let encoded = codec.encode(&original, &options).expect("encode failed");
+
+        // Verify JPEG magic bytes
+        assert!(
+            encoded.len() >= 3,
+            "encoded data too short: {} bytes",
+            encoded.len()
+        );
+        assert_eq!(
+            &encoded[..3],
+            &[0xFF, 0xD8, 0xFF],
+            "missing JPEG magic bytes"
+        );
+
+        // Decode back and verify dimensions
+        let decoded = codec.decode(&encoded).expect("decode failed");
+        assert_eq!(decoded.width, original.width);
+        assert_eq!(decoded.height, original.height);
+        assert_eq!(
+            decoded.data.len(),
+            (decoded.width * decoded.height * 4) as usize
+        );
+    }
+
+    #[test]
+    fn encode_produces_smaller_at_lower_quality() {
+        let codec = JpegCodec;
+        let image = create_test_image(128, 96);
+
+        let high = codec
+            .encode(
+                &image,
+                &EncodeOptions {
+                    quality: 95,
+                    ..Default::default()
+                },
+            )
+            .expect("encode q95 failed");
+        let low = codec
+            .encode(
+                &image,
+                &EncodeOptions {
+                    quality: 30,
+                    ..Default::default()
+                },
+            )
+            .expect("encode q30 failed");
+
+        assert!(
+            low.len() < high.len(),
+            "low quality ({} bytes) should be smaller than high quality ({} bytes)",
+            low.len(),
+            high.len(),
+        );
+    }
+
+    #[test]
+    fn baseline_option_disables_progressive() {
+        let codec = JpegCodec;
+        let image = create_test_image(64, 48);
+        let mut options = EncodeOptions::default();
+        options.codec.jpeg.progressive = false;
+
+        let encoded = codec.encode(&image, &options).expect("encode failed");
+
+        // Baseline JPEGs use SOF0/SOF1, progressive ones use SOF2.
+        assert!(!has_marker(&encoded, 0xC2), "baseline output should not contain SOF2");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+        assert_eq!(decoded.width, 64);
+    }
+
+    #[test]
+    fn default_options_write_progressive() {
+        let codec = JpegCodec;
+        let image = create_test_image(64, 48);
+
+        let encoded = codec
+            .encode(&image, &EncodeOptions::default())
+            .expect("encode failed");
+
+        assert!(has_marker(&encoded, 0xC2), "default output should be progressive");
+    }
+
+    #[test]
+    fn full_chroma_is_larger_than_subsampled() {
+        let codec = JpegCodec;
+        let image = create_test_image(128, 96);
+        let mut options = EncodeOptions {
+            quality: 90,
+            ..Default::default()
+        };
+
+        let subsampled = codec.encode(&image, &options).expect("encode 4:2:0 failed");
+        options.codec.jpeg.chroma_subsampling = ChromaSubsampling::Yuv444;
+        let full = codec.encode(&image, &options).expect("encode 4:4:4 failed");
+
+        assert!(
+            full.len() > subsampled.len(),
+            "4:4:4 ({} bytes) should be larger than 4:2:0 ({} bytes)",
+            full.len(),
+            subsampled.len(),
+        );
+    }
+
+    #[test]
+    fn metadata_roundtrip_with_keep_policy() {
+        let codec = JpegCodec;
+        let image = create_test_image(16, 16).with_metadata(sample_metadata());
+        let options = EncodeOptions {
+            metadata: MetadataPolicy::Keep,
+            ..Default::default()
+        };
+
+        let encoded = codec.encode(&image, &options).expect("encode failed");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.metadata, sample_metadata());
+    }
+
+    #[test]
+    fn large_icc_profile_spans_several_segments() {
+        let codec = JpegCodec;
+        let icc: Vec<u8> = (0..150_000u32).map(|i| i as u8).collect();
+        let image = create_test_image(8, 8).with_metadata(Metadata {
+            icc: Some(icc.clone()),
+            ..Default::default()
+        });
+
+        let encoded = codec.encode(&image, &EncodeOptions::default()).expect("encode failed");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.metadata.icc, Some(icc));
+    }
+
+    /// Whether the JPEG stream contains a `0xFF <marker>` segment.
+    fn has_marker(data: &[u8], marker: u8) -> bool {
+        data.windows(2).any(|w| w == [0xFF, marker])
+    }
+
+    #[test]
+    fn decode_invalid_data_returns_error() {
+        let codec = JpegCodec;
+        let result = codec.decode(b"not a jpeg");
+        assert!(result.is_err(), "decoding invalid data should fail");
+    }
+
+    #[test]
+    fn oversized_header_is_rejected_before_decoding() {
+        let mut jpeg = JpegCodec
+            .encode(&create_test_image(16, 16), &EncodeOptions::default())
+            .expect("encode failed");
+        // Rewrite the SOF2 frame size to 60000x60000.
+        let sof = jpeg
+            .windows(2)
+            .position(|marker| marker == [0xFF, 0xC2])
+            .expect("no SOF2 marker");
+        jpeg[sof + 5..sof + 9].copy_from_slice(&[0xEA, 0x60, 0xEA, 0x60]);
+
+        assert!(matches!(
+            JpegCodec.decode(&jpeg),
+            Err(Error::LimitExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn lossless_is_rejected() {
+        let codec = JpegCodec;
+        let image = create_test_image(8, 8);
+        let options = EncodeOptions {
+            lossless: true,
+            ..Default::default()
+        };
+
+        let result = codec.encode(&image, &options);
+        assert!(matches!(
+            result,
+            Err(Error::LosslessNotSupported(Format::Jpeg))
+        ));
+    }
+}
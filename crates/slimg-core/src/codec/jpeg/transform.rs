@@ -0,0 +1,536 @@
+//! Lossless JPEG transforms on the DCT coefficients, through the libjpeg
+//! transform code (`transupp.c`) that ships with MozJPEG's `jpegtran`.
+
+use std::cell::Cell;
+use std::ffi::{c_int, c_uint, c_ulong};
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use mozjpeg::Marker;
+use mozjpeg::decompress::MarkerData;
+use mozjpeg_sys::*;
+
+use crate::crop::{CropMode, calculate_crop_region};
+use crate::error::{Error, Result};
+use crate::metadata::{MetadataPolicy, reset_exif_orientation};
+use crate::orientation::Orientation;
+
+use super::{metadata_from_markers, panic_message, write_metadata};
+
+/// A lossless rewrite of a JPEG file.
+///
+/// Everything here works on the quantized DCT coefficients, so the pixels
+/// are never decoded and no generation loss is added.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JpegTransform {
+    /// Rotation or flip to apply, read the same way as the EXIF orientation.
+    pub orientation: Orientation,
+    /// Region to keep, measured after the rotation or flip. The top-left
+    /// corner moves up and left to the nearest MCU boundary (8 or 16 pixels)
+    /// and the region grows to match.
+    pub crop: Option<CropMode>,
+    /// Drop the partial MCUs along the right and bottom edges that a rotation
+    /// or flip cannot move, instead of failing.
+    pub trim: bool,
+    /// Write a progressive JPEG with optimized scans.
+    pub progressive: bool,
+    /// Compute optimal Huffman tables instead of the standard ones.
+    pub optimize_coding: bool,
+    /// Which metadata to carry over. With a rotation or flip, the EXIF
+    /// Orientation tag is reset to 1 and `Keep` carries only EXIF, XMP and
+    /// ICC instead of every marker.
+    pub metadata: MetadataPolicy,
+}
+
+impl Default for JpegTransform {
+    fn default() -> Self {
+        Self {
+            orientation: Orientation::Normal,
+            crop: None,
+            trim: false,
+            progressive: true,
+            optimize_coding: true,
+            metadata: MetadataPolicy::default(),
+        }
+    }
+}
+
+/// Rewrite a JPEG file without decoding it.
+pub fn transform_jpeg(data: &[u8], transform: &JpegTransform) -> Result<Vec<u8>> {
+    // libjpeg reports errors by unwinding; note whether we got as far as
+    // writing to tell a broken input from a failed transform.
+    let writing = Cell::new(false);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        run_transform(data, transform, &writing)
+    }));
+
+    match result {
+        Ok(inner) => inner,
+        Err(panic) if writing.get() => Err(Error::Encode(format!(
+            "jpeg transform: {}",
+            panic_message(&panic)
+        ))),
+        Err(panic) => Err(Error::Decode(format!(
+            "jpeg transform: {}",
+            panic_message(&panic)
+        ))),
+    }
+}
+
+/// The steps of `jpegtran`: read the coefficients, let transupp rearrange
+/// them, and write them out with new scans and Huffman tables.
+fn run_transform(data: &[u8], transform: &JpegTransform, writing: &Cell<bool>) -> Result<Vec<u8>> {
+    let mut src = Decompressor::new();
+    let src = &mut src.cinfo;
+    let mut dst = Compressor::new();
+
+    unsafe {
+        jpeg_mem_src(src, data.as_ptr(), data.len() as c_ulong);
+        // Keep copies every marker as it is; otherwise the metadata is read
+        // so the policy can filter it. A rotation or flip also goes through
+        // the metadata so the EXIF Orientation tag can be reset, or viewers
+        // would rotate the result a second time.
+        let reorients = transform.orientation != Orientation::Normal;
+        let keep_markers = transform.metadata == MetadataPolicy::Keep && !reorients;
+        if keep_markers {
+            jcopy_markers_setup(src, JCOPYOPT_ALL);
+        } else {
+            jpeg_save_markers(src, Marker::APP(1).into(), 0xFFFF);
+            jpeg_save_markers(src, Marker::APP(2).into(), 0xFFFF);
+        }
+        jpeg_read_header(src, 1);
+
+        let mut info = transform_info(transform, src.image_width, src.image_height)?;
+        if jtransform_request_workspace(src, &mut info) == 0 {
+            return Err(Error::Encode(format!(
+                "jpeg transform: {}x{} is not a whole number of MCUs; \
+                 enable trim to drop the partial edge",
+                src.image_width, src.image_height
+            )));
+        }
+        let src_coefs = jpeg_read_coefficients(src);
+
+        writing.set(true);
+        let cinfo = &mut dst.cinfo;
+        jpeg_copy_critical_parameters(src, cinfo);
+        let dst_coefs = jtransform_adjust_parameters(src, cinfo, src_coefs, &mut info);
+        if transform.progressive {
+            // Build the scan script again for this file's components.
+            jpeg_simple_progression(cinfo);
+        } else {
+            jpeg_c_set_bool_param(cinfo, J_BOOLEAN_PARAM::JBOOLEAN_OPTIMIZE_SCANS, 0);
+            cinfo.num_scans = 0;
+            cinfo.scan_info = ptr::null();
+        }
+        cinfo.optimize_coding = transform.optimize_coding.into();
+        jpeg_mem_dest(cinfo, &mut dst.out, &mut dst.out_len);
+
+        jpeg_write_coefficients(cinfo, dst_coefs);
+        if keep_markers {
+            jcopy_markers_execute(src, cinfo, JCOPYOPT_ALL);
+        } else {
+            let mut metadata = metadata_from_markers(saved_markers(src)).filter(transform.metadata);
+            if reorients && let Some(exif) = metadata.exif.as_mut() {
+                reset_exif_orientation(exif);
+            }
+            write_metadata(&metadata, |marker, data| {
+                jpeg_write_marker(cinfo, marker.into(), data.as_ptr(), data.len() as c_uint)
+            });
+        }
+        jtransform_execute_transform(src, cinfo, src_coefs, &mut info);
+        jpeg_finish_compress(cinfo);
+        jpeg_finish_decompress(src);
+
+        Ok(slice::from_raw_parts(dst.out, dst.out_len as usize).to_vec())
+    }
+}
+
+/// Transform options for an image of the given size.
+fn transform_info(
+    transform: &JpegTransform,
+    width: u32,
+    height: u32,
+) -> Result<jpeg_transform_info> {
+    // Every field is a number, a boolean or a null pointer.
+    let mut info: jpeg_transform_info = unsafe { mem::zeroed() };
+    info.transform = jxform(transform.orientation);
+    info.perfect = (!transform.trim).into();
+    info.trim = transform.trim.into();
+
+    if let Some(mode) = &transform.crop {
+        let (width, height) = if transform.orientation.swaps_dimensions() {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        let (x, y, crop_w, crop_h) = calculate_crop_region(width, height, mode)?;
+        info.crop = 1;
+        info.crop_xoffset = x;
+        info.crop_xoffset_set = JCROP_POS;
+        info.crop_yoffset = y;
+        info.crop_yoffset_set = JCROP_POS;
+        info.crop_width = crop_w;
+        info.crop_width_set = JCROP_POS;
+        info.crop_height = crop_h;
+        info.crop_height_set = JCROP_POS;
+    }
+    Ok(info)
+}
+
+fn jxform(orientation: Orientation) -> c_int {
+    match orientation {
+        Orientation::Normal => JXFORM_NONE,
+        Orientation::FlipHorizontal => JXFORM_FLIP_H,
+        Orientation::Rotate180 => JXFORM_ROT_180,
+        Orientation::FlipVertical => JXFORM_FLIP_V,
+        Orientation::Transpose => JXFORM_TRANSPOSE,
+        Orientation::Rotate90 => JXFORM_ROT_90,
+        Orientation::Transverse => JXFORM_TRANSVERSE,
+        Orientation::Rotate270 => JXFORM_ROT_270,
+    }
+}
+
+/// The markers libjpeg saved while reading the header.
+fn saved_markers(cinfo: &jpeg_decompress_struct) -> impl Iterator<Item = MarkerData<'_>> {
+    let mut next = cinfo.marker_list;
+    std::iter::from_fn(move || {
+        // Saved markers live as long as the decompressor.
+        let marker = unsafe { next.as_ref()? };
+        next = marker.next;
+        Some(MarkerData {
+            marker: marker.marker.into(),
+            data: unsafe { slice::from_raw_parts(marker.data, marker.data_length as usize) },
+        })
+    })
+}
+
+/// Error manager that unwinds with libjpeg's message instead of exiting.
+fn init_error_mgr(err: &mut jpeg_error_mgr) {
+    unsafe { jpeg_std_error(err) };
+    err.error_exit = Some(unwind_error_exit);
+    err.emit_message = Some(ignore_message);
+}
+
+extern "C-unwind" fn unwind_error_exit(cinfo: &mut jpeg_common_struct) {
+    // JMSG_LENGTH_MAX in jerror.h.
+    let mut buffer = [0u8; 200];
+    let format = unsafe { cinfo.err.as_ref() }.and_then(|err| err.format_message);
+    let msg = match format {
+        Some(format) => {
+            // The binding takes the buffer by shared reference, but libjpeg
+            // writes into it.
+            let format: unsafe extern "C-unwind" fn(&mut jpeg_common_struct, *mut u8) =
+                unsafe { mem::transmute(format) };
+            unsafe { format(cinfo, buffer.as_mut_ptr()) };
+            let len = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+            String::from_utf8_lossy(&buffer[..len]).into_owned()
+        }
+        None => "libjpeg error".to_string(),
+    };
+    panic::resume_unwind(Box::new(msg));
+}
+
+extern "C-unwind" fn ignore_message(_cinfo: &mut jpeg_common_struct, _level: c_int) {}
+
+/// libjpeg decompressor, destroyed on drop even when an error unwinds.
+struct Decompressor {
+    cinfo: jpeg_decompress_struct,
+    err: jpeg_error_mgr,
+}
+
+impl Decompressor {
+    fn new() -> Box<Self> {
+        // libjpeg keeps pointers into both structs, so they must not move.
+        let mut this: Box<Self> = Box::new(unsafe { mem::zeroed() });
+        init_error_mgr(&mut this.err);
+        this.cinfo.common.err = &mut this.err;
+        unsafe { jpeg_create_decompress(&mut this.cinfo) };
+        this
+    }
+}
+
+impl Drop for Decompressor {
+    fn drop(&mut self) {
+        unsafe { jpeg_destroy_decompress(&mut self.cinfo) };
+    }
+}
+
+/// libjpeg compressor writing to a buffer that libjpeg allocates.
+struct Compressor {
+    cinfo: jpeg_compress_struct,
+    err: jpeg_error_mgr,
+    out: *mut u8,
+    out_len: c_ulong,
+}
+
+impl Compressor {
+    fn new() -> Box<Self> {
+        let mut this: Box<Self> = Box::new(unsafe { mem::zeroed() });
+        init_error_mgr(&mut this.err);
+        this.cinfo.common.err = &mut this.err;
+        unsafe { jpeg_create_compress(&mut this.cinfo) };
+        this
+    }
+}
+
+impl Drop for Compressor {
+    fn drop(&mut self) {
+        unsafe {
+            jpeg_destroy_compress(&mut self.cinfo);
+            if !self.out.is_null() {
+                libc::free(self.out.cast());
+            }
+        }
+    }
+}
+
+// transupp.h, built into mozjpeg-sys by its `jpegtran` feature. The enums
+// are C ints.
+
+const JXFORM_NONE: c_int = 0;
+const JXFORM_FLIP_H: c_int = 1;
+const JXFORM_FLIP_V: c_int = 2;
+const JXFORM_TRANSPOSE: c_int = 3;
+const JXFORM_TRANSVERSE: c_int = 4;
+const JXFORM_ROT_90: c_int = 5;
+const JXFORM_ROT_180: c_int = 6;
+const JXFORM_ROT_270: c_int = 7;
+
+const JCROP_POS: c_int = 1;
+
+const JCOPYOPT_ALL: c_int = 2;
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+struct jpeg_transform_info {
+    transform: c_int,
+    perfect: boolean,
+    trim: boolean,
+    force_grayscale: boolean,
+    crop: boolean,
+    slow_hflip: boolean,
+    crop_width: JDIMENSION,
+    crop_width_set: c_int,
+    crop_height: JDIMENSION,
+    crop_height_set: c_int,
+    crop_xoffset: JDIMENSION,
+    crop_xoffset_set: c_int,
+    crop_yoffset: JDIMENSION,
+    crop_yoffset_set: c_int,
+    drop_ptr: *mut jpeg_decompress_struct,
+    drop_coef_arrays: *mut *mut jvirt_barray_control,
+    num_components: c_int,
+    workspace_coef_arrays: *mut *mut jvirt_barray_control,
+    output_width: JDIMENSION,
+    output_height: JDIMENSION,
+    x_crop_offset: JDIMENSION,
+    y_crop_offset: JDIMENSION,
+    drop_width: JDIMENSION,
+    drop_height: JDIMENSION,
+    imcu_sample_width: c_int,
+    imcu_sample_height: c_int,
+}
+
+unsafe extern "C-unwind" {
+    fn jtransform_request_workspace(
+        srcinfo: &mut jpeg_decompress_struct,
+        info: &mut jpeg_transform_info,
+    ) -> boolean;
+    fn jtransform_adjust_parameters(
+        srcinfo: &mut jpeg_decompress_struct,
+        dstinfo: &mut jpeg_compress_struct,
+        src_coef_arrays: *mut *mut jvirt_barray_control,
+        info: &mut jpeg_transform_info,
+    ) -> *mut *mut jvirt_barray_control;
+    fn jtransform_execute_transform(
+        srcinfo: &mut jpeg_decompress_struct,
+        dstinfo: &mut jpeg_compress_struct,
+        src_coef_arrays: *mut *mut jvirt_barray_control,
+        info: &mut jpeg_transform_info,
+    );
+    fn jcopy_markers_setup(srcinfo: &mut jpeg_decompress_struct, option: c_int);
+    fn jcopy_markers_execute(
+        srcinfo: &mut jpeg_decompress_struct,
+        dstinfo: &mut jpeg_compress_struct,
+        option: c_int,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::jpeg::JpegCodec;
+    use crate::codec::{Codec, EncodeOptions, ImageData};
+    use crate::metadata::{Metadata, exif_orientation, sample_metadata};
+
+    /// A 32x16 JPEG: left half red, right half blue.
+    fn two_tone_jpeg(metadata: Metadata) -> Vec<u8> {
+        let data = (0..32 * 16)
+            .flat_map(|i| {
+                if i % 32 < 16 {
+                    [255, 0, 0, 255]
+                } else {
+                    [0, 0, 255, 255]
+                }
+            })
+            .collect();
+        let image = ImageData::new(32, 16, data).with_metadata(metadata);
+        let options = EncodeOptions {
+            quality: 95,
+            metadata: MetadataPolicy::Keep,
+            ..Default::default()
+        };
+        JpegCodec.encode(&image, &options).expect("encode failed")
+    }
+
+    fn pixel(image: &ImageData, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * image.width + x) * 4) as usize;
+        image.data[i..i + 4].try_into().unwrap()
+    }
+
+    fn is_red(px: [u8; 4]) -> bool {
+        px[0] > 200 && px[2] < 60
+    }
+
+    #[test]
+    fn untouched_transform_keeps_pixels() {
+        let jpeg = two_tone_jpeg(Metadata::default());
+        let original = JpegCodec.decode(&jpeg).expect("decode failed");
+
+        for progressive in [true, false] {
+            let transform = JpegTransform {
+                progressive,
+                ..Default::default()
+            };
+            let out = transform_jpeg(&jpeg, &transform).expect("transform failed");
+            let decoded = JpegCodec.decode(&out).expect("decode failed");
+
+            assert_eq!(decoded.data, original.data, "progressive: {progressive}");
+        }
+    }
+
+    #[test]
+    fn rotation_moves_blocks() {
+        let jpeg = two_tone_jpeg(Metadata::default());
+        let transform = JpegTransform {
+            orientation: Orientation::Rotate90,
+            ..Default::default()
+        };
+
+        let out = transform_jpeg(&jpeg, &transform).expect("transform failed");
+        let decoded = JpegCodec.decode(&out).expect("decode failed");
+
+        assert_eq!((decoded.width, decoded.height), (16, 32));
+        // The left half ends up on top.
+        assert!(is_red(pixel(&decoded, 8, 4)));
+        assert!(!is_red(pixel(&decoded, 8, 28)));
+    }
+
+    #[test]
+    fn crop_snaps_to_mcu_grid() {
+        let jpeg = two_tone_jpeg(Metadata::default());
+        let transform = JpegTransform {
+            crop: Some(CropMode::Region {
+                x: 20,
+                y: 3,
+                width: 8,
+                height: 8,
+            }),
+            ..Default::default()
+        };
+
+        let out = transform_jpeg(&jpeg, &transform).expect("transform failed");
+        let decoded = JpegCodec.decode(&out).expect("decode failed");
+
+        // 4:2:0 MCUs are 16x16, so the origin moves to (16, 0).
+        assert_eq!((decoded.width, decoded.height), (12, 11));
+        assert!(!is_red(pixel(&decoded, 6, 6)));
+    }
+
+    #[test]
+    fn partial_mcu_needs_trim() {
+        let image = ImageData::new(20, 16, [10, 200, 30, 255].repeat(20 * 16));
+        let jpeg = JpegCodec
+            .encode(&image, &EncodeOptions::default())
+            .expect("encode failed");
+        let mut transform = JpegTransform {
+            orientation: Orientation::FlipHorizontal,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            transform_jpeg(&jpeg, &transform),
+            Err(Error::Encode(_))
+        ));
+
+        transform.trim = true;
+        let out = transform_jpeg(&jpeg, &transform).expect("transform failed");
+        let decoded = JpegCodec.decode(&out).expect("decode failed");
+        assert_eq!((decoded.width, decoded.height), (16, 16));
+    }
+
+    #[test]
+    fn metadata_follows_policy() {
+        let metadata = sample_metadata();
+        let jpeg = two_tone_jpeg(metadata.clone());
+
+        let decode_with = |policy| {
+            let transform = JpegTransform {
+                metadata: policy,
+                ..Default::default()
+            };
+            let out = transform_jpeg(&jpeg, &transform).expect("transform failed");
+            JpegCodec.decode(&out).expect("decode failed").metadata
+        };
+
+        assert_eq!(decode_with(MetadataPolicy::Keep), metadata);
+        assert_eq!(
+            decode_with(MetadataPolicy::Safe),
+            metadata.filter(MetadataPolicy::Safe)
+        );
+        assert_eq!(decode_with(MetadataPolicy::Strip), Metadata::default());
+    }
+
+    #[test]
+    fn rotation_resets_exif_orientation() {
+        // IFD0 with Orientation = 6 (rotate 90° clockwise).
+        let exif = b"MM\0*\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0\x06\0\0\0\0\0\0".to_vec();
+        let jpeg = two_tone_jpeg(Metadata {
+            exif: Some(exif),
+            ..Default::default()
+        });
+
+        for policy in [MetadataPolicy::Keep, MetadataPolicy::Safe] {
+            let transform = JpegTransform {
+                orientation: Orientation::Rotate90,
+                metadata: policy,
+                ..Default::default()
+            };
+            let out = transform_jpeg(&jpeg, &transform).expect("transform failed");
+            let decoded = JpegCodec.decode(&out).expect("decode failed");
+
+            assert_eq!((decoded.width, decoded.height), (16, 32));
+            assert_eq!(
+                decoded.metadata.orientation,
+                Orientation::Normal,
+                "{policy:?}"
+            );
+            let exif = decoded.metadata.exif.expect("exif dropped");
+            assert_eq!(
+                exif_orientation(&exif),
+                Some(Orientation::Normal),
+                "{policy:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_data_is_a_decode_error() {
+        assert!(matches!(
+            transform_jpeg(b"\xFF\xD8\xFFnot a jpeg", &JpegTransform::default()),
+            Err(Error::Decode(_))
+        ));
+    }
+}
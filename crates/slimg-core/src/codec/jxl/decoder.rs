@@ -2,7 +2,14 @@ use std::ptr;
 
 use libjxl_sys::*;
 
+use crate::animation::{Animation, Frame};
+use crate::codec::{ImageData, ImageInfo, PixelFormat};
 use crate::error::{Error, Result};
+use crate::format::Format;
+use crate::hdr::{ColorPrimaries, ContentLightLevel, HdrMetadata, TransferFunction};
+use crate::limits::DecodeLimits;
+use crate::metadata::{Metadata, strip_exif_header};
+use crate::orientation::Orientation;
 
 /// Safe wrapper around libjxl decoder.
 pub(crate) struct Decoder {
@@ -19,15 +26,37 @@ impl Decoder {
         Ok(Self { ptr })
     }
 
-    /// Decode JXL data into RGBA pixels. Returns (width, height, rgba_pixels).
-    pub fn decode_to_rgba(&mut self, data: &[u8]) -> Result<(u32, u32, Vec<u8>)> {
+    /// Decode every frame of JXL data into RGBA pixels, along with its ICC
+    /// profile or HDR colour encoding and Exif/XMP boxes. Samples deeper than
+    /// 8 bits come back as 16-bit integers, or as floats when the codestream
+    /// stores floats. A still image is a single frame.
+    ///
+    /// With `first_only`, decoding stops after the first frame of an
+    /// animation; Exif and XMP boxes stored after the codestream are then
+    /// not read.
+    ///
+    /// The size in the headers, each frame and each output buffer are
+    /// checked against `limits` before libjxl is given anything to fill.
+    pub fn decode_frames(
+        &mut self,
+        data: &[u8],
+        limits: &DecodeLimits,
+        first_only: bool,
+    ) -> Result<Animation> {
         unsafe { JxlDecoderReset(self.ptr) };
 
-        let events = JxlDecoderStatus_JXL_DEC_BASIC_INFO | JxlDecoderStatus_JXL_DEC_FULL_IMAGE;
+        let events = JxlDecoderStatus_JXL_DEC_BASIC_INFO
+            | JxlDecoderStatus_JXL_DEC_COLOR_ENCODING
+            | JxlDecoderStatus_JXL_DEC_FRAME
+            | JxlDecoderStatus_JXL_DEC_FULL_IMAGE
+            | JxlDecoderStatus_JXL_DEC_BOX;
         let status = unsafe { JxlDecoderSubscribeEvents(self.ptr, events as i32) };
         if status != JxlDecoderStatus_JXL_DEC_SUCCESS {
             return Err(Error::Decode("failed to subscribe decoder events".into()));
         }
+        unsafe { JxlDecoderSetDecompressBoxes(self.ptr, 1) };
+        // Hand back the stored pixels; the pipeline applies the orientation.
+        unsafe { JxlDecoderSetKeepOrientation(self.ptr, 1) };
 
         let status = unsafe {
             JxlDecoderSetInput(self.ptr, data.as_ptr(), data.len())
@@ -37,20 +66,33 @@ impl Decoder {
         }
         unsafe { JxlDecoderCloseInput(self.ptr) };
 
-        let format = JxlPixelFormat {
-            num_channels: 4,
-            data_type: JxlDataType_JXL_TYPE_UINT8,
-            endianness: JxlEndianness_JXL_NATIVE_ENDIAN,
-            align: 0,
-        };
-
         let mut width = 0u32;
         let mut height = 0u32;
+        let mut pixel_format = PixelFormat::Rgba8;
         let mut pixels: Vec<u8> = Vec::new();
+        let mut metadata = Metadata::default();
+        let mut orientation = Orientation::Normal;
+        let mut intensity_target = 0f32;
+        // Animation ticks per second as (numerator, denominator), and loops.
+        let mut tick_rate = (1000u64, 1u64);
+        let mut loop_count = 0u32;
+        // Decoded frames with their duration in ticks.
+        let mut frames: Vec<(Vec<u8>, u32)> = Vec::new();
+        let mut frame_ticks = 0u32;
+        let mut last_frame = false;
+        // Box currently being read: its type and the buffer libjxl writes into.
+        let mut current_box: Option<(JxlBoxType, Vec<u8>)> = None;
 
         loop {
             let status = unsafe { JxlDecoderProcessInput(self.ptr) };
 
+            // A new box or the end of the file completes the box being read.
+            let box_ended = status == JxlDecoderStatus_JXL_DEC_BOX
+                || status == JxlDecoderStatus_JXL_DEC_SUCCESS;
+            if let Some((box_type, buf)) = current_box.take_if(|_| box_ended) {
+                self.finish_box(&box_type, buf, &mut metadata);
+            }
+
             if status == JxlDecoderStatus_JXL_DEC_BASIC_INFO {
                 let mut info: JxlBasicInfo = unsafe { std::mem::zeroed() };
                 let s = unsafe { JxlDecoderGetBasicInfo(self.ptr, &mut info) };
@@ -59,42 +101,418 @@ impl Decoder {
                 }
                 width = info.xsize;
                 height = info.ysize;
-
-                let mut buf_size: usize = 0;
-                let s = unsafe {
-                    JxlDecoderImageOutBufferSize(self.ptr, &format, &mut buf_size)
+                limits.check_size(width, height)?;
+                orientation = Orientation::from_exif(info.orientation as u16).unwrap_or_default();
+                intensity_target = info.intensity_target;
+                pixel_format = if info.exponent_bits_per_sample > 0 {
+                    PixelFormat::Rgba32F
+                } else if info.bits_per_sample > 8 {
+                    PixelFormat::Rgba16
+                } else {
+                    PixelFormat::Rgba8
                 };
-                if s != JxlDecoderStatus_JXL_DEC_SUCCESS {
-                    return Err(Error::Decode("failed to get output buffer size".into()));
+                if info.have_animation != 0 {
+                    let animation = info.animation;
+                    tick_rate = (
+                        u64::from(animation.tps_numerator.max(1)),
+                        u64::from(animation.tps_denominator.max(1)),
+                    );
+                    loop_count = animation.num_loops;
                 }
-
-                pixels.resize(buf_size, 0);
-                let s = unsafe {
-                    JxlDecoderSetImageOutBuffer(
-                        self.ptr,
-                        &format,
-                        pixels.as_mut_ptr().cast(),
-                        pixels.len(),
-                    )
-                };
+            } else if status == JxlDecoderStatus_JXL_DEC_COLOR_ENCODING {
+                match self.hdr_encoding(intensity_target) {
+                    Some(hdr) => metadata.hdr = Some(hdr),
+                    None => metadata.icc = self.embedded_icc_profile(),
+                }
+            } else if status == JxlDecoderStatus_JXL_DEC_FRAME {
+                let mut header: JxlFrameHeader = unsafe { std::mem::zeroed() };
+                let s = unsafe { JxlDecoderGetFrameHeader(self.ptr, &mut header) };
                 if s != JxlDecoderStatus_JXL_DEC_SUCCESS {
-                    return Err(Error::Decode("failed to set output buffer".into()));
+                    return Err(Error::Decode("failed to get frame header".into()));
+                }
+                frame_ticks = header.duration;
+                last_frame = header.is_last != 0;
+                limits.check_frames(frames.len() as u32 + 1)?;
+            } else if status == JxlDecoderStatus_JXL_DEC_NEED_IMAGE_OUT_BUFFER {
+                let decoded: usize = frames.iter().map(|(pixels, _)| pixels.len()).sum();
+                pixels = self.set_output_buffer(pixel_format, decoded, limits)?;
+            } else if status == JxlDecoderStatus_JXL_DEC_BOX {
+                current_box = self.start_box()?;
+            } else if status == JxlDecoderStatus_JXL_DEC_BOX_NEED_MORE_OUTPUT {
+                if let Some((_, buf)) = current_box.as_mut() {
+                    self.grow_box_buffer(buf)?;
                 }
             } else if status == JxlDecoderStatus_JXL_DEC_FULL_IMAGE {
-                return Ok((width, height, pixels));
+                frames.push((std::mem::take(&mut pixels), frame_ticks));
+                // Keep going after the last frame too: Exif and XMP boxes
+                // may follow, and reading them decodes no more pixels.
+                if first_only && !last_frame {
+                    break;
+                }
             } else if status == JxlDecoderStatus_JXL_DEC_SUCCESS {
-                if !pixels.is_empty() {
-                    return Ok((width, height, pixels));
+                if frames.is_empty() {
+                    return Err(Error::Decode(
+                        "decoder finished without producing image".into(),
+                    ));
                 }
-                return Err(Error::Decode("decoder finished without producing image".into()));
+                break;
             } else if status == JxlDecoderStatus_JXL_DEC_ERROR {
                 return Err(Error::Decode("JXL decoding failed".into()));
             }
             // JXL_DEC_NEED_MORE_INPUT shouldn't happen since we closed input
         }
+
+        // The codestream orientation wins over any Exif tag.
+        metadata.orientation = orientation;
+        let (tps_num, tps_den) = tick_rate;
+        let frames = frames
+            .into_iter()
+            .map(|(pixels, ticks)| {
+                let image = ImageData::from_raw(width, height, pixel_format, pixels)
+                    .with_metadata(metadata.clone());
+                let duration_ms = u64::from(ticks) * 1000 * tps_den / tps_num;
+                Frame::new(image, duration_ms.min(u64::from(u32::MAX)) as u32)
+            })
+            .collect();
+        Ok(Animation { frames, loop_count })
+    }
+
+    /// Read the headers, frame headers and Exif/XMP boxes of JXL data.
+    /// Without a `JXL_DEC_FULL_IMAGE` subscription libjxl skips the pixels.
+    pub fn probe(&mut self, data: &[u8]) -> Result<ImageInfo> {
+        unsafe { JxlDecoderReset(self.ptr) };
+
+        let events = JxlDecoderStatus_JXL_DEC_BASIC_INFO
+            | JxlDecoderStatus_JXL_DEC_COLOR_ENCODING
+            | JxlDecoderStatus_JXL_DEC_FRAME
+            | JxlDecoderStatus_JXL_DEC_BOX;
+        let status = unsafe { JxlDecoderSubscribeEvents(self.ptr, events as i32) };
+        if status != JxlDecoderStatus_JXL_DEC_SUCCESS {
+            return Err(Error::Decode("failed to subscribe decoder events".into()));
+        }
+        unsafe { JxlDecoderSetDecompressBoxes(self.ptr, 1) };
+        unsafe { JxlDecoderSetKeepOrientation(self.ptr, 1) };
+
+        let status = unsafe { JxlDecoderSetInput(self.ptr, data.as_ptr(), data.len()) };
+        if status != JxlDecoderStatus_JXL_DEC_SUCCESS {
+            return Err(Error::Decode("failed to set decoder input".into()));
+        }
+        unsafe { JxlDecoderCloseInput(self.ptr) };
+
+        let mut info: Option<JxlBasicInfo> = None;
+        let mut metadata = Metadata::default();
+        let mut frame_count = 0u32;
+        let mut current_box: Option<(JxlBoxType, Vec<u8>)> = None;
+
+        loop {
+            let status = unsafe { JxlDecoderProcessInput(self.ptr) };
+
+            let box_ended = status == JxlDecoderStatus_JXL_DEC_BOX
+                || status == JxlDecoderStatus_JXL_DEC_SUCCESS;
+            if let Some((box_type, buf)) = current_box.take_if(|_| box_ended) {
+                self.finish_box(&box_type, buf, &mut metadata);
+            }
+
+            if status == JxlDecoderStatus_JXL_DEC_BASIC_INFO {
+                let mut basic: JxlBasicInfo = unsafe { std::mem::zeroed() };
+                let s = unsafe { JxlDecoderGetBasicInfo(self.ptr, &mut basic) };
+                if s != JxlDecoderStatus_JXL_DEC_SUCCESS {
+                    return Err(Error::Decode("failed to get basic info".into()));
+                }
+                info = Some(basic);
+            } else if status == JxlDecoderStatus_JXL_DEC_COLOR_ENCODING {
+                let intensity_target = info.map_or(0.0, |info| info.intensity_target);
+                match self.hdr_encoding(intensity_target) {
+                    Some(hdr) => metadata.hdr = Some(hdr),
+                    None => metadata.icc = self.embedded_icc_profile(),
+                }
+            } else if status == JxlDecoderStatus_JXL_DEC_FRAME {
+                frame_count += 1;
+            } else if status == JxlDecoderStatus_JXL_DEC_BOX {
+                current_box = self.start_box()?;
+            } else if status == JxlDecoderStatus_JXL_DEC_BOX_NEED_MORE_OUTPUT {
+                if let Some((_, buf)) = current_box.as_mut() {
+                    self.grow_box_buffer(buf)?;
+                }
+            } else if status == JxlDecoderStatus_JXL_DEC_SUCCESS {
+                let info = info
+                    .ok_or_else(|| Error::Decode("decoder finished without basic info".into()))?;
+                metadata.orientation =
+                    Orientation::from_exif(info.orientation as u16).unwrap_or_default();
+                return Ok(ImageInfo {
+                    format: Format::Jxl,
+                    width: info.xsize,
+                    height: info.ysize,
+                    bit_depth: info.bits_per_sample as u8,
+                    has_alpha: info.alpha_bits > 0,
+                    frame_count: frame_count.max(1),
+                    orientation: metadata.orientation,
+                    metadata: metadata.summary(),
+                });
+            } else if status == JxlDecoderStatus_JXL_DEC_ERROR {
+                return Err(Error::Decode("JXL header parsing failed".into()));
+            }
+        }
+    }
+
+    /// Rebuild the JPEG file a JXL was recompressed from. Returns `None` when
+    /// the file carries no JPEG reconstruction data.
+    pub fn reconstruct_jpeg(&mut self, data: &[u8]) -> Result<Option<Vec<u8>>> {
+        unsafe { JxlDecoderReset(self.ptr) };
+
+        let events =
+            JxlDecoderStatus_JXL_DEC_JPEG_RECONSTRUCTION | JxlDecoderStatus_JXL_DEC_FULL_IMAGE;
+        let status = unsafe { JxlDecoderSubscribeEvents(self.ptr, events as i32) };
+        if status != JxlDecoderStatus_JXL_DEC_SUCCESS {
+            return Err(Error::Decode("failed to subscribe decoder events".into()));
+        }
+        let status = unsafe { JxlDecoderSetInput(self.ptr, data.as_ptr(), data.len()) };
+        if status != JxlDecoderStatus_JXL_DEC_SUCCESS {
+            return Err(Error::Decode("failed to set decoder input".into()));
+        }
+        unsafe { JxlDecoderCloseInput(self.ptr) };
+
+        // Recompression saves around a fifth, so this rarely needs to grow.
+        let mut jpeg: Vec<u8> = Vec::with_capacity(data.len() * 2 + BOX_CHUNK);
+        loop {
+            let status = unsafe { JxlDecoderProcessInput(self.ptr) };
+
+            if status == JxlDecoderStatus_JXL_DEC_JPEG_RECONSTRUCTION {
+                self.set_jpeg_buffer(&mut jpeg)?;
+            } else if status == JxlDecoderStatus_JXL_DEC_JPEG_NEED_MORE_OUTPUT {
+                self.release_jpeg_buffer(&mut jpeg);
+                jpeg.reserve(jpeg.capacity());
+                self.set_jpeg_buffer(&mut jpeg)?;
+            } else if status == JxlDecoderStatus_JXL_DEC_FULL_IMAGE {
+                self.release_jpeg_buffer(&mut jpeg);
+                return Ok(Some(jpeg));
+            } else if status == JxlDecoderStatus_JXL_DEC_NEED_IMAGE_OUT_BUFFER
+                || status == JxlDecoderStatus_JXL_DEC_SUCCESS
+            {
+                // libjxl wants pixels instead: there is no JPEG to give back.
+                return Ok(None);
+            } else if status == JxlDecoderStatus_JXL_DEC_ERROR {
+                return Err(Error::Decode("JXL decoder error".into()));
+            }
+        }
+    }
+
+    /// Let libjxl write into the spare capacity of `jpeg`.
+    fn set_jpeg_buffer(&mut self, jpeg: &mut Vec<u8>) -> Result<()> {
+        let spare = jpeg.spare_capacity_mut();
+        let status = unsafe {
+            JxlDecoderSetJPEGBuffer(self.ptr, spare.as_mut_ptr().cast(), spare.len())
+        };
+        if status != JxlDecoderStatus_JXL_DEC_SUCCESS {
+            return Err(Error::Decode("failed to set JPEG buffer".into()));
+        }
+        Ok(())
+    }
+
+    /// Take back the JPEG buffer and extend `jpeg` by what was written.
+    fn release_jpeg_buffer(&mut self, jpeg: &mut Vec<u8>) {
+        let remaining = unsafe { JxlDecoderReleaseJPEGBuffer(self.ptr) };
+        let written = jpeg.capacity() - jpeg.len() - remaining;
+        unsafe { jpeg.set_len(jpeg.len() + written) };
+    }
+
+    /// Allocate a buffer for the next frame and hand it to libjxl. The
+    /// buffer must stay alive until the frame's `JXL_DEC_FULL_IMAGE`.
+    ///
+    /// `decoded` is the size of the frames already decoded; together with
+    /// the new buffer it must fit in the allocation limit.
+    fn set_output_buffer(
+        &mut self,
+        pixel_format: PixelFormat,
+        decoded: usize,
+        limits: &DecodeLimits,
+    ) -> Result<Vec<u8>> {
+        let format = jxl_pixel_format(pixel_format);
+
+        let mut buf_size: usize = 0;
+        let s = unsafe { JxlDecoderImageOutBufferSize(self.ptr, &format, &mut buf_size) };
+        if s != JxlDecoderStatus_JXL_DEC_SUCCESS {
+            return Err(Error::Decode("failed to get output buffer size".into()));
+        }
+        limits.check_alloc((decoded as u64).saturating_add(buf_size as u64))?;
+
+        let mut pixels = vec![0u8; buf_size];
+        let s = unsafe {
+            JxlDecoderSetImageOutBuffer(self.ptr, &format, pixels.as_mut_ptr().cast(), pixels.len())
+        };
+        if s != JxlDecoderStatus_JXL_DEC_SUCCESS {
+            return Err(Error::Decode("failed to set output buffer".into()));
+        }
+        Ok(pixels)
     }
 }
 
+/// Interleaved RGBA output layout for `pixel_format`.
+pub(crate) fn jxl_pixel_format(pixel_format: PixelFormat) -> JxlPixelFormat {
+    let data_type = match pixel_format {
+        PixelFormat::Rgba8 => JxlDataType_JXL_TYPE_UINT8,
+        PixelFormat::Rgba16 => JxlDataType_JXL_TYPE_UINT16,
+        PixelFormat::Rgba32F => JxlDataType_JXL_TYPE_FLOAT,
+    };
+    JxlPixelFormat {
+        num_channels: 4,
+        data_type,
+        endianness: JxlEndianness_JXL_NATIVE_ENDIAN,
+        align: 0,
+    }
+}
+
+/// Whether `encoding` is the standard sRGB colour space.
+fn is_srgb(encoding: &JxlColorEncoding) -> bool {
+    encoding.color_space == JxlColorSpace_JXL_COLOR_SPACE_RGB
+        && encoding.white_point == JxlWhitePoint_JXL_WHITE_POINT_D65
+        && encoding.primaries == JxlPrimaries_JXL_PRIMARIES_SRGB
+        && encoding.transfer_function == JxlTransferFunction_JXL_TRANSFER_FUNCTION_SRGB
+}
+
+/// Initial output buffer size for metadata boxes.
+const BOX_CHUNK: usize = 64 * 1024;
+
+impl Decoder {
+    /// The ICC profile describing the decoded pixels, or `None` when they
+    /// are plain sRGB. Enum-described spaces such as Display P3 are returned
+    /// as a profile synthesized by libjxl.
+    fn embedded_icc_profile(&self) -> Option<Vec<u8>> {
+        let target = JxlColorProfileTarget_JXL_COLOR_PROFILE_TARGET_DATA;
+        unsafe {
+            let mut encoding: JxlColorEncoding = std::mem::zeroed();
+            if JxlDecoderGetColorAsEncodedProfile(self.ptr, target, &mut encoding)
+                == JxlDecoderStatus_JXL_DEC_SUCCESS
+                && is_srgb(&encoding)
+            {
+                return None;
+            }
+
+            let mut size = 0usize;
+            if JxlDecoderGetICCProfileSize(self.ptr, target, &mut size)
+                != JxlDecoderStatus_JXL_DEC_SUCCESS
+                || size == 0
+            {
+                return None;
+            }
+            let mut icc = vec![0u8; size];
+            if JxlDecoderGetColorAsICCProfile(self.ptr, target, icc.as_mut_ptr(), size)
+                != JxlDecoderStatus_JXL_DEC_SUCCESS
+            {
+                return None;
+            }
+            Some(icc)
+        }
+    }
+
+    /// The HDR signalling of an enum-described PQ or HLG colour space.
+    ///
+    /// JXL has no MaxCLL/MaxFALL; the header's intensity target (peak
+    /// luminance in nits) stands in for MaxCLL unless it is libjxl's 10000
+    /// nit default for PQ.
+    fn hdr_encoding(&self, intensity_target: f32) -> Option<HdrMetadata> {
+        let target = JxlColorProfileTarget_JXL_COLOR_PROFILE_TARGET_DATA;
+        let mut encoding: JxlColorEncoding = unsafe { std::mem::zeroed() };
+        let status =
+            unsafe { JxlDecoderGetColorAsEncodedProfile(self.ptr, target, &mut encoding) };
+        if status != JxlDecoderStatus_JXL_DEC_SUCCESS
+            || encoding.color_space != JxlColorSpace_JXL_COLOR_SPACE_RGB
+            || encoding.white_point != JxlWhitePoint_JXL_WHITE_POINT_D65
+        {
+            return None;
+        }
+
+        let transfer = if encoding.transfer_function == JxlTransferFunction_JXL_TRANSFER_FUNCTION_PQ
+        {
+            TransferFunction::Pq
+        } else if encoding.transfer_function == JxlTransferFunction_JXL_TRANSFER_FUNCTION_HLG {
+            TransferFunction::Hlg
+        } else {
+            return None;
+        };
+        let primaries = if encoding.primaries == JxlPrimaries_JXL_PRIMARIES_SRGB {
+            ColorPrimaries::Bt709
+        } else if encoding.primaries == JxlPrimaries_JXL_PRIMARIES_P3 {
+            ColorPrimaries::DisplayP3
+        } else if encoding.primaries == JxlPrimaries_JXL_PRIMARIES_2100 {
+            ColorPrimaries::Bt2020
+        } else {
+            return None;
+        };
+        let content_light_level = (intensity_target > 0.0 && intensity_target < 10000.0)
+            .then(|| ContentLightLevel {
+                max_cll: intensity_target.round() as u16,
+                max_fall: 0,
+            });
+        Some(HdrMetadata {
+            transfer,
+            primaries,
+            content_light_level,
+        })
+    }
+
+    /// Start reading the current box if it holds Exif or XMP.
+    fn start_box(&mut self) -> Result<Option<(JxlBoxType, Vec<u8>)>> {
+        let mut box_type: JxlBoxType = [0; 4];
+        let status = unsafe { JxlDecoderGetBoxType(self.ptr, box_type.as_mut_ptr(), 1) };
+        if status != JxlDecoderStatus_JXL_DEC_SUCCESS {
+            return Err(Error::Decode("failed to get box type".into()));
+        }
+        if !matches!(&box_type_bytes(&box_type), b"Exif" | b"xml ") {
+            return Ok(None);
+        }
+
+        let mut buf = vec![0u8; BOX_CHUNK];
+        let status = unsafe { JxlDecoderSetBoxBuffer(self.ptr, buf.as_mut_ptr(), buf.len()) };
+        if status != JxlDecoderStatus_JXL_DEC_SUCCESS {
+            return Err(Error::Decode("failed to set box buffer".into()));
+        }
+        // Track the filled length in `buf.len()`; capacity holds the rest.
+        buf.truncate(0);
+        Ok(Some((box_type, buf)))
+    }
+
+    /// Hand libjxl a larger buffer for the rest of the current box.
+    fn grow_box_buffer(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        let remaining = unsafe { JxlDecoderReleaseBoxBuffer(self.ptr) };
+        let written = buf.capacity() - remaining;
+        unsafe { buf.set_len(written) };
+        buf.reserve(written.max(BOX_CHUNK));
+
+        let spare = buf.spare_capacity_mut();
+        let status = unsafe {
+            JxlDecoderSetBoxBuffer(self.ptr, spare.as_mut_ptr().cast(), spare.len())
+        };
+        if status != JxlDecoderStatus_JXL_DEC_SUCCESS {
+            return Err(Error::Decode("failed to set box buffer".into()));
+        }
+        Ok(())
+    }
+
+    /// Release the box buffer and store its contents in `metadata`.
+    fn finish_box(&mut self, box_type: &JxlBoxType, mut buf: Vec<u8>, metadata: &mut Metadata) {
+        let remaining = unsafe { JxlDecoderReleaseBoxBuffer(self.ptr) };
+        let filled = buf.capacity() - remaining;
+        unsafe { buf.set_len(filled) };
+
+        match &box_type_bytes(box_type) {
+            // Exif boxes start with a 4-byte offset to the TIFF header.
+            b"Exif" if buf.len() >= 4 => {
+                let offset = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]) as usize;
+                if let Some(tiff) = buf.get(4 + offset..) {
+                    metadata.exif = Some(strip_exif_header(tiff).to_vec());
+                }
+            }
+            b"xml " => metadata.xmp = Some(buf),
+            _ => {}
+        }
+    }
+}
+
+fn box_type_bytes(box_type: &JxlBoxType) -> [u8; 4] {
+    box_type.map(|c| c as u8)
+}
+
 impl Drop for Decoder {
     fn drop(&mut self) {
         unsafe {
@@ -2,8 +2,12 @@ use std::ptr;
 
 use libjxl_sys::*;
 
+use crate::codec::{ImageData, PixelFormat};
 use crate::error::{Error, Result};
+use crate::hdr::{ColorPrimaries, HdrMetadata, TransferFunction};
+use crate::metadata::Metadata;
 
+use super::decoder::jxl_pixel_format;
 use super::types::EncodeConfig;
 
 /// Safe wrapper around libjxl encoder.
@@ -21,18 +25,34 @@ impl Encoder {
         Ok(Self { ptr })
     }
 
-    /// Encode RGBA pixel data into JXL format.
+    /// Encode RGBA frames into JXL format, embedding `metadata`. HDR
+    /// pixels are tagged with their PQ/HLG colour encoding instead of an ICC
+    /// profile.
+    ///
+    /// Each frame is an image and its duration in milliseconds; all share
+    /// the first frame's size and pixel format. More than one frame writes an
+    /// animation that plays `loop_count` times.
     pub fn encode_rgba(
         &mut self,
-        pixels: &[u8],
-        width: u32,
-        height: u32,
+        frames: &[(&ImageData, u32)],
+        loop_count: u32,
         config: &EncodeConfig,
+        metadata: &Metadata,
     ) -> Result<Vec<u8>> {
         unsafe { JxlEncoderReset(self.ptr) };
 
-        self.set_basic_info(width, height, config)?;
-        self.set_color_encoding()?;
+        let Some(&(first, _)) = frames.first() else {
+            return Err(Error::Encode("no frames to encode".into()));
+        };
+        let (width, height, pixel_format) = (first.width, first.height, first.pixel_format);
+        let animation = (frames.len() > 1).then_some(loop_count);
+        self.set_basic_info(width, height, pixel_format, animation, config, metadata)?;
+        match (&metadata.hdr, &metadata.icc) {
+            (Some(hdr), _) => self.set_hdr_color_encoding(hdr)?,
+            (None, Some(icc)) => self.set_icc_profile(icc)?,
+            (None, None) => self.set_color_encoding()?,
+        }
+        self.add_metadata_boxes(metadata)?;
 
         let frame_settings =
             unsafe { JxlEncoderFrameSettingsCreate(self.ptr, ptr::null()) };
@@ -43,31 +63,86 @@ impl Encoder {
         }
 
         self.configure_frame(frame_settings, config)?;
-        self.add_frame(frame_settings, pixels, width, height)?;
+        for &(image, duration_ms) in frames {
+            if animation.is_some() {
+                self.set_frame_duration(frame_settings, duration_ms)?;
+            }
+            self.add_frame(frame_settings, &image.data, width, height, pixel_format)?;
+        }
 
         unsafe { JxlEncoderCloseInput(self.ptr) };
 
         self.process_output()
     }
 
+    /// Recompress JPEG bytes without decoding them to pixels, storing the
+    /// reconstruction data libjxl needs to give back the exact original file.
+    pub fn encode_jpeg(&mut self, jpeg: &[u8], config: &EncodeConfig) -> Result<Vec<u8>> {
+        unsafe { JxlEncoderReset(self.ptr) };
+        unsafe {
+            check_status(
+                JxlEncoderStoreJPEGMetadata(self.ptr, 1),
+                "store jpeg metadata",
+            )?;
+        }
+
+        let frame_settings = unsafe { JxlEncoderFrameSettingsCreate(self.ptr, ptr::null()) };
+        if frame_settings.is_null() {
+            return Err(Error::Encode("failed to create frame settings".into()));
+        }
+        // Distance and lossless settings do not apply: the DCT coefficients
+        // are kept as they are.
+        self.set_speed(frame_settings, config)?;
+        unsafe {
+            check_status(
+                JxlEncoderAddJPEGFrame(frame_settings, jpeg.as_ptr(), jpeg.len()),
+                "add jpeg frame",
+            )?;
+            JxlEncoderCloseInput(self.ptr);
+        }
+
+        self.process_output()
+    }
+
     fn set_basic_info(
         &self,
         width: u32,
         height: u32,
+        pixel_format: PixelFormat,
+        animation: Option<u32>,
         config: &EncodeConfig,
+        metadata: &Metadata,
     ) -> Result<()> {
+        // Float samples are stored as IEEE single precision.
+        let (bits, exponent_bits) = match pixel_format {
+            PixelFormat::Rgba8 => (8, 0),
+            PixelFormat::Rgba16 => (16, 0),
+            PixelFormat::Rgba32F => (32, 8),
+        };
         unsafe {
             let mut info: JxlBasicInfo = std::mem::zeroed();
             info.xsize = width;
             info.ysize = height;
-            info.bits_per_sample = 8;
-            info.exponent_bits_per_sample = 0;
+            info.bits_per_sample = bits;
+            info.exponent_bits_per_sample = exponent_bits;
             info.num_color_channels = 3;
             info.num_extra_channels = 1;
-            info.alpha_bits = 8;
-            info.alpha_exponent_bits = 0;
-            info.orientation = JxlOrientation_JXL_ORIENT_IDENTITY;
+            info.alpha_bits = bits;
+            info.alpha_exponent_bits = exponent_bits;
+            // JxlOrientation uses the EXIF numbering.
+            info.orientation = metadata.orientation.to_exif() as JxlOrientation;
+            // Peak luminance; left at zero, libjxl picks its default.
+            if let Some(level) = metadata.hdr.and_then(|hdr| hdr.content_light_level) {
+                info.intensity_target = level.max_cll as f32;
+            }
             info.uses_original_profile = if config.lossless { 1 } else { 0 };
+            // Frame durations are given in milliseconds.
+            if let Some(loop_count) = animation {
+                info.have_animation = 1;
+                info.animation.tps_numerator = 1000;
+                info.animation.tps_denominator = 1;
+                info.animation.num_loops = loop_count;
+            }
 
             check_status(
                 JxlEncoderSetBasicInfo(self.ptr, &info),
@@ -87,6 +162,70 @@ impl Encoder {
         }
     }
 
+    fn set_hdr_color_encoding(&self, hdr: &HdrMetadata) -> Result<()> {
+        unsafe {
+            let mut color: JxlColorEncoding = std::mem::zeroed();
+            color.color_space = JxlColorSpace_JXL_COLOR_SPACE_RGB;
+            color.white_point = JxlWhitePoint_JXL_WHITE_POINT_D65;
+            color.primaries = match hdr.primaries {
+                ColorPrimaries::Bt709 => JxlPrimaries_JXL_PRIMARIES_SRGB,
+                ColorPrimaries::DisplayP3 => JxlPrimaries_JXL_PRIMARIES_P3,
+                ColorPrimaries::Bt2020 => JxlPrimaries_JXL_PRIMARIES_2100,
+            };
+            color.transfer_function = match hdr.transfer {
+                TransferFunction::Pq => JxlTransferFunction_JXL_TRANSFER_FUNCTION_PQ,
+                TransferFunction::Hlg => JxlTransferFunction_JXL_TRANSFER_FUNCTION_HLG,
+            };
+            color.rendering_intent = JxlRenderingIntent_JXL_RENDERING_INTENT_RELATIVE;
+            check_status(
+                JxlEncoderSetColorEncoding(self.ptr, &color),
+                "set color encoding",
+            )
+        }
+    }
+
+    fn set_icc_profile(&self, icc: &[u8]) -> Result<()> {
+        unsafe {
+            check_status(
+                JxlEncoderSetICCProfile(self.ptr, icc.as_ptr(), icc.len()),
+                "set icc profile",
+            )
+        }
+    }
+
+    /// Store EXIF and XMP in `Exif` and `xml ` container boxes.
+    fn add_metadata_boxes(&self, metadata: &Metadata) -> Result<()> {
+        if metadata.exif.is_none() && metadata.xmp.is_none() {
+            return Ok(());
+        }
+        unsafe { check_status(JxlEncoderUseBoxes(self.ptr), "use boxes")? };
+
+        if let Some(exif) = &metadata.exif {
+            // The Exif box starts with the offset of the TIFF header.
+            let contents = [&[0u8; 4][..], exif].concat();
+            self.add_box(b"Exif", &contents)?;
+        }
+        if let Some(xmp) = &metadata.xmp {
+            self.add_box(b"xml ", xmp)?;
+        }
+        Ok(())
+    }
+
+    fn add_box(&self, box_type: &[u8; 4], contents: &[u8]) -> Result<()> {
+        unsafe {
+            check_status(
+                JxlEncoderAddBox(
+                    self.ptr,
+                    box_type.as_ptr().cast(),
+                    contents.as_ptr(),
+                    contents.len(),
+                    0,
+                ),
+                "add box",
+            )
+        }
+    }
+
     fn configure_frame(
         &self,
         settings: *mut JxlEncoderFrameSettings,
@@ -100,6 +239,7 @@ impl Encoder {
                 )?;
             }
         }
+        self.set_speed(settings, config)?;
         unsafe {
             check_status(
                 JxlEncoderSetFrameDistance(settings, config.distance),
@@ -108,21 +248,60 @@ impl Encoder {
         }
     }
 
+    /// Apply the effort and decoding speed of `config`.
+    fn set_speed(
+        &self,
+        settings: *mut JxlEncoderFrameSettings,
+        config: &EncodeConfig,
+    ) -> Result<()> {
+        unsafe {
+            check_status(
+                JxlEncoderFrameSettingsSetOption(
+                    settings,
+                    JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_EFFORT,
+                    config.effort,
+                ),
+                "set effort",
+            )?;
+            check_status(
+                JxlEncoderFrameSettingsSetOption(
+                    settings,
+                    JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_DECODING_SPEED,
+                    config.decoding_speed,
+                ),
+                "set decoding speed",
+            )
+        }
+    }
+
+    /// Set the duration, in ticks of one millisecond, of the next frame.
+    fn set_frame_duration(
+        &self,
+        settings: *mut JxlEncoderFrameSettings,
+        duration_ms: u32,
+    ) -> Result<()> {
+        unsafe {
+            let mut header: JxlFrameHeader = std::mem::zeroed();
+            JxlEncoderInitFrameHeader(&mut header);
+            header.duration = duration_ms;
+            check_status(
+                JxlEncoderSetFrameHeader(settings, &header),
+                "set frame header",
+            )
+        }
+    }
+
     fn add_frame(
         &self,
         settings: *mut JxlEncoderFrameSettings,
         pixels: &[u8],
         width: u32,
         height: u32,
+        pixel_format: PixelFormat,
     ) -> Result<()> {
-        let format = JxlPixelFormat {
-            num_channels: 4,
-            data_type: JxlDataType_JXL_TYPE_UINT8,
-            endianness: JxlEndianness_JXL_NATIVE_ENDIAN,
-            align: 0,
-        };
+        let format = jxl_pixel_format(pixel_format);
 
-        let expected = (width as usize) * (height as usize) * 4;
+        let expected = (width as usize) * (height as usize) * pixel_format.bytes_per_pixel();
         debug_assert_eq!(pixels.len(), expected);
 
         unsafe {
@@ -2,10 +2,31 @@ mod decoder;
 mod encoder;
 mod types;
 
-use crate::error::Result;
+use crate::animation::Animation;
+use crate::color::prepare_for_encode;
+use crate::error::{Error, Result};
 use crate::format::Format;
+use crate::limits::DecodeLimits;
 
-use super::{Codec, EncodeOptions, ImageData};
+use super::{Codec, EncodeOptions, ImageData, ImageInfo};
+
+/// JPEG XL encoder settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JxlOptions {
+    /// Encoder effort (1..=10). Higher is slower but smaller.
+    pub effort: u8,
+    /// Decoding speed tier (0..=4). Higher decodes faster at some cost in size.
+    pub decoding_speed: u8,
+}
+
+impl Default for JxlOptions {
+    fn default() -> Self {
+        Self {
+            effort: 7,
+            decoding_speed: 0,
+        }
+    }
+}
 
 /// JXL codec backed by libjxl (BSD-3-Clause) for both encoding and decoding.
 pub struct JxlCodec;
@@ -16,21 +37,94 @@ impl Codec for JxlCodec {
     }
 
     fn decode(&self, data: &[u8]) -> Result<ImageData> {
-        let mut dec = decoder::Decoder::new()?;
-        let (width, height, pixels) = dec.decode_to_rgba(data)?;
-        Ok(ImageData::new(width, height, pixels))
+        self.decode_limited(data, &DecodeLimits::default())
     }
 
     fn encode(&self, image: &ImageData, options: &EncodeOptions) -> Result<Vec<u8>> {
-        let config = types::EncodeConfig::from_quality(options.quality);
+        self.encode_animation(&Animation::from_still(image.clone()), options)
+    }
+
+    fn probe(&self, data: &[u8]) -> Result<ImageInfo> {
+        decoder::Decoder::new()?.probe(data)
+    }
+
+    fn decode_animation(&self, data: &[u8]) -> Result<Animation> {
+        self.decode_animation_limited(data, &DecodeLimits::default())
+    }
+
+    /// Decoding stops after the first frame, so only that frame counts
+    /// against the allocation limit.
+    fn decode_limited(&self, data: &[u8], limits: &DecodeLimits) -> Result<ImageData> {
+        let limits = DecodeLimits {
+            max_frames: None,
+            ..*limits
+        };
+        let mut dec = decoder::Decoder::new()?;
+        dec.decode_frames(data, &limits, true)?
+            .into_first_frame()
+            .ok_or_else(|| Error::Decode("JXL file has no frames".into()))
+    }
+
+    /// libjxl reports the size of each output buffer before it is
+    /// allocated, so the limits are checked as the file is decoded rather
+    /// than by a separate probe.
+    fn decode_animation_limited(&self, data: &[u8], limits: &DecodeLimits) -> Result<Animation> {
+        let mut dec = decoder::Decoder::new()?;
+        dec.decode_frames(data, limits, false)
+    }
+
+    fn encode_animation(&self, animation: &Animation, options: &EncodeOptions) -> Result<Vec<u8>> {
+        animation.validate()?;
+        let config = types::EncodeConfig::new(options.quality, options.lossless, &options.codec.jxl);
         let mut enc = encoder::Encoder::new()?;
-        enc.encode_rgba(&image.data, image.width, image.height, &config)
+        let images: Vec<_> = animation
+            .frames
+            .iter()
+            .map(|frame| prepare_for_encode(&frame.image, options, Format::Jxl))
+            .collect();
+        let frames: Vec<(&ImageData, u32)> = images
+            .iter()
+            .zip(&animation.frames)
+            .map(|(image, frame)| (&**image, frame.duration_ms))
+            .collect();
+        let metadata = images[0].metadata.filter(options.metadata);
+        enc.encode_rgba(&frames, animation.loop_count, &config, &metadata)
     }
 }
 
+/// Recompress a JPEG file into JXL without decoding it, keeping the data
+/// needed to rebuild the original file byte for byte with [`jxl_to_jpeg`].
+///
+/// The DCT coefficients are carried over as they are, so only the effort and
+/// decoding speed of `options` apply.
+pub fn jpeg_to_jxl(jpeg: &[u8], options: &JxlOptions) -> Result<Vec<u8>> {
+    let config = types::EncodeConfig::new(100, true, options);
+    let mut enc = encoder::Encoder::new()?;
+    enc.encode_jpeg(jpeg, &config)
+}
+
+/// Rebuild the original JPEG file from a JXL made by [`jpeg_to_jxl`].
+pub fn jxl_to_jpeg(data: &[u8]) -> Result<Vec<u8>> {
+    let mut dec = decoder::Decoder::new()?;
+    dec.reconstruct_jpeg(data)?
+        .ok_or_else(|| Error::Decode("JXL file has no JPEG reconstruction data".into()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codec::PixelFormat;
+    use crate::hdr::{ColorPrimaries, ContentLightLevel, HdrMetadata, TransferFunction};
+    use crate::limits::Limit;
+    use crate::metadata::{Metadata, MetadataPolicy};
+    use crate::orientation::Orientation;
+
+    fn create_test_jpeg(width: u32, height: u32) -> Vec<u8> {
+        let image = create_test_image(width, height);
+        crate::codec::jpeg::JpegCodec
+            .encode(&image, &EncodeOptions::default())
+            .expect("jpeg encode failed")
+    }
 
     fn create_test_image(width: u32, height: u32) -> ImageData {
         let mut pixels = Vec::with_capacity((width * height * 4) as usize);
@@ -50,7 +144,10 @@ mod tests {
     fn encode_lossy_produces_valid_jxl() {
         let codec = JxlCodec;
         let image = create_test_image(8, 8);
-        let options = EncodeOptions { quality: 80 };
+        let options = EncodeOptions {
+            quality: 80,
+            ..Default::default()
+        };
 
         let encoded = codec.encode(&image, &options).expect("encode should succeed");
         assert!(!encoded.is_empty(), "encoded data should not be empty");
@@ -69,7 +166,10 @@ mod tests {
     fn encode_lossless_produces_valid_jxl() {
         let codec = JxlCodec;
         let image = create_test_image(8, 8);
-        let options = EncodeOptions { quality: 100 };
+        let options = EncodeOptions {
+            quality: 100,
+            ..Default::default()
+        };
 
         let encoded = codec.encode(&image, &options).expect("lossless encode should succeed");
         assert!(!encoded.is_empty());
@@ -79,7 +179,10 @@ mod tests {
     fn roundtrip_lossy() {
         let codec = JxlCodec;
         let original = create_test_image(16, 16);
-        let options = EncodeOptions { quality: 90 };
+        let options = EncodeOptions {
+            quality: 90,
+            ..Default::default()
+        };
 
         let encoded = codec.encode(&original, &options).expect("encode failed");
         let decoded = codec.decode(&encoded).expect("decode failed");
@@ -93,7 +196,10 @@ mod tests {
     fn roundtrip_lossless() {
         let codec = JxlCodec;
         let original = create_test_image(4, 4);
-        let options = EncodeOptions { quality: 100 };
+        let options = EncodeOptions {
+            quality: 100,
+            ..Default::default()
+        };
 
         let encoded = codec.encode(&original, &options).expect("encode failed");
         let decoded = codec.decode(&encoded).expect("decode failed");
@@ -106,4 +212,235 @@ mod tests {
             "lossless roundtrip should produce identical pixels"
         );
     }
+
+    #[test]
+    fn sixteen_bit_lossless_roundtrip() {
+        let codec = JxlCodec;
+        let samples: Vec<u16> = (0..4 * 4 * 4).map(|i| (i * 4099 % 65536) as u16).collect();
+        let original = ImageData::from_rgba16(4, 4, samples.clone());
+        let options = EncodeOptions {
+            lossless: true,
+            ..Default::default()
+        };
+
+        let encoded = codec.encode(&original, &options).expect("encode failed");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.pixel_format, PixelFormat::Rgba16);
+        assert_eq!(decoded.rgba16_samples(), samples);
+    }
+
+    #[test]
+    fn exif_and_xmp_roundtrip_in_boxes() {
+        let codec = JxlCodec;
+        let metadata = Metadata {
+            exif: Some(b"MM\0*\0\0\0\x08\0\0\0\0\0\0".to_vec()),
+            xmp: Some(b"<x:xmpmeta/>".to_vec()),
+            icc: None,
+            ..Default::default()
+        };
+        let original = create_test_image(4, 4).with_metadata(metadata.clone());
+        let options = EncodeOptions {
+            lossless: true,
+            metadata: MetadataPolicy::Keep,
+            ..Default::default()
+        };
+
+        let encoded = codec.encode(&original, &options).expect("encode failed");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.metadata, metadata);
+    }
+
+    #[test]
+    fn lossless_flag_overrides_quality() {
+        let codec = JxlCodec;
+        let original = create_test_image(4, 4);
+        let options = EncodeOptions {
+            quality: 50,
+            lossless: true,
+            ..Default::default()
+        };
+
+        let encoded = codec.encode(&original, &options).expect("encode failed");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.data, original.data);
+    }
+
+    #[test]
+    fn orientation_is_stored_in_codestream() {
+        let codec = JxlCodec;
+        let original = create_test_image(6, 4).with_metadata(Metadata {
+            orientation: Orientation::Rotate90,
+            ..Default::default()
+        });
+        let options = EncodeOptions {
+            lossless: true,
+            ..Default::default()
+        };
+
+        let encoded = codec.encode(&original, &options).expect("encode failed");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+
+        // Pixels come back as stored; the orientation is reported separately.
+        assert_eq!((decoded.width, decoded.height), (6, 4));
+        assert_eq!(decoded.data, original.data);
+        assert_eq!(decoded.metadata.orientation, Orientation::Rotate90);
+    }
+
+    #[test]
+    fn hdr_colour_encoding_roundtrips() {
+        let codec = JxlCodec;
+        let hdr = HdrMetadata {
+            transfer: TransferFunction::Pq,
+            primaries: ColorPrimaries::Bt2020,
+            content_light_level: Some(ContentLightLevel {
+                max_cll: 1000,
+                max_fall: 0,
+            }),
+        };
+        let samples: Vec<u16> = (0..4 * 4 * 4).map(|i| (i * 1000) as u16).collect();
+        let original = ImageData::from_rgba16(4, 4, samples.clone()).with_metadata(Metadata {
+            hdr: Some(hdr),
+            ..Default::default()
+        });
+        let options = EncodeOptions {
+            lossless: true,
+            ..Default::default()
+        };
+
+        let encoded = codec.encode(&original, &options).expect("encode failed");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.metadata.hdr, Some(hdr));
+        assert_eq!(decoded.metadata.icc, None);
+        assert_eq!(decoded.rgba16_samples(), samples);
+    }
+
+    #[test]
+    fn animation_roundtrip_keeps_frames_and_timing() {
+        let codec = JxlCodec;
+        let frames = [
+            ([255, 0, 0, 255], 100),
+            ([0, 255, 0, 128], 250),
+            ([0, 0, 255, 255], 40),
+        ]
+        .into_iter()
+        .map(|(px, duration_ms)| {
+            crate::animation::Frame::new(ImageData::new(4, 4, px.repeat(16)), duration_ms)
+        })
+        .collect();
+        let original = Animation {
+            frames,
+            loop_count: 5,
+        };
+        let options = EncodeOptions {
+            lossless: true,
+            ..Default::default()
+        };
+
+        let encoded = codec
+            .encode_animation(&original, &options)
+            .expect("encode failed");
+        let decoded = codec.decode_animation(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.loop_count, 5);
+        assert_eq!(decoded.frames.len(), 3);
+        for (orig, out) in original.frames.iter().zip(&decoded.frames) {
+            assert_eq!(out.image.data, orig.image.data);
+            assert_eq!(out.duration_ms, orig.duration_ms);
+        }
+    }
+
+    #[test]
+    fn output_buffers_are_checked_against_the_allocation_limit() {
+        let codec = JxlCodec;
+        let frames = (0..3)
+            .map(|_| crate::animation::Frame::new(create_test_image(8, 8), 100))
+            .collect();
+        let options = EncodeOptions {
+            lossless: true,
+            ..Default::default()
+        };
+        let encoded = codec
+            .encode_animation(
+                &Animation {
+                    frames,
+                    loop_count: 0,
+                },
+                &options,
+            )
+            .expect("encode failed");
+        let limits = |max_alloc| DecodeLimits {
+            max_alloc: Some(max_alloc),
+            ..DecodeLimits::none()
+        };
+        let frame_bytes = 8 * 8 * 4;
+
+        let decoded = codec.decode_animation_limited(&encoded, &limits(3 * frame_bytes));
+        assert_eq!(decoded.expect("decode failed").frames.len(), 3);
+        assert!(matches!(
+            codec.decode_animation_limited(&encoded, &limits(3 * frame_bytes - 1)),
+            Err(Error::LimitExceeded {
+                limit: Limit::Alloc,
+                actual,
+                ..
+            }) if actual == 3 * frame_bytes
+        ));
+        // A still decode stops after the first frame.
+        let still = codec.decode_limited(&encoded, &limits(frame_bytes));
+        assert_eq!(still.expect("still decode failed").width, 8);
+        assert!(
+            codec
+                .decode_limited(&encoded, &limits(frame_bytes - 1))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn probe_reads_codestream_headers() {
+        let codec = JxlCodec;
+        let original = create_test_image(6, 4).with_metadata(Metadata {
+            orientation: Orientation::Rotate90,
+            ..Default::default()
+        });
+        let options = EncodeOptions {
+            lossless: true,
+            ..Default::default()
+        };
+
+        let encoded = codec.encode(&original, &options).expect("encode failed");
+        let info = codec.probe(&encoded).expect("probe failed");
+
+        assert_eq!((info.width, info.height), (6, 4));
+        assert_eq!(info.display_dimensions(), (4, 6));
+        assert_eq!(info.orientation, Orientation::Rotate90);
+        assert_eq!(info.bit_depth, 8);
+        assert_eq!(info.frame_count, 1);
+    }
+
+    #[test]
+    fn jpeg_recompression_rebuilds_original_bytes() {
+        let jpeg = create_test_jpeg(32, 24);
+
+        let jxl = jpeg_to_jxl(&jpeg, &JxlOptions::default()).expect("transcode failed");
+        assert_eq!(Format::from_magic_bytes(&jxl), Some(Format::Jxl));
+
+        let rebuilt = jxl_to_jpeg(&jxl).expect("reconstruction failed");
+        assert_eq!(rebuilt, jpeg);
+
+        // The recompressed file is still an ordinary JXL.
+        let decoded = JxlCodec.decode(&jxl).expect("decode failed");
+        assert_eq!((decoded.width, decoded.height), (32, 24));
+    }
+
+    #[test]
+    fn jxl_without_jpeg_data_is_not_reconstructed() {
+        let encoded = JxlCodec
+            .encode(&create_test_image(4, 4), &EncodeOptions::default())
+            .expect("encode failed");
+
+        assert!(matches!(jxl_to_jpeg(&encoded), Err(Error::Decode(_))));
+    }
 }
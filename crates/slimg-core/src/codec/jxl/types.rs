@@ -1,15 +1,23 @@
+use super::JxlOptions;
+
 /// JXL encoding configuration.
 pub(crate) struct EncodeConfig {
     pub lossless: bool,
     pub distance: f32,
+    pub effort: i64,
+    pub decoding_speed: i64,
 }
 
 impl EncodeConfig {
-    pub fn from_quality(quality: u8) -> Self {
-        if quality >= 100 {
+    pub fn new(quality: u8, lossless: bool, options: &JxlOptions) -> Self {
+        let effort = options.effort.clamp(1, 10) as i64;
+        let decoding_speed = options.decoding_speed.min(4) as i64;
+        if lossless || quality >= 100 {
             return Self {
                 lossless: true,
                 distance: 0.0,
+                effort,
+                decoding_speed,
             };
         }
         let distance =
@@ -17,6 +25,8 @@ impl EncodeConfig {
         Self {
             lossless: false,
             distance,
+            effort,
+            decoding_speed,
         }
     }
 }
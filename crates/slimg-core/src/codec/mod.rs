@@ -1,51 +1,287 @@
 pub mod avif;
+pub mod bmp;
+pub mod gif;
+pub mod ico;
 pub mod jpeg;
 pub mod jxl;
 pub mod png;
+pub mod pnm;
 pub mod qoi;
+pub mod tga;
+pub mod tiff;
 pub mod webp;
 
-use crate::error::Result;
+use std::borrow::Cow;
+use std::io::Cursor;
+
+use image::{ColorType, DynamicImage, ImageBuffer, ImageDecoder};
+
+use crate::animation::Animation;
+use crate::color::ColorPolicy;
+use crate::error::{Error, Result};
 use crate::format::Format;
+use crate::hdr::ToneMapOperator;
+use crate::limits::DecodeLimits;
+use crate::metadata::{Metadata, MetadataPolicy, MetadataSummary};
+use crate::orientation::Orientation;
 
-/// Decoded image data in RGBA format (4 bytes per pixel).
+pub use avif::AvifOptions;
+pub use gif::GifOptions;
+pub use jpeg::{ChromaSubsampling, JpegOptions, JpegTransform};
+pub use jxl::JxlOptions;
+pub use png::{PngOptions, PngQuantizeOptions};
+pub use qoi::{QoiColorspace, QoiOptions};
+pub use webp::WebPOptions;
+
+/// Sample layout of [`ImageData::data`].
+///
+/// Every layout stores four interleaved RGBA channels. Samples wider than a
+/// byte are in native byte order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PixelFormat {
+    /// 8-bit unsigned integer channels.
+    #[default]
+    Rgba8,
+    /// 16-bit unsigned integer channels.
+    Rgba16,
+    /// 32-bit float channels, nominally in `0.0..=1.0`.
+    Rgba32F,
+}
+
+impl PixelFormat {
+    /// Size of one channel sample in bytes.
+    pub fn bytes_per_channel(self) -> usize {
+        match self {
+            PixelFormat::Rgba8 => 1,
+            PixelFormat::Rgba16 => 2,
+            PixelFormat::Rgba32F => 4,
+        }
+    }
+
+    /// Size of one RGBA pixel in bytes.
+    pub fn bytes_per_pixel(self) -> usize {
+        self.bytes_per_channel() * 4
+    }
+}
+
+/// Decoded image data as interleaved RGBA pixels.
 #[derive(Debug, Clone)]
 pub struct ImageData {
     pub width: u32,
     pub height: u32,
     pub data: Vec<u8>,
+    /// Layout of the samples in `data`.
+    pub pixel_format: PixelFormat,
+    /// Metadata read from the source file, if any.
+    pub metadata: Metadata,
 }
 
 impl ImageData {
-    /// Create a new `ImageData`.
+    /// Create a new 8-bit `ImageData`.
     ///
     /// # Panics (debug only)
     /// Panics if `data.len()` does not equal `width * height * 4`.
     pub fn new(width: u32, height: u32, data: Vec<u8>) -> Self {
+        Self::from_raw(width, height, PixelFormat::Rgba8, data)
+    }
+
+    /// Create an `ImageData` from raw bytes laid out as `pixel_format`.
+    ///
+    /// # Panics (debug only)
+    /// Panics if `data.len()` does not match the dimensions and format.
+    pub fn from_raw(width: u32, height: u32, pixel_format: PixelFormat, data: Vec<u8>) -> Self {
+        let bpp = pixel_format.bytes_per_pixel();
         debug_assert_eq!(
             data.len(),
-            (width as usize) * (height as usize) * 4,
-            "ImageData: expected {} bytes ({}x{}x4), got {}",
-            (width as usize) * (height as usize) * 4,
+            (width as usize) * (height as usize) * bpp,
+            "ImageData: expected {} bytes ({}x{}x{}), got {}",
+            (width as usize) * (height as usize) * bpp,
             width,
             height,
+            bpp,
             data.len(),
         );
         Self {
             width,
             height,
             data,
+            pixel_format,
+            metadata: Metadata::default(),
         }
     }
 
-    /// Convert RGBA pixel data to RGB by dropping the alpha channel.
+    /// Create a 16-bit `ImageData` from RGBA samples.
+    pub fn from_rgba16(width: u32, height: u32, samples: Vec<u16>) -> Self {
+        let data = samples.iter().flat_map(|s| s.to_ne_bytes()).collect();
+        Self::from_raw(width, height, PixelFormat::Rgba16, data)
+    }
+
+    /// Create a floating-point `ImageData` from RGBA samples.
+    pub fn from_rgba32f(width: u32, height: u32, samples: Vec<f32>) -> Self {
+        let data = samples.iter().flat_map(|s| s.to_ne_bytes()).collect();
+        Self::from_raw(width, height, PixelFormat::Rgba32F, data)
+    }
+
+    /// Attach metadata to this image.
+    pub fn with_metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Convert to another pixel format, scaling samples to the new range.
+    /// Float samples outside `0.0..=1.0` are clamped when narrowing.
+    pub fn to_pixel_format(&self, pixel_format: PixelFormat) -> ImageData {
+        self.as_pixel_format(pixel_format).into_owned()
+    }
+
+    /// Borrow `self` when it already has `pixel_format`, convert otherwise.
+    pub(crate) fn as_pixel_format(&self, pixel_format: PixelFormat) -> Cow<'_, ImageData> {
+        if self.pixel_format == pixel_format {
+            return Cow::Borrowed(self);
+        }
+        let Some(dynamic) = self.to_dynamic() else {
+            return Cow::Borrowed(self);
+        };
+        let converted = match pixel_format {
+            PixelFormat::Rgba8 => DynamicImage::ImageRgba8(dynamic.into_rgba8()),
+            PixelFormat::Rgba16 => DynamicImage::ImageRgba16(dynamic.into_rgba16()),
+            PixelFormat::Rgba32F => DynamicImage::ImageRgba32F(dynamic.into_rgba32f()),
+        };
+        Cow::Owned(Self::from_dynamic(converted).with_metadata(self.metadata.clone()))
+    }
+
+    /// Convert RGBA pixel data to 8-bit RGB by dropping the alpha channel.
     pub fn to_rgb(&self) -> Vec<u8> {
-        self.data
+        self.as_pixel_format(PixelFormat::Rgba8)
+            .data
             .chunks_exact(4)
             .flat_map(|px| &px[..3])
             .copied()
             .collect()
     }
+
+    /// The samples of a [`PixelFormat::Rgba16`] image.
+    pub(crate) fn rgba16_samples(&self) -> Vec<u16> {
+        debug_assert_eq!(self.pixel_format, PixelFormat::Rgba16);
+        self.data
+            .chunks_exact(2)
+            .map(|b| u16::from_ne_bytes([b[0], b[1]]))
+            .collect()
+    }
+
+    /// The samples of a [`PixelFormat::Rgba32F`] image.
+    pub(crate) fn rgba32f_samples(&self) -> Vec<f32> {
+        debug_assert_eq!(self.pixel_format, PixelFormat::Rgba32F);
+        self.data
+            .chunks_exact(4)
+            .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+            .collect()
+    }
+
+    /// View the pixels as an `image` crate buffer, or `None` when `data`
+    /// does not match the dimensions. Metadata is not carried over.
+    pub(crate) fn to_dynamic(&self) -> Option<DynamicImage> {
+        let (w, h) = (self.width, self.height);
+        match self.pixel_format {
+            PixelFormat::Rgba8 => {
+                ImageBuffer::from_raw(w, h, self.data.clone()).map(DynamicImage::ImageRgba8)
+            }
+            PixelFormat::Rgba16 => {
+                ImageBuffer::from_raw(w, h, self.rgba16_samples()).map(DynamicImage::ImageRgba16)
+            }
+            PixelFormat::Rgba32F => {
+                ImageBuffer::from_raw(w, h, self.rgba32f_samples()).map(DynamicImage::ImageRgba32F)
+            }
+        }
+    }
+
+    /// Build RGBA `ImageData` with the narrowest pixel format that keeps the
+    /// precision of `image`.
+    pub(crate) fn from_dynamic(image: DynamicImage) -> ImageData {
+        let (w, h) = (image.width(), image.height());
+        let color = image.color();
+        if matches!(color, ColorType::Rgb32F | ColorType::Rgba32F) {
+            Self::from_rgba32f(w, h, image.into_rgba32f().into_raw())
+        } else if color.bytes_per_pixel() > color.channel_count() {
+            Self::from_rgba16(w, h, image.into_rgba16().into_raw())
+        } else {
+            Self::new(w, h, image.into_rgba8().into_raw())
+        }
+    }
+}
+
+/// What an image file's headers say about it, read without decoding pixels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageInfo {
+    /// Format detected from the file.
+    pub format: Format,
+    /// Width of the stored pixels, before the orientation is applied.
+    pub width: u32,
+    /// Height of the stored pixels, before the orientation is applied.
+    pub height: u32,
+    /// Bits per channel sample as stored.
+    pub bit_depth: u8,
+    /// Whether the image has an alpha channel. The channel may still be
+    /// fully opaque.
+    pub has_alpha: bool,
+    /// Number of frames; 1 for still images.
+    pub frame_count: u32,
+    /// How the stored pixels must be transformed to display upright.
+    pub orientation: Orientation,
+    /// Which metadata the file carries.
+    pub metadata: MetadataSummary,
+}
+
+impl ImageInfo {
+    /// Width and height once the orientation is applied, as
+    /// [`decode`](crate::decode) returns them.
+    pub fn display_dimensions(&self) -> (u32, u32) {
+        if self.orientation.swaps_dimensions() {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        }
+    }
+
+    /// Whether an ICC colour profile is embedded.
+    pub fn has_color_profile(&self) -> bool {
+        self.metadata.icc_bytes.is_some()
+    }
+
+    /// A still image described by its size, sample layout and metadata.
+    pub(crate) fn still(
+        format: Format,
+        (width, height): (u32, u32),
+        bit_depth: u8,
+        has_alpha: bool,
+        metadata: &Metadata,
+    ) -> Self {
+        Self {
+            format,
+            width,
+            height,
+            bit_depth,
+            has_alpha,
+            frame_count: 1,
+            orientation: metadata.orientation,
+            metadata: metadata.summary(),
+        }
+    }
+}
+
+/// Per-format encoder settings.
+///
+/// Each codec only reads its own entry; the defaults reproduce the tuning
+/// slimg has always used.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CodecOptions {
+    pub jpeg: JpegOptions,
+    pub png: PngOptions,
+    pub webp: WebPOptions,
+    pub avif: AvifOptions,
+    pub jxl: JxlOptions,
+    pub qoi: QoiOptions,
+    pub gif: GifOptions,
 }
 
 /// Options for encoding an image.
@@ -53,11 +289,32 @@ impl ImageData {
 pub struct EncodeOptions {
     /// Quality value in the range 0..=100.
     pub quality: u8,
+    /// Encode without any loss. Codecs that cannot guarantee this return
+    /// [`Error::LosslessNotSupported`](crate::Error::LosslessNotSupported).
+    /// The pixels are not colour-converted either: an ICC profile or HDR
+    /// signalling the format cannot keep is dropped.
+    pub lossless: bool,
+    /// Format-specific encoder settings.
+    pub codec: CodecOptions,
+    /// Which of the image's metadata to embed in the output.
+    pub metadata: MetadataPolicy,
+    /// Whether to keep the source colour profile or convert to sRGB.
+    pub color: ColorPolicy,
+    /// How to bring HDR pixels into SDR range when the target cannot keep
+    /// them as HDR.
+    pub tone_map: ToneMapOperator,
 }
 
 impl Default for EncodeOptions {
     fn default() -> Self {
-        Self { quality: 80 }
+        Self {
+            quality: 80,
+            lossless: false,
+            codec: CodecOptions::default(),
+            metadata: MetadataPolicy::default(),
+            color: ColorPolicy::default(),
+            tone_map: ToneMapOperator::default(),
+        }
     }
 }
 
@@ -67,10 +324,140 @@ pub trait Codec {
     fn format(&self) -> Format;
 
     /// Decode raw file bytes into RGBA `ImageData`.
+    ///
+    /// The decoded size is capped, by [`DecodeLimits::default`] or by the
+    /// decoder's own limit, so a small file cannot claim unbounded memory.
     fn decode(&self, data: &[u8]) -> Result<ImageData>;
 
+    /// Read the size, sample layout, frame count and metadata from the
+    /// file's headers without decoding any pixels.
+    fn probe(&self, data: &[u8]) -> Result<ImageInfo>;
+
     /// Encode `ImageData` into the codec's file format.
     fn encode(&self, image: &ImageData, options: &EncodeOptions) -> Result<Vec<u8>>;
+
+    /// Whether a lower `options.quality` can make the output smaller. A
+    /// size budget only searches the quality when it can.
+    fn quality_affects_size(&self, options: &EncodeOptions) -> bool {
+        !options.lossless
+    }
+
+    /// Decode every frame. Still images come back as a single frame.
+    fn decode_animation(&self, data: &[u8]) -> Result<Animation> {
+        self.decode(data).map(Animation::from_still)
+    }
+
+    /// Like [`decode`](Self::decode), but the headers are checked against
+    /// `limits` first, so an oversized file fails with
+    /// [`Error::LimitExceeded`] before any pixels are allocated.
+    fn decode_limited(&self, data: &[u8], limits: &DecodeLimits) -> Result<ImageData> {
+        if !limits.is_none() {
+            let info = self.probe(data)?;
+            limits.check(&ImageInfo {
+                frame_count: 1,
+                ..info
+            })?;
+        }
+        self.decode(data)
+    }
+
+    /// Like [`decode_animation`](Self::decode_animation), with every frame
+    /// counted against `limits` before decoding.
+    ///
+    /// Animated formats override this to check the canvas size and frame
+    /// count the decoder itself reads, before any frame is composited, so
+    /// the limits hold even when the probe and the decoder disagree.
+    fn decode_animation_limited(&self, data: &[u8], limits: &DecodeLimits) -> Result<Animation> {
+        if !limits.is_none() {
+            limits.check(&self.probe(data)?)?;
+        }
+        self.decode_animation(data)
+    }
+
+    /// Encode every frame of `animation`. Formats without animation encode
+    /// a single frame and fail with [`Error::AnimationNotSupported`] for more.
+    fn encode_animation(&self, animation: &Animation, options: &EncodeOptions) -> Result<Vec<u8>> {
+        animation.validate()?;
+        match animation.frames.as_slice() {
+            [frame] => self.encode(&frame.image, options),
+            _ => Err(Error::AnimationNotSupported(self.format())),
+        }
+    }
+}
+
+/// Decode pixels and metadata with the `image` crate, keeping 16-bit and
+/// float sources at their full precision.
+pub(crate) fn decode_with_image(
+    data: &[u8],
+    format: image::ImageFormat,
+) -> image::ImageResult<ImageData> {
+    let mut decoder = image::ImageReader::with_format(Cursor::new(data), format).into_decoder()?;
+    let metadata = Metadata::from_decoder(&mut decoder);
+    let image = DynamicImage::from_decoder(decoder)?;
+
+    Ok(ImageData::from_dynamic(image).with_metadata(metadata))
+}
+
+/// Read the size, sample layout and metadata of a still image from the
+/// headers, using the `image` crate's decoder for `image_format`.
+pub(crate) fn probe_with_image(
+    data: &[u8],
+    image_format: image::ImageFormat,
+    format: Format,
+) -> image::ImageResult<ImageInfo> {
+    let mut decoder =
+        image::ImageReader::with_format(Cursor::new(data), image_format).into_decoder()?;
+    let metadata = Metadata::from_decoder(&mut decoder);
+    let color = decoder.original_color_type();
+    let bit_depth = (color.bits_per_pixel() / u16::from(color.channel_count().max(1))) as u8;
+    Ok(ImageInfo::still(
+        format,
+        decoder.dimensions(),
+        bit_depth,
+        decoder.color_type().has_alpha(),
+        &metadata,
+    ))
+}
+
+/// Encode with one of the `image` crate's encoders.
+///
+/// The pixels are first reduced to the smallest layout that holds them
+/// exactly, so opaque images are written without alpha and grey ones
+/// without colour. Encoders that cannot take the result narrow it further.
+pub(crate) fn encode_with_image(
+    image: &ImageData,
+    format: image::ImageFormat,
+) -> image::ImageResult<Vec<u8>> {
+    let Some(dynamic) = image.to_dynamic() else {
+        return Err(image::ImageError::Parameter(
+            image::error::ParameterError::from_kind(
+                image::error::ParameterErrorKind::DimensionMismatch,
+            ),
+        ));
+    };
+    let mut out = Cursor::new(Vec::new());
+    compact(dynamic).write_to(&mut out, format)?;
+    Ok(out.into_inner())
+}
+
+/// Drop the alpha channel of opaque images and the colour channels of grey
+/// ones. Float samples become 16-bit, which every encoder here can take or
+/// narrow.
+pub(crate) fn compact(image: DynamicImage) -> DynamicImage {
+    let wide = image.color().bytes_per_pixel() > image.color().channel_count();
+    let rgba = image.to_rgba16();
+    let opaque = rgba.pixels().all(|p| p[3] == u16::MAX);
+    let grey = rgba.pixels().all(|p| p[0] == p[1] && p[1] == p[2]);
+    match (wide, grey, opaque) {
+        (true, true, true) => DynamicImage::ImageLuma16(image.into_luma16()),
+        (true, true, false) => DynamicImage::ImageLumaA16(image.into_luma_alpha16()),
+        (true, false, true) => DynamicImage::ImageRgb16(image.into_rgb16()),
+        (true, false, false) => DynamicImage::ImageRgba16(rgba),
+        (false, true, true) => DynamicImage::ImageLuma8(image.into_luma8()),
+        (false, true, false) => DynamicImage::ImageLumaA8(image.into_luma_alpha8()),
+        (false, false, true) => DynamicImage::ImageRgb8(image.into_rgb8()),
+        (false, false, false) => DynamicImage::ImageRgba8(image.into_rgba8()),
+    }
 }
 
 /// Return the appropriate codec for the given format.
@@ -82,6 +469,12 @@ pub fn get_codec(format: Format) -> Box<dyn Codec> {
         Format::Avif => Box::new(avif::AvifCodec),
         Format::Jxl => Box::new(jxl::JxlCodec),
         Format::Qoi => Box::new(qoi::QoiCodec),
+        Format::Gif => Box::new(gif::GifCodec),
+        Format::Tiff => Box::new(tiff::TiffCodec),
+        Format::Bmp => Box::new(bmp::BmpCodec),
+        Format::Tga => Box::new(tga::TgaCodec),
+        Format::Ico => Box::new(ico::IcoCodec),
+        Format::Pnm => Box::new(pnm::PnmCodec),
     }
 }
 
@@ -103,6 +496,31 @@ mod tests {
     fn encode_options_default() {
         let opts = EncodeOptions::default();
         assert_eq!(opts.quality, 80);
+        assert!(!opts.lossless);
+        assert_eq!(opts.codec, CodecOptions::default());
+        assert_eq!(opts.metadata, MetadataPolicy::Safe);
+        assert_eq!(opts.color, ColorPolicy::Preserve);
+    }
+
+    #[test]
+    fn pixel_format_conversions_scale_samples() {
+        let img = ImageData::new(1, 1, vec![255, 128, 0, 255]);
+
+        let wide = img.to_pixel_format(PixelFormat::Rgba16);
+        assert_eq!(wide.pixel_format, PixelFormat::Rgba16);
+        assert_eq!(wide.rgba16_samples(), vec![65535, 128 * 257, 0, 65535]);
+
+        let float = wide.to_pixel_format(PixelFormat::Rgba32F);
+        assert_eq!(float.rgba32f_samples()[0], 1.0);
+
+        let back = float.to_pixel_format(PixelFormat::Rgba8);
+        assert_eq!(back.data, img.data);
+    }
+
+    #[test]
+    fn to_rgb_narrows_high_depth_samples() {
+        let img = ImageData::from_rgba16(1, 1, vec![65535, 0, 257, 65535]);
+        assert_eq!(img.to_rgb(), vec![255, 0, 1]);
     }
 
     #[test]
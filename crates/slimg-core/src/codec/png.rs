@@ -1,12 +1,58 @@
+use std::borrow::Cow;
 use std::io::Cursor;
 
-use image::ImageEncoder;
-use image::codecs::png::PngEncoder;
+use image::codecs::png::PngDecoder;
+use image::{AnimationDecoder, ImageDecoder};
 
+use crate::animation::{Animation, Disposal, Frame};
+use crate::color::prepare_for_encode;
 use crate::error::{Error, Result};
 use crate::format::Format;
+use crate::limits::DecodeLimits;
+use crate::metadata::Metadata;
+use crate::quantize::{self, IndexedImage, QuantizeOptions};
 
-use super::{Codec, EncodeOptions, ImageData};
+use super::{
+    Codec, EncodeOptions, ImageData, ImageInfo, PixelFormat, decode_with_image, probe_with_image,
+};
+
+/// PNG encoder settings.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PngOptions {
+    /// OxiPNG optimization preset (0..=6). `None` derives it from quality.
+    pub optimization_level: Option<u8>,
+    /// Write an Adam7-interlaced PNG.
+    pub interlace: bool,
+    /// Allow OxiPNG to rewrite the colour of fully transparent pixels.
+    pub optimize_alpha: bool,
+    /// Reduce to a palette before optimizing (lossy). `None` keeps every pixel.
+    pub quantize: Option<PngQuantizeOptions>,
+}
+
+/// Lossy palette settings for PNG.
+///
+/// The encoder picks the smallest palette that still reaches the requested
+/// `quality`, and falls back to a lossless truecolour PNG when even
+/// `max_colors` colours score below `min_quality`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PngQuantizeOptions {
+    /// Maximum palette size (2..=256).
+    pub max_colors: u16,
+    /// Dithering strength, from 0.0 (off) to 1.0 (full Floyd–Steinberg).
+    pub dithering: f32,
+    /// Lowest acceptable quality score (0..=100).
+    pub min_quality: u8,
+}
+
+impl Default for PngQuantizeOptions {
+    fn default() -> Self {
+        Self {
+            max_colors: 256,
+            dithering: 1.0,
+            min_quality: 0,
+        }
+    }
+}
 
 /// PNG codec backed by OxiPNG for optimization.
 pub struct PngCodec;
@@ -16,51 +62,367 @@ impl Codec for PngCodec {
         Format::Png
     }
 
-    fn decode(&self, data: &[u8]) -> Result<ImageData> {
-        let img = image::load_from_memory_with_format(data, image::ImageFormat::Png)
-            .map_err(|e| Error::Decode(format!("png decode: {e}")))?;
+    /// Without quantization the quality only picks an oxipng preset, which
+    /// barely changes the size.
+    fn quality_affects_size(&self, options: &EncodeOptions) -> bool {
+        !options.lossless && options.codec.png.quantize.is_some()
+    }
 
-        let rgba = img.to_rgba8();
-        let width = rgba.width();
-        let height = rgba.height();
+    fn decode(&self, data: &[u8]) -> Result<ImageData> {
+        decode_with_image(data, image::ImageFormat::Png)
+            .map_err(|e| Error::Decode(format!("png decode: {e}")))
+    }
 
-        Ok(ImageData::new(width, height, rgba.into_raw()))
+    fn probe(&self, data: &[u8]) -> Result<ImageInfo> {
+        let info = probe_with_image(data, image::ImageFormat::Png, Format::Png)
+            .map_err(|e| Error::Decode(format!("png header: {e}")))?;
+        let frame_count = apng_control(data).map_or(1, |control| control.disposals.len() as u32);
+        Ok(ImageInfo {
+            frame_count,
+            ..info
+        })
     }
 
     fn encode(&self, image: &ImageData, options: &EncodeOptions) -> Result<Vec<u8>> {
-        // First, encode as raw PNG using the image crate's PngEncoder.
-        let mut raw_png = Cursor::new(Vec::new());
-        PngEncoder::new(&mut raw_png)
-            .write_image(
-                &image.data,
-                image.width,
-                image.height,
-                image::ExtendedColorType::Rgba8,
-            )
-            .map_err(|e| Error::Encode(format!("png raw encode: {e}")))?;
-
-        let raw_bytes = raw_png.into_inner();
-
-        // Map quality to oxipng optimization preset.
-        let preset = match options.quality {
+        let image = &*prepare_for_encode(image, options, Format::Png);
+        encode_png(image, None, options)
+    }
+
+    fn decode_animation(&self, data: &[u8]) -> Result<Animation> {
+        self.decode_animation_limited(data, &DecodeLimits::default())
+    }
+
+    fn decode_animation_limited(&self, data: &[u8], limits: &DecodeLimits) -> Result<Animation> {
+        let Some(control) = apng_control(data) else {
+            return self.decode_limited(data, limits).map(Animation::from_still);
+        };
+        decode_apng(data, &control, limits)
+    }
+
+    fn encode_animation(&self, animation: &Animation, options: &EncodeOptions) -> Result<Vec<u8>> {
+        animation.validate()?;
+        if !animation.is_animated() {
+            return self.encode(&animation.frames[0].image, options);
+        }
+        let frames: Vec<_> = animation
+            .frames
+            .iter()
+            .map(|frame| prepare_for_encode(&frame.image, options, Format::Png))
+            .collect();
+        encode_png(&stack_frames(&frames), Some(animation), options)
+    }
+}
+
+/// Encode `image`, or the frames of `animation` stacked top to bottom in
+/// `image`, then optimize the result with OxiPNG.
+fn encode_png(
+    image: &ImageData,
+    animation: Option<&Animation>,
+    options: &EncodeOptions,
+) -> Result<Vec<u8>> {
+    let png = options.codec.png;
+    let metadata = image.metadata.filter(options.metadata);
+
+    // First, encode as raw PNG: indexed when quantizing, RGBA otherwise.
+    let indexed = match png.quantize {
+        Some(settings) if !options.lossless => quantize_for_quality(
+            &image.as_pixel_format(PixelFormat::Rgba8),
+            &settings,
+            options.quality,
+        ),
+        _ => None,
+    };
+    let raw_bytes = match indexed {
+        Some(indexed) => encode_indexed(&indexed, &metadata, animation)?,
+        None => encode_rgba(image, &metadata, animation)?,
+    };
+
+    // Map quality to oxipng optimization preset unless one is given.
+    let preset = match png.optimization_level {
+        Some(level) => level.min(6),
+        None => match options.quality {
             90..=100 => 1,
             70..=89 => 2,
             50..=69 => 3,
             30..=49 => 4,
             _ => 6,
-        };
+        },
+    };
+
+    let mut opts = oxipng::Options::from_preset(preset);
+    opts.interlace = Some(png.interlace);
+    // Rewriting transparent pixels would break a bit-exact round trip.
+    opts.optimize_alpha = png.optimize_alpha && !options.lossless;
+    let optimized = oxipng::optimize_from_memory(&raw_bytes, &opts)
+        .map_err(|e| Error::Encode(format!("oxipng optimize: {e}")))?;
+
+    Ok(optimized)
+}
+
+/// Stack equally sized frames into one tall image. Quantizing the stack
+/// gives every frame the same palette, which APNG requires.
+fn stack_frames(frames: &[Cow<'_, ImageData>]) -> ImageData {
+    let first = &frames[0];
+    let data = frames
+        .iter()
+        .flat_map(|frame| frame.as_pixel_format(first.pixel_format).into_owned().data)
+        .collect();
+    let height = first.height * frames.len() as u32;
+    ImageData::from_raw(first.width, height, first.pixel_format, data)
+        .with_metadata(first.metadata.clone())
+}
+
+/// Loop count and frame disposal read from APNG control chunks.
+struct ApngControl {
+    num_plays: u32,
+    disposals: Vec<Disposal>,
+}
+
+/// Read the `acTL` and `fcTL` chunks, or `None` when the PNG has fewer than
+/// two frames.
+fn apng_control(data: &[u8]) -> Option<ApngControl> {
+    let mut control = None;
+    let mut num_frames = 0;
+    let mut pos = 8;
+    while let Some(header) = data.get(pos..pos + 8) {
+        let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let body = data.get(pos + 8..pos + 8 + len)?;
+        match &header[4..] {
+            b"acTL" if len >= 8 => {
+                num_frames = u32::from_be_bytes([body[0], body[1], body[2], body[3]]);
+                control = Some(ApngControl {
+                    num_plays: u32::from_be_bytes([body[4], body[5], body[6], body[7]]),
+                    disposals: Vec::new(),
+                });
+            }
+            b"fcTL" if len >= 26 => {
+                if let Some(control) = control.as_mut() {
+                    control.disposals.push(match body[24] {
+                        1 => Disposal::Background,
+                        2 => Disposal::Previous,
+                        _ => Disposal::None,
+                    });
+                }
+            }
+            b"IEND" => break,
+            _ => {}
+        }
+        pos += 12 + len;
+    }
+    control.filter(|_| num_frames > 1)
+}
+
+/// Decode every APNG frame, composited onto the full canvas.
+/// Frames come out at 8 bits per channel.
+fn decode_apng(data: &[u8], control: &ApngControl, limits: &DecodeLimits) -> Result<Animation> {
+    let apng_error = |e: image::ImageError| Error::Decode(format!("apng decode: {e}"));
+    let mut decoder = PngDecoder::new(Cursor::new(data)).map_err(apng_error)?;
+    let metadata = Metadata::from_decoder(&mut decoder);
+    limits.check(&ImageInfo {
+        frame_count: control.disposals.len() as u32,
+        ..ImageInfo::still(Format::Png, decoder.dimensions(), 8, true, &metadata)
+    })?;
+    let frames = decoder
+        .apng()
+        .and_then(|apng| apng.into_frames().collect_frames())
+        .map_err(apng_error)?;
 
-        let opts = oxipng::Options::from_preset(preset);
-        let optimized = oxipng::optimize_from_memory(&raw_bytes, &opts)
-            .map_err(|e| Error::Encode(format!("oxipng optimize: {e}")))?;
+    let frames = frames
+        .into_iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            let (num, den) = frame.delay().numer_denom_ms();
+            let buffer = frame.into_buffer();
+            let image = ImageData::new(buffer.width(), buffer.height(), buffer.into_raw())
+                .with_metadata(metadata.clone());
+            Frame {
+                image,
+                duration_ms: num.checked_div(den).unwrap_or(0),
+                disposal: control.disposals.get(i).copied().unwrap_or_default(),
+            }
+        })
+        .collect();
+    Ok(Animation {
+        frames,
+        loop_count: control.num_plays,
+    })
+}
+
+/// APNG frame delay as a `(numerator, denominator)` fraction of a second.
+fn frame_delay(duration_ms: u32) -> (u16, u16) {
+    let (mut num, mut den) = (duration_ms, 1000);
+    while num > u32::from(u16::MAX) && den > 1 {
+        num /= 10;
+        den /= 10;
+    }
+    (num.min(u32::from(u16::MAX)) as u16, den)
+}
+
+/// iTXt keyword under which XMP is stored.
+const XMP_KEYWORD: &str = "XML:com.adobe.xmp";
+
+/// Write an RGBA PNG: 8-bit for 8-bit input, 16-bit for anything deeper.
+fn encode_rgba(
+    image: &ImageData,
+    metadata: &Metadata,
+    animation: Option<&Animation>,
+) -> Result<Vec<u8>> {
+    let (bit_depth, data) = match image.pixel_format {
+        PixelFormat::Rgba8 => (png::BitDepth::Eight, Cow::Borrowed(&image.data[..])),
+        _ => {
+            // PNG stores 16-bit samples big-endian.
+            let wide = image.as_pixel_format(PixelFormat::Rgba16);
+            let data = wide.rgba16_samples().iter().flat_map(|s| s.to_be_bytes()).collect();
+            (png::BitDepth::Sixteen, Cow::Owned(data))
+        }
+    };
+    let height = image.height / frame_count(animation);
+    let info = png_info(
+        image.width,
+        height,
+        png::ColorType::Rgba,
+        bit_depth,
+        metadata,
+    );
+    write_png(info, None, metadata, &data, animation)
+        .map_err(|e| Error::Encode(format!("png raw encode: {e}")))
+}
+
+/// PNG header with ICC and EXIF chunks from `metadata`.
+fn png_info(
+    width: u32,
+    height: u32,
+    color_type: png::ColorType,
+    bit_depth: png::BitDepth,
+    metadata: &Metadata,
+) -> png::Info<'_> {
+    let mut info = png::Info::with_size(width, height);
+    info.color_type = color_type;
+    info.bit_depth = bit_depth;
+    info.icc_profile = metadata.icc.as_deref().map(Cow::Borrowed);
+    info.exif_metadata = metadata.exif.as_deref().map(Cow::Borrowed);
+    info
+}
+
+/// Number of frames stacked in the image passed to the writers.
+fn frame_count(animation: Option<&Animation>) -> u32 {
+    animation.map_or(1, |animation| animation.frames.len() as u32)
+}
+
+/// Write a complete PNG stream, adding XMP as an iTXt chunk.
+/// `palette` is `(PLTE, tRNS)` for indexed images. With an `animation`,
+/// `data` holds its frames one after another and an APNG is written.
+fn write_png(
+    info: png::Info<'_>,
+    palette: Option<(Vec<u8>, Vec<u8>)>,
+    metadata: &Metadata,
+    data: &[u8],
+    animation: Option<&Animation>,
+) -> std::result::Result<Vec<u8>, png::EncodingError> {
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::with_info(&mut out, info)?;
+    // OxiPNG recompresses everything, so a fast first pass is enough.
+    encoder.set_compression(png::Compression::Fast);
+    if let Some((plte, trns)) = palette {
+        encoder.set_palette(plte);
+        if !trns.is_empty() {
+            encoder.set_trns(trns);
+        }
+    }
+    if let Some(xmp) = &metadata.xmp {
+        encoder.add_itxt_chunk(
+            XMP_KEYWORD.to_string(),
+            String::from_utf8_lossy(xmp).into_owned(),
+        )?;
+    }
+
+    if let Some(animation) = animation {
+        encoder.set_animated(frame_count(Some(animation)), animation.loop_count)?;
+    }
+
+    let mut writer = encoder.write_header()?;
+    match animation {
+        None => writer.write_image_data(data)?,
+        Some(animation) => {
+            let frame_len = data.len() / animation.frames.len();
+            for (frame, rows) in animation.frames.iter().zip(data.chunks_exact(frame_len)) {
+                let (num, den) = frame_delay(frame.duration_ms);
+                writer.set_frame_delay(num, den)?;
+                writer.set_dispose_op(match frame.disposal {
+                    Disposal::None => png::DisposeOp::None,
+                    Disposal::Background => png::DisposeOp::Background,
+                    Disposal::Previous => png::DisposeOp::Previous,
+                })?;
+                writer.write_image_data(rows)?;
+            }
+        }
+    }
+    writer.finish()?;
+
+    Ok(out)
+}
 
-        Ok(optimized)
+/// Quantize with the largest allowed palette, then keep halving it while the
+/// result still meets `target_quality`. Returns `None` below `min_quality`.
+fn quantize_for_quality(
+    image: &ImageData,
+    settings: &PngQuantizeOptions,
+    target_quality: u8,
+) -> Option<IndexedImage> {
+    let options = QuantizeOptions {
+        max_colors: settings.max_colors.clamp(2, 256),
+        dithering: settings.dithering,
+    };
+
+    // The search only shrinks the palette while the score stays above the
+    // floor, so a final score below `min_quality` means the full palette
+    // missed it too.
+    let floor = target_quality.max(settings.min_quality);
+    let (best, score) = quantize::quantize_to_quality(image, &options, floor);
+    (score >= settings.min_quality).then_some(best)
+}
+
+/// Write an 8-bit indexed PNG, with a tRNS chunk when any entry is translucent.
+fn encode_indexed(
+    indexed: &IndexedImage,
+    metadata: &Metadata,
+    animation: Option<&Animation>,
+) -> Result<Vec<u8>> {
+    // Translucent entries go first so the tRNS chunk stays short.
+    let mut order: Vec<usize> = (0..indexed.palette.len()).collect();
+    order.sort_by_key(|&i| indexed.palette[i][3] == 255);
+    let mut remap = vec![0u8; indexed.palette.len()];
+    for (new, &old) in order.iter().enumerate() {
+        remap[old] = new as u8;
     }
+
+    let palette: Vec<u8> = order
+        .iter()
+        .flat_map(|&i| &indexed.palette[i][..3])
+        .copied()
+        .collect();
+    let trns: Vec<u8> = order
+        .iter()
+        .map(|&i| indexed.palette[i][3])
+        .take_while(|&a| a < 255)
+        .collect();
+    let indices: Vec<u8> = indexed.indices.iter().map(|&i| remap[i as usize]).collect();
+
+    let info = png_info(
+        indexed.width,
+        indexed.height / frame_count(animation),
+        png::ColorType::Indexed,
+        png::BitDepth::Eight,
+        metadata,
+    );
+    write_png(info, Some((palette, trns)), metadata, &indices, animation)
+        .map_err(|e| Error::Encode(format!("png indexed encode: {e}")))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::metadata::{MetadataPolicy, sample_metadata};
 
     fn create_test_image(width: u32, height: u32) -> ImageData {
         let size = (width * height * 4) as usize;
@@ -81,7 +443,10 @@ mod tests {
     fn encode_and_decode_roundtrip() {
         let codec = PngCodec;
         let original = create_test_image(64, 48);
-        let options = EncodeOptions { quality: 90 };
+        let options = EncodeOptions {
+            quality: 90,
+            ..Default::default()
+        };
 
         let encoded = codec.encode(&original, &options).expect("encode failed");
 
@@ -104,10 +469,227 @@ mod tests {
         assert_eq!(decoded.data, original.data, "PNG should be lossless");
     }
 
+    #[test]
+    fn sixteen_bit_roundtrip_keeps_precision() {
+        let codec = PngCodec;
+        let samples: Vec<u16> = (0..8 * 8 * 4).map(|i| (i * 1021 % 65536) as u16).collect();
+        let original = ImageData::from_rgba16(8, 8, samples.clone());
+
+        let encoded = codec
+            .encode(&original, &EncodeOptions::default())
+            .expect("encode failed");
+
+        // IHDR bit depth follows the 4-byte width and height.
+        assert_eq!(encoded[24], 16, "IHDR should declare 16-bit samples");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+        assert_eq!(decoded.pixel_format, PixelFormat::Rgba16);
+        assert_eq!(decoded.rgba16_samples(), samples);
+    }
+
+    #[test]
+    fn interlace_option_writes_adam7() {
+        let codec = PngCodec;
+        let original = create_test_image(32, 32);
+        let mut options = EncodeOptions::default();
+        options.codec.png.interlace = true;
+
+        let encoded = codec.encode(&original, &options).expect("encode failed");
+
+        // IHDR interlace method is the last byte of the 13-byte IHDR payload.
+        assert_eq!(encoded[28], 1, "IHDR should declare Adam7 interlacing");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+        assert_eq!(decoded.data, original.data);
+    }
+
     #[test]
     fn decode_invalid_data_returns_error() {
         let codec = PngCodec;
         let result = codec.decode(b"not a png");
         assert!(result.is_err(), "decoding invalid data should fail");
     }
+
+    /// PNG color type byte from the IHDR chunk.
+    fn color_type(png: &[u8]) -> u8 {
+        png[25]
+    }
+
+    /// Gradient with pseudo-random noise, closer to photographic content.
+    fn create_noisy_image(width: u32, height: u32) -> ImageData {
+        let mut image = create_test_image(width, height);
+        let mut seed = 0x2545_f491u32;
+        for px in image.data.chunks_exact_mut(4) {
+            for c in &mut px[..3] {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                *c = c.saturating_add((seed >> 27) as u8);
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn quantize_writes_indexed_png() {
+        let codec = PngCodec;
+        let original = create_noisy_image(64, 48);
+        let mut options = EncodeOptions::default();
+        options.codec.png.quantize = Some(PngQuantizeOptions::default());
+
+        let lossless = codec
+            .encode(&original, &EncodeOptions::default())
+            .expect("lossless encode failed");
+        let lossy = codec.encode(&original, &options).expect("lossy encode failed");
+
+        assert_eq!(color_type(&lossy), 3, "expected an indexed PNG");
+        assert!(
+            lossy.len() < lossless.len(),
+            "quantized ({} bytes) should be smaller than lossless ({} bytes)",
+            lossy.len(),
+            lossless.len(),
+        );
+        let decoded = codec.decode(&lossy).expect("decode failed");
+        assert_eq!(decoded.width, 64);
+        assert_eq!(decoded.height, 48);
+    }
+
+    #[test]
+    fn quantize_keeps_alpha_in_trns() {
+        let codec = PngCodec;
+        let mut original = create_test_image(32, 32);
+        for px in original.data.chunks_exact_mut(4).step_by(4) {
+            px[3] = 0;
+        }
+        let mut options = EncodeOptions::default();
+        options.codec.png.quantize = Some(PngQuantizeOptions::default());
+
+        let encoded = codec.encode(&original, &options).expect("encode failed");
+
+        assert!(encoded.windows(4).any(|w| w == b"tRNS"));
+        let decoded = codec.decode(&encoded).expect("decode failed");
+        for (orig, out) in original.data.chunks_exact(4).zip(decoded.data.chunks_exact(4)) {
+            assert_eq!(orig[3] == 0, out[3] == 0, "transparency should be preserved");
+        }
+    }
+
+    #[test]
+    fn quantize_below_min_quality_falls_back_to_truecolor() {
+        let codec = PngCodec;
+        let original = create_test_image(64, 48);
+        let mut options = EncodeOptions::default();
+        options.codec.png.quantize = Some(PngQuantizeOptions {
+            max_colors: 2,
+            min_quality: 100,
+            ..Default::default()
+        });
+
+        let encoded = codec.encode(&original, &options).expect("encode failed");
+
+        assert_ne!(color_type(&encoded), 3);
+        let decoded = codec.decode(&encoded).expect("decode failed");
+        assert_eq!(decoded.data, original.data);
+    }
+
+    #[test]
+    fn metadata_roundtrip_with_keep_policy() {
+        let codec = PngCodec;
+        let image = create_test_image(16, 16).with_metadata(sample_metadata());
+        let mut options = EncodeOptions {
+            metadata: MetadataPolicy::Keep,
+            ..Default::default()
+        };
+
+        let truecolor = codec.encode(&image, &options).expect("encode failed");
+        options.codec.png.quantize = Some(PngQuantizeOptions::default());
+        let indexed = codec.encode(&image, &options).expect("indexed encode failed");
+
+        for encoded in [truecolor, indexed] {
+            let decoded = codec.decode(&encoded).expect("decode failed");
+            assert_eq!(decoded.metadata, sample_metadata());
+        }
+    }
+
+    #[test]
+    fn lossless_ignores_quantize() {
+        let codec = PngCodec;
+        let original = create_test_image(64, 48);
+        let mut options = EncodeOptions {
+            lossless: true,
+            ..Default::default()
+        };
+        options.codec.png.quantize = Some(PngQuantizeOptions::default());
+
+        let encoded = codec.encode(&original, &options).expect("encode failed");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.data, original.data);
+    }
+
+    /// Three 16x12 frames with distinct colours, timings and disposals.
+    fn sample_animation() -> Animation {
+        let frames = [
+            ([255, 0, 0, 255], 100),
+            ([0, 255, 0, 128], 250),
+            ([0, 0, 255, 255], 40),
+        ]
+        .into_iter()
+        .map(|(px, duration_ms)| {
+            Frame::new(ImageData::new(16, 12, px.repeat(16 * 12)), duration_ms)
+        })
+        .collect::<Vec<_>>();
+        let mut animation = Animation {
+            frames,
+            loop_count: 3,
+        };
+        animation.frames[1].disposal = Disposal::Background;
+        animation
+    }
+
+    #[test]
+    fn apng_roundtrip_keeps_frames_and_timing() {
+        let codec = PngCodec;
+        let original = sample_animation();
+
+        let encoded = codec
+            .encode_animation(&original, &EncodeOptions::default())
+            .expect("encode failed");
+        let decoded = codec.decode_animation(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.loop_count, 3);
+        assert_eq!(decoded.frames.len(), 3);
+        for (orig, out) in original.frames.iter().zip(&decoded.frames) {
+            assert_eq!(out.image.data, orig.image.data);
+            assert_eq!(out.duration_ms, orig.duration_ms);
+            assert_eq!(out.disposal, orig.disposal);
+        }
+        // Still decoding sees the first frame.
+        let first = codec.decode(&encoded).expect("decode failed");
+        assert_eq!(first.data, original.frames[0].image.data);
+    }
+
+    #[test]
+    fn quantized_apng_shares_one_palette() {
+        let codec = PngCodec;
+        let mut options = EncodeOptions::default();
+        options.codec.png.quantize = Some(PngQuantizeOptions::default());
+
+        let encoded = codec
+            .encode_animation(&sample_animation(), &options)
+            .expect("encode failed");
+
+        assert_eq!(color_type(&encoded), 3, "expected an indexed APNG");
+        let decoded = codec.decode_animation(&encoded).expect("decode failed");
+        assert_eq!(decoded.frames.len(), 3);
+    }
+
+    #[test]
+    fn single_frame_animation_is_a_plain_png() {
+        let codec = PngCodec;
+        let still = Animation::from_still(create_test_image(8, 8));
+
+        let encoded = codec
+            .encode_animation(&still, &EncodeOptions::default())
+            .expect("encode failed");
+
+        assert!(!encoded.windows(4).any(|w| w == b"acTL"));
+        let decoded = codec.decode_animation(&encoded).expect("decode failed");
+        assert!(!decoded.is_animated());
+    }
 }
@@ -0,0 +1,128 @@
+use image::DynamicImage;
+use image::codecs::pnm::{PnmEncoder, PnmSubtype, SampleEncoding};
+
+use crate::color::prepare_for_encode;
+use crate::error::{Error, Result};
+use crate::format::Format;
+
+use super::{
+    Codec, EncodeOptions, ImageData, ImageInfo, compact, decode_with_image, probe_with_image,
+};
+
+/// Netpbm codec (PBM, PGM, PPM and PAM) backed by the `image` crate.
+/// Lossless format — quality is ignored.
+///
+/// Opaque 8-bit images are written as binary PGM or PPM. Everything else
+/// becomes PAM, which keeps alpha and 16-bit samples.
+pub struct PnmCodec;
+
+impl Codec for PnmCodec {
+    fn format(&self) -> Format {
+        Format::Pnm
+    }
+
+    fn quality_affects_size(&self, _options: &EncodeOptions) -> bool {
+        false
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<ImageData> {
+        decode_with_image(data, image::ImageFormat::Pnm)
+            .map_err(|e| Error::Decode(format!("pnm decode: {e}")))
+    }
+
+    fn probe(&self, data: &[u8]) -> Result<ImageInfo> {
+        probe_with_image(data, image::ImageFormat::Pnm, Format::Pnm)
+            .map_err(|e| Error::Decode(format!("pnm header: {e}")))
+    }
+
+    fn encode(&self, image: &ImageData, options: &EncodeOptions) -> Result<Vec<u8>> {
+        let image = prepare_for_encode(image, options, Format::Pnm);
+        let Some(dynamic) = image.to_dynamic() else {
+            return Err(Error::Encode(format!(
+                "pnm encode: {} bytes do not make a {}x{} {:?} image",
+                image.data.len(),
+                image.width,
+                image.height,
+                image.pixel_format
+            )));
+        };
+
+        let mut out = Vec::new();
+        let encoder = PnmEncoder::new(&mut out);
+        let compacted = compact(dynamic);
+        let result = match &compacted {
+            DynamicImage::ImageLuma8(_) => compacted.write_with_encoder(
+                encoder.with_subtype(PnmSubtype::Graymap(SampleEncoding::Binary)),
+            ),
+            DynamicImage::ImageRgb8(_) => compacted.write_with_encoder(
+                encoder.with_subtype(PnmSubtype::Pixmap(SampleEncoding::Binary)),
+            ),
+            _ => compacted.write_with_encoder(encoder),
+        };
+        result.map_err(|e| Error::Encode(format!("pnm encode: {e}")))?;
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::PixelFormat;
+
+    #[test]
+    fn opaque_colour_is_written_as_ppm() {
+        let original = ImageData::new(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 255]);
+
+        let encoded = PnmCodec
+            .encode(&original, &EncodeOptions::default())
+            .expect("encode failed");
+        assert_eq!(&encoded[..2], b"P6");
+
+        let decoded = PnmCodec.decode(&encoded).expect("decode failed");
+        assert_eq!(decoded.data, original.data);
+    }
+
+    #[test]
+    fn opaque_grey_is_written_as_pgm() {
+        let original = ImageData::new(2, 1, vec![10, 10, 10, 255, 200, 200, 200, 255]);
+
+        let encoded = PnmCodec
+            .encode(&original, &EncodeOptions::default())
+            .expect("encode failed");
+        assert_eq!(&encoded[..2], b"P5");
+    }
+
+    #[test]
+    fn alpha_is_written_as_pam() {
+        let original = ImageData::new(1, 1, vec![1, 2, 3, 4]);
+
+        let encoded = PnmCodec
+            .encode(&original, &EncodeOptions::default())
+            .expect("encode failed");
+        assert_eq!(&encoded[..2], b"P7");
+
+        let decoded = PnmCodec.decode(&encoded).expect("decode failed");
+        assert_eq!(decoded.data, original.data);
+    }
+
+    #[test]
+    fn sixteen_bit_samples_survive() {
+        let original =
+            ImageData::from_rgba16(2, 1, vec![1, 2, 3, 65535, 40000, 50000, 60000, 65535]);
+
+        let encoded = PnmCodec
+            .encode(&original, &EncodeOptions::default())
+            .expect("encode failed");
+        let decoded = PnmCodec.decode(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.pixel_format, PixelFormat::Rgba16);
+        assert_eq!(decoded.data, original.data);
+    }
+
+    #[test]
+    fn decode_ascii_bitmap() {
+        let decoded = PnmCodec.decode(b"P1\n2 1\n1 0\n").expect("decode failed");
+
+        assert_eq!(decoded.data, vec![0, 0, 0, 255, 255, 255, 255, 255]);
+    }
+}
@@ -1,9 +1,29 @@
 use rapid_qoi::{Colors, Qoi};
 
+use crate::color::prepare_for_encode;
 use crate::error::{Error, Result};
 use crate::format::Format;
+use crate::limits::DecodeLimits;
+use crate::metadata::Metadata;
+
+use super::{Codec, EncodeOptions, ImageData, ImageInfo, PixelFormat};
+
+/// Colourspace tag written into the QOI header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QoiColorspace {
+    /// sRGB colour channels with linear alpha.
+    #[default]
+    Srgb,
+    /// All channels linear.
+    Linear,
+}
 
-use super::{Codec, EncodeOptions, ImageData};
+/// QOI encoder settings.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct QoiOptions {
+    /// Colourspace recorded in the header (informational only).
+    pub colorspace: QoiColorspace,
+}
 
 /// QOI codec backed by rapid-qoi. Lossless format — quality is ignored.
 pub struct QoiCodec;
@@ -13,7 +33,18 @@ impl Codec for QoiCodec {
         Format::Qoi
     }
 
+    fn quality_affects_size(&self, _options: &EncodeOptions) -> bool {
+        false
+    }
+
     fn decode(&self, data: &[u8]) -> Result<ImageData> {
+        self.decode_limited(data, &DecodeLimits::default())
+    }
+
+    /// The header is checked against `limits` before the pixels are
+    /// allocated.
+    fn decode_limited(&self, data: &[u8], limits: &DecodeLimits) -> Result<ImageData> {
+        limits.check(&self.probe(data)?)?;
         let (header, pixels) =
             Qoi::decode_alloc(data).map_err(|e| Error::Decode(format!("qoi decode: {e}")))?;
 
@@ -33,11 +64,30 @@ impl Codec for QoiCodec {
         Ok(ImageData::new(width, height, rgba))
     }
 
-    fn encode(&self, image: &ImageData, _options: &EncodeOptions) -> Result<Vec<u8>> {
+    fn probe(&self, data: &[u8]) -> Result<ImageInfo> {
+        let header =
+            Qoi::decode_header(data).map_err(|e| Error::Decode(format!("qoi header: {e}")))?;
+        Ok(ImageInfo::still(
+            Format::Qoi,
+            (header.width, header.height),
+            8,
+            header.colors.has_alpha(),
+            &Metadata::default(),
+        ))
+    }
+
+    fn encode(&self, image: &ImageData, options: &EncodeOptions) -> Result<Vec<u8>> {
+        // QOI has no room for an ICC profile.
+        let image = prepare_for_encode(image, options, Format::Qoi);
+        let image = image.as_pixel_format(PixelFormat::Rgba8);
+        let colors = match options.codec.qoi.colorspace {
+            QoiColorspace::Srgb => Colors::SrgbLinA,
+            QoiColorspace::Linear => Colors::Rgba,
+        };
         let qoi = Qoi {
             width: image.width,
             height: image.height,
-            colors: Colors::SrgbLinA,
+            colors,
         };
 
         let encoded = qoi
@@ -71,7 +121,10 @@ mod tests {
     fn encode_and_decode_roundtrip() {
         let codec = QoiCodec;
         let original = create_test_image(64, 48);
-        let options = EncodeOptions { quality: 90 };
+        let options = EncodeOptions {
+            quality: 90,
+            ..Default::default()
+        };
 
         let encoded = codec.encode(&original, &options).expect("encode failed");
 
@@ -89,4 +142,33 @@ mod tests {
         assert_eq!(decoded.height, original.height);
         assert_eq!(decoded.data, original.data, "QOI should be lossless");
     }
+
+    #[test]
+    fn linear_colorspace_sets_header_flag() {
+        let codec = QoiCodec;
+        let original = create_test_image(8, 8);
+        let mut options = EncodeOptions::default();
+        options.codec.qoi.colorspace = QoiColorspace::Linear;
+
+        let encoded = codec.encode(&original, &options).expect("encode failed");
+
+        // Header: magic(4) + width(4) + height(4) + channels(1) + colorspace(1)
+        assert_eq!(encoded[13], 1, "colorspace byte should be 1 (linear)");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+        assert_eq!(decoded.data, original.data);
+    }
+
+    #[test]
+    fn oversized_header_is_rejected_before_allocating() {
+        // 60000x60000 RGBA, with no pixels behind the header.
+        let mut bomb = b"qoif".to_vec();
+        bomb.extend_from_slice(&60_000u32.to_be_bytes());
+        bomb.extend_from_slice(&60_000u32.to_be_bytes());
+        bomb.extend_from_slice(&[4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+
+        assert!(matches!(
+            QoiCodec.decode(&bomb),
+            Err(Error::LimitExceeded { .. })
+        ));
+    }
 }
@@ -0,0 +1,97 @@
+use crate::color::prepare_for_encode;
+use crate::error::{Error, Result};
+use crate::format::Format;
+
+use super::{
+    Codec, EncodeOptions, ImageData, ImageInfo, decode_with_image, encode_with_image,
+    probe_with_image,
+};
+
+/// TGA codec backed by the `image` crate. Lossless format — quality is
+/// ignored.
+///
+/// Pixels are run-length encoded as 8-bit grey, RGB or RGBA, whichever is
+/// smallest.
+pub struct TgaCodec;
+
+impl Codec for TgaCodec {
+    fn format(&self) -> Format {
+        Format::Tga
+    }
+
+    fn quality_affects_size(&self, _options: &EncodeOptions) -> bool {
+        false
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<ImageData> {
+        decode_with_image(data, image::ImageFormat::Tga)
+            .map_err(|e| Error::Decode(format!("tga decode: {e}")))
+    }
+
+    fn probe(&self, data: &[u8]) -> Result<ImageInfo> {
+        probe_with_image(data, image::ImageFormat::Tga, Format::Tga)
+            .map_err(|e| Error::Decode(format!("tga header: {e}")))
+    }
+
+    fn encode(&self, image: &ImageData, options: &EncodeOptions) -> Result<Vec<u8>> {
+        let image = prepare_for_encode(image, options, Format::Tga);
+        encode_with_image(&image, image::ImageFormat::Tga)
+            .map_err(|e| Error::Encode(format!("tga encode: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: u32, height: u32, alpha: u8) -> ImageData {
+        let data = (0..width * height)
+            .flat_map(|i| [(i * 7) as u8, (i * 13) as u8, (i * 29) as u8, alpha])
+            .collect();
+        ImageData::new(width, height, data)
+    }
+
+    #[test]
+    fn encode_and_decode_roundtrip() {
+        let original = gradient(13, 7, 255);
+
+        let encoded = TgaCodec
+            .encode(&original, &EncodeOptions::default())
+            .expect("encode failed");
+        assert_eq!(Format::from_magic_bytes(&encoded), Some(Format::Tga));
+
+        let decoded = TgaCodec.decode(&encoded).expect("decode failed");
+        assert_eq!((decoded.width, decoded.height), (13, 7));
+        assert_eq!(decoded.data, original.data, "TGA should be lossless");
+    }
+
+    #[test]
+    fn alpha_is_kept() {
+        let original = gradient(4, 4, 100);
+
+        let encoded = TgaCodec
+            .encode(&original, &EncodeOptions::default())
+            .expect("encode failed");
+        let decoded = TgaCodec.decode(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.data, original.data);
+    }
+
+    #[test]
+    fn flat_image_is_run_length_encoded() {
+        let original = ImageData::new(64, 64, [40, 80, 120, 255].repeat(64 * 64));
+
+        let encoded = TgaCodec
+            .encode(&original, &EncodeOptions::default())
+            .expect("encode failed");
+
+        assert!(encoded.len() < 1024, "{} bytes", encoded.len());
+        let decoded = TgaCodec.decode(&encoded).expect("decode failed");
+        assert_eq!(decoded.data, original.data);
+    }
+
+    #[test]
+    fn decode_invalid_data() {
+        assert!(matches!(TgaCodec.decode(&[0xFF; 8]), Err(Error::Decode(_))));
+    }
+}
@@ -0,0 +1,356 @@
+use std::io::{Cursor, Seek, Write};
+
+use image::DynamicImage;
+use tiff::TiffResult;
+use tiff::decoder::Decoder;
+use tiff::encoder::{Compression, DeflateLevel, Predictor, TiffEncoder, colortype};
+use tiff::tags::Tag;
+
+use crate::animation::{Animation, Frame};
+use crate::color::prepare_for_encode;
+use crate::error::{Error, Result};
+use crate::format::Format;
+use crate::limits::DecodeLimits;
+
+use super::{
+    Codec, EncodeOptions, ImageData, ImageInfo, compact, decode_with_image, probe_with_image,
+};
+
+/// TIFF codec. Lossless format — quality is ignored.
+///
+/// Pages are read as the frames of an [`Animation`] and written back as
+/// deflate-compressed pages. 16-bit and float samples are kept.
+pub struct TiffCodec;
+
+impl Codec for TiffCodec {
+    fn format(&self) -> Format {
+        Format::Tiff
+    }
+
+    fn quality_affects_size(&self, _options: &EncodeOptions) -> bool {
+        false
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<ImageData> {
+        decode_with_image(data, image::ImageFormat::Tiff)
+            .map_err(|e| Error::Decode(format!("tiff decode: {e}")))
+    }
+
+    fn probe(&self, data: &[u8]) -> Result<ImageInfo> {
+        let info = probe_with_image(data, image::ImageFormat::Tiff, Format::Tiff)
+            .map_err(|e| Error::Decode(format!("tiff header: {e}")))?;
+        // Thumbnails and other reduced-resolution pages are not frames.
+        let frame_count = page_offsets(data).map_or(1, |pages| pages.len().max(1) as u32);
+        Ok(ImageInfo {
+            frame_count,
+            ..info
+        })
+    }
+
+    fn encode(&self, image: &ImageData, options: &EncodeOptions) -> Result<Vec<u8>> {
+        self.encode_animation(&Animation::from_still(image.clone()), options)
+    }
+
+    fn decode_animation(&self, data: &[u8]) -> Result<Animation> {
+        self.decode_animation_limited(data, &DecodeLimits::default())
+    }
+
+    /// Every page is checked before it is decoded, as later pages need not
+    /// match the size of the first.
+    fn decode_animation_limited(&self, data: &[u8], limits: &DecodeLimits) -> Result<Animation> {
+        let pages = page_offsets(data).map_err(|e| Error::Decode(format!("tiff decode: {e}")))?;
+        if pages.len() < 2 {
+            return self.decode_limited(data, limits).map(Animation::from_still);
+        }
+
+        let page_count = pages.len() as u32;
+        let mut frames: Vec<Frame> = Vec::with_capacity(pages.len());
+        let mut patched = data.to_vec();
+        for (i, offset) in pages.into_iter().enumerate() {
+            set_first_page(&mut patched, offset);
+            let info = probe_with_image(&patched, image::ImageFormat::Tiff, Format::Tiff)
+                .map_err(|e| Error::Decode(format!("tiff header: {e}")))?;
+            if let Some(first) = frames.first().map(|f| &f.image)
+                && (info.width, info.height) != (first.width, first.height)
+            {
+                return Err(Error::Decode(format!(
+                    "tiff decode: page {i} is {}x{}, expected {}x{}",
+                    info.width, info.height, first.width, first.height
+                )));
+            }
+            limits.check(&ImageInfo {
+                frame_count: page_count,
+                ..info
+            })?;
+            frames.push(Frame::new(self.decode(&patched)?, 0));
+        }
+
+        // Pages may differ in bit depth; bring them all to the widest.
+        let widest = frames
+            .iter()
+            .map(|f| f.image.pixel_format)
+            .max_by_key(|format| format.bytes_per_channel())
+            .unwrap_or_default();
+        for frame in &mut frames {
+            frame.image = frame.image.to_pixel_format(widest);
+        }
+        Ok(Animation {
+            frames,
+            loop_count: 0,
+        })
+    }
+
+    fn encode_animation(&self, animation: &Animation, options: &EncodeOptions) -> Result<Vec<u8>> {
+        animation.validate()?;
+        let pages = animation
+            .frames
+            .iter()
+            .map(|frame| {
+                let image = prepare_for_encode(&frame.image, options, Format::Tiff);
+                image.to_dynamic().ok_or_else(|| {
+                    Error::Encode(format!(
+                        "tiff encode: {} bytes do not make a {}x{} {:?} image",
+                        image.data.len(),
+                        image.width,
+                        image.height,
+                        image.pixel_format
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        encode_tiff(pages).map_err(|e| Error::Encode(format!("tiff encode: {e}")))
+    }
+}
+
+/// Directory offsets of the full-resolution pages, skipping thumbnails and
+/// other reduced-resolution images.
+fn page_offsets(data: &[u8]) -> TiffResult<Vec<u64>> {
+    let mut decoder = Decoder::new(Cursor::new(data))?;
+    let mut pages = Vec::new();
+    loop {
+        let subfile_type = decoder
+            .find_tag_unsigned::<u32>(Tag::NewSubfileType)?
+            .unwrap_or(0);
+        if subfile_type & 1 == 0
+            && let Some(pointer) = decoder.ifd_pointer()
+        {
+            pages.push(pointer.0);
+        }
+        if !decoder.more_images() {
+            return Ok(pages);
+        }
+        decoder.next_image()?;
+    }
+}
+
+/// Point the header of `data` at the directory at `offset`, so a decoder
+/// that only reads the first page reads that one.
+fn set_first_page(data: &mut [u8], offset: u64) {
+    let little_endian = data.starts_with(b"II");
+    let big_tiff = data.get(2..4) == Some(if little_endian { &[43, 0] } else { &[0, 43] });
+    if big_tiff {
+        let bytes = if little_endian {
+            offset.to_le_bytes()
+        } else {
+            offset.to_be_bytes()
+        };
+        data[8..16].copy_from_slice(&bytes);
+    } else {
+        // Classic TIFF offsets are 32-bit, so the decoder handed us one that fits.
+        let offset = offset as u32;
+        let bytes = if little_endian {
+            offset.to_le_bytes()
+        } else {
+            offset.to_be_bytes()
+        };
+        data[4..8].copy_from_slice(&bytes);
+    }
+}
+
+/// Write every page with deflate compression, using the smallest sample
+/// layout that holds each one exactly.
+fn encode_tiff(pages: Vec<DynamicImage>) -> TiffResult<Vec<u8>> {
+    // The horizontal predictor only works on integer samples.
+    let predictor = match pages.first() {
+        Some(DynamicImage::ImageRgba32F(_)) => Predictor::None,
+        _ => Predictor::Horizontal,
+    };
+
+    let mut out = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut out)?
+        .with_compression(Compression::Deflate(DeflateLevel::Best))
+        .with_predictor(predictor);
+    for page in pages {
+        write_page(&mut encoder, page)?;
+    }
+    Ok(out.into_inner())
+}
+
+fn write_page<W: Write + Seek>(encoder: &mut TiffEncoder<W>, page: DynamicImage) -> TiffResult<()> {
+    let (width, height) = (page.width(), page.height());
+    if let DynamicImage::ImageRgba32F(buf) = &page {
+        return encoder.write_image::<colortype::RGBA32Float>(width, height, buf.as_raw());
+    }
+    match compact(page) {
+        DynamicImage::ImageLuma8(buf) => {
+            encoder.write_image::<colortype::Gray8>(width, height, buf.as_raw())
+        }
+        DynamicImage::ImageLuma16(buf) => {
+            encoder.write_image::<colortype::Gray16>(width, height, buf.as_raw())
+        }
+        DynamicImage::ImageRgb8(buf) => {
+            encoder.write_image::<colortype::RGB8>(width, height, buf.as_raw())
+        }
+        DynamicImage::ImageRgb16(buf) => {
+            encoder.write_image::<colortype::RGB16>(width, height, buf.as_raw())
+        }
+        // Grey with alpha is written as RGBA, which every reader understands.
+        page @ DynamicImage::ImageLumaA8(_) | page @ DynamicImage::ImageRgba8(_) => {
+            encoder.write_image::<colortype::RGBA8>(width, height, page.to_rgba8().as_raw())
+        }
+        page => encoder.write_image::<colortype::RGBA16>(width, height, page.to_rgba16().as_raw()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::PixelFormat;
+
+    fn gradient(width: u32, height: u32, seed: u32) -> ImageData {
+        let data = (0..width * height)
+            .flat_map(|i| {
+                let v = i + seed;
+                [
+                    (v * 7) as u8,
+                    (v * 13) as u8,
+                    (v * 29) as u8,
+                    255 - (v % 64) as u8,
+                ]
+            })
+            .collect();
+        ImageData::new(width, height, data)
+    }
+
+    #[test]
+    fn encode_and_decode_roundtrip() {
+        let original = gradient(24, 9, 0);
+
+        let encoded = TiffCodec
+            .encode(&original, &EncodeOptions::default())
+            .expect("encode failed");
+        assert_eq!(Format::from_magic_bytes(&encoded), Some(Format::Tiff));
+
+        let decoded = TiffCodec.decode(&encoded).expect("decode failed");
+        assert_eq!((decoded.width, decoded.height), (24, 9));
+        assert_eq!(decoded.data, original.data, "TIFF should be lossless");
+    }
+
+    #[test]
+    fn flat_image_is_compressed() {
+        let original = ImageData::new(128, 128, [90, 60, 30, 255].repeat(128 * 128));
+
+        let encoded = TiffCodec
+            .encode(&original, &EncodeOptions::default())
+            .expect("encode failed");
+
+        assert!(encoded.len() < 2048, "{} bytes", encoded.len());
+    }
+
+    #[test]
+    fn sixteen_bit_and_float_samples_survive() {
+        let wide = ImageData::from_rgba16(2, 1, vec![1, 2, 3, 65535, 40000, 50000, 60000, 1234]);
+        let float = ImageData::from_rgba32f(1, 1, vec![0.25, 0.5, 0.125, 1.0]);
+
+        for original in [wide, float] {
+            let encoded = TiffCodec
+                .encode(&original, &EncodeOptions::default())
+                .expect("encode failed");
+            let decoded = TiffCodec.decode(&encoded).expect("decode failed");
+
+            assert_eq!(decoded.pixel_format, original.pixel_format);
+            assert_eq!(decoded.data, original.data);
+        }
+    }
+
+    #[test]
+    fn pages_roundtrip_as_frames() {
+        let animation = Animation {
+            frames: (0..3)
+                .map(|i| Frame::new(gradient(8, 6, i * 40), 0))
+                .collect(),
+            loop_count: 0,
+        };
+
+        let encoded = TiffCodec
+            .encode_animation(&animation, &EncodeOptions::default())
+            .expect("encode failed");
+        let decoded = TiffCodec.decode_animation(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.frames.len(), 3);
+        for (page, original) in decoded.frames.iter().zip(&animation.frames) {
+            assert_eq!(page.image.data, original.image.data);
+        }
+        // A still decode reads the first page.
+        let first = TiffCodec.decode(&encoded).expect("decode failed");
+        assert_eq!(first.data, animation.frames[0].image.data);
+    }
+
+    #[test]
+    fn pages_of_different_depth_are_widened() {
+        let pages = vec![
+            DynamicImage::ImageRgb8(image::RgbImage::from_pixel(2, 2, image::Rgb([10, 20, 30]))),
+            DynamicImage::ImageRgb16(image::ImageBuffer::from_pixel(
+                2,
+                2,
+                image::Rgb([1000u16, 2000, 3000]),
+            )),
+        ];
+        let encoded = encode_tiff(pages).expect("encode failed");
+
+        let decoded = TiffCodec.decode_animation(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.frames.len(), 2);
+        for frame in &decoded.frames {
+            assert_eq!(frame.image.pixel_format, PixelFormat::Rgba16);
+        }
+        assert_eq!(
+            decoded.frames[0].image.rgba16_samples()[..4],
+            [2570, 5140, 7710, 65535]
+        );
+    }
+
+    #[test]
+    fn reduced_resolution_pages_are_skipped() {
+        let mut out = Cursor::new(Vec::new());
+        let mut encoder = TiffEncoder::new(&mut out).unwrap();
+        encoder
+            .write_image::<colortype::RGB8>(2, 2, &[200; 12])
+            .unwrap();
+        let mut thumbnail = encoder.new_image::<colortype::RGB8>(1, 1).unwrap();
+        thumbnail
+            .encoder()
+            .write_tag(Tag::NewSubfileType, 1u32)
+            .unwrap();
+        thumbnail.write_data(&[100; 3]).unwrap();
+
+        let decoded = TiffCodec
+            .decode_animation(out.get_ref())
+            .expect("decode failed");
+
+        assert!(!decoded.is_animated());
+        assert_eq!(decoded.width(), 2);
+    }
+
+    #[test]
+    fn decode_invalid_data() {
+        assert!(matches!(
+            TiffCodec.decode(b"II*\0garbage"),
+            Err(Error::Decode(_))
+        ));
+        assert!(matches!(
+            TiffCodec.decode_animation(b"II*\0garbage"),
+            Err(Error::Decode(_))
+        ));
+    }
+}
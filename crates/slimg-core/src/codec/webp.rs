@@ -1,7 +1,46 @@
+use std::ffi::CStr;
+use std::io::Cursor;
+
+use image::ImageDecoder;
+use image::codecs::webp::WebPDecoder;
+use libwebp_sys::{
+    WEBP_MUX_ABI_VERSION, WebPAnimEncoder, WebPAnimEncoderAdd, WebPAnimEncoderAssemble,
+    WebPAnimEncoderDelete, WebPAnimEncoderGetError, WebPAnimEncoderNewInternal,
+    WebPAnimEncoderOptions, WebPAnimEncoderOptionsInitInternal, WebPData, WebPDataClear,
+    WebPPicture, WebPPictureFree, WebPPictureImportRGBA,
+};
+
+use crate::animation::{Animation, Disposal, Frame};
+use crate::color::prepare_for_encode;
 use crate::error::{Error, Result};
 use crate::format::Format;
+use crate::limits::DecodeLimits;
+use crate::metadata::Metadata;
+
+use super::{
+    Codec, EncodeOptions, ImageData, ImageInfo, PixelFormat, decode_with_image, probe_with_image,
+};
+
+/// WebP encoder settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WebPOptions {
+    /// Compression method (0..=6). Higher is slower but smaller.
+    pub method: u8,
+    /// Quality of the alpha plane (0..=100).
+    pub alpha_quality: u8,
+    /// Use the slower, sharper RGB to YUV conversion.
+    pub sharp_yuv: bool,
+}
 
-use super::{Codec, EncodeOptions, ImageData};
+impl Default for WebPOptions {
+    fn default() -> Self {
+        Self {
+            method: 4,
+            alpha_quality: 100,
+            sharp_yuv: false,
+        }
+    }
+}
 
 /// WebP codec backed by libwebp.
 pub struct WebPCodec;
@@ -12,27 +51,339 @@ impl Codec for WebPCodec {
     }
 
     fn decode(&self, data: &[u8]) -> Result<ImageData> {
-        let img = image::load_from_memory_with_format(data, image::ImageFormat::WebP)
-            .map_err(|e| Error::Decode(format!("webp decode: {e}")))?;
-
-        let rgba = img.to_rgba8();
-        let width = rgba.width();
-        let height = rgba.height();
+        decode_with_image(data, image::ImageFormat::WebP)
+            .map_err(|e| Error::Decode(format!("webp decode: {e}")))
+    }
 
-        Ok(ImageData::new(width, height, rgba.into_raw()))
+    fn probe(&self, data: &[u8]) -> Result<ImageInfo> {
+        let info = probe_with_image(data, image::ImageFormat::WebP, Format::WebP)
+            .map_err(|e| Error::Decode(format!("webp header: {e}")))?;
+        let frame_count = anim_control(data).map_or(1, |control| control.disposals.len() as u32);
+        Ok(ImageInfo {
+            frame_count,
+            ..info
+        })
     }
 
     fn encode(&self, image: &ImageData, options: &EncodeOptions) -> Result<Vec<u8>> {
+        let image = prepare_for_encode(image, options, Format::WebP);
+        // libwebp only takes 8-bit samples.
+        let image = &*image.as_pixel_format(PixelFormat::Rgba8);
+        let config = webp_config(options)?;
+
         let encoder = webp::Encoder::from_rgba(&image.data, image.width, image.height);
-        let encoded = encoder.encode(options.quality as f32);
+        let encoded = encoder
+            .encode_advanced(&config)
+            .map_err(|e| Error::Encode(format!("webp encode: {e:?}")))?;
+
+        let metadata = image.metadata.filter(options.metadata);
+        if metadata.is_empty() {
+            return Ok(encoded.to_vec());
+        }
+        embed_metadata(&encoded, image, &metadata)
+    }
+
+    fn decode_animation(&self, data: &[u8]) -> Result<Animation> {
+        self.decode_animation_limited(data, &DecodeLimits::default())
+    }
+
+    fn decode_animation_limited(&self, data: &[u8], limits: &DecodeLimits) -> Result<Animation> {
+        let Some(control) = anim_control(data) else {
+            return self.decode_limited(data, limits).map(Animation::from_still);
+        };
+        decode_animated(data, &control, limits)
+    }
+
+    fn encode_animation(&self, animation: &Animation, options: &EncodeOptions) -> Result<Vec<u8>> {
+        animation.validate()?;
+        if !animation.is_animated() {
+            return self.encode(&animation.frames[0].image, options);
+        }
+        let images: Vec<ImageData> = animation
+            .frames
+            .iter()
+            .map(|frame| {
+                let image = prepare_for_encode(&frame.image, options, Format::WebP);
+                image.as_pixel_format(PixelFormat::Rgba8).into_owned()
+            })
+            .collect();
+        let config = webp_config(options)?;
+        let encoded = encode_animated(&images, animation, &config)?;
+
+        let first = &images[0];
+        let metadata = first.metadata.filter(options.metadata);
+        if metadata.is_empty() {
+            return Ok(encoded);
+        }
+        embed_metadata(&encoded, first, &metadata)
+    }
+}
+
+/// libwebp settings for `options`.
+fn webp_config(options: &EncodeOptions) -> Result<webp::WebPConfig> {
+    let settings = options.codec.webp;
+    let mut config = webp::WebPConfig::new()
+        .map_err(|_| Error::Encode("webp config init failed".to_string()))?;
+    // In lossless mode libwebp reads `quality` as compression effort.
+    config.lossless = options.lossless as i32;
+    config.quality = options.quality as f32;
+    config.method = settings.method.min(6) as i32;
+    config.alpha_quality = settings.alpha_quality.min(100) as i32;
+    config.alpha_compression = 1;
+    config.use_sharp_yuv = settings.sharp_yuv as i32;
+    // Keep RGB under fully transparent pixels so lossless output is bit-exact.
+    config.exact = options.lossless as i32;
+    Ok(config)
+}
+
+/// Owns a `WebPAnimEncoder` and deletes it on drop.
+struct AnimEncoder(*mut WebPAnimEncoder);
+
+impl AnimEncoder {
+    fn error(&self) -> String {
+        // SAFETY: the encoder is live and returns a NUL-terminated string.
+        unsafe { CStr::from_ptr(WebPAnimEncoderGetError(self.0)) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+impl Drop for AnimEncoder {
+    fn drop(&mut self) {
+        // SAFETY: the pointer came from WebPAnimEncoderNewInternal and is
+        // deleted only here.
+        unsafe { WebPAnimEncoderDelete(self.0) }
+    }
+}
+
+/// Encode 8-bit frames with libwebp's animation encoder, which picks its own
+/// sub-rectangles and disposal for each full-canvas frame.
+///
+/// The `webp` crate's wrapper ends the animation at timestamp 0, which drops
+/// the last frame's duration, so the encoder is driven directly.
+fn encode_animated(
+    images: &[ImageData],
+    animation: &Animation,
+    config: &webp::WebPConfig,
+) -> Result<Vec<u8>> {
+    let (width, height) = (images[0].width as i32, images[0].height as i32);
+    let abi = WEBP_MUX_ABI_VERSION as i32;
+
+    let mut anim_options = std::mem::MaybeUninit::<WebPAnimEncoderOptions>::uninit();
+    // SAFETY: the init function fills in every field before it is read.
+    let mut anim_options = unsafe {
+        if WebPAnimEncoderOptionsInitInternal(anim_options.as_mut_ptr(), abi) == 0 {
+            return Err(Error::Encode("webp anim options init failed".to_string()));
+        }
+        anim_options.assume_init()
+    };
+    anim_options.anim_params.loop_count = animation.loop_count.min(u16::MAX as u32) as i32;
+
+    // SAFETY: the options are initialised and outlive the call.
+    let encoder =
+        AnimEncoder(unsafe { WebPAnimEncoderNewInternal(width, height, &anim_options, abi) });
+    if encoder.0.is_null() {
+        return Err(Error::Encode("webp anim encoder init failed".to_string()));
+    }
+
+    let mut timestamp = 0i32;
+    for (image, frame) in images.iter().zip(&animation.frames) {
+        let mut picture = WebPPicture::new()
+            .map_err(|_| Error::Encode("webp picture init failed".to_string()))?;
+        picture.use_argb = 1;
+        picture.width = width;
+        picture.height = height;
+        // SAFETY: `image.data` holds `height` rows of `width * 4` bytes; the
+        // picture copies them and is freed before the next iteration.
+        let added = unsafe {
+            let imported = WebPPictureImportRGBA(&mut picture, image.data.as_ptr(), width * 4) != 0;
+            let added =
+                imported && WebPAnimEncoderAdd(encoder.0, &mut picture, timestamp, config) != 0;
+            WebPPictureFree(&mut picture);
+            added
+        };
+        if !added {
+            return Err(Error::Encode(format!(
+                "webp anim encode: {}",
+                encoder.error()
+            )));
+        }
+        timestamp = timestamp.saturating_add(frame.duration_ms.min(i32::MAX as u32) as i32);
+    }
+
+    let mut data = WebPData::default();
+    // SAFETY: a null frame marks the end time of the last frame; `data` is
+    // copied out and then released with libwebp's allocator.
+    unsafe {
+        if WebPAnimEncoderAdd(encoder.0, std::ptr::null_mut(), timestamp, std::ptr::null()) == 0
+            || WebPAnimEncoderAssemble(encoder.0, &mut data) == 0
+        {
+            return Err(Error::Encode(format!(
+                "webp anim assemble: {}",
+                encoder.error()
+            )));
+        }
+        let encoded = std::slice::from_raw_parts(data.bytes, data.size).to_vec();
+        WebPDataClear(&mut data);
+        Ok(encoded)
+    }
+}
+
+/// Loop count and frame disposal read from the `ANIM` and `ANMF` chunks.
+struct AnimControl {
+    loop_count: u32,
+    disposals: Vec<Disposal>,
+}
+
+/// Read the animation chunks, or `None` when the file has fewer than two
+/// frames.
+fn anim_control(data: &[u8]) -> Option<AnimControl> {
+    let mut control = AnimControl {
+        loop_count: 0,
+        disposals: Vec::new(),
+    };
+    for (fourcc, payload) in riff_chunks(data)? {
+        match fourcc {
+            b"ANIM" if payload.len() >= 6 => {
+                control.loop_count = u16::from_le_bytes([payload[4], payload[5]]).into();
+            }
+            b"ANMF" if payload.len() >= 16 => control.disposals.push(if payload[15] & 1 == 1 {
+                Disposal::Background
+            } else {
+                Disposal::None
+            }),
+            _ => {}
+        }
+    }
+    (control.disposals.len() > 1).then_some(control)
+}
+
+/// Decode every frame of an animated WebP with libwebp, which composites
+/// each one onto the full canvas.
+fn decode_animated(data: &[u8], control: &AnimControl, limits: &DecodeLimits) -> Result<Animation> {
+    // The `image` decoder only reads the metadata; its compositing is not
+    // bit-exact.
+    let mut decoder = WebPDecoder::new(Cursor::new(data))
+        .map_err(|e| Error::Decode(format!("webp decode: {e}")))?;
+    let metadata = Metadata::from_decoder(&mut decoder);
+    limits.check(&ImageInfo {
+        frame_count: control.disposals.len() as u32,
+        ..ImageInfo::still(Format::WebP, decoder.dimensions(), 8, true, &metadata)
+    })?;
+    let decoded = webp::AnimDecoder::new(data)
+        .decode()
+        .map_err(|e| Error::Decode(format!("webp anim decode: {e}")))?;
+
+    // libwebp reports the time each frame ends.
+    let mut start = 0;
+    let frames = (0..decoded.len())
+        .filter_map(|i| decoded.get_frame(i))
+        .enumerate()
+        .map(|(i, frame)| {
+            let end = frame.get_time_ms();
+            let image = ImageData::new(frame.width(), frame.height(), frame.get_image().to_vec())
+                .with_metadata(metadata.clone());
+            let duration_ms = end.saturating_sub(start).max(0) as u32;
+            start = end;
+            Frame {
+                image,
+                duration_ms,
+                disposal: control.disposals.get(i).copied().unwrap_or_default(),
+            }
+        })
+        .collect();
+    Ok(Animation {
+        frames,
+        loop_count: control.loop_count,
+    })
+}
+
+// VP8X feature flags.
+const ICC_FLAG: u8 = 0x20;
+const ALPHA_FLAG: u8 = 0x10;
+const EXIF_FLAG: u8 = 0x08;
+const XMP_FLAG: u8 = 0x04;
+
+/// Rewrite a WebP file as an extended (VP8X) container holding `metadata`.
+///
+/// libwebp's simple encoder only writes the image chunks, so ICCP goes right
+/// after the VP8X header and EXIF/XMP at the end, as the container spec orders them.
+fn embed_metadata(webp: &[u8], image: &ImageData, metadata: &Metadata) -> Result<Vec<u8>> {
+    let chunks = riff_chunks(webp)
+        .ok_or_else(|| Error::Encode("webp mux: malformed encoder output".to_string()))?;
+
+    let mut flags = 0u8;
+    let mut body: Vec<(&[u8; 4], &[u8])> = Vec::new();
+    for (fourcc, payload) in chunks {
+        match fourcc {
+            b"VP8X" => flags = payload.first().copied().unwrap_or(0),
+            b"ICCP" | b"EXIF" | b"XMP " => {}
+            _ => {
+                // A lossless bitstream stores alpha inline.
+                if fourcc == b"VP8L" && image.data.chunks_exact(4).any(|px| px[3] < 255) {
+                    flags |= ALPHA_FLAG;
+                }
+                body.push((fourcc, payload));
+            }
+        }
+    }
+
+    let mut tail = Vec::new();
+    if let Some(icc) = &metadata.icc {
+        flags |= ICC_FLAG;
+        body.insert(0, (b"ICCP", icc.as_slice()));
+    }
+    if let Some(exif) = &metadata.exif {
+        flags |= EXIF_FLAG;
+        tail.push((b"EXIF", exif.as_slice()));
+    }
+    if let Some(xmp) = &metadata.xmp {
+        flags |= XMP_FLAG;
+        tail.push((b"XMP ", xmp.as_slice()));
+    }
+
+    let mut vp8x = [0u8; 10];
+    vp8x[0] = flags;
+    vp8x[4..7].copy_from_slice(&(image.width - 1).to_le_bytes()[..3]);
+    vp8x[7..10].copy_from_slice(&(image.height - 1).to_le_bytes()[..3]);
+
+    let mut out = Vec::with_capacity(webp.len() + 64);
+    out.extend_from_slice(b"RIFF\0\0\0\0WEBP");
+    for (fourcc, payload) in std::iter::once((b"VP8X", &vp8x[..])).chain(body).chain(tail) {
+        out.extend_from_slice(fourcc);
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(payload);
+        if payload.len() % 2 == 1 {
+            out.push(0);
+        }
+    }
+    let riff_size = (out.len() - 8) as u32;
+    out[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    Ok(out)
+}
 
-        Ok(encoded.to_vec())
+/// Split a RIFF/WEBP file into `(fourcc, payload)` chunks.
+fn riff_chunks(data: &[u8]) -> Option<Vec<(&[u8; 4], &[u8])>> {
+    if data.get(..4)? != b"RIFF" || data.get(8..12)? != b"WEBP" {
+        return None;
+    }
+    let mut chunks = Vec::new();
+    let mut pos = 12;
+    while pos < data.len() {
+        let fourcc: &[u8; 4] = data.get(pos..pos + 4)?.try_into().ok()?;
+        let size = u32::from_le_bytes(data.get(pos + 4..pos + 8)?.try_into().ok()?) as usize;
+        let payload = data.get(pos + 8..pos + 8 + size)?;
+        chunks.push((fourcc, payload));
+        pos += 8 + size + size % 2;
     }
+    Some(chunks)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::metadata::{MetadataPolicy, sample_metadata};
 
     fn create_test_image(width: u32, height: u32) -> ImageData {
         let size = (width * height * 4) as usize;
@@ -53,7 +404,10 @@ mod tests {
     fn encode_and_decode_roundtrip() {
         let codec = WebPCodec;
         let original = create_test_image(64, 48);
-        let options = EncodeOptions { quality: 90 };
+        let options = EncodeOptions {
+            quality: 90,
+            ..Default::default()
+        };
 
         let encoded = codec.encode(&original, &options).expect("encode failed");
 
@@ -73,10 +427,22 @@ mod tests {
         let image = create_test_image(128, 96);
 
         let high = codec
-            .encode(&image, &EncodeOptions { quality: 95 })
+            .encode(
+                &image,
+                &EncodeOptions {
+                    quality: 95,
+                    ..Default::default()
+                },
+            )
             .expect("encode q95 failed");
         let low = codec
-            .encode(&image, &EncodeOptions { quality: 20 })
+            .encode(
+                &image,
+                &EncodeOptions {
+                    quality: 20,
+                    ..Default::default()
+                },
+            )
             .expect("encode q20 failed");
 
         assert!(
@@ -86,4 +452,141 @@ mod tests {
             high.len(),
         );
     }
+
+    #[test]
+    fn slower_method_is_not_larger() {
+        let codec = WebPCodec;
+        let image = create_test_image(128, 96);
+        let mut options = EncodeOptions::default();
+
+        options.codec.webp.method = 0;
+        let fast = codec.encode(&image, &options).expect("encode m0 failed");
+        options.codec.webp.method = 6;
+        let slow = codec.encode(&image, &options).expect("encode m6 failed");
+
+        assert!(
+            slow.len() <= fast.len(),
+            "method 6 ({} bytes) should not be larger than method 0 ({} bytes)",
+            slow.len(),
+            fast.len(),
+        );
+    }
+
+    #[test]
+    fn metadata_roundtrip_with_keep_policy() {
+        let codec = WebPCodec;
+        let image = create_test_image(16, 16).with_metadata(sample_metadata());
+        let mut options = EncodeOptions {
+            metadata: MetadataPolicy::Keep,
+            ..Default::default()
+        };
+
+        let lossy = codec.encode(&image, &options).expect("lossy encode failed");
+        options.lossless = true;
+        let lossless = codec.encode(&image, &options).expect("lossless encode failed");
+
+        for encoded in [lossy, lossless] {
+            let decoded = codec.decode(&encoded).expect("decode failed");
+            assert_eq!(decoded.metadata, sample_metadata());
+            assert_eq!(decoded.width, 16);
+        }
+    }
+
+    #[test]
+    fn metadata_keeps_alpha_in_lossless_container() {
+        let codec = WebPCodec;
+        let mut image = create_test_image(8, 8).with_metadata(sample_metadata());
+        image.data[3] = 0;
+        let options = EncodeOptions {
+            lossless: true,
+            metadata: MetadataPolicy::Keep,
+            ..Default::default()
+        };
+
+        let encoded = codec.encode(&image, &options).expect("encode failed");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.data, image.data);
+    }
+
+    #[test]
+    fn lossless_roundtrip_is_bit_exact() {
+        let codec = WebPCodec;
+        let mut original = create_test_image(32, 24);
+        // Fully transparent pixels with non-zero colour must survive too.
+        for px in original.data.chunks_exact_mut(4).step_by(3) {
+            px[3] = 0;
+        }
+        let options = EncodeOptions {
+            lossless: true,
+            ..Default::default()
+        };
+
+        let encoded = codec.encode(&original, &options).expect("encode failed");
+        let decoded = codec.decode(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.data, original.data);
+    }
+
+    /// Three 16x12 frames with distinct colours and timings.
+    fn sample_animation() -> Animation {
+        let frames = [
+            ([255, 0, 0, 255], 100),
+            ([0, 255, 0, 128], 250),
+            ([0, 0, 255, 255], 40),
+        ]
+        .into_iter()
+        .map(|(px, duration_ms)| {
+            Frame::new(ImageData::new(16, 12, px.repeat(16 * 12)), duration_ms)
+        })
+        .collect();
+        Animation {
+            frames,
+            loop_count: 2,
+        }
+    }
+
+    #[test]
+    fn lossless_animation_roundtrip() {
+        let codec = WebPCodec;
+        let original = sample_animation();
+        let options = EncodeOptions {
+            lossless: true,
+            ..Default::default()
+        };
+
+        let encoded = codec
+            .encode_animation(&original, &options)
+            .expect("encode failed");
+        let decoded = codec.decode_animation(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.loop_count, 2);
+        assert_eq!(decoded.frames.len(), 3);
+        for (orig, out) in original.frames.iter().zip(&decoded.frames) {
+            assert_eq!(out.image.data, orig.image.data);
+            assert_eq!(out.duration_ms, orig.duration_ms);
+        }
+    }
+
+    #[test]
+    fn animation_keeps_metadata() {
+        let codec = WebPCodec;
+        let mut original = sample_animation();
+        for frame in &mut original.frames {
+            frame.image.metadata = sample_metadata();
+        }
+        let options = EncodeOptions {
+            metadata: MetadataPolicy::Keep,
+            ..Default::default()
+        };
+
+        let encoded = codec
+            .encode_animation(&original, &options)
+            .expect("encode failed");
+        let decoded = codec.decode_animation(&encoded).expect("decode failed");
+
+        assert_eq!(decoded.frames.len(), 3);
+        assert_eq!(decoded.metadata(), Some(&sample_metadata()));
+        assert_eq!(decoded.frames[2].duration_ms, 40);
+    }
 }
@@ -0,0 +1,248 @@
+use std::borrow::Cow;
+
+use moxcms::{ColorProfile, Layout, TransformOptions};
+
+use crate::codec::{EncodeOptions, ImageData, PixelFormat};
+use crate::format::Format;
+use crate::hdr;
+use crate::metadata::MetadataPolicy;
+
+/// What to do with the source ICC profile when encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorPolicy {
+    /// Embed the source profile in the output. Pixels are converted to sRGB
+    /// only when the profile would otherwise be lost: the target format
+    /// cannot store one, or the metadata policy strips it.
+    #[default]
+    Preserve,
+    /// Convert pixels to sRGB and drop the source profile.
+    Srgb,
+}
+
+/// Convert pixels from the image's embedded ICC profile to sRGB.
+///
+/// The returned image has no ICC profile. Images without a profile are
+/// assumed to be sRGB already and are returned unchanged. A profile that
+/// cannot be parsed or applied is dropped and the pixels are left as they are.
+pub fn to_srgb(image: &ImageData) -> ImageData {
+    let mut metadata = image.metadata.clone();
+    let Some(icc) = metadata.icc.take() else {
+        return image.clone();
+    };
+
+    let data = srgb_transform(&icc, image).unwrap_or_else(|| image.data.clone());
+    ImageData::from_raw(image.width, image.height, image.pixel_format, data).with_metadata(metadata)
+}
+
+/// Apply `options.color` for an encoder writing `format`.
+///
+/// HDR images are tone-mapped with `options.tone_map` unless `format` can
+/// signal HDR and the policy is [`ColorPolicy::Preserve`].
+///
+/// With `options.lossless` the pixels are never rewritten: a profile or HDR
+/// signalling that would be converted away is dropped instead, and the
+/// stored values are written as they are.
+pub(crate) fn prepare_for_encode<'a>(
+    image: &'a ImageData,
+    options: &EncodeOptions,
+    format: Format,
+) -> Cow<'a, ImageData> {
+    if image.metadata.hdr.is_some() {
+        if format.supports_hdr() && options.color == ColorPolicy::Preserve {
+            return Cow::Borrowed(image);
+        }
+        if options.lossless {
+            let mut image = image.clone();
+            image.metadata.hdr = None;
+            return Cow::Owned(image);
+        }
+        return Cow::Owned(hdr::tone_map(image, options.tone_map));
+    }
+
+    let profile_lost = !format.supports_icc_profile() || options.metadata == MetadataPolicy::Strip;
+    let convert = match options.color {
+        ColorPolicy::Preserve => profile_lost,
+        ColorPolicy::Srgb => true,
+    };
+
+    if !convert || image.metadata.icc.is_none() {
+        Cow::Borrowed(image)
+    } else if options.lossless {
+        let mut image = image.clone();
+        image.metadata.icc = None;
+        Cow::Owned(image)
+    } else {
+        Cow::Owned(to_srgb(image))
+    }
+}
+
+/// Transform the pixels from `icc` to sRGB at their own bit depth, or
+/// `None` if the profile is unusable.
+fn srgb_transform(icc: &[u8], image: &ImageData) -> Option<Vec<u8>> {
+    let source = ColorProfile::new_from_slice(icc).ok()?;
+    let srgb = ColorProfile::new_srgb();
+    let options = TransformOptions::default();
+
+    match image.pixel_format {
+        PixelFormat::Rgba8 => {
+            let transform = source
+                .create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgba, options)
+                .ok()?;
+            let mut out = vec![0u8; image.data.len()];
+            transform.transform(&image.data, &mut out).ok()?;
+            Some(out)
+        }
+        PixelFormat::Rgba16 => {
+            let transform = source
+                .create_transform_16bit(Layout::Rgba, &srgb, Layout::Rgba, options)
+                .ok()?;
+            let samples = image.rgba16_samples();
+            let mut out = vec![0u16; samples.len()];
+            transform.transform(&samples, &mut out).ok()?;
+            Some(out.iter().flat_map(|s| s.to_ne_bytes()).collect())
+        }
+        PixelFormat::Rgba32F => {
+            let transform = source
+                .create_transform_f32(Layout::Rgba, &srgb, Layout::Rgba, options)
+                .ok()?;
+            let samples = image.rgba32f_samples();
+            let mut out = vec![0f32; samples.len()];
+            transform.transform(&samples, &mut out).ok()?;
+            Some(out.iter().flat_map(|s| s.to_ne_bytes()).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::Metadata;
+
+    fn tagged(icc: Vec<u8>) -> ImageData {
+        // Pure P3 green, half transparent.
+        ImageData::new(1, 1, vec![0, 255, 0, 128]).with_metadata(Metadata {
+            icc: Some(icc),
+            ..Default::default()
+        })
+    }
+
+    fn display_p3() -> Vec<u8> {
+        ColorProfile::new_display_p3().encode().expect("encode profile")
+    }
+
+    #[test]
+    fn display_p3_green_is_clipped_into_srgb() {
+        let converted = to_srgb(&tagged(display_p3()));
+
+        assert_eq!(converted.metadata.icc, None);
+        let px = &converted.data;
+        // P3 green lies outside sRGB: red goes to 0 and green stays saturated.
+        assert!(px[0] < 10, "red = {}", px[0]);
+        assert!(px[1] > 240, "green = {}", px[1]);
+        assert_eq!(px[3], 128, "alpha must be untouched");
+    }
+
+    #[test]
+    fn sixteen_bit_pixels_are_converted_at_full_depth() {
+        let image = ImageData::from_rgba16(1, 1, vec![0, 65535, 0, 30000]).with_metadata(Metadata {
+            icc: Some(display_p3()),
+            ..Default::default()
+        });
+        let converted = to_srgb(&image);
+
+        assert_eq!(converted.pixel_format, PixelFormat::Rgba16);
+        let px = converted.rgba16_samples();
+        assert!(px[0] < 2560, "red = {}", px[0]);
+        assert!(px[1] > 61440, "green = {}", px[1]);
+        assert_eq!(px[3], 30000, "alpha must be untouched");
+    }
+
+    #[test]
+    fn untagged_image_is_unchanged() {
+        let image = ImageData::new(1, 1, vec![10, 20, 30, 255]);
+        assert_eq!(to_srgb(&image).data, image.data);
+    }
+
+    #[test]
+    fn broken_profile_is_dropped() {
+        let converted = to_srgb(&tagged(vec![0x42; 300]));
+        assert_eq!(converted.metadata.icc, None);
+        assert_eq!(converted.data, vec![0, 255, 0, 128]);
+    }
+
+    #[test]
+    fn preserve_keeps_profile_for_formats_that_store_it() {
+        let image = tagged(display_p3());
+        let options = EncodeOptions::default();
+
+        let png = prepare_for_encode(&image, &options, Format::Png);
+        assert!(matches!(png, Cow::Borrowed(_)));
+
+        let qoi = prepare_for_encode(&image, &options, Format::Qoi);
+        assert_eq!(qoi.metadata.icc, None);
+    }
+
+    #[test]
+    fn hdr_is_kept_for_hdr_formats_and_tone_mapped_otherwise() {
+        let image = ImageData::from_rgba16(1, 1, vec![60000, 30000, 30000, 65535]).with_metadata(
+            Metadata {
+                hdr: hdr::HdrMetadata::from_cicp(9, 16),
+                ..Default::default()
+            },
+        );
+        let options = EncodeOptions::default();
+
+        let avif = prepare_for_encode(&image, &options, Format::Avif);
+        assert!(matches!(avif, Cow::Borrowed(_)));
+
+        let png = prepare_for_encode(&image, &options, Format::Png);
+        assert_eq!(png.metadata.hdr, None);
+        assert_ne!(png.data, image.data);
+
+        let srgb = EncodeOptions {
+            color: ColorPolicy::Srgb,
+            ..Default::default()
+        };
+        assert_eq!(prepare_for_encode(&image, &srgb, Format::Jxl).metadata.hdr, None);
+    }
+
+    #[test]
+    fn strip_or_srgb_policy_converts() {
+        let image = tagged(display_p3());
+
+        let strip = EncodeOptions {
+            metadata: MetadataPolicy::Strip,
+            ..Default::default()
+        };
+        assert_eq!(prepare_for_encode(&image, &strip, Format::Png).metadata.icc, None);
+
+        let srgb = EncodeOptions {
+            color: ColorPolicy::Srgb,
+            ..Default::default()
+        };
+        assert_eq!(prepare_for_encode(&image, &srgb, Format::Jpeg).metadata.icc, None);
+    }
+
+    #[test]
+    fn lossless_drops_what_it_cannot_keep_instead_of_converting() {
+        let lossless = EncodeOptions {
+            lossless: true,
+            ..Default::default()
+        };
+
+        let image = tagged(display_p3());
+        let qoi = prepare_for_encode(&image, &lossless, Format::Qoi);
+        assert_eq!(qoi.metadata.icc, None);
+        assert_eq!(qoi.data, image.data);
+
+        let image = ImageData::from_rgba16(1, 1, vec![60000, 30000, 30000, 65535]).with_metadata(
+            Metadata {
+                hdr: hdr::HdrMetadata::from_cicp(9, 16),
+                ..Default::default()
+            },
+        );
+        let png = prepare_for_encode(&image, &lossless, Format::Png);
+        assert_eq!(png.metadata.hdr, None);
+        assert_eq!(png.data, image.data);
+    }
+}
@@ -1,5 +1,7 @@
-use crate::codec::ImageData;
+use crate::codec::{ImageData, PixelFormat};
 use crate::error::{Error, Result};
+use crate::gravity::Gravity;
+use crate::resize::{self, LinearLight, ResizeFilter, ResizeMode, ResizeOptions};
 
 /// How to crop an image.
 #[derive(Debug, Clone, PartialEq)]
@@ -11,8 +13,17 @@ pub enum CropMode {
         width: u32,
         height: u32,
     },
-    /// Crop to an aspect ratio (centered). Width and height define the ratio (e.g. 16:9).
-    AspectRatio { width: u32, height: u32 },
+    /// Crop to an aspect ratio. Width and height define the ratio (e.g. 16:9);
+    /// `gravity` picks which part of the image is kept.
+    AspectRatio {
+        width: u32,
+        height: u32,
+        gravity: Gravity,
+    },
+    /// Crop to an aspect ratio, placing the window over the most detailed
+    /// part of the image: edges, saturated colour, skin tones and texture.
+    /// Use [`crop_region`] to see which region it picks.
+    Smart { width: u32, height: u32 },
 }
 
 /// Calculate the crop region (x, y, width, height) for a given image size and crop mode.
@@ -41,36 +52,240 @@ pub fn calculate_crop_region(
         CropMode::AspectRatio {
             width: rw,
             height: rh,
+            gravity,
         } => {
-            if rw == 0 || rh == 0 {
-                return Err(Error::Crop("aspect ratio must be non-zero".to_string()));
+            if !gravity.is_valid() {
+                return Err(Error::Crop(
+                    "focal point must be within 0.0..=1.0".to_string(),
+                ));
             }
-            let target_ratio = rw as f64 / rh as f64;
-            let img_ratio = img_w as f64 / img_h as f64;
+            let (crop_w, crop_h) = aspect_size(img_w, img_h, rw, rh)?;
+            let (x, y) = gravity.crop_offset(img_w, img_h, crop_w, crop_h);
+
+            Ok((x, y, crop_w, crop_h))
+        }
+        CropMode::Smart { .. } => Err(Error::Crop(
+            "smart crop needs the image; use crop_region".to_string(),
+        )),
+    }
+}
+
+/// The crop region (x, y, width, height) that [`crop`] takes from `image`.
+///
+/// Unlike [`calculate_crop_region`], this looks at the pixels, so it also
+/// handles [`CropMode::Smart`].
+pub fn crop_region(image: &ImageData, mode: &CropMode) -> Result<(u32, u32, u32, u32)> {
+    match *mode {
+        CropMode::Smart { width, height } => {
+            let (crop_w, crop_h) = aspect_size(image.width, image.height, width, height)?;
+            let (x, y) = smart_offset(image, crop_w, crop_h)?;
+            Ok((x, y, crop_w, crop_h))
+        }
+        _ => calculate_crop_region(image.width, image.height, mode),
+    }
+}
 
-            let (crop_w, crop_h) = if img_ratio > target_ratio {
-                let h = img_h;
-                let w = (h as f64 * target_ratio).round() as u32;
-                (w, h)
+/// The largest `rw`:`rh` window that fits in the image.
+fn aspect_size(img_w: u32, img_h: u32, rw: u32, rh: u32) -> Result<(u32, u32)> {
+    if rw == 0 || rh == 0 {
+        return Err(Error::Crop("aspect ratio must be non-zero".to_string()));
+    }
+    let target_ratio = rw as f64 / rh as f64;
+    let img_ratio = img_w as f64 / img_h as f64;
+
+    if img_ratio > target_ratio {
+        let w = (img_h as f64 * target_ratio).round() as u32;
+        Ok((w.max(1), img_h))
+    } else {
+        let h = (img_w as f64 / target_ratio).round() as u32;
+        Ok((img_w, h.max(1)))
+    }
+}
+
+/// Longest side of the copy that smart crop scores.
+const SMART_SIDE: u32 = 256;
+/// Side of the blocks whose luma entropy is measured.
+const ENTROPY_BLOCK: usize = 8;
+
+const EDGE_WEIGHT: f32 = 1.0;
+const SKIN_WEIGHT: f32 = 1.8;
+const SATURATION_WEIGHT: f32 = 0.3;
+const ENTROPY_WEIGHT: f32 = 0.5;
+
+/// A typical skin colour, as a unit RGB vector.
+const SKIN: [f32; 3] = [0.7347, 0.5369, 0.4144];
+
+/// Where to put a `crop_w`x`crop_h` window so it covers the most
+/// interesting pixels.
+///
+/// The image is scored on a small copy: every pixel gets points for edge
+/// energy, saturated colour, skin tones and the entropy of its block, and
+/// the window with the highest total wins. Points near the middle of the
+/// window count twice, so subjects are not cut at its edges. Ties go to the
+/// window closest to the centre, so a featureless image crops like
+/// [`CropMode::AspectRatio`].
+fn smart_offset(image: &ImageData, crop_w: u32, crop_h: u32) -> Result<(u32, u32)> {
+    if (crop_w, crop_h) == (image.width, image.height) {
+        return Ok((0, 0));
+    }
+
+    let scale = (SMART_SIDE as f64 / image.width.max(image.height) as f64).min(1.0);
+    let small_w = ((image.width as f64 * scale).round() as u32).max(1);
+    let small_h = ((image.height as f64 * scale).round() as u32).max(1);
+    let options = ResizeOptions {
+        filter: ResizeFilter::Bilinear,
+        linear_light: LinearLight::Never,
+        ..Default::default()
+    };
+    let small = if (small_w, small_h) == (image.width, image.height) {
+        image.as_pixel_format(PixelFormat::Rgba8).into_owned()
+    } else {
+        resize::resize_with(image, &ResizeMode::Exact(small_w, small_h), &options)?
+            .to_pixel_format(PixelFormat::Rgba8)
+    };
+
+    let scores = score_map(&small);
+    let (w, h) = (small_w as usize, small_h as usize);
+
+    // Summed-area table, one row and column larger than the image.
+    let mut table = vec![0f64; (w + 1) * (h + 1)];
+    for y in 0..h {
+        let mut row = 0f64;
+        for x in 0..w {
+            row += f64::from(scores[y * w + x]);
+            table[(y + 1) * (w + 1) + x + 1] = table[y * (w + 1) + x + 1] + row;
+        }
+    }
+    let sum = |x0: usize, y0: usize, x1: usize, y1: usize| {
+        table[y1 * (w + 1) + x1] - table[y0 * (w + 1) + x1] - table[y1 * (w + 1) + x0]
+            + table[y0 * (w + 1) + x0]
+    };
+
+    let win_w = ((crop_w as f64 * scale).round() as usize).clamp(1, w);
+    let win_h = ((crop_h as f64 * scale).round() as usize).clamp(1, h);
+    let (inset_x, inset_y) = (win_w / 4, win_h / 4);
+    let (centre_x, centre_y) = ((w - win_w) as f64 / 2.0, (h - win_h) as f64 / 2.0);
+
+    let mut best = (f64::NEG_INFINITY, f64::INFINITY, 0, 0);
+    for y in 0..=h - win_h {
+        for x in 0..=w - win_w {
+            let score = sum(x, y, x + win_w, y + win_h)
+                + sum(
+                    x + inset_x,
+                    y + inset_y,
+                    x + win_w - inset_x,
+                    y + win_h - inset_y,
+                );
+            let distance = (x as f64 - centre_x).hypot(y as f64 - centre_y);
+            // Summing in a different order leaves rounding noise on ties.
+            let margin = 1e-9 * score.abs().max(1.0);
+            if score > best.0 + margin || (score >= best.0 - margin && distance < best.1) {
+                best = (score, distance, x, y);
+            }
+        }
+    }
+
+    let (_, _, x, y) = best;
+    let x = ((x as f64 / scale).round() as u32).min(image.width - crop_w);
+    let y = ((y as f64 / scale).round() as u32).min(image.height - crop_h);
+    Ok((x, y))
+}
+
+/// How interesting each pixel of an 8-bit image is.
+fn score_map(image: &ImageData) -> Vec<f32> {
+    let (w, h) = (image.width as usize, image.height as usize);
+    let pixels: Vec<[f32; 4]> = image
+        .data
+        .chunks_exact(4)
+        .map(|p| [0, 1, 2, 3].map(|c| f32::from(p[c]) / 255.0))
+        .collect();
+    let luma: Vec<f32> = pixels
+        .iter()
+        .map(|[r, g, b, _]| 0.2126 * r + 0.7152 * g + 0.0722 * b)
+        .collect();
+    let entropy = block_entropy(&luma, w, h);
+
+    let mut scores = Vec::with_capacity(w * h);
+    for y in 0..h {
+        for x in 0..w {
+            let i = y * w + x;
+            let [r, g, b, a] = pixels[i];
+            let l = luma[i];
+
+            // Laplacian of luma, with the border pixels repeated.
+            let neighbours = luma[y * w + x.saturating_sub(1)]
+                + luma[y * w + (x + 1).min(w - 1)]
+                + luma[y.saturating_sub(1) * w + x]
+                + luma[(y + 1).min(h - 1) * w + x];
+            let edge = (4.0 * l - neighbours).abs().min(1.0);
+
+            let max = r.max(g).max(b);
+            let min = r.min(g).min(b);
+            let chroma = if max > 0.0 { (max - min) / max } else { 0.0 };
+            let saturation = if (0.05..=0.9).contains(&l) && chroma > 0.4 {
+                (chroma - 0.4) / 0.6
             } else {
-                let w = img_w;
-                let h = (w as f64 / target_ratio).round() as u32;
-                (w, h)
+                0.0
             };
 
-            let x = (img_w - crop_w) / 2;
-            let y = (img_h - crop_h) / 2;
+            let magnitude = (r * r + g * g + b * b).sqrt();
+            let skin = if magnitude > 0.0 && (0.2..=1.0).contains(&l) {
+                let d = [r, g, b]
+                    .iter()
+                    .zip(SKIN)
+                    .map(|(c, s)| (c / magnitude - s).powi(2))
+                    .sum::<f32>()
+                    .sqrt();
+                ((1.0 - d - 0.8) / 0.2).max(0.0)
+            } else {
+                0.0
+            };
 
-            Ok((x, y, crop_w, crop_h))
+            let score = EDGE_WEIGHT * edge
+                + SKIN_WEIGHT * skin
+                + SATURATION_WEIGHT * saturation
+                + ENTROPY_WEIGHT
+                    * entropy[(y / ENTROPY_BLOCK) * w.div_ceil(ENTROPY_BLOCK) + x / ENTROPY_BLOCK];
+            // Transparent pixels are background, whatever colour they hide.
+            scores.push(score * a);
         }
     }
+    scores
+}
+
+/// Shannon entropy of a 16-bin luma histogram per block, scaled to 0..=1.
+fn block_entropy(luma: &[f32], w: usize, h: usize) -> Vec<f32> {
+    let (blocks_w, blocks_h) = (w.div_ceil(ENTROPY_BLOCK), h.div_ceil(ENTROPY_BLOCK));
+    let mut entropy = Vec::with_capacity(blocks_w * blocks_h);
+    for by in 0..blocks_h {
+        for bx in 0..blocks_w {
+            let mut histogram = [0u32; 16];
+            let mut count = 0;
+            for y in by * ENTROPY_BLOCK..((by + 1) * ENTROPY_BLOCK).min(h) {
+                for x in bx * ENTROPY_BLOCK..((bx + 1) * ENTROPY_BLOCK).min(w) {
+                    histogram[((luma[y * w + x] * 16.0) as usize).min(15)] += 1;
+                    count += 1;
+                }
+            }
+            let bits: f32 = histogram
+                .iter()
+                .filter(|&&n| n > 0)
+                .map(|&n| {
+                    let p = n as f32 / count as f32;
+                    -p * p.log2()
+                })
+                .sum();
+            entropy.push(bits / 4.0);
+        }
+    }
+    entropy
 }
 
 /// Crop an image according to the given mode.
 pub fn crop(image: &ImageData, mode: &CropMode) -> Result<ImageData> {
-    let (x, y, crop_w, crop_h) = calculate_crop_region(image.width, image.height, mode)?;
+    let (x, y, crop_w, crop_h) = crop_region(image, mode)?;
 
-    let bytes_per_pixel = 4usize;
+    let bytes_per_pixel = image.pixel_format.bytes_per_pixel();
     let src_stride = image.width as usize * bytes_per_pixel;
     let dst_stride = crop_w as usize * bytes_per_pixel;
 
@@ -83,12 +298,14 @@ pub fn crop(image: &ImageData, mode: &CropMode) -> Result<ImageData> {
             .copy_from_slice(&image.data[src_offset..src_offset + dst_stride]);
     }
 
-    Ok(ImageData::new(crop_w, crop_h, data))
+    Ok(ImageData::from_raw(crop_w, crop_h, image.pixel_format, data)
+        .with_metadata(image.metadata.clone()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codec::PixelFormat;
 
     #[test]
     fn region_valid_crop() {
@@ -175,6 +392,7 @@ mod tests {
             &CropMode::AspectRatio {
                 width: 1,
                 height: 1,
+                gravity: Gravity::Center,
             },
         )
         .unwrap();
@@ -189,6 +407,7 @@ mod tests {
             &CropMode::AspectRatio {
                 width: 1,
                 height: 1,
+                gravity: Gravity::Center,
             },
         )
         .unwrap();
@@ -203,6 +422,7 @@ mod tests {
             &CropMode::AspectRatio {
                 width: 16,
                 height: 9,
+                gravity: Gravity::Center,
             },
         )
         .unwrap();
@@ -220,6 +440,7 @@ mod tests {
             &CropMode::AspectRatio {
                 width: 2,
                 height: 1,
+                gravity: Gravity::Center,
             },
         )
         .unwrap();
@@ -234,6 +455,45 @@ mod tests {
             &CropMode::AspectRatio {
                 width: 0,
                 height: 1,
+                gravity: Gravity::Center,
+            },
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn aspect_gravity_picks_the_kept_part() {
+        let region = |gravity| {
+            calculate_crop_region(
+                100,
+                300,
+                &CropMode::AspectRatio {
+                    width: 1,
+                    height: 1,
+                    gravity,
+                },
+            )
+            .unwrap()
+        };
+        assert_eq!(region(Gravity::North), (0, 0, 100, 100));
+        assert_eq!(region(Gravity::SouthWest), (0, 200, 100, 100));
+        // East has nothing to move on a full-width window.
+        assert_eq!(region(Gravity::East), (0, 100, 100, 100));
+        assert_eq!(
+            region(Gravity::Focal { x: 0.5, y: 0.25 }),
+            (0, 25, 100, 100)
+        );
+    }
+
+    #[test]
+    fn aspect_focal_point_outside_errors() {
+        let result = calculate_crop_region(
+            200,
+            100,
+            &CropMode::AspectRatio {
+                width: 1,
+                height: 1,
+                gravity: Gravity::Focal { x: 0.5, y: -0.1 },
             },
         );
         assert!(result.is_err());
@@ -270,6 +530,7 @@ mod tests {
             &CropMode::AspectRatio {
                 width: 1,
                 height: 1,
+                gravity: Gravity::Center,
             },
         )
         .unwrap();
@@ -325,4 +586,134 @@ mod tests {
         assert_eq!(result.height, 50);
         assert_eq!(result.data, img.data);
     }
+
+    #[test]
+    fn crop_high_bit_depth_keeps_samples() {
+        let samples: Vec<f32> = (0..4 * 2 * 4).map(|i| i as f32 / 32.0).collect();
+        let img = ImageData::from_rgba32f(4, 2, samples.clone());
+        let result = crop(
+            &img,
+            &CropMode::Region {
+                x: 1,
+                y: 1,
+                width: 2,
+                height: 1,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(result.pixel_format, PixelFormat::Rgba32F);
+        assert_eq!(result.rgba32f_samples(), samples[20..28].to_vec());
+    }
+
+    /// A flat grey image with `paint` drawing over some of its pixels.
+    fn grey_with(
+        width: u32,
+        height: u32,
+        paint: impl Fn(u32, u32) -> Option<[u8; 4]>,
+    ) -> ImageData {
+        let data = (0..width * height)
+            .flat_map(|i| paint(i % width, i / width).unwrap_or([128, 128, 128, 255]))
+            .collect();
+        ImageData::new(width, height, data)
+    }
+
+    fn smart(width: u32, height: u32) -> CropMode {
+        CropMode::Smart { width, height }
+    }
+
+    #[test]
+    fn smart_follows_detail() {
+        // A checkerboard patch near the right edge.
+        let img = grey_with(600, 200, |x, y| {
+            ((420..540).contains(&x) && (40..160).contains(&y)).then(|| {
+                if (x / 4 + y / 4) % 2 == 0 {
+                    [0, 0, 0, 255]
+                } else {
+                    [255; 4]
+                }
+            })
+        });
+        let (x, y, w, h) = crop_region(&img, &smart(1, 1)).unwrap();
+        assert_eq!((y, w, h), (0, 200, 200));
+        assert!(x <= 420 && x + w >= 540, "{x}");
+    }
+
+    #[test]
+    fn smart_keeps_faces_in_portraits() {
+        // A skin-coloured disc near the top of a portrait, and a darker
+        // grey one with as much outline near the bottom.
+        let img = grey_with(200, 600, |x, y| {
+            let dx = x as f32 - 100.0;
+            if dx.hypot(y as f32 - 110.0) < 60.0 {
+                Some([224, 172, 140, 255])
+            } else if dx.hypot(y as f32 - 490.0) < 60.0 {
+                Some([75, 75, 75, 255])
+            } else {
+                None
+            }
+        });
+        let (x, y, w, h) = crop_region(&img, &smart(1, 1)).unwrap();
+        assert_eq!((x, w, h), (0, 200, 200));
+        assert!(y <= 50, "{y}");
+    }
+
+    #[test]
+    fn smart_ignores_colour_under_transparency() {
+        // Noise on the left is fully transparent; a red square on the right
+        // is what shows.
+        let img = grey_with(600, 200, |x, y| {
+            if x < 200 {
+                let v = ((x * 7919 + y * 104_729) % 256) as u8;
+                Some([v, 255 - v, v / 2, 0])
+            } else if (440..520).contains(&x) && (60..140).contains(&y) {
+                Some([220, 20, 20, 255])
+            } else {
+                None
+            }
+        });
+        let (x, _, w, _) = crop_region(&img, &smart(1, 1)).unwrap();
+        assert!(x <= 440 && x + w >= 520, "{x}");
+    }
+
+    #[test]
+    fn smart_centres_featureless_images() {
+        let img = create_test_image(300, 100);
+        assert_eq!(
+            crop_region(&img, &smart(1, 1)).unwrap(),
+            calculate_crop_region(
+                300,
+                100,
+                &CropMode::AspectRatio {
+                    width: 1,
+                    height: 1,
+                    gravity: Gravity::Center,
+                }
+            )
+            .unwrap()
+        );
+        assert_eq!(crop_region(&img, &smart(3, 1)).unwrap(), (0, 0, 300, 100));
+    }
+
+    #[test]
+    fn smart_crop_takes_the_chosen_region() {
+        let img = grey_with(300, 100, |x, _| (x >= 250).then_some([255, 0, 0, 255]));
+        let (x, y, w, h) = crop_region(&img, &smart(1, 1)).unwrap();
+        let region = CropMode::Region {
+            x,
+            y,
+            width: w,
+            height: h,
+        };
+        assert_eq!(
+            crop(&img, &smart(1, 1)).unwrap().data,
+            crop(&img, &region).unwrap().data
+        );
+    }
+
+    #[test]
+    fn smart_needs_the_pixels() {
+        assert!(calculate_crop_region(300, 100, &smart(1, 1)).is_err());
+        assert!(crop_region(&create_test_image(300, 100), &smart(0, 1)).is_err());
+    }
 }
@@ -1,4 +1,5 @@
 use crate::format::Format;
+use crate::limits::Limit;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -11,6 +12,12 @@ pub enum Error {
     #[error("encoding not supported for {0:?} (license restriction)")]
     EncodingNotSupported(Format),
 
+    #[error("lossless encoding not supported for {0:?}")]
+    LosslessNotSupported(Format),
+
+    #[error("animation not supported for {0:?}")]
+    AnimationNotSupported(Format),
+
     #[error("decode error: {0}")]
     Decode(String),
 
@@ -26,6 +33,22 @@ pub enum Error {
     #[error("extend error: {0}")]
     Extend(String),
 
+    #[error("output cannot fit in {max_bytes} bytes (smallest was {smallest} bytes)")]
+    TargetSizeUnreachable { max_bytes: u64, smallest: u64 },
+
+    #[error(
+        "image dimensions differ: expected {expected_width}x{expected_height}, got {actual_width}x{actual_height}"
+    )]
+    DimensionMismatch {
+        expected_width: u32,
+        expected_height: u32,
+        actual_width: u32,
+        actual_height: u32,
+    },
+
+    #[error("{limit} {actual} is over the decode limit of {max}")]
+    LimitExceeded { limit: Limit, actual: u64, max: u64 },
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
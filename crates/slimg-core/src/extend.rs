@@ -1,5 +1,6 @@
 use crate::codec::ImageData;
 use crate::error::{Error, Result};
+use crate::gravity::Gravity;
 
 /// Fill color for the extended canvas region.
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -23,11 +24,21 @@ impl FillColor {
 /// How to extend (add padding to) an image.
 #[derive(Debug, Clone, PartialEq)]
 pub enum ExtendMode {
-    /// Extend the canvas so the image fits the given aspect ratio (centered).
-    /// `width` and `height` define the ratio (e.g. 16:9).
-    AspectRatio { width: u32, height: u32 },
-    /// Extend the canvas to an exact pixel size (centered).
-    Size { width: u32, height: u32 },
+    /// Extend the canvas so the image fits the given aspect ratio.
+    /// `width` and `height` define the ratio (e.g. 16:9); `gravity` places
+    /// the image on the canvas.
+    AspectRatio {
+        width: u32,
+        height: u32,
+        gravity: Gravity,
+    },
+    /// Extend the canvas to an exact pixel size, placing the image by
+    /// `gravity`.
+    Size {
+        width: u32,
+        height: u32,
+        gravity: Gravity,
+    },
 }
 
 /// Calculate the extended canvas dimensions and the offset at which the
@@ -43,12 +54,14 @@ pub fn calculate_extend_region(
         ExtendMode::AspectRatio {
             width: rw,
             height: rh,
+            gravity,
         } => {
             if rw == 0 || rh == 0 {
                 return Err(Error::Extend(
                     "aspect ratio must be non-zero".to_string(),
                 ));
             }
+            check_gravity(gravity)?;
 
             let target_ratio = rw as f64 / rh as f64;
             let img_ratio = img_w as f64 / img_h as f64;
@@ -65,12 +78,15 @@ pub fn calculate_extend_region(
                 (w, h)
             };
 
-            let off_x = (canvas_w - img_w) / 2;
-            let off_y = (canvas_h - img_h) / 2;
+            let (off_x, off_y) = gravity.extend_offset(canvas_w, canvas_h, img_w, img_h);
 
             Ok((canvas_w, canvas_h, off_x, off_y))
         }
-        ExtendMode::Size { width, height } => {
+        ExtendMode::Size {
+            width,
+            height,
+            gravity,
+        } => {
             if width == 0 || height == 0 {
                 return Err(Error::Extend(
                     "extend dimensions must be non-zero".to_string(),
@@ -81,25 +97,36 @@ pub fn calculate_extend_region(
                     "target size ({width}x{height}) is smaller than image ({img_w}x{img_h})"
                 )));
             }
+            check_gravity(gravity)?;
 
-            let off_x = (width - img_w) / 2;
-            let off_y = (height - img_h) / 2;
+            let (off_x, off_y) = gravity.extend_offset(width, height, img_w, img_h);
 
             Ok((width, height, off_x, off_y))
         }
     }
 }
 
+fn check_gravity(gravity: Gravity) -> Result<()> {
+    if gravity.is_valid() {
+        Ok(())
+    } else {
+        Err(Error::Extend(
+            "focal point must be within 0.0..=1.0".to_string(),
+        ))
+    }
+}
+
 /// Extend an image by adding padding around it.
 pub fn extend(image: &ImageData, mode: &ExtendMode, fill: &FillColor) -> Result<ImageData> {
     let (canvas_w, canvas_h, off_x, off_y) =
         calculate_extend_region(image.width, image.height, mode)?;
 
-    let expected_size = image.width as usize * image.height as usize * 4;
+    let bytes_per_pixel = image.pixel_format.bytes_per_pixel();
+    let expected_size = image.width as usize * image.height as usize * bytes_per_pixel;
     if image.data.len() != expected_size {
         return Err(Error::Extend(format!(
-            "invalid image data: expected {} bytes ({}x{}x4), got {}",
-            expected_size, image.width, image.height, image.data.len()
+            "invalid image data: expected {} bytes ({}x{}x{}), got {}",
+            expected_size, image.width, image.height, bytes_per_pixel, image.data.len()
         )));
     }
 
@@ -108,16 +135,17 @@ pub fn extend(image: &ImageData, mode: &ExtendMode, fill: &FillColor) -> Result<
         return Ok(image.clone());
     }
 
-    let bytes_per_pixel = 4usize;
     let canvas_stride = canvas_w as usize * bytes_per_pixel;
     let src_stride = image.width as usize * bytes_per_pixel;
 
     // Fill canvas with background color
     let mut data = vec![0u8; canvas_h as usize * canvas_stride];
     if !matches!(fill, FillColor::Transparent) {
-        let fill_rgba = fill.as_rgba();
+        let fill_pixel = ImageData::new(1, 1, fill.as_rgba().to_vec())
+            .to_pixel_format(image.pixel_format)
+            .data;
         for pixel in data.chunks_exact_mut(bytes_per_pixel) {
-            pixel.copy_from_slice(&fill_rgba);
+            pixel.copy_from_slice(&fill_pixel);
         }
     }
 
@@ -129,12 +157,14 @@ pub fn extend(image: &ImageData, mode: &ExtendMode, fill: &FillColor) -> Result<
             .copy_from_slice(&image.data[src_offset..src_offset + src_stride]);
     }
 
-    Ok(ImageData::new(canvas_w, canvas_h, data))
+    Ok(ImageData::from_raw(canvas_w, canvas_h, image.pixel_format, data)
+        .with_metadata(image.metadata.clone()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codec::PixelFormat;
 
     // ── AspectRatio tests ───────────────────────────────────────────
 
@@ -146,6 +176,7 @@ mod tests {
             &ExtendMode::AspectRatio {
                 width: 1,
                 height: 1,
+                gravity: Gravity::Center,
             },
         )
         .unwrap();
@@ -160,6 +191,7 @@ mod tests {
             &ExtendMode::AspectRatio {
                 width: 1,
                 height: 1,
+                gravity: Gravity::Center,
             },
         )
         .unwrap();
@@ -174,6 +206,7 @@ mod tests {
             &ExtendMode::AspectRatio {
                 width: 16,
                 height: 9,
+                gravity: Gravity::Center,
             },
         )
         .unwrap();
@@ -188,6 +221,7 @@ mod tests {
             &ExtendMode::AspectRatio {
                 width: 9,
                 height: 16,
+                gravity: Gravity::Center,
             },
         )
         .unwrap();
@@ -202,6 +236,7 @@ mod tests {
             &ExtendMode::AspectRatio {
                 width: 2,
                 height: 1,
+                gravity: Gravity::Center,
             },
         )
         .unwrap();
@@ -216,6 +251,7 @@ mod tests {
             &ExtendMode::AspectRatio {
                 width: 0,
                 height: 1,
+                gravity: Gravity::Center,
             },
         );
         assert!(result.is_err());
@@ -231,6 +267,7 @@ mod tests {
             &ExtendMode::Size {
                 width: 1000,
                 height: 1000,
+                gravity: Gravity::Center,
             },
         )
         .unwrap();
@@ -245,6 +282,7 @@ mod tests {
             &ExtendMode::Size {
                 width: 800,
                 height: 600,
+                gravity: Gravity::Center,
             },
         )
         .unwrap();
@@ -259,6 +297,7 @@ mod tests {
             &ExtendMode::Size {
                 width: 1000,
                 height: 600,
+                gravity: Gravity::Center,
             },
         )
         .unwrap();
@@ -273,6 +312,7 @@ mod tests {
             &ExtendMode::Size {
                 width: 500,
                 height: 500,
+                gravity: Gravity::Center,
             },
         );
         assert!(result.is_err());
@@ -286,6 +326,7 @@ mod tests {
             &ExtendMode::Size {
                 width: 700,
                 height: 600,
+                gravity: Gravity::Center,
             },
         );
         assert!(result.is_err());
@@ -299,6 +340,48 @@ mod tests {
             &ExtendMode::Size {
                 width: 0,
                 height: 0,
+                gravity: Gravity::Center,
+            },
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn gravity_places_the_image() {
+        let north = calculate_extend_region(
+            200,
+            100,
+            &ExtendMode::AspectRatio {
+                width: 1,
+                height: 1,
+                gravity: Gravity::North,
+            },
+        )
+        .unwrap();
+        assert_eq!(north, (200, 200, 0, 0));
+
+        let south_east = calculate_extend_region(
+            200,
+            100,
+            &ExtendMode::Size {
+                width: 300,
+                height: 160,
+                gravity: Gravity::SouthEast,
+            },
+        )
+        .unwrap();
+        assert_eq!(south_east, (300, 160, 100, 60));
+    }
+
+    #[test]
+    fn focal_point_outside_errors() {
+        let result = calculate_extend_region(
+            200,
+            100,
+            &ExtendMode::Size {
+                width: 400,
+                height: 400,
+                gravity: Gravity::Focal { x: 2.0, y: 0.5 },
             },
         );
         assert!(result.is_err());
@@ -321,6 +404,7 @@ mod tests {
             &ExtendMode::AspectRatio {
                 width: 1,
                 height: 1,
+                gravity: Gravity::Center,
             },
             &FillColor::Solid([255, 255, 255, 255]),
         )
@@ -338,6 +422,7 @@ mod tests {
             &ExtendMode::Size {
                 width: 4,
                 height: 4,
+                gravity: Gravity::Center,
             },
             &FillColor::Solid([255, 0, 0, 255]),
         )
@@ -357,6 +442,7 @@ mod tests {
             &ExtendMode::Size {
                 width: 4,
                 height: 4,
+                gravity: Gravity::Center,
             },
             &FillColor::Transparent,
         )
@@ -376,16 +462,17 @@ mod tests {
             &ExtendMode::Size {
                 width: 4,
                 height: 3,
+                gravity: Gravity::Center,
             },
             &FillColor::Solid([0, 0, 0, 0]),
         )
         .unwrap();
 
         let stride = 4 * 4; // 4 pixels * 4 bytes
-        let offset = 1 * stride + 1 * 4; // row 1, col 1
+        let offset = stride + 4; // row 1, col 1
         assert_eq!(&result.data[offset..offset + 4], &[10, 20, 30, 255]);
 
-        let offset2 = 1 * stride + 2 * 4;
+        let offset2 = stride + 2 * 4; // row 1, col 2
         assert_eq!(&result.data[offset2..offset2 + 4], &[40, 50, 60, 255]);
     }
 
@@ -397,6 +484,7 @@ mod tests {
             &ExtendMode::AspectRatio {
                 width: 2,
                 height: 1,
+                gravity: Gravity::Center,
             },
             &FillColor::Solid([255, 255, 255, 255]),
         )
@@ -405,4 +493,25 @@ mod tests {
         assert_eq!(result.height, 100);
         assert_eq!(result.data, img.data);
     }
+
+    #[test]
+    fn extend_fills_high_bit_depth_canvas() {
+        let img = ImageData::from_rgba16(1, 1, vec![1, 2, 3, 4]);
+        let result = extend(
+            &img,
+            &ExtendMode::Size {
+                width: 3,
+                height: 1,
+                gravity: Gravity::Center,
+            },
+            &FillColor::Solid([255, 0, 0, 255]),
+        )
+        .unwrap();
+
+        assert_eq!(result.pixel_format, PixelFormat::Rgba16);
+        assert_eq!(
+            result.rgba16_samples(),
+            vec![65535, 0, 0, 65535, 1, 2, 3, 4, 65535, 0, 0, 65535]
+        );
+    }
 }
@@ -9,6 +9,12 @@ pub enum Format {
     Avif,
     Jxl,
     Qoi,
+    Gif,
+    Tiff,
+    Bmp,
+    Tga,
+    Ico,
+    Pnm,
 }
 
 impl Format {
@@ -22,6 +28,12 @@ impl Format {
             "avif" => Some(Self::Avif),
             "jxl" => Some(Self::Jxl),
             "qoi" => Some(Self::Qoi),
+            "gif" => Some(Self::Gif),
+            "tif" | "tiff" => Some(Self::Tiff),
+            "bmp" | "dib" => Some(Self::Bmp),
+            "tga" => Some(Self::Tga),
+            "ico" | "cur" => Some(Self::Ico),
+            "pnm" | "pbm" | "pgm" | "ppm" | "pam" => Some(Self::Pnm),
             _ => None,
         }
     }
@@ -54,6 +66,36 @@ impl Format {
         if data.len() >= 4 && &data[..4] == b"qoif" {
             return Some(Self::Qoi);
         }
+        if data.len() >= 6 && (&data[..6] == b"GIF87a" || &data[..6] == b"GIF89a") {
+            return Some(Self::Gif);
+        }
+        // TIFF: byte order mark + 42, or 43 for BigTIFF
+        if data.len() >= 4 && matches!(&data[..4], b"II*\0" | b"MM\0*" | b"II+\0" | b"MM\0+") {
+            return Some(Self::Tiff);
+        }
+        if data.len() >= 14 && &data[..2] == b"BM" {
+            return Some(Self::Bmp);
+        }
+        // ICO and CUR: reserved 0, type 1 or 2, then a non-zero image count
+        if data.len() >= 6
+            && data[..2] == [0, 0]
+            && matches!(data[2..4], [1, 0] | [2, 0])
+            && data[4..6] != [0, 0]
+        {
+            return Some(Self::Ico);
+        }
+        // Netpbm: "P1" to "P7" followed by whitespace
+        if data.len() >= 3
+            && data[0] == b'P'
+            && (b'1'..=b'7').contains(&data[1])
+            && data[2].is_ascii_whitespace()
+        {
+            return Some(Self::Pnm);
+        }
+        // TGA has no leading magic, so it is checked last.
+        if is_tga(data) {
+            return Some(Self::Tga);
+        }
         None
     }
 
@@ -66,6 +108,12 @@ impl Format {
             Self::Avif => "avif",
             Self::Jxl => "jxl",
             Self::Qoi => "qoi",
+            Self::Gif => "gif",
+            Self::Tiff => "tiff",
+            Self::Bmp => "bmp",
+            Self::Tga => "tga",
+            Self::Ico => "ico",
+            Self::Pnm => "pnm",
         }
     }
 
@@ -73,6 +121,65 @@ impl Format {
     pub fn can_encode(&self) -> bool {
         true
     }
+
+    /// Whether this format can be encoded losslessly.
+    pub fn supports_lossless(&self) -> bool {
+        matches!(
+            self,
+            Self::Png
+                | Self::WebP
+                | Self::Jxl
+                | Self::Qoi
+                | Self::Tiff
+                | Self::Bmp
+                | Self::Tga
+                | Self::Ico
+                | Self::Pnm
+        )
+    }
+
+    /// Whether slimg can embed an ICC colour profile in this format.
+    pub fn supports_icc_profile(&self) -> bool {
+        matches!(self, Self::Jpeg | Self::Png | Self::WebP | Self::Jxl)
+    }
+
+    /// Whether the encoder can signal PQ/HLG pixels, so HDR images are
+    /// written without tone mapping.
+    pub fn supports_hdr(&self) -> bool {
+        matches!(self, Self::Avif | Self::Jxl)
+    }
+
+    /// Whether this format can hold more than one frame. TIFF stores
+    /// frames as pages.
+    pub fn supports_animation(&self) -> bool {
+        matches!(
+            self,
+            Self::Png | Self::WebP | Self::Avif | Self::Jxl | Self::Gif | Self::Tiff
+        )
+    }
+}
+
+/// Whether `data` looks like a TGA file: either the TGA 2.0 footer or an
+/// 18-byte header with a known image type and pixel depth.
+fn is_tga(data: &[u8]) -> bool {
+    const FOOTER: &[u8] = b"TRUEVISION-XFILE.\0";
+    if data.len() >= 26 + FOOTER.len() && data.ends_with(FOOTER) {
+        return true;
+    }
+    if data.len() < 18 {
+        return false;
+    }
+    let color_map = data[1];
+    let image_type = data[2];
+    let width = u16::from_le_bytes([data[12], data[13]]);
+    let height = u16::from_le_bytes([data[14], data[15]]);
+    let depth = data[16];
+    let known_type = match color_map {
+        0 => matches!(image_type, 2 | 3 | 10 | 11),
+        1 => matches!(image_type, 1 | 9),
+        _ => false,
+    };
+    known_type && width > 0 && height > 0 && matches!(depth, 8 | 15 | 16 | 24 | 32)
 }
 
 #[cfg(test)]
@@ -162,9 +269,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn extension_gif() {
+        assert_eq!(
+            Format::from_extension(Path::new("anim.GIF")),
+            Some(Format::Gif)
+        );
+    }
+
+    #[test]
+    fn extension_raster_inputs() {
+        for (path, format) in [
+            ("scan.tif", Format::Tiff),
+            ("scan.TIFF", Format::Tiff),
+            ("export.bmp", Format::Bmp),
+            ("export.dib", Format::Bmp),
+            ("texture.tga", Format::Tga),
+            ("favicon.ico", Format::Ico),
+            ("pointer.cur", Format::Ico),
+            ("frame.ppm", Format::Pnm),
+            ("frame.pgm", Format::Pnm),
+            ("frame.pbm", Format::Pnm),
+            ("frame.pam", Format::Pnm),
+            ("frame.pnm", Format::Pnm),
+        ] {
+            assert_eq!(
+                Format::from_extension(Path::new(path)),
+                Some(format),
+                "{path}"
+            );
+        }
+    }
+
     #[test]
     fn extension_unknown() {
-        assert_eq!(Format::from_extension(Path::new("file.bmp")), None);
+        assert_eq!(Format::from_extension(Path::new("file.psd")), None);
     }
 
     #[test]
@@ -238,6 +377,89 @@ mod tests {
         assert_eq!(Format::from_magic_bytes(&data), Some(Format::Qoi));
     }
 
+    #[test]
+    fn magic_gif() {
+        assert_eq!(
+            Format::from_magic_bytes(b"GIF87a\x01\x00"),
+            Some(Format::Gif)
+        );
+        assert_eq!(
+            Format::from_magic_bytes(b"GIF89a\x01\x00"),
+            Some(Format::Gif)
+        );
+        assert_eq!(Format::from_magic_bytes(b"GIF90a"), None);
+    }
+
+    #[test]
+    fn magic_tiff() {
+        assert_eq!(
+            Format::from_magic_bytes(b"II*\0\x08\0\0\0"),
+            Some(Format::Tiff)
+        );
+        assert_eq!(
+            Format::from_magic_bytes(b"MM\0*\0\0\0\x08"),
+            Some(Format::Tiff)
+        );
+        assert_eq!(
+            Format::from_magic_bytes(b"II+\0\x08\0\0\0"),
+            Some(Format::Tiff)
+        );
+    }
+
+    #[test]
+    fn magic_bmp() {
+        let mut data = b"BM".to_vec();
+        data.extend_from_slice(&[0x00; 12]);
+        assert_eq!(Format::from_magic_bytes(&data), Some(Format::Bmp));
+    }
+
+    #[test]
+    fn magic_ico_and_cur() {
+        assert_eq!(
+            Format::from_magic_bytes(&[0, 0, 1, 0, 1, 0]),
+            Some(Format::Ico)
+        );
+        assert_eq!(
+            Format::from_magic_bytes(&[0, 0, 2, 0, 1, 0]),
+            Some(Format::Ico)
+        );
+        // An icon directory with no images
+        assert_eq!(Format::from_magic_bytes(&[0, 0, 1, 0, 0, 0]), None);
+    }
+
+    #[test]
+    fn magic_pnm() {
+        assert_eq!(
+            Format::from_magic_bytes(b"P6\n2 2\n255\n"),
+            Some(Format::Pnm)
+        );
+        assert_eq!(
+            Format::from_magic_bytes(b"P7\nWIDTH 1\n"),
+            Some(Format::Pnm)
+        );
+        assert_eq!(Format::from_magic_bytes(b"P8\n"), None);
+        assert_eq!(Format::from_magic_bytes(b"PK\x03\x04"), None);
+    }
+
+    #[test]
+    fn magic_tga_header() {
+        // Uncompressed true-colour, 4x2 pixels, 32 bits per pixel
+        let mut data = vec![0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 2, 0, 32, 8];
+        data.extend_from_slice(&[0x00; 32]);
+        assert_eq!(Format::from_magic_bytes(&data), Some(Format::Tga));
+
+        data[16] = 12; // not a TGA pixel depth
+        assert_eq!(Format::from_magic_bytes(&data), None);
+    }
+
+    #[test]
+    fn magic_tga_footer() {
+        let mut data = vec![0xAA; 40];
+        data.extend_from_slice(&[0x00; 8]);
+        data.extend_from_slice(b"TRUEVISION-XFILE.\0");
+        assert_eq!(Format::from_magic_bytes(&data), Some(Format::Tga));
+    }
+
     #[test]
     fn magic_unknown() {
         let data = [0x00, 0x00, 0x00, 0x00];
@@ -264,6 +486,12 @@ mod tests {
         assert_eq!(Format::Avif.extension(), "avif");
         assert_eq!(Format::Jxl.extension(), "jxl");
         assert_eq!(Format::Qoi.extension(), "qoi");
+        assert_eq!(Format::Gif.extension(), "gif");
+        assert_eq!(Format::Tiff.extension(), "tiff");
+        assert_eq!(Format::Bmp.extension(), "bmp");
+        assert_eq!(Format::Tga.extension(), "tga");
+        assert_eq!(Format::Ico.extension(), "ico");
+        assert_eq!(Format::Pnm.extension(), "pnm");
     }
 
     // ── can_encode ──────────────────────────────────────────────
@@ -276,5 +504,89 @@ mod tests {
         assert!(Format::Avif.can_encode());
         assert!(Format::Jxl.can_encode());
         assert!(Format::Qoi.can_encode());
+        assert!(Format::Gif.can_encode());
+        assert!(Format::Tiff.can_encode());
+        assert!(Format::Bmp.can_encode());
+        assert!(Format::Tga.can_encode());
+        assert!(Format::Ico.can_encode());
+        assert!(Format::Pnm.can_encode());
+    }
+
+    // ── supports_lossless ───────────────────────────────────────
+
+    #[test]
+    fn supports_lossless_only_for_lossless_capable_formats() {
+        assert!(!Format::Jpeg.supports_lossless());
+        assert!(Format::Png.supports_lossless());
+        assert!(Format::WebP.supports_lossless());
+        assert!(!Format::Avif.supports_lossless());
+        assert!(Format::Jxl.supports_lossless());
+        assert!(Format::Qoi.supports_lossless());
+        assert!(!Format::Gif.supports_lossless());
+        assert!(Format::Tiff.supports_lossless());
+        assert!(Format::Bmp.supports_lossless());
+        assert!(Format::Tga.supports_lossless());
+        assert!(Format::Ico.supports_lossless());
+        assert!(Format::Pnm.supports_lossless());
+    }
+
+    // ── supports_icc_profile ────────────────────────────────────
+
+    #[test]
+    fn supports_icc_profile_only_where_encoder_embeds_it() {
+        assert!(Format::Jpeg.supports_icc_profile());
+        assert!(Format::Png.supports_icc_profile());
+        assert!(Format::WebP.supports_icc_profile());
+        assert!(!Format::Avif.supports_icc_profile());
+        assert!(Format::Jxl.supports_icc_profile());
+        assert!(!Format::Qoi.supports_icc_profile());
+        assert!(!Format::Gif.supports_icc_profile());
+        assert!(!Format::Tiff.supports_icc_profile());
+        assert!(!Format::Bmp.supports_icc_profile());
+    }
+
+    // ── supports_hdr ────────────────────────────────────────────
+
+    #[test]
+    fn supports_hdr_only_for_avif_and_jxl() {
+        assert!(Format::Avif.supports_hdr());
+        assert!(Format::Jxl.supports_hdr());
+        for format in [
+            Format::Jpeg,
+            Format::Png,
+            Format::WebP,
+            Format::Qoi,
+            Format::Gif,
+            Format::Tiff,
+            Format::Pnm,
+        ] {
+            assert!(!format.supports_hdr(), "{format:?}");
+        }
+    }
+
+    // ── supports_animation ──────────────────────────────────────
+
+    #[test]
+    fn supports_animation_excludes_still_only_formats() {
+        for format in [
+            Format::Png,
+            Format::WebP,
+            Format::Avif,
+            Format::Jxl,
+            Format::Gif,
+            Format::Tiff,
+        ] {
+            assert!(format.supports_animation(), "{format:?}");
+        }
+        for format in [
+            Format::Jpeg,
+            Format::Qoi,
+            Format::Bmp,
+            Format::Tga,
+            Format::Ico,
+            Format::Pnm,
+        ] {
+            assert!(!format.supports_animation(), "{format:?}");
+        }
     }
 }
@@ -0,0 +1,147 @@
+/// Which part of an image to keep when cropping, or where to put it when
+/// extending the canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Gravity {
+    /// Keep the middle, or pad evenly on both sides.
+    #[default]
+    Center,
+    /// Keep the top edge, or pad only below.
+    North,
+    /// Keep the top-right corner.
+    NorthEast,
+    /// Keep the right edge, or pad only on the left.
+    East,
+    /// Keep the bottom-right corner.
+    SouthEast,
+    /// Keep the bottom edge, or pad only above.
+    South,
+    /// Keep the bottom-left corner.
+    SouthWest,
+    /// Keep the left edge, or pad only on the right.
+    West,
+    /// Keep the top-left corner.
+    NorthWest,
+    /// A point of the image, as fractions of its width and height from the
+    /// top-left corner (`0.0..=1.0`), kept as close to the middle of the
+    /// result as the image allows.
+    Focal { x: f64, y: f64 },
+}
+
+impl Gravity {
+    /// Horizontal and vertical anchors in half steps: 0 is the left or top
+    /// edge, 1 the middle and 2 the right or bottom edge. Focal points are
+    /// placed by the callers instead.
+    fn anchors(self) -> (u8, u8) {
+        match self {
+            Self::Center | Self::Focal { .. } => (1, 1),
+            Self::North => (1, 0),
+            Self::NorthEast => (2, 0),
+            Self::East => (2, 1),
+            Self::SouthEast => (2, 2),
+            Self::South => (1, 2),
+            Self::SouthWest => (0, 2),
+            Self::West => (0, 1),
+            Self::NorthWest => (0, 0),
+        }
+    }
+
+    /// Whether a focal point lies within the image.
+    pub(crate) fn is_valid(self) -> bool {
+        match self {
+            Self::Focal { x, y } => (0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y),
+            _ => true,
+        }
+    }
+
+    /// Top-left corner of a `crop_w`x`crop_h` window inside an
+    /// `img_w`x`img_h` image.
+    pub(crate) fn crop_offset(
+        self,
+        img_w: u32,
+        img_h: u32,
+        crop_w: u32,
+        crop_h: u32,
+    ) -> (u32, u32) {
+        let (free_w, free_h) = (img_w - crop_w, img_h - crop_h);
+        if let Self::Focal { x, y } = self {
+            // The window's middle sits on the point.
+            return (
+                clamp_offset(x * img_w as f64 - crop_w as f64 / 2.0, free_w),
+                clamp_offset(y * img_h as f64 - crop_h as f64 / 2.0, free_h),
+            );
+        }
+        let (ax, ay) = self.anchors();
+        (anchor(free_w, ax), anchor(free_h, ay))
+    }
+
+    /// Top-left corner of an `img_w`x`img_h` image on a
+    /// `canvas_w`x`canvas_h` canvas.
+    pub(crate) fn extend_offset(
+        self,
+        canvas_w: u32,
+        canvas_h: u32,
+        img_w: u32,
+        img_h: u32,
+    ) -> (u32, u32) {
+        let (free_w, free_h) = (canvas_w - img_w, canvas_h - img_h);
+        if let Self::Focal { x, y } = self {
+            // The point sits on the canvas's middle.
+            return (
+                clamp_offset(canvas_w as f64 / 2.0 - x * img_w as f64, free_w),
+                clamp_offset(canvas_h as f64 / 2.0 - y * img_h as f64, free_h),
+            );
+        }
+        let (ax, ay) = self.anchors();
+        (anchor(free_w, ax), anchor(free_h, ay))
+    }
+}
+
+fn anchor(free: u32, half_steps: u8) -> u32 {
+    match half_steps {
+        0 => 0,
+        1 => free / 2,
+        _ => free,
+    }
+}
+
+fn clamp_offset(offset: f64, free: u32) -> u32 {
+    offset.round().clamp(0.0, free as f64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compass_anchors_reach_the_edges() {
+        assert_eq!(Gravity::Center.crop_offset(200, 100, 100, 50), (50, 25));
+        assert_eq!(Gravity::NorthWest.crop_offset(200, 100, 100, 50), (0, 0));
+        assert_eq!(Gravity::South.crop_offset(200, 100, 100, 50), (50, 50));
+        assert_eq!(Gravity::East.extend_offset(200, 100, 100, 50), (100, 25));
+        assert_eq!(Gravity::North.extend_offset(200, 100, 100, 50), (50, 0));
+    }
+
+    #[test]
+    fn focal_point_is_kept_central() {
+        let focal = Gravity::Focal { x: 0.25, y: 0.5 };
+        // The window's middle lands on x = 100 of 400.
+        assert_eq!(focal.crop_offset(400, 100, 100, 100), (50, 0));
+        // Near the edge, the window stops at it.
+        let corner = Gravity::Focal { x: 0.0, y: 1.0 };
+        assert_eq!(corner.crop_offset(400, 200, 100, 100), (0, 100));
+        // Extending moves x = 25 of the image to the canvas's middle (100).
+        assert_eq!(focal.extend_offset(200, 100, 100, 100), (75, 0));
+        assert_eq!(corner.extend_offset(200, 200, 100, 100), (100, 0));
+    }
+
+    #[test]
+    fn focal_point_must_be_inside() {
+        assert!(Gravity::Focal { x: 1.0, y: 0.0 }.is_valid());
+        assert!(!Gravity::Focal { x: 1.5, y: 0.5 }.is_valid());
+        let nan = Gravity::Focal {
+            x: f64::NAN,
+            y: 0.5,
+        };
+        assert!(!nan.is_valid());
+    }
+}
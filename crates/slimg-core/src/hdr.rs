@@ -0,0 +1,351 @@
+use crate::codec::{ImageData, PixelFormat};
+
+/// HDR transfer function (EOTF) of the stored pixel values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferFunction {
+    /// SMPTE ST 2084 perceptual quantizer, absolute luminance up to 10000 nits.
+    Pq,
+    /// ARIB STD-B67 hybrid log-gamma, relative to the display peak.
+    Hlg,
+}
+
+/// Colour primaries of an HDR image (D65 white point).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorPrimaries {
+    /// ITU-R BT.709, the same primaries as sRGB.
+    Bt709,
+    /// Display P3.
+    DisplayP3,
+    /// ITU-R BT.2020 / BT.2100.
+    Bt2020,
+}
+
+/// CTA-861.3 content light level, in nits. Zero means unknown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ContentLightLevel {
+    /// Maximum content light level of any pixel (MaxCLL).
+    pub max_cll: u16,
+    /// Maximum frame-average light level (MaxFALL).
+    pub max_fall: u16,
+}
+
+/// How the pixels of an HDR image are encoded.
+///
+/// Unlike EXIF and XMP this describes the pixels themselves, so it is kept
+/// under every [`MetadataPolicy`](crate::MetadataPolicy).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HdrMetadata {
+    pub transfer: TransferFunction,
+    pub primaries: ColorPrimaries,
+    pub content_light_level: Option<ContentLightLevel>,
+}
+
+/// Operator used to compress HDR highlights into SDR range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneMapOperator {
+    /// Hard-clip everything brighter than SDR white.
+    Clip,
+    /// Extended Reinhard on luminance, reaching white at the content peak.
+    #[default]
+    Reinhard,
+    /// Hable's filmic curve, per channel.
+    Hable,
+}
+
+/// Reference white of SDR content in an HDR signal, in nits (ITU-R BT.2408).
+const SDR_WHITE_NITS: f32 = 203.0;
+
+/// Peak assumed when the content light level is unknown, in nits.
+const DEFAULT_PEAK_NITS: f32 = 1000.0;
+
+impl TransferFunction {
+    /// The ITU-T H.273 `transfer_characteristics` code point.
+    pub fn cicp(self) -> u16 {
+        match self {
+            Self::Pq => 16,
+            Self::Hlg => 18,
+        }
+    }
+
+    /// Convert a signal value in 0..=1 to display luminance in nits.
+    fn to_nits(self, signal: f32) -> f32 {
+        match self {
+            Self::Pq => pq_eotf(signal),
+            Self::Hlg => hlg_inverse_oetf(signal) * DEFAULT_PEAK_NITS,
+        }
+    }
+}
+
+impl ColorPrimaries {
+    /// The ITU-T H.273 `colour_primaries` code point.
+    pub fn cicp(self) -> u16 {
+        match self {
+            Self::Bt709 => 1,
+            Self::Bt2020 => 9,
+            Self::DisplayP3 => 12,
+        }
+    }
+
+    /// Linear RGB to BT.709 linear RGB.
+    fn to_bt709(self) -> [[f32; 3]; 3] {
+        match self {
+            Self::Bt709 => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            Self::DisplayP3 => [
+                [1.2249, -0.2247, 0.0],
+                [-0.0420, 1.0419, 0.0],
+                [-0.0197, -0.0786, 1.0979],
+            ],
+            Self::Bt2020 => [
+                [1.6605, -0.5876, -0.0728],
+                [-0.1246, 1.1329, -0.0083],
+                [-0.0182, -0.1006, 1.1187],
+            ],
+        }
+    }
+
+    /// Luminance weights of linear RGB in these primaries.
+    fn luma(self) -> [f32; 3] {
+        match self {
+            Self::Bt709 => [0.2126, 0.7152, 0.0722],
+            Self::DisplayP3 => [0.2290, 0.6917, 0.0793],
+            Self::Bt2020 => [0.2627, 0.6780, 0.0593],
+        }
+    }
+}
+
+impl HdrMetadata {
+    /// Build from ITU-T H.273 code points, or `None` if the transfer
+    /// function is not an HDR one.
+    pub fn from_cicp(primaries: u16, transfer: u16) -> Option<Self> {
+        let transfer = match transfer {
+            16 => TransferFunction::Pq,
+            18 => TransferFunction::Hlg,
+            _ => return None,
+        };
+        let primaries = match primaries {
+            1 => ColorPrimaries::Bt709,
+            9 => ColorPrimaries::Bt2020,
+            12 => ColorPrimaries::DisplayP3,
+            _ => return None,
+        };
+        Some(Self {
+            transfer,
+            primaries,
+            content_light_level: None,
+        })
+    }
+
+    /// Brightest luminance in the content, in nits: MaxCLL when known,
+    /// otherwise 1000.
+    pub fn peak_nits(&self) -> f32 {
+        match self.content_light_level {
+            Some(level) if level.max_cll > 0 => level.max_cll as f32,
+            _ => DEFAULT_PEAK_NITS,
+        }
+    }
+}
+
+/// Tone-map an HDR image to SDR sRGB with `operator`.
+///
+/// The result keeps the input's pixel format and has no HDR metadata or ICC
+/// profile. Images without HDR metadata are returned unchanged.
+pub fn tone_map(image: &ImageData, operator: ToneMapOperator) -> ImageData {
+    let mut metadata = image.metadata.clone();
+    let Some(hdr) = metadata.hdr.take() else {
+        return image.clone();
+    };
+    metadata.icc = None;
+
+    let to_bt709 = hdr.primaries.to_bt709();
+    let white = (hdr.peak_nits() / SDR_WHITE_NITS).max(1.0);
+
+    let mut samples = image.as_pixel_format(PixelFormat::Rgba32F).rgba32f_samples();
+    for px in samples.chunks_exact_mut(4) {
+        let mut linear = [0f32; 3];
+        for (c, value) in linear.iter_mut().enumerate() {
+            *value = hdr.transfer.to_nits(px[c].clamp(0.0, 1.0)) / SDR_WHITE_NITS;
+        }
+        if hdr.transfer == TransferFunction::Hlg {
+            linear = hlg_ootf(linear, hdr.primaries.luma());
+        }
+
+        let mut rgb = [0f32; 3];
+        for (row, out) in to_bt709.iter().zip(rgb.iter_mut()) {
+            *out = (row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2]).max(0.0);
+        }
+
+        let mapped = apply_operator(operator, rgb, white);
+        for c in 0..3 {
+            px[c] = srgb_oetf(mapped[c].clamp(0.0, 1.0));
+        }
+    }
+
+    ImageData::from_rgba32f(image.width, image.height, samples)
+        .to_pixel_format(image.pixel_format)
+        .with_metadata(metadata)
+}
+
+fn apply_operator(operator: ToneMapOperator, rgb: [f32; 3], white: f32) -> [f32; 3] {
+    match operator {
+        ToneMapOperator::Clip => rgb,
+        ToneMapOperator::Reinhard => {
+            let [r, g, b] = ColorPrimaries::Bt709.luma();
+            let luma = r * rgb[0] + g * rgb[1] + b * rgb[2];
+            if luma <= 0.0 {
+                return rgb;
+            }
+            let mapped = luma * (1.0 + luma / (white * white)) / (1.0 + luma);
+            rgb.map(|c| c * mapped / luma)
+        }
+        ToneMapOperator::Hable => {
+            let scale = hable(white);
+            rgb.map(|c| hable(c) / scale)
+        }
+    }
+}
+
+/// Hable's "Uncharted 2" filmic curve.
+fn hable(x: f32) -> f32 {
+    const A: f32 = 0.15;
+    const B: f32 = 0.50;
+    const C: f32 = 0.10;
+    const D: f32 = 0.20;
+    const E: f32 = 0.02;
+    const F: f32 = 0.30;
+    (x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F) - E / F
+}
+
+/// SMPTE ST 2084 EOTF: signal in 0..=1 to nits.
+fn pq_eotf(signal: f32) -> f32 {
+    const M1: f32 = 2610.0 / 16384.0;
+    const M2: f32 = 2523.0 / 4096.0 * 128.0;
+    const C1: f32 = 3424.0 / 4096.0;
+    const C2: f32 = 2413.0 / 4096.0 * 32.0;
+    const C3: f32 = 2392.0 / 4096.0 * 32.0;
+
+    let p = signal.powf(1.0 / M2);
+    let y = ((p - C1).max(0.0) / (C2 - C3 * p)).powf(1.0 / M1);
+    y * 10000.0
+}
+
+/// HLG inverse OETF: signal in 0..=1 to relative scene light in 0..=1.
+fn hlg_inverse_oetf(signal: f32) -> f32 {
+    const A: f32 = 0.178_832_77;
+    const B: f32 = 0.284_668_92;
+    const C: f32 = 0.559_910_7;
+
+    if signal <= 0.5 {
+        signal * signal / 3.0
+    } else {
+        (((signal - C) / A).exp() + B) / 12.0
+    }
+}
+
+/// HLG OOTF for a 1000-nit display (system gamma 1.2).
+fn hlg_ootf(rgb: [f32; 3], luma: [f32; 3]) -> [f32; 3] {
+    let scene = (luma[0] * rgb[0] + luma[1] * rgb[1] + luma[2] * rgb[2]) * SDR_WHITE_NITS
+        / DEFAULT_PEAK_NITS;
+    if scene <= 0.0 {
+        return rgb;
+    }
+    let gain = scene.powf(0.2);
+    rgb.map(|c| c * gain)
+}
+
+fn srgb_oetf(linear: f32) -> f32 {
+    if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::Metadata;
+
+    fn pq(samples: Vec<u16>, max_cll: u16) -> ImageData {
+        ImageData::from_rgba16(1, 1, samples).with_metadata(Metadata {
+            hdr: Some(HdrMetadata {
+                transfer: TransferFunction::Pq,
+                primaries: ColorPrimaries::Bt2020,
+                content_light_level: Some(ContentLightLevel {
+                    max_cll,
+                    max_fall: 0,
+                }),
+            }),
+            ..Default::default()
+        })
+    }
+
+    /// 16-bit PQ signal for `nits`.
+    fn pq_signal(nits: f32) -> u16 {
+        const M1: f32 = 2610.0 / 16384.0;
+        const M2: f32 = 2523.0 / 4096.0 * 128.0;
+        const C1: f32 = 3424.0 / 4096.0;
+        const C2: f32 = 2413.0 / 4096.0 * 32.0;
+        const C3: f32 = 2392.0 / 4096.0 * 32.0;
+        let y = (nits / 10000.0).powf(M1);
+        let signal = ((C1 + C2 * y) / (1.0 + C3 * y)).powf(M2);
+        (signal * 65535.0).round() as u16
+    }
+
+    #[test]
+    fn pq_eotf_matches_reference_points() {
+        assert!(pq_eotf(0.0) < 0.01);
+        assert!((pq_eotf(1.0) - 10000.0).abs() < 1.0);
+        // 0.58 is about 203 nits, the BT.2408 reference white.
+        assert!((pq_eotf(0.5806) - 203.0).abs() < 2.0);
+    }
+
+    #[test]
+    fn reference_white_maps_close_to_sdr_white() {
+        let white = pq_signal(SDR_WHITE_NITS);
+        let image = pq(vec![white, white, white, 65535], 1000);
+
+        let mapped = tone_map(&image, ToneMapOperator::Clip);
+        assert_eq!(mapped.metadata.hdr, None);
+        assert_eq!(mapped.pixel_format, PixelFormat::Rgba16);
+        let px = mapped.rgba16_samples();
+        assert!(px[..3].iter().all(|&c| c > 64000), "{px:?}");
+        assert_eq!(px[3], 65535);
+    }
+
+    #[test]
+    fn operators_compress_highlights_differently() {
+        let highlight = pq_signal(600.0);
+        let image = pq(vec![highlight, highlight, highlight, 65535], 1000);
+
+        let clip = tone_map(&image, ToneMapOperator::Clip).rgba16_samples();
+        let reinhard = tone_map(&image, ToneMapOperator::Reinhard).rgba16_samples();
+        let hable = tone_map(&image, ToneMapOperator::Hable).rgba16_samples();
+
+        // Clipping saturates the highlight; the curves keep detail below white.
+        assert_eq!(clip[0], 65535);
+        assert!(reinhard[0] < 65535);
+        assert!(hable[0] < 65535);
+        assert_ne!(reinhard, hable);
+    }
+
+    #[test]
+    fn sdr_image_is_unchanged() {
+        let image = ImageData::new(1, 1, vec![10, 20, 30, 255]);
+        assert_eq!(tone_map(&image, ToneMapOperator::Reinhard).data, image.data);
+    }
+
+    #[test]
+    fn cicp_code_points_roundtrip() {
+        for transfer in [TransferFunction::Pq, TransferFunction::Hlg] {
+            for primaries in [
+                ColorPrimaries::Bt709,
+                ColorPrimaries::DisplayP3,
+                ColorPrimaries::Bt2020,
+            ] {
+                let hdr = HdrMetadata::from_cicp(primaries.cicp(), transfer.cicp()).unwrap();
+                assert_eq!((hdr.transfer, hdr.primaries), (transfer, primaries));
+            }
+        }
+        assert_eq!(HdrMetadata::from_cicp(1, 13), None, "sRGB is not HDR");
+    }
+}
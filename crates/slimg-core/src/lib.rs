@@ -1,17 +1,45 @@
+pub mod animation;
+pub mod auto;
 pub mod codec;
+pub mod color;
 pub mod crop;
 pub mod error;
 pub mod extend;
 pub mod format;
+pub mod gravity;
+pub mod hdr;
+pub mod limits;
+pub mod metadata;
+pub mod metrics;
+pub mod orientation;
 pub mod pipeline;
+pub mod quantize;
 pub mod resize;
+pub mod target;
 
-pub use codec::{Codec, EncodeOptions, ImageData};
+pub use animation::{Animation, Disposal, Frame};
+pub use auto::{AutoFormat, Rejected, Rejection};
+pub use codec::{
+    AvifOptions, ChromaSubsampling, Codec, CodecOptions, EncodeOptions, GifOptions, ImageData,
+    ImageInfo, JpegOptions, JpegTransform, JxlOptions, PixelFormat, PngOptions, PngQuantizeOptions,
+    QoiColorspace, QoiOptions, WebPOptions,
+};
+pub use color::ColorPolicy;
 pub use crop::CropMode;
 pub use error::{Error, Result};
 pub use extend::{ExtendMode, FillColor};
 pub use format::Format;
+pub use gravity::Gravity;
+pub use hdr::{ColorPrimaries, ContentLightLevel, HdrMetadata, ToneMapOperator, TransferFunction};
+pub use limits::{DecodeLimits, Limit};
+pub use metadata::{Metadata, MetadataPolicy, MetadataSummary};
+pub use orientation::Orientation;
 pub use pipeline::{
-    PipelineOptions, PipelineResult, convert, decode, decode_file, optimize, output_path,
+    DecodeOptions, OptimizeOptions, PipelineOptions, PipelineResult, convert, convert_animation,
+    decode, decode_animation, decode_animation_file, decode_animation_file_with,
+    decode_animation_with, decode_file, decode_file_with, decode_with, never_larger, optimize,
+    optimize_with, output_path, probe, probe_file, transcode_jpeg,
 };
-pub use resize::ResizeMode;
+pub use quantize::{IndexedImage, QuantizeOptions};
+pub use resize::{LinearLight, ResizeFilter, ResizeMode, ResizeOptions};
+pub use target::{QualityTarget, SizeLimit};
@@ -0,0 +1,203 @@
+use std::fmt;
+
+use crate::codec::ImageInfo;
+use crate::error::{Error, Result};
+
+/// Caps on what a file may make the decoder allocate.
+///
+/// Limits are checked against the file's headers before any pixel buffer is
+/// allocated, so a small file declaring a huge canvas is rejected with
+/// [`Error::LimitExceeded`] instead of exhausting memory. `None` leaves a
+/// limit off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Widest image accepted, in pixels.
+    pub max_width: Option<u32>,
+    /// Tallest image accepted, in pixels.
+    pub max_height: Option<u32>,
+    /// Most pixels accepted in one frame.
+    pub max_pixels: Option<u64>,
+    /// Most bytes the decoded frames may take together.
+    pub max_alloc: Option<u64>,
+    /// Most frames accepted in an animation or multi-page file.
+    pub max_frames: Option<u32>,
+}
+
+impl Default for DecodeLimits {
+    /// Only the decoded size is capped, at 4 GiB: enough for a 32000x32000
+    /// RGBA image but not for the 60000x60000 canvas of a decompression
+    /// bomb.
+    fn default() -> Self {
+        Self {
+            max_alloc: Some(4 << 30),
+            ..Self::none()
+        }
+    }
+}
+
+/// Which [`DecodeLimits`] field a file exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Width,
+    Height,
+    Pixels,
+    Alloc,
+    Frames,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Width => "width",
+            Self::Height => "height",
+            Self::Pixels => "pixel count",
+            Self::Alloc => "decoded size in bytes",
+            Self::Frames => "frame count",
+        })
+    }
+}
+
+impl DecodeLimits {
+    /// No limits at all.
+    pub const fn none() -> Self {
+        Self {
+            max_width: None,
+            max_height: None,
+            max_pixels: None,
+            max_alloc: None,
+            max_frames: None,
+        }
+    }
+
+    /// Whether every limit is off, so there is nothing to check.
+    pub fn is_none(&self) -> bool {
+        *self == Self::none()
+    }
+
+    /// Fail with [`Error::LimitExceeded`] if the image described by `info`
+    /// is over any limit.
+    ///
+    /// The decoded size is estimated from the RGBA buffers slimg decodes
+    /// into: 4 bytes a pixel up to 8 bits per sample, 8 up to 16 and 16
+    /// for floats, for every frame.
+    pub fn check(&self, info: &ImageInfo) -> Result<()> {
+        let pixels = u64::from(info.width) * u64::from(info.height);
+        let bytes_per_pixel = match info.bit_depth {
+            0..=8 => 4,
+            9..=16 => 8,
+            _ => 16,
+        };
+        let alloc = pixels
+            .saturating_mul(bytes_per_pixel)
+            .saturating_mul(u64::from(info.frame_count.max(1)));
+
+        self.check_size(info.width, info.height)?;
+        self.check_frames(info.frame_count)?;
+        self.check_alloc(alloc)
+    }
+
+    /// Fail if one frame of `width`x`height` is over the width, height or
+    /// pixel count limit.
+    pub(crate) fn check_size(&self, width: u32, height: u32) -> Result<()> {
+        within(Limit::Width, width, self.max_width)?;
+        within(Limit::Height, height, self.max_height)?;
+        within(
+            Limit::Pixels,
+            u64::from(width) * u64::from(height),
+            self.max_pixels,
+        )
+    }
+
+    /// Fail if `count` frames are over the frame limit.
+    pub(crate) fn check_frames(&self, count: u32) -> Result<()> {
+        within(Limit::Frames, count, self.max_frames)
+    }
+
+    /// Fail if `bytes` of decoded pixels are over the allocation limit.
+    /// Decoders call this with the size of the buffer they are about to
+    /// allocate plus what earlier frames already hold.
+    pub(crate) fn check_alloc(&self, bytes: u64) -> Result<()> {
+        within(Limit::Alloc, bytes, self.max_alloc)
+    }
+}
+
+fn within<T: Into<u64>>(limit: Limit, actual: T, max: Option<T>) -> Result<()> {
+    let actual = actual.into();
+    match max.map(Into::into) {
+        Some(max) if actual > max => Err(Error::LimitExceeded { limit, actual, max }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::Format;
+    use crate::metadata::Metadata;
+
+    fn info(width: u32, height: u32, bit_depth: u8, frame_count: u32) -> ImageInfo {
+        let metadata = Metadata::default();
+        ImageInfo {
+            frame_count,
+            ..ImageInfo::still(Format::Png, (width, height), bit_depth, true, &metadata)
+        }
+    }
+
+    fn exceeded(limits: DecodeLimits, info: &ImageInfo) -> Option<Limit> {
+        match limits.check(info) {
+            Ok(()) => None,
+            Err(Error::LimitExceeded { limit, .. }) => Some(limit),
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+
+    #[test]
+    fn default_rejects_a_bomb_but_not_a_large_photo() {
+        let limits = DecodeLimits::default();
+        assert_eq!(
+            exceeded(limits, &info(60_000, 60_000, 8, 1)),
+            Some(Limit::Alloc)
+        );
+        assert_eq!(exceeded(limits, &info(12_000, 8_000, 16, 1)), None);
+        assert_eq!(
+            exceeded(DecodeLimits::none(), &info(60_000, 60_000, 8, 1)),
+            None
+        );
+    }
+
+    #[test]
+    fn each_limit_is_reported() {
+        let image = info(400, 300, 8, 10);
+        let mut limits = DecodeLimits {
+            max_width: Some(400),
+            max_height: Some(300),
+            max_pixels: Some(120_000),
+            max_alloc: Some(4_800_000),
+            max_frames: Some(10),
+        };
+        assert_eq!(exceeded(limits, &image), None);
+
+        // Tighten one at a time; earlier checks win.
+        limits.max_alloc = Some(4_799_999);
+        assert_eq!(exceeded(limits, &image), Some(Limit::Alloc));
+        limits.max_frames = Some(9);
+        assert_eq!(exceeded(limits, &image), Some(Limit::Frames));
+        limits.max_pixels = Some(119_999);
+        assert_eq!(exceeded(limits, &image), Some(Limit::Pixels));
+        limits.max_height = Some(299);
+        assert_eq!(exceeded(limits, &image), Some(Limit::Height));
+        limits.max_width = Some(399);
+        assert_eq!(exceeded(limits, &image), Some(Limit::Width));
+    }
+
+    #[test]
+    fn deep_samples_count_towards_the_allocation() {
+        let limits = DecodeLimits {
+            max_alloc: Some(1_000_000),
+            ..DecodeLimits::none()
+        };
+        assert_eq!(exceeded(limits, &info(400, 300, 8, 1)), None);
+        assert_eq!(exceeded(limits, &info(400, 300, 16, 1)), None);
+        assert_eq!(exceeded(limits, &info(400, 300, 32, 1)), Some(Limit::Alloc));
+    }
+}
@@ -0,0 +1,425 @@
+use crate::hdr::HdrMetadata;
+use crate::orientation::Orientation;
+
+/// EXIF, XMP and ICC data carried alongside decoded pixels.
+///
+/// Codecs fill this in on decode and write back whatever the
+/// [`MetadataPolicy`] lets through on encode, as far as the target format
+/// can store it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// EXIF as a TIFF structure, starting at the `II`/`MM` byte-order mark
+    /// (no `Exif\0\0` prefix).
+    pub exif: Option<Vec<u8>>,
+    /// XMP packet (UTF-8 XML).
+    pub xmp: Option<Vec<u8>>,
+    /// ICC colour profile.
+    pub icc: Option<Vec<u8>>,
+    /// How the pixels must be rotated or mirrored to display upright, read
+    /// from the EXIF tag or the container (AVIF `irot`/`imir`, JXL header).
+    pub orientation: Orientation,
+    /// Transfer function, primaries and light levels of HDR pixels.
+    pub hdr: Option<HdrMetadata>,
+}
+
+/// Which metadata a file carries, without the data itself.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MetadataSummary {
+    /// Size of the EXIF block in bytes, if there is one.
+    pub exif_bytes: Option<usize>,
+    /// Size of the XMP packet in bytes, if there is one.
+    pub xmp_bytes: Option<usize>,
+    /// Size of the ICC colour profile in bytes, if there is one.
+    pub icc_bytes: Option<usize>,
+    /// HDR signalling, if the pixels are HDR.
+    pub hdr: Option<HdrMetadata>,
+}
+
+/// Which metadata to carry over when encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MetadataPolicy {
+    /// Keep EXIF, XMP and ICC exactly as decoded.
+    Keep,
+    /// Drop all metadata. HDR signalling describes the pixels and is kept.
+    Strip,
+    /// Keep the ICC profile and the EXIF copyright, artist and orientation
+    /// tags. Everything else, including GPS and XMP, is dropped.
+    #[default]
+    Safe,
+}
+
+/// IFD0 tags kept by [`MetadataPolicy::Safe`]: Orientation, Artist, Copyright.
+const SAFE_EXIF_TAGS: [u16; 3] = [ORIENTATION_TAG, 0x013B, 0x8298];
+
+impl Metadata {
+    /// True when no metadata is present.
+    pub fn is_empty(&self) -> bool {
+        self.exif.is_none()
+            && self.xmp.is_none()
+            && self.icc.is_none()
+            && self.orientation == Orientation::Normal
+            && self.hdr.is_none()
+    }
+
+    /// Which blocks are present and how large they are.
+    pub fn summary(&self) -> MetadataSummary {
+        MetadataSummary {
+            exif_bytes: self.exif.as_ref().map(Vec::len),
+            xmp_bytes: self.xmp.as_ref().map(Vec::len),
+            icc_bytes: self.icc.as_ref().map(Vec::len),
+            hdr: self.hdr,
+        }
+    }
+
+    /// The subset of this metadata allowed by `policy`.
+    pub fn filter(&self, policy: MetadataPolicy) -> Metadata {
+        match policy {
+            MetadataPolicy::Keep => self.clone(),
+            MetadataPolicy::Strip => Metadata {
+                hdr: self.hdr,
+                ..Default::default()
+            },
+            MetadataPolicy::Safe => Metadata {
+                exif: self
+                    .exif
+                    .as_deref()
+                    .and_then(|exif| filter_exif(exif, &SAFE_EXIF_TAGS)),
+                xmp: None,
+                icc: self.icc.clone(),
+                orientation: self.orientation,
+                hdr: self.hdr,
+            },
+        }
+    }
+
+    /// Read metadata from an `image` crate decoder.
+    ///
+    /// Broken metadata is dropped rather than failing the decode.
+    pub(crate) fn from_decoder(decoder: &mut impl image::ImageDecoder) -> Metadata {
+        let exif = decoder
+            .exif_metadata()
+            .ok()
+            .flatten()
+            .map(|exif| strip_exif_header(&exif).to_vec());
+        Metadata {
+            orientation: exif
+                .as_deref()
+                .and_then(exif_orientation)
+                .unwrap_or_default(),
+            exif,
+            xmp: decoder.xmp_metadata().ok().flatten(),
+            icc: decoder.icc_profile().ok().flatten(),
+            hdr: None,
+        }
+    }
+}
+
+/// EXIF Orientation tag ID.
+const ORIENTATION_TAG: u16 = 0x0112;
+
+/// The Orientation tag of a TIFF structure, if present and valid.
+pub(crate) fn exif_orientation(exif: &[u8]) -> Option<Orientation> {
+    let (order, entries) = read_ifd0(exif)?;
+    let entry = entries
+        .iter()
+        .find(|e| e.tag == ORIENTATION_TAG && e.kind == 3)?;
+    Orientation::from_exif(order.u16(entry.value))
+}
+
+/// Rewrite the Orientation tag of a TIFF structure to 1 (upright), in place.
+pub(crate) fn reset_exif_orientation(exif: &mut [u8]) {
+    let Some((order, _)) = read_ifd0(exif) else {
+        return;
+    };
+    let offset = order.u32(&exif[4..8]) as usize;
+    let count = order.u16(&exif[offset..offset + 2]) as usize;
+    for i in 0..count {
+        let start = offset + 2 + i * 12;
+        let entry = &mut exif[start..start + 12];
+        if order.u16(&entry[0..2]) == ORIENTATION_TAG && order.u16(&entry[2..4]) == 3 {
+            // A single SHORT is stored inline at the start of the value field.
+            let mut value = Vec::with_capacity(2);
+            order.put_u16(&mut value, Orientation::Normal.to_exif());
+            entry[8..10].copy_from_slice(&value);
+        }
+    }
+}
+
+/// Remove the `Exif\0\0` prefix some containers put in front of the TIFF data.
+pub(crate) fn strip_exif_header(exif: &[u8]) -> &[u8] {
+    exif.strip_prefix(b"Exif\0\0").unwrap_or(exif)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    fn u16(self, b: &[u8]) -> u16 {
+        let b = [b[0], b[1]];
+        match self {
+            Self::Little => u16::from_le_bytes(b),
+            Self::Big => u16::from_be_bytes(b),
+        }
+    }
+
+    fn u32(self, b: &[u8]) -> u32 {
+        let b = [b[0], b[1], b[2], b[3]];
+        match self {
+            Self::Little => u32::from_le_bytes(b),
+            Self::Big => u32::from_be_bytes(b),
+        }
+    }
+
+    fn put_u16(self, out: &mut Vec<u8>, v: u16) {
+        match self {
+            Self::Little => out.extend_from_slice(&v.to_le_bytes()),
+            Self::Big => out.extend_from_slice(&v.to_be_bytes()),
+        }
+    }
+
+    fn put_u32(self, out: &mut Vec<u8>, v: u32) {
+        match self {
+            Self::Little => out.extend_from_slice(&v.to_le_bytes()),
+            Self::Big => out.extend_from_slice(&v.to_be_bytes()),
+        }
+    }
+}
+
+/// One IFD entry with its value bytes, still in the file's byte order.
+#[derive(Debug, Clone, Copy)]
+struct IfdEntry<'a> {
+    tag: u16,
+    kind: u16,
+    count: u32,
+    value: &'a [u8],
+}
+
+/// Size in bytes of one value of the given TIFF field type.
+fn type_size(kind: u16) -> Option<usize> {
+    match kind {
+        1 | 2 | 6 | 7 => Some(1),
+        3 | 8 => Some(2),
+        4 | 9 | 11 => Some(4),
+        5 | 10 | 12 => Some(8),
+        _ => None,
+    }
+}
+
+/// Parse the first IFD of a TIFF structure. Entries of unknown type are skipped.
+fn read_ifd0(exif: &[u8]) -> Option<(ByteOrder, Vec<IfdEntry<'_>>)> {
+    let order = match exif.get(..4)? {
+        b"II*\0" => ByteOrder::Little,
+        b"MM\0*" => ByteOrder::Big,
+        _ => return None,
+    };
+    let offset = order.u32(exif.get(4..8)?) as usize;
+    let count = order.u16(exif.get(offset..offset.checked_add(2)?)?) as usize;
+
+    let mut entries = Vec::with_capacity(count);
+    for i in 0..count {
+        let start = offset + 2 + i * 12;
+        let raw = exif.get(start..start + 12)?;
+        let tag = order.u16(&raw[0..2]);
+        let kind = order.u16(&raw[2..4]);
+        let n = order.u32(&raw[4..8]);
+        let Some(size) = type_size(kind).and_then(|s| s.checked_mul(n as usize)) else {
+            continue;
+        };
+        let value = if size <= 4 {
+            &raw[8..8 + size]
+        } else {
+            let at = order.u32(&raw[8..12]) as usize;
+            match exif.get(at..at.saturating_add(size)) {
+                Some(value) => value,
+                None => continue,
+            }
+        };
+        entries.push(IfdEntry {
+            tag,
+            kind,
+            count: n,
+            value,
+        });
+    }
+
+    Some((order, entries))
+}
+
+/// Serialize a single-IFD TIFF structure.
+fn write_ifd0(order: ByteOrder, entries: &[IfdEntry<'_>]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(match order {
+        ByteOrder::Little => b"II*\0",
+        ByteOrder::Big => b"MM\0*",
+    });
+    order.put_u32(&mut out, 8);
+    order.put_u16(&mut out, entries.len() as u16);
+
+    let data_start = 8 + 2 + entries.len() * 12 + 4;
+    let mut data = Vec::new();
+    for entry in entries {
+        order.put_u16(&mut out, entry.tag);
+        order.put_u16(&mut out, entry.kind);
+        order.put_u32(&mut out, entry.count);
+        if entry.value.len() <= 4 {
+            let mut inline = [0u8; 4];
+            inline[..entry.value.len()].copy_from_slice(entry.value);
+            out.extend_from_slice(&inline);
+        } else {
+            order.put_u32(&mut out, (data_start + data.len()) as u32);
+            data.extend_from_slice(entry.value);
+            // Values must start on a word boundary.
+            if data.len() % 2 == 1 {
+                data.push(0);
+            }
+        }
+    }
+    order.put_u32(&mut out, 0); // no next IFD
+
+    out.extend_from_slice(&data);
+    out
+}
+
+/// Rebuild `exif` with only the listed IFD0 tags. Returns `None` when none
+/// of them are present or the data is not valid TIFF.
+fn filter_exif(exif: &[u8], keep: &[u16]) -> Option<Vec<u8>> {
+    let (order, entries) = read_ifd0(exif)?;
+    let mut kept: Vec<IfdEntry<'_>> = entries
+        .into_iter()
+        .filter(|e| keep.contains(&e.tag))
+        .collect();
+    if kept.is_empty() {
+        return None;
+    }
+    kept.sort_by_key(|e| e.tag);
+    Some(write_ifd0(order, &kept))
+}
+
+/// Test fixture: little-endian EXIF with Orientation 6, Copyright and a
+/// GPS IFD pointer.
+#[cfg(test)]
+pub(crate) fn sample_exif() -> Vec<u8> {
+    let copyright = b"(c) slimg\0";
+    let entries = [
+        IfdEntry {
+            tag: 0x0112,
+            kind: 3,
+            count: 1,
+            value: &[6, 0],
+        },
+        IfdEntry {
+            tag: 0x8298,
+            kind: 2,
+            count: copyright.len() as u32,
+            value: copyright,
+        },
+        IfdEntry {
+            tag: 0x8825,
+            kind: 4,
+            count: 1,
+            value: &[0x40, 0, 0, 0],
+        },
+    ];
+    write_ifd0(ByteOrder::Little, &entries)
+}
+
+/// Test fixture: [`sample_exif`] with XMP and a dummy ICC profile, as a
+/// decoder reports it.
+#[cfg(test)]
+pub(crate) fn sample_metadata() -> Metadata {
+    Metadata {
+        exif: Some(sample_exif()),
+        xmp: Some(b"<x:xmpmeta/>".to_vec()),
+        icc: Some(vec![1, 2, 3]),
+        orientation: Orientation::Rotate90,
+        hdr: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keep_returns_everything() {
+        let meta = sample_metadata();
+        assert_eq!(meta.filter(MetadataPolicy::Keep), meta);
+    }
+
+    #[test]
+    fn strip_returns_nothing() {
+        assert!(sample_metadata().filter(MetadataPolicy::Strip).is_empty());
+    }
+
+    #[test]
+    fn hdr_survives_every_policy() {
+        let meta = Metadata {
+            hdr: HdrMetadata::from_cicp(9, 18),
+            ..sample_metadata()
+        };
+        for policy in [MetadataPolicy::Keep, MetadataPolicy::Strip, MetadataPolicy::Safe] {
+            assert_eq!(meta.filter(policy).hdr, meta.hdr, "{policy:?}");
+        }
+    }
+
+    #[test]
+    fn safe_drops_gps_and_xmp() {
+        let safe = sample_metadata().filter(MetadataPolicy::Safe);
+
+        assert_eq!(safe.icc, Some(vec![1, 2, 3]));
+        assert_eq!(safe.xmp, None);
+
+        let exif = safe.exif.expect("copyright should be kept");
+        let (_, entries) = read_ifd0(&exif).expect("valid tiff");
+        let tags: Vec<u16> = entries.iter().map(|e| e.tag).collect();
+        assert_eq!(tags, vec![0x0112, 0x8298]);
+        assert_eq!(entries[1].value, b"(c) slimg\0");
+    }
+
+    #[test]
+    fn safe_without_kept_tags_drops_exif() {
+        let gps_only = write_ifd0(
+            ByteOrder::Big,
+            &[IfdEntry {
+                tag: 0x8825,
+                kind: 4,
+                count: 1,
+                value: &[0, 0, 0, 8],
+            }],
+        );
+        let meta = Metadata {
+            exif: Some(gps_only),
+            ..Default::default()
+        };
+        assert_eq!(meta.filter(MetadataPolicy::Safe).exif, None);
+    }
+
+    #[test]
+    fn invalid_exif_is_dropped_by_safe() {
+        let meta = Metadata {
+            exif: Some(b"garbage".to_vec()),
+            ..Default::default()
+        };
+        assert_eq!(meta.filter(MetadataPolicy::Safe).exif, None);
+    }
+
+    #[test]
+    fn orientation_is_read_and_reset() {
+        let mut exif = sample_exif();
+        assert_eq!(exif_orientation(&exif), Some(Orientation::Rotate90));
+
+        reset_exif_orientation(&mut exif);
+        assert_eq!(exif_orientation(&exif), Some(Orientation::Normal));
+        // The other tags are untouched.
+        assert_eq!(read_ifd0(&exif).unwrap().1.len(), 3);
+    }
+
+    #[test]
+    fn strip_exif_header_removes_prefix() {
+        assert_eq!(strip_exif_header(b"Exif\0\0II*\0"), b"II*\0");
+        assert_eq!(strip_exif_header(b"MM\0*"), b"MM\0*");
+    }
+}
@@ -0,0 +1,547 @@
+//! Scores for how far an encoded image has drifted from its source.
+//!
+//! Every function compares a `distorted` image against a `reference` of the
+//! same size and fails with [`Error::DimensionMismatch`] otherwise.
+
+use ssimulacra2::{LinearRgb, compute_frame_ssimulacra2};
+
+use crate::codec::{ImageData, PixelFormat};
+use crate::error::{Error, Result};
+
+/// Grey levels translucent pixels are blended onto before comparing. The
+/// worse of the two scores counts, so a difference hidden against one
+/// backdrop still shows against the other.
+const BACKDROPS: [f32; 2] = [0.0, 1.0];
+
+/// Smallest side SSIMULACRA2 accepts; smaller images are padded up to it.
+const MIN_SIDE: usize = 8;
+
+/// Weight of each scale in the multi-scale SSIM behind [`dssim`], finest
+/// first.
+const SCALE_WEIGHTS: [f64; 5] = [0.028, 0.197, 0.322, 0.298, 0.155];
+
+/// Weight of the L\*, a\* and b\* channels. Lightness carries most of what
+/// the eye notices.
+const CHANNEL_WEIGHTS: [f64; 3] = [0.6, 0.2, 0.2];
+
+/// Error, in 8-bit steps, that [`heatmap`] paints fully red. Larger errors
+/// shade on towards yellow.
+const HEATMAP_RED: u8 = 32;
+
+/// Every score [`compare`] computes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scores {
+    /// See [`psnr`].
+    pub psnr: f64,
+    /// See [`ssim`].
+    pub ssim: f64,
+    /// See [`dssim`].
+    pub dssim: f64,
+    /// See [`ssimulacra2`].
+    pub ssimulacra2: f64,
+    /// See [`max_error`].
+    pub max_error: u8,
+}
+
+/// Compute every metric in this module at once.
+pub fn compare(reference: &ImageData, distorted: &ImageData) -> Result<Scores> {
+    Ok(Scores {
+        psnr: psnr(reference, distorted)?,
+        ssim: ssim(reference, distorted)?,
+        dssim: dssim(reference, distorted)?,
+        ssimulacra2: ssimulacra2(reference, distorted)?,
+        max_error: max_error(reference, distorted)?,
+    })
+}
+
+/// Peak signal-to-noise ratio in dB over the 8-bit RGBA samples. Higher is
+/// better and identical images score infinity; above about 40 dB is hard to
+/// tell apart.
+pub fn psnr(reference: &ImageData, distorted: &ImageData) -> Result<f64> {
+    let (reference, distorted) = rgba8_pair(reference, distorted)?;
+    let squared: f64 = reference
+        .data
+        .iter()
+        .zip(&distorted.data)
+        .map(|(&a, &b)| (f64::from(a) - f64::from(b)).powi(2))
+        .sum();
+    let mse = squared / reference.data.len().max(1) as f64;
+    Ok(10.0 * (255.0 * 255.0 / mse).log10())
+}
+
+/// Largest difference, in 8-bit steps, between any channel of any pixel.
+/// Unlike the other scores this counts the colour of fully transparent
+/// pixels, so it catches every change to the stored samples.
+pub fn max_error(reference: &ImageData, distorted: &ImageData) -> Result<u8> {
+    let (reference, distorted) = rgba8_pair(reference, distorted)?;
+    Ok(reference
+        .data
+        .iter()
+        .zip(&distorted.data)
+        .map(|(&a, &b)| a.abs_diff(b))
+        .max()
+        .unwrap_or(0))
+}
+
+/// Structural similarity of the luma, over an 11-tap Gaussian window. 1
+/// means identical; lower is worse.
+pub fn ssim(reference: &ImageData, distorted: &ImageData) -> Result<f64> {
+    worst_over_backdrops(reference, distorted, f64::min, |a, b| {
+        plane_ssim(&a.to_luma(), &b.to_luma())
+    })
+}
+
+/// SSIMULACRA2 score of `distorted` against `reference`. 100 means identical;
+/// about 90 is visually lossless, 70 high quality and 50 medium quality.
+/// Heavily distorted images can score below zero.
+pub fn ssimulacra2(reference: &ImageData, distorted: &ImageData) -> Result<f64> {
+    worst_over_backdrops(reference, distorted, f64::min, |a, b| {
+        let (width, height) = (a.width, a.height);
+        let (Ok(a), Ok(b)) = (
+            LinearRgb::new(a.pixels, width, height),
+            LinearRgb::new(b.pixels, width, height),
+        ) else {
+            unreachable!("pixel counts match the dimensions");
+        };
+        compute_frame_ssimulacra2(a, b).expect("images are the same size and at least 8x8")
+    })
+}
+
+/// DSSIM of `distorted` against `reference`: `1 / SSIM - 1`, where SSIM is
+/// the multi-scale structural similarity of the L\*a\*b\* channels. 0 means
+/// identical and larger is worse; below about 0.001 is hard to see.
+pub fn dssim(reference: &ImageData, distorted: &ImageData) -> Result<f64> {
+    worst_over_backdrops(reference, distorted, f64::max, |a, b| {
+        let ssim = multi_scale_ssim(a.to_lab(), b.to_lab());
+        1.0 / ssim.max(f64::EPSILON) - 1.0
+    })
+}
+
+/// An opaque image showing where `distorted` differs from `reference`.
+/// Matching pixels show the reference as dim grey; differing pixels turn red
+/// as the largest channel error approaches [`HEATMAP_RED`], then yellow as
+/// it grows further.
+pub fn heatmap(reference: &ImageData, distorted: &ImageData) -> Result<ImageData> {
+    let (reference, distorted) = rgba8_pair(reference, distorted)?;
+    let data = reference
+        .data
+        .chunks_exact(4)
+        .zip(distorted.data.chunks_exact(4))
+        .flat_map(|(a, b)| {
+            let error = a
+                .iter()
+                .zip(b)
+                .map(|(&a, &b)| a.abs_diff(b))
+                .max()
+                .unwrap_or(0);
+            let luma =
+                (0.2126 * f32::from(a[0]) + 0.7152 * f32::from(a[1]) + 0.0722 * f32::from(a[2]))
+                    * f32::from(a[3])
+                    / 255.0;
+            let grey = luma / 3.0;
+            let red = (f32::from(error) / f32::from(HEATMAP_RED)).min(1.0);
+            let yellow =
+                f32::from(error.saturating_sub(HEATMAP_RED)) / f32::from(u8::MAX - HEATMAP_RED);
+            [
+                grey + (255.0 - grey) * red,
+                grey * (1.0 - red) + 255.0 * yellow,
+                grey * (1.0 - red),
+            ]
+            .map(|c| c.round() as u8)
+            .into_iter()
+            .chain([u8::MAX])
+        })
+        .collect();
+    Ok(ImageData::new(reference.width, reference.height, data))
+}
+
+/// Fail unless the two images have the same dimensions.
+fn check_dimensions(reference: &ImageData, distorted: &ImageData) -> Result<()> {
+    if (reference.width, reference.height) != (distorted.width, distorted.height) {
+        return Err(Error::DimensionMismatch {
+            expected_width: reference.width,
+            expected_height: reference.height,
+            actual_width: distorted.width,
+            actual_height: distorted.height,
+        });
+    }
+    Ok(())
+}
+
+/// Both images as 8-bit RGBA, once their dimensions are known to match.
+fn rgba8_pair(reference: &ImageData, distorted: &ImageData) -> Result<(ImageData, ImageData)> {
+    check_dimensions(reference, distorted)?;
+    Ok((
+        reference.to_pixel_format(PixelFormat::Rgba8),
+        distorted.to_pixel_format(PixelFormat::Rgba8),
+    ))
+}
+
+/// Score the two images flattened onto each backdrop and keep the score
+/// `worse` picks. Opaque images look the same on any backdrop, so they are
+/// scored once.
+fn worst_over_backdrops(
+    reference: &ImageData,
+    distorted: &ImageData,
+    worse: fn(f64, f64) -> f64,
+    score: impl Fn(LinearImage, LinearImage) -> f64,
+) -> Result<f64> {
+    check_dimensions(reference, distorted)?;
+    let reference = Rgba::new(reference);
+    let distorted = Rgba::new(distorted);
+    let backdrops = if reference.is_opaque() && distorted.is_opaque() {
+        &BACKDROPS[..1]
+    } else {
+        &BACKDROPS[..]
+    };
+    Ok(backdrops
+        .iter()
+        .map(|&backdrop| score(reference.flatten(backdrop), distorted.flatten(backdrop)))
+        .reduce(worse)
+        .unwrap_or_default())
+}
+
+/// Float RGBA samples, still sRGB-encoded.
+struct Rgba {
+    samples: Vec<f32>,
+    width: usize,
+    height: usize,
+}
+
+impl Rgba {
+    fn new(image: &ImageData) -> Self {
+        Self {
+            samples: image
+                .as_pixel_format(PixelFormat::Rgba32F)
+                .rgba32f_samples(),
+            width: image.width as usize,
+            height: image.height as usize,
+        }
+    }
+
+    fn is_opaque(&self) -> bool {
+        self.samples.chunks_exact(4).all(|px| px[3] >= 1.0)
+    }
+
+    /// Linear light blended onto a `backdrop` grey, with the last row and
+    /// column repeated to pad each side to at least [`MIN_SIDE`].
+    fn flatten(&self, backdrop: f32) -> LinearImage {
+        let width = self.width.max(MIN_SIDE);
+        let height = self.height.max(MIN_SIDE);
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let row = y.min(self.height.saturating_sub(1)) * self.width;
+            for x in 0..width {
+                let i = (row + x.min(self.width.saturating_sub(1))) * 4;
+                let px = self.samples.get(i..i + 4).unwrap_or(&[0.0; 4]);
+                let alpha = px[3].clamp(0.0, 1.0);
+                pixels.push(
+                    [px[0], px[1], px[2]]
+                        .map(|c| srgb_eotf(c.clamp(0.0, 1.0)) * alpha + backdrop * (1.0 - alpha)),
+                );
+            }
+        }
+        LinearImage {
+            pixels,
+            width,
+            height,
+        }
+    }
+}
+
+/// Opaque linear-light sRGB pixels.
+struct LinearImage {
+    pixels: Vec<[f32; 3]>,
+    width: usize,
+    height: usize,
+}
+
+impl LinearImage {
+    /// Gamma-encoded luma of the pixels.
+    fn to_luma(&self) -> Plane {
+        Plane {
+            data: self
+                .pixels
+                .iter()
+                .map(|&[r, g, b]| srgb_oetf(0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b))
+                .collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// CIE L\*a\*b\* planes (D65), each scaled by 1/100.
+    fn to_lab(&self) -> [Plane; 3] {
+        const WHITE: [f32; 3] = [0.950_47, 1.0, 1.088_83];
+        let f = |t: f32| {
+            if t > 0.008_856 {
+                t.cbrt()
+            } else {
+                7.787 * t + 16.0 / 116.0
+            }
+        };
+
+        let mut planes = [(); 3].map(|_| Plane::new(self.width, self.height));
+        for (i, [r, g, b]) in self.pixels.iter().copied().enumerate() {
+            let x = 0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b;
+            let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b;
+            let z = 0.019_333_9 * r + 0.119_192 * g + 0.950_304_1 * b;
+            let [fx, fy, fz] = [x / WHITE[0], y / WHITE[1], z / WHITE[2]].map(f);
+            planes[0].data[i] = 1.16 * fy - 0.16;
+            planes[1].data[i] = 5.0 * (fx - fy);
+            planes[2].data[i] = 2.0 * (fy - fz);
+        }
+        planes
+    }
+}
+
+/// Mean SSIM of the channels over [`SCALE_WEIGHTS`], halving the size at
+/// each step while both sides stay at least [`MIN_SIDE`].
+fn multi_scale_ssim(mut a: [Plane; 3], mut b: [Plane; 3]) -> f64 {
+    let (mut total, mut weights) = (0.0, 0.0);
+    for (scale, weight) in SCALE_WEIGHTS.into_iter().enumerate() {
+        if scale > 0 {
+            if a[0].width / 2 < MIN_SIDE || a[0].height / 2 < MIN_SIDE {
+                break;
+            }
+            a = a.map(|plane| plane.downsample());
+            b = b.map(|plane| plane.downsample());
+        }
+        let score: f64 = CHANNEL_WEIGHTS
+            .iter()
+            .zip(a.iter().zip(&b))
+            .map(|(channel, (a, b))| channel * plane_ssim(a, b))
+            .sum();
+        total += weight * score;
+        weights += weight;
+    }
+    total / weights
+}
+
+/// Mean SSIM of two planes of samples in `0.0..=1.0`, over an 11-tap
+/// Gaussian window with a standard deviation of 1.5.
+fn plane_ssim(a: &Plane, b: &Plane) -> f64 {
+    const C1: f32 = 0.01 * 0.01;
+    const C2: f32 = 0.03 * 0.03;
+
+    let mu_a = a.blur();
+    let mu_b = b.blur();
+    let aa = a.zip_map(a, |x, y| x * y).blur();
+    let bb = b.zip_map(b, |x, y| x * y).blur();
+    let ab = a.zip_map(b, |x, y| x * y).blur();
+
+    let sum: f64 = (0..a.data.len())
+        .map(|i| {
+            let (ma, mb) = (mu_a.data[i], mu_b.data[i]);
+            let var_a = aa.data[i] - ma * ma;
+            let var_b = bb.data[i] - mb * mb;
+            let cov = ab.data[i] - ma * mb;
+            let ssim = ((2.0 * ma * mb + C1) * (2.0 * cov + C2))
+                / ((ma * ma + mb * mb + C1) * (var_a + var_b + C2));
+            ssim as f64
+        })
+        .sum();
+    sum / a.data.len() as f64
+}
+
+/// One channel of an image.
+#[derive(Clone)]
+struct Plane {
+    data: Vec<f32>,
+    width: usize,
+    height: usize,
+}
+
+impl Plane {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            data: vec![0.0; width * height],
+            width,
+            height,
+        }
+    }
+
+    fn zip_map(&self, other: &Plane, f: impl Fn(f32, f32) -> f32) -> Plane {
+        Plane {
+            data: self
+                .data
+                .iter()
+                .zip(&other.data)
+                .map(|(&a, &b)| f(a, b))
+                .collect(),
+            ..*self
+        }
+    }
+
+    /// Separable Gaussian blur, repeating the edge samples.
+    fn blur(&self) -> Plane {
+        const RADIUS: usize = 5;
+        let weights: [f32; 2 * RADIUS + 1] = std::array::from_fn(|i| {
+            let d = i as f32 - RADIUS as f32;
+            (-d * d / (2.0 * 1.5 * 1.5)).exp()
+        });
+        let sum: f32 = weights.iter().sum();
+        let kernel = weights.map(|w| w / sum);
+        let (w, h) = (self.width, self.height);
+        let tap =
+            |i: usize, offset: usize, len: usize| (i + offset).saturating_sub(RADIUS).min(len - 1);
+
+        let mut rows = Plane::new(w, h);
+        for y in 0..h {
+            for x in 0..w {
+                rows.data[y * w + x] = kernel
+                    .iter()
+                    .enumerate()
+                    .map(|(k, weight)| weight * self.data[y * w + tap(x, k, w)])
+                    .sum();
+            }
+        }
+        let mut out = Plane::new(w, h);
+        for y in 0..h {
+            for x in 0..w {
+                out.data[y * w + x] = kernel
+                    .iter()
+                    .enumerate()
+                    .map(|(k, weight)| weight * rows.data[tap(y, k, h) * w + x])
+                    .sum();
+            }
+        }
+        out
+    }
+
+    /// Halve both sides by averaging 2x2 blocks.
+    fn downsample(self) -> Plane {
+        let (w, h) = (self.width / 2, self.height / 2);
+        let mut out = Plane::new(w, h);
+        for y in 0..h {
+            for x in 0..w {
+                let at = |dx: usize, dy: usize| self.data[(2 * y + dy) * self.width + 2 * x + dx];
+                out.data[y * w + x] = (at(0, 0) + at(1, 0) + at(0, 1) + at(1, 1)) / 4.0;
+            }
+        }
+        out
+    }
+}
+
+fn srgb_eotf(encoded: f32) -> f32 {
+    if encoded <= 0.040_45 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn srgb_oetf(linear: f32) -> f32 {
+    if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: u32, height: u32) -> ImageData {
+        let data = (0..width * height)
+            .flat_map(|i| {
+                let (x, y) = (i % width, i / width);
+                [(x * 255 / width) as u8, (y * 255 / height) as u8, 128, 255]
+            })
+            .collect();
+        ImageData::new(width, height, data)
+    }
+
+    fn with_noise(image: &ImageData, amplitude: i32) -> ImageData {
+        let mut state = 0x9e37_79b9u32;
+        let data = image
+            .data
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| {
+                if i % 4 == 3 {
+                    return v;
+                }
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                let delta = (state % (2 * amplitude as u32 + 1)) as i32 - amplitude;
+                (v as i32 + delta).clamp(0, 255) as u8
+            })
+            .collect();
+        ImageData::new(image.width, image.height, data)
+    }
+
+    #[test]
+    fn identical_images_score_perfectly() {
+        let image = gradient(32, 24);
+        let scores = compare(&image, &image).unwrap();
+
+        assert!((scores.ssimulacra2 - 100.0).abs() < 1e-6);
+        assert!(scores.dssim.abs() < 1e-6);
+        assert!((scores.ssim - 1.0).abs() < 1e-6);
+        assert_eq!(scores.psnr, f64::INFINITY);
+        assert_eq!(scores.max_error, 0);
+    }
+
+    #[test]
+    fn scores_get_worse_with_more_distortion() {
+        let image = gradient(48, 48);
+        let light = compare(&image, &with_noise(&image, 4)).unwrap();
+        let heavy = compare(&image, &with_noise(&image, 40)).unwrap();
+
+        assert!(light.ssimulacra2 > heavy.ssimulacra2);
+        assert!(light.dssim < heavy.dssim);
+        assert!(light.dssim > 0.0);
+        assert!(light.ssim > heavy.ssim);
+        assert!(light.psnr > heavy.psnr);
+        assert_eq!(light.max_error, 4);
+        assert!(heavy.max_error > 4);
+    }
+
+    #[test]
+    fn tiny_images_are_padded() {
+        let image = gradient(3, 2);
+        let noisy = with_noise(&image, 30);
+
+        assert!(ssimulacra2(&image, &noisy).unwrap() < 100.0);
+        assert!(dssim(&image, &noisy).unwrap() > 0.0);
+    }
+
+    #[test]
+    fn colour_under_transparent_pixels_is_ignored() {
+        let clear = ImageData::new(16, 16, [255, 0, 0, 0].repeat(256));
+        let other = ImageData::new(16, 16, [0, 0, 255, 0].repeat(256));
+        let visible = ImageData::new(16, 16, [0, 0, 255, 128].repeat(256));
+
+        assert!((ssimulacra2(&clear, &other).unwrap() - 100.0).abs() < 1e-6);
+        assert!(ssimulacra2(&clear, &visible).unwrap() < 90.0);
+        assert_eq!(max_error(&clear, &other).unwrap(), 255);
+    }
+
+    #[test]
+    fn mismatched_dimensions_are_rejected() {
+        let result = compare(&gradient(8, 8), &gradient(8, 9));
+
+        assert!(matches!(
+            result,
+            Err(Error::DimensionMismatch {
+                expected_height: 8,
+                actual_height: 9,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn heatmap_marks_changed_pixels() {
+        let image = ImageData::new(2, 1, [0, 0, 0, 255].repeat(2));
+        let changed = ImageData::new(2, 1, vec![0, 0, 0, 255, 255, 0, 0, 255]);
+
+        let map = heatmap(&image, &changed).unwrap();
+
+        assert_eq!(&map.data[..4], &[0, 0, 0, 255]);
+        assert_eq!(&map.data[4..], &[255, 255, 0, 255]);
+    }
+}
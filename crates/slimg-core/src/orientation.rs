@@ -0,0 +1,245 @@
+use crate::codec::{ImageData, PixelFormat};
+use crate::metadata::reset_exif_orientation;
+
+/// How stored pixels must be transformed to display upright.
+///
+/// Variants follow the EXIF Orientation tag values 1 to 8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Orientation {
+    /// Stored upright (EXIF 1).
+    #[default]
+    Normal,
+    /// Mirrored left to right (EXIF 2).
+    FlipHorizontal,
+    /// Rotated by 180 degrees (EXIF 3).
+    Rotate180,
+    /// Mirrored top to bottom (EXIF 4).
+    FlipVertical,
+    /// Mirrored along the top-left to bottom-right diagonal (EXIF 5).
+    Transpose,
+    /// Needs a 90 degree clockwise rotation (EXIF 6).
+    Rotate90,
+    /// Mirrored along the top-right to bottom-left diagonal (EXIF 7).
+    Transverse,
+    /// Needs a 270 degree clockwise rotation (EXIF 8).
+    Rotate270,
+}
+
+impl Orientation {
+    /// Parse an EXIF Orientation tag value.
+    pub fn from_exif(value: u16) -> Option<Self> {
+        match value {
+            1 => Some(Self::Normal),
+            2 => Some(Self::FlipHorizontal),
+            3 => Some(Self::Rotate180),
+            4 => Some(Self::FlipVertical),
+            5 => Some(Self::Transpose),
+            6 => Some(Self::Rotate90),
+            7 => Some(Self::Transverse),
+            8 => Some(Self::Rotate270),
+            _ => None,
+        }
+    }
+
+    /// The EXIF Orientation tag value.
+    pub fn to_exif(self) -> u16 {
+        match self {
+            Self::Normal => 1,
+            Self::FlipHorizontal => 2,
+            Self::Rotate180 => 3,
+            Self::FlipVertical => 4,
+            Self::Transpose => 5,
+            Self::Rotate90 => 6,
+            Self::Transverse => 7,
+            Self::Rotate270 => 8,
+        }
+    }
+
+    /// Whether applying this orientation swaps width and height.
+    pub fn swaps_dimensions(self) -> bool {
+        self.quarter_turns() % 2 == 1
+    }
+
+    /// The orientation equivalent to applying `self` and then `next`.
+    pub(crate) fn then(self, next: Orientation) -> Orientation {
+        let turns = self.quarter_turns();
+        let (turns, flip) = if self.flips() {
+            // A mirror reverses the direction of any rotation after it.
+            (turns + 4 - next.quarter_turns(), !next.flips())
+        } else {
+            (turns + next.quarter_turns(), next.flips())
+        };
+        Self::from_parts(turns % 4, flip)
+    }
+
+    /// Every orientation is a clockwise rotation by this many quarter turns,
+    /// followed by a horizontal flip when [`flips`](Self::flips) is true.
+    fn quarter_turns(self) -> u32 {
+        match self {
+            Self::Normal | Self::FlipHorizontal => 0,
+            Self::Rotate90 | Self::Transpose => 1,
+            Self::Rotate180 | Self::FlipVertical => 2,
+            Self::Rotate270 | Self::Transverse => 3,
+        }
+    }
+
+    fn flips(self) -> bool {
+        matches!(
+            self,
+            Self::FlipHorizontal | Self::FlipVertical | Self::Transpose | Self::Transverse
+        )
+    }
+
+    fn from_parts(quarter_turns: u32, flip: bool) -> Orientation {
+        match (quarter_turns, flip) {
+            (0, false) => Self::Normal,
+            (0, true) => Self::FlipHorizontal,
+            (1, false) => Self::Rotate90,
+            (1, true) => Self::Transpose,
+            (2, false) => Self::Rotate180,
+            (2, true) => Self::FlipVertical,
+            (3, false) => Self::Rotate270,
+            _ => Self::Transverse,
+        }
+    }
+
+    /// Rotate and mirror pixels so they display upright.
+    fn transform(
+        self,
+        width: u32,
+        height: u32,
+        pixel_format: PixelFormat,
+        data: &[u8],
+    ) -> (u32, u32, Vec<u8>) {
+        if self == Self::Normal {
+            return (width, height, data.to_vec());
+        }
+
+        let bpp = pixel_format.bytes_per_pixel();
+        let (w, h) = (width as usize, height as usize);
+        let (out_w, out_h) = if self.swaps_dimensions() {
+            (h, w)
+        } else {
+            (w, h)
+        };
+        let mut out = vec![0u8; data.len()];
+
+        for y in 0..out_h {
+            for x in 0..out_w {
+                // Undo the flip, then the rotation, to find the source pixel.
+                let rx = if self.flips() { out_w - 1 - x } else { x };
+                let (sx, sy) = match self.quarter_turns() {
+                    0 => (rx, y),
+                    1 => (y, h - 1 - rx),
+                    2 => (w - 1 - rx, h - 1 - y),
+                    _ => (w - 1 - y, rx),
+                };
+                let src = (sy * w + sx) * bpp;
+                let dst = (y * out_w + x) * bpp;
+                out[dst..dst + bpp].copy_from_slice(&data[src..src + bpp]);
+            }
+        }
+
+        (out_w as u32, out_h as u32, out)
+    }
+}
+
+/// Apply the image's stored orientation to its pixels.
+///
+/// The result is upright: its metadata orientation is [`Orientation::Normal`]
+/// and any EXIF Orientation tag is rewritten to 1, so viewers do not rotate
+/// it a second time.
+pub fn auto_orient(image: &ImageData) -> ImageData {
+    let orientation = image.metadata.orientation;
+    let (width, height, data) =
+        orientation.transform(image.width, image.height, image.pixel_format, &image.data);
+
+    let mut metadata = image.metadata.clone();
+    metadata.orientation = Orientation::Normal;
+    if let Some(exif) = metadata.exif.as_mut() {
+        reset_exif_orientation(exif);
+    }
+
+    ImageData::from_raw(width, height, image.pixel_format, data).with_metadata(metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::Metadata;
+
+    const ALL: [Orientation; 8] = [
+        Orientation::Normal,
+        Orientation::FlipHorizontal,
+        Orientation::Rotate180,
+        Orientation::FlipVertical,
+        Orientation::Transpose,
+        Orientation::Rotate90,
+        Orientation::Transverse,
+        Orientation::Rotate270,
+    ];
+
+    /// 3x2 image whose red channel numbers the pixels row by row:
+    ///
+    /// ```text
+    /// 0 1 2
+    /// 3 4 5
+    /// ```
+    fn numbered(orientation: Orientation) -> ImageData {
+        let data = (0..6u8).flat_map(|i| [i, 0, 0, 255]).collect();
+        ImageData::new(3, 2, data).with_metadata(Metadata {
+            orientation,
+            ..Default::default()
+        })
+    }
+
+    fn red(image: &ImageData) -> Vec<u8> {
+        image.data.chunks_exact(4).map(|p| p[0]).collect()
+    }
+
+    #[test]
+    fn exif_values_roundtrip() {
+        for orientation in ALL {
+            assert_eq!(
+                Orientation::from_exif(orientation.to_exif()),
+                Some(orientation)
+            );
+        }
+        assert_eq!(Orientation::from_exif(0), None);
+        assert_eq!(Orientation::from_exif(9), None);
+    }
+
+    #[test]
+    fn all_orientations_produce_expected_pixels() {
+        let cases: [(Orientation, (u32, u32), [u8; 6]); 8] = [
+            (Orientation::Normal, (3, 2), [0, 1, 2, 3, 4, 5]),
+            (Orientation::FlipHorizontal, (3, 2), [2, 1, 0, 5, 4, 3]),
+            (Orientation::Rotate180, (3, 2), [5, 4, 3, 2, 1, 0]),
+            (Orientation::FlipVertical, (3, 2), [3, 4, 5, 0, 1, 2]),
+            (Orientation::Transpose, (2, 3), [0, 3, 1, 4, 2, 5]),
+            (Orientation::Rotate90, (2, 3), [3, 0, 4, 1, 5, 2]),
+            (Orientation::Transverse, (2, 3), [5, 2, 4, 1, 3, 0]),
+            (Orientation::Rotate270, (2, 3), [2, 5, 1, 4, 0, 3]),
+        ];
+
+        for (orientation, (w, h), expected) in cases {
+            let upright = auto_orient(&numbered(orientation));
+            assert_eq!((upright.width, upright.height), (w, h), "{orientation:?}");
+            assert_eq!(red(&upright), expected, "{orientation:?}");
+            assert_eq!(upright.metadata.orientation, Orientation::Normal);
+        }
+    }
+
+    #[test]
+    fn then_matches_applying_twice() {
+        for first in ALL {
+            for second in ALL {
+                let mut step = auto_orient(&numbered(first));
+                step.metadata.orientation = second;
+                let twice = auto_orient(&step);
+                let combined = auto_orient(&numbered(first.then(second)));
+                assert_eq!(red(&twice), red(&combined), "{first:?} then {second:?}");
+            }
+        }
+    }
+}
@@ -1,28 +1,125 @@
 use std::fs;
+use std::io::Cursor;
 use std::path::{Path, PathBuf};
+use std::{panic, thread};
 
-use crate::codec::{EncodeOptions, ImageData, get_codec};
+use image::{ImageFormat, ImageReader};
+
+use crate::animation::Animation;
+use crate::auto::{self, AutoFormat, Rejected};
+use crate::codec::jpeg::{self, JpegTransform};
+use crate::codec::{CodecOptions, EncodeOptions, ImageData, ImageInfo, get_codec, jxl};
+use crate::color::ColorPolicy;
 use crate::error::{Error, Result};
 use crate::extend::{self, ExtendMode, FillColor};
 use crate::format::Format;
+use crate::hdr::ToneMapOperator;
+use crate::limits::DecodeLimits;
+use crate::metadata::MetadataPolicy;
+use crate::metrics;
+use crate::orientation;
 use crate::crop::{self, CropMode};
-use crate::resize::{self, ResizeMode};
+use crate::resize::{self, ResizeMode, ResizeOptions};
+use crate::target::{self, QualityTarget, SizeLimit};
 
 /// Options for a conversion pipeline.
 #[derive(Debug, Clone)]
 pub struct PipelineOptions {
-    /// Target output format.
+    /// Target output format. Ignored when `auto_format` is set.
     pub format: Format,
     /// Encoding quality (0..=100).
     pub quality: u8,
+    /// Encode losslessly; fails for formats that cannot guarantee it.
+    pub lossless: bool,
     /// Optional resize to apply before encoding.
     pub resize: Option<ResizeMode>,
+    /// Filter and enlargement settings for `resize`.
+    pub resize_options: ResizeOptions,
     /// Optional crop to apply before encoding.
     pub crop: Option<CropMode>,
     /// Optional extend (padding) to apply after crop and before resize.
     pub extend: Option<ExtendMode>,
     /// Fill color for the extended region (defaults to opaque white).
     pub fill_color: Option<FillColor>,
+    /// Per-format encoder settings.
+    pub codec: CodecOptions,
+    /// Which source metadata to carry into the output.
+    pub metadata: MetadataPolicy,
+    /// Whether to keep the source colour profile or convert to sRGB.
+    pub color: ColorPolicy,
+    /// Tone-mapping operator for HDR sources written to SDR formats.
+    pub tone_map: ToneMapOperator,
+    /// Byte budget for the output. The quality is lowered from `quality`
+    /// until the output fits.
+    pub max_size: Option<SizeLimit>,
+    /// Perceptual quality to aim for. When set, `quality` is ignored and the
+    /// lowest quality that reaches the target is used instead.
+    pub quality_target: Option<QualityTarget>,
+    /// Encode to several formats at the same perceived quality and keep the
+    /// smallest, instead of encoding to `format`.
+    pub auto_format: Option<AutoFormat>,
+}
+
+impl PipelineOptions {
+    /// Options that encode to `format` at quality 80 with no transforms.
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            quality: 80,
+            lossless: false,
+            resize: None,
+            resize_options: ResizeOptions::default(),
+            crop: None,
+            extend: None,
+            fill_color: None,
+            codec: CodecOptions::default(),
+            metadata: MetadataPolicy::default(),
+            color: ColorPolicy::default(),
+            tone_map: ToneMapOperator::default(),
+            max_size: None,
+            quality_target: None,
+            auto_format: None,
+        }
+    }
+}
+
+/// Options for [`optimize_with`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OptimizeOptions {
+    /// How to re-encode the image.
+    pub encode: EncodeOptions,
+    /// Byte budget for the output. The quality is lowered from
+    /// `encode.quality` until the output fits.
+    pub max_size: Option<SizeLimit>,
+    /// Perceptual quality to aim for instead of `encode.quality`.
+    pub quality_target: Option<QualityTarget>,
+    /// Return the input unchanged, with
+    /// [`PipelineResult::kept_original`] set, when re-encoding would not
+    /// make it smaller and the input holds no metadata that
+    /// `encode.metadata` drops. See [`never_larger`].
+    pub never_larger: bool,
+    /// Caps checked against the input's headers before it is decoded.
+    pub limits: DecodeLimits,
+}
+
+/// Options for decoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// Rotate and mirror the pixels according to the EXIF or container
+    /// orientation. When false, pixels are returned as stored and the
+    /// orientation is left in [`Metadata::orientation`](crate::Metadata).
+    pub auto_orient: bool,
+    /// Caps checked against the file's headers before pixels are allocated.
+    pub limits: DecodeLimits,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            auto_orient: true,
+            limits: DecodeLimits::default(),
+        }
+    }
 }
 
 /// Result of a pipeline conversion.
@@ -36,6 +133,19 @@ pub struct PipelineResult {
     pub width: u32,
     /// Height of the output image in pixels.
     pub height: u32,
+    /// Quality the output was encoded at, which a size budget may have
+    /// lowered. `None` for lossless output.
+    pub quality: Option<u8>,
+    /// Score the output reached under the quality target's metric. `None`
+    /// without a target, or for lossless output.
+    pub score: Option<f64>,
+    /// With [`PipelineOptions::auto_format`], the candidate formats that
+    /// lost and why. Empty otherwise.
+    pub rejected: Vec<Rejected>,
+    /// The encode was no smaller than the input, so `data` holds the input
+    /// bytes unchanged. See [`OptimizeOptions::never_larger`] and
+    /// [`never_larger`].
+    pub kept_original: bool,
 }
 
 impl PipelineResult {
@@ -46,28 +156,263 @@ impl PipelineResult {
     }
 }
 
-/// Detect the format from magic bytes and decode the raw image data.
+/// Detect the format from magic bytes and decode the image upright.
 pub fn decode(data: &[u8]) -> Result<(ImageData, Format)> {
+    decode_with(data, &DecodeOptions::default())
+}
+
+/// Detect the format from magic bytes and decode with the given options.
+pub fn decode_with(data: &[u8], options: &DecodeOptions) -> Result<(ImageData, Format)> {
     let format = Format::from_magic_bytes(data)
         .ok_or_else(|| Error::UnknownFormat("unrecognised magic bytes".to_string()))?;
 
     let codec = get_codec(format);
-    let image = codec.decode(data)?;
+    let image = codec.decode_limited(data, &options.limits)?;
+    let image = if options.auto_orient {
+        orientation::auto_orient(&image)
+    } else {
+        image
+    };
     Ok((image, format))
 }
 
-/// Read a file from disk, detect its format, and decode it.
+/// Read a file from disk, detect its format, and decode it upright.
 pub fn decode_file(path: &Path) -> Result<(ImageData, Format)> {
+    decode_file_with(path, &DecodeOptions::default())
+}
+
+/// Read a file from disk, detect its format, and decode with the given options.
+pub fn decode_file_with(path: &Path, options: &DecodeOptions) -> Result<(ImageData, Format)> {
+    let data = fs::read(path)?;
+    decode_with(&data, options)
+}
+
+/// Detect the format from magic bytes and decode every frame upright.
+pub fn decode_animation(data: &[u8]) -> Result<(Animation, Format)> {
+    decode_animation_with(data, &DecodeOptions::default())
+}
+
+/// Detect the format from magic bytes and decode every frame with the given
+/// options.
+pub fn decode_animation_with(data: &[u8], options: &DecodeOptions) -> Result<(Animation, Format)> {
+    let format = Format::from_magic_bytes(data)
+        .ok_or_else(|| Error::UnknownFormat("unrecognised magic bytes".to_string()))?;
+
+    let codec = get_codec(format);
+    let animation = codec.decode_animation_limited(data, &options.limits)?;
+    let animation = if options.auto_orient {
+        animation.try_map_frames(|image| Ok(orientation::auto_orient(image)))?
+    } else {
+        animation
+    };
+    Ok((animation, format))
+}
+
+/// Read a file from disk, detect its format, and decode every frame upright.
+pub fn decode_animation_file(path: &Path) -> Result<(Animation, Format)> {
+    decode_animation_file_with(path, &DecodeOptions::default())
+}
+
+/// Read a file from disk, detect its format, and decode every frame with the
+/// given options.
+pub fn decode_animation_file_with(
+    path: &Path,
+    options: &DecodeOptions,
+) -> Result<(Animation, Format)> {
+    let data = fs::read(path)?;
+    decode_animation_with(&data, options)
+}
+
+/// Detect the format from magic bytes and read the image's headers without
+/// decoding its pixels.
+pub fn probe(data: &[u8]) -> Result<ImageInfo> {
+    let format = Format::from_magic_bytes(data)
+        .ok_or_else(|| Error::UnknownFormat("unrecognised magic bytes".to_string()))?;
+    get_codec(format).probe(data)
+}
+
+/// Read a file from disk, detect its format, and probe its headers.
+pub fn probe_file(path: &Path) -> Result<ImageInfo> {
     let data = fs::read(path)?;
-    decode(&data)
+    probe(&data)
 }
 
 /// Convert an image to the specified format, optionally resizing first.
 pub fn convert(image: &ImageData, options: &PipelineOptions) -> Result<PipelineResult> {
-    if !options.format.can_encode() {
-        return Err(Error::EncodingNotSupported(options.format));
+    check_target(options, false)?;
+
+    let animation = Animation::from_still(transform(image, options)?);
+    encode_prepared(&animation, options)
+}
+
+/// Convert every frame of an animation to the specified format.
+///
+/// Crop, extend and resize are applied to each frame; a smart crop takes
+/// the same region from all of them. Fails with
+/// [`Error::AnimationNotSupported`] when `animation` has several frames and
+/// the target format can only hold one.
+pub fn convert_animation(
+    animation: &Animation,
+    options: &PipelineOptions,
+) -> Result<PipelineResult> {
+    check_target(options, animation.is_animated())?;
+
+    // Placing the window on the first frame keeps it from wandering.
+    let placed;
+    let options = match (&options.crop, animation.frames.first()) {
+        (Some(mode @ CropMode::Smart { .. }), Some(first)) => {
+            let (x, y, width, height) = crop::crop_region(&first.image, mode)?;
+            placed = PipelineOptions {
+                crop: Some(CropMode::Region {
+                    x,
+                    y,
+                    width,
+                    height,
+                }),
+                ..options.clone()
+            };
+            &placed
+        }
+        _ => options,
+    };
+
+    let animation = animation.try_map_frames(|image| transform(image, options))?;
+    encode_prepared(&animation, options)
+}
+
+/// Encode the prepared frames to the target format, or the best of the
+/// automatic candidates.
+fn encode_prepared(animation: &Animation, options: &PipelineOptions) -> Result<PipelineResult> {
+    match &options.auto_format {
+        Some(auto) => encode_auto(animation, options, auto),
+        None => encode_as(animation, options, options.format),
+    }
+}
+
+/// Encode to every candidate format at the same perceived quality and keep
+/// the smallest.
+///
+/// Candidates aim at `quality_target` when there is one. Otherwise the first
+/// candidate that encodes is made at `quality`, and the SSIMULACRA2 score it
+/// reaches becomes the target for the rest. The remaining candidates are
+/// encoded in parallel.
+fn encode_auto(
+    animation: &Animation,
+    options: &PipelineOptions,
+    auto: &AutoFormat,
+) -> Result<PipelineResult> {
+    let formats = auto.formats()?;
+    let attempt = |format: Format, options: &PipelineOptions| {
+        check_format(format, options, animation.is_animated())?;
+        encode_as(animation, options, format)
+    };
+
+    let mut outcomes = Vec::with_capacity(formats.len());
+    let mut quality_target = options.quality_target;
+    if quality_target.is_none() && !options.lossless {
+        for &format in &formats {
+            let outcome = attempt(format, options).and_then(|mut result| {
+                let codec = get_codec(format);
+                let score = target::score_frames(
+                    codec.as_ref(),
+                    animation,
+                    &result.data,
+                    metrics::ssimulacra2,
+                    f64::min,
+                )?;
+                result.score = Some(score);
+                Ok(result)
+            });
+            let anchor = outcome.as_ref().ok().and_then(|result| result.score);
+            outcomes.push((format, outcome));
+            if let Some(score) = anchor {
+                quality_target = Some(QualityTarget::Ssimulacra2(score));
+                break;
+            }
+        }
+    }
+
+    let options = PipelineOptions {
+        quality_target,
+        ..options.clone()
+    };
+    let (attempt, options) = (&attempt, &options);
+    thread::scope(|scope| {
+        let handles: Vec<_> = formats[outcomes.len()..]
+            .iter()
+            .map(|&format| scope.spawn(move || (format, attempt(format, options))))
+            .collect();
+        for handle in handles {
+            outcomes.push(handle.join().unwrap_or_else(|e| panic::resume_unwind(e)));
+        }
+    });
+    auto::choose(outcomes)
+}
+
+/// Encode the prepared frames to `format`, within the size budget.
+fn encode_as(
+    animation: &Animation,
+    options: &PipelineOptions,
+    format: Format,
+) -> Result<PipelineResult> {
+    let codec = get_codec(format);
+    let fitted = target::encode_within(
+        codec.as_ref(),
+        animation,
+        &encode_options(options),
+        options.quality_target.as_ref(),
+        options.max_size.as_ref(),
+        &options.resize_options,
+    )?;
+
+    Ok(PipelineResult {
+        data: fitted.data,
+        format,
+        width: fitted.width,
+        height: fitted.height,
+        quality: fitted.quality,
+        score: fitted.score,
+        rejected: Vec::new(),
+        kept_original: false,
+    })
+}
+
+/// Reject targets that cannot encode, or cannot honour `lossless` or hold
+/// several frames when asked to. Automatic candidates are checked as they
+/// are tried instead.
+fn check_target(options: &PipelineOptions, animated: bool) -> Result<()> {
+    match options.auto_format {
+        Some(_) => Ok(()),
+        None => check_format(options.format, options, animated),
+    }
+}
+
+fn check_format(format: Format, options: &PipelineOptions, animated: bool) -> Result<()> {
+    if !format.can_encode() {
+        return Err(Error::EncodingNotSupported(format));
+    }
+    if options.lossless && !format.supports_lossless() {
+        return Err(Error::LosslessNotSupported(format));
+    }
+    if animated && !format.supports_animation() {
+        return Err(Error::AnimationNotSupported(format));
+    }
+    Ok(())
+}
+
+fn encode_options(options: &PipelineOptions) -> EncodeOptions {
+    EncodeOptions {
+        quality: options.quality,
+        lossless: options.lossless,
+        codec: options.codec,
+        metadata: options.metadata,
+        color: options.color,
+        tone_map: options.tone_map,
     }
+}
 
+/// Apply the crop, extend and resize steps of `options` to one image.
+fn transform(image: &ImageData, options: &PipelineOptions) -> Result<ImageData> {
     let image = match &options.crop {
         Some(mode) => crop::crop(image, mode)?,
         None => image.clone(),
@@ -83,45 +428,215 @@ pub fn convert(image: &ImageData, options: &PipelineOptions) -> Result<PipelineR
         None => image,
     };
 
-    let image = match &options.resize {
-        Some(mode) => resize::resize(&image, mode)?,
-        None => image,
-    };
-
-    let codec = get_codec(options.format);
-    let encode_opts = EncodeOptions {
-        quality: options.quality,
-    };
-    let data = codec.encode(&image, &encode_opts)?;
+    match &options.resize {
+        Some(mode) => resize::resize_with(&image, mode, &options.resize_options),
+        None => Ok(image),
+    }
+}
 
-    Ok(PipelineResult {
+/// Decode the data and re-encode in the same format with the given options.
+/// Every frame of an animation is kept.
+///
+/// With `lossless`, a JPEG is not decoded at all: its DCT coefficients are
+/// rewritten with new Huffman tables and scans, so repeated runs never lose
+/// quality.
+pub fn optimize(data: &[u8], options: &EncodeOptions) -> Result<PipelineResult> {
+    optimize_with(
         data,
-        format: options.format,
-        width: image.width,
-        height: image.height,
-    })
+        &OptimizeOptions {
+            encode: *options,
+            ..Default::default()
+        },
+    )
 }
 
-/// Decode the data and re-encode in the same format at the given quality.
-pub fn optimize(data: &[u8], quality: u8) -> Result<PipelineResult> {
-    let (image, format) = decode(data)?;
+/// [`optimize`], within an optional size budget.
+///
+/// A JPEG rewritten losslessly cannot be made smaller to fit, so it fails
+/// with [`Error::TargetSizeUnreachable`] when it is over the budget.
+pub fn optimize_with(data: &[u8], options: &OptimizeOptions) -> Result<PipelineResult> {
+    let encode = &options.encode;
+    if encode.lossless && Format::from_magic_bytes(data) == Some(Format::Jpeg) {
+        // jpegtran holds every DCT coefficient of the image in memory.
+        options.limits.check(&probe(data)?)?;
+        let transform = JpegTransform {
+            progressive: encode.codec.jpeg.progressive,
+            optimize_coding: encode.codec.jpeg.optimize_coding,
+            metadata: encode.metadata,
+            ..Default::default()
+        };
+        let encoded = jpeg::transform_jpeg(data, &transform)?;
+        if let Some(limit) = options.max_size
+            && encoded.len() as u64 > limit.max_bytes
+        {
+            return Err(Error::TargetSizeUnreachable {
+                max_bytes: limit.max_bytes,
+                smallest: encoded.len() as u64,
+            });
+        }
+        let (width, height) = probe(&encoded)?.display_dimensions();
+        let result = PipelineResult {
+            data: encoded,
+            format: Format::Jpeg,
+            width,
+            height,
+            quality: None,
+            score: None,
+            rejected: Vec::new(),
+            kept_original: false,
+        };
+        if options.never_larger {
+            return never_larger(data, result, encode.metadata);
+        }
+        return Ok(result);
+    }
+
+    let decode_options = DecodeOptions {
+        limits: options.limits,
+        ..Default::default()
+    };
+    let (animation, format) = decode_animation_with(data, &decode_options)?;
 
     if !format.can_encode() {
         return Err(Error::EncodingNotSupported(format));
     }
+    if encode.lossless && !format.supports_lossless() {
+        return Err(Error::LosslessNotSupported(format));
+    }
 
     let codec = get_codec(format);
-    let encode_opts = EncodeOptions { quality };
-    let encoded = codec.encode(&image, &encode_opts)?;
+    let fitted = target::encode_within(
+        codec.as_ref(),
+        &animation,
+        encode,
+        options.quality_target.as_ref(),
+        options.max_size.as_ref(),
+        &ResizeOptions::default(),
+    )?;
+    let result = PipelineResult {
+        data: fitted.data,
+        format,
+        width: fitted.width,
+        height: fitted.height,
+        quality: fitted.quality,
+        score: fitted.score,
+        rejected: Vec::new(),
+        kept_original: false,
+    };
+    if options.never_larger {
+        return never_larger(data, result, encode.metadata);
+    }
+    Ok(result)
+}
+
+/// Hand back `original` instead of `result` when the encode did not make it
+/// smaller, with [`PipelineResult::kept_original`] set.
+///
+/// `original` is the file `result` was converted from; its headers are
+/// probed for the dimensions when it is kept. It is only kept when it holds
+/// no metadata that `policy` would drop, so GPS or XMP never come back
+/// under [`MetadataPolicy::Safe`] or [`MetadataPolicy::Strip`].
+pub fn never_larger(
+    original: &[u8],
+    result: PipelineResult,
+    policy: MetadataPolicy,
+) -> Result<PipelineResult> {
+    if result.data.len() < original.len() {
+        return Ok(result);
+    }
+    let info = probe(original)?;
+    if !metadata_within(original, &info, policy)? {
+        return Ok(result);
+    }
+    let (width, height) = info.display_dimensions();
+    Ok(unchanged(original, info.format, width, height))
+}
+
+/// Whether the metadata of `data`, described by `info`, is already all that
+/// `policy` lets through.
+///
+/// The probe only says which blocks are present, so under
+/// [`MetadataPolicy::Safe`] an EXIF block is decoded to see which tags it
+/// holds.
+fn metadata_within(data: &[u8], info: &ImageInfo, policy: MetadataPolicy) -> Result<bool> {
+    let present = info.metadata;
+    Ok(match policy {
+        MetadataPolicy::Keep => true,
+        MetadataPolicy::Strip => {
+            present.exif_bytes.is_none()
+                && present.xmp_bytes.is_none()
+                && present.icc_bytes.is_none()
+        }
+        MetadataPolicy::Safe if present.xmp_bytes.is_some() => false,
+        MetadataPolicy::Safe if present.exif_bytes.is_none() => true,
+        MetadataPolicy::Safe => {
+            let options = DecodeOptions {
+                auto_orient: false,
+                ..Default::default()
+            };
+            let (image, _) = decode_with(data, &options)?;
+            image.metadata.exif == image.metadata.filter(policy).exif
+        }
+    })
+}
+
+/// A result holding the input bytes as they were.
+fn unchanged(data: &[u8], format: Format, width: u32, height: u32) -> PipelineResult {
+    PipelineResult {
+        data: data.to_vec(),
+        format,
+        width,
+        height,
+        quality: None,
+        score: None,
+        rejected: Vec::new(),
+        kept_original: true,
+    }
+}
+
+/// Losslessly move a JPEG into JPEG XL, or a recompressed JPEG XL back to
+/// the JPEG it came from, without touching the pixels.
+///
+/// The JPEG file, metadata included, is kept byte for byte, so no transform
+/// or metadata policy applies. Fails with [`Error::UnsupportedFormat`] unless
+/// `data` is a JPEG and `format` is [`Format::Jxl`], or `data` is a JXL
+/// holding JPEG reconstruction data and `format` is [`Format::Jpeg`].
+pub fn transcode_jpeg(data: &[u8], format: Format, codec: &CodecOptions) -> Result<PipelineResult> {
+    let source = Format::from_magic_bytes(data)
+        .ok_or_else(|| Error::UnknownFormat("unrecognised magic bytes".to_string()))?;
+
+    let encoded = match (source, format) {
+        (Format::Jpeg, Format::Jxl) => jxl::jpeg_to_jxl(data, &codec.jxl)?,
+        (Format::Jxl, Format::Jpeg) => jxl::jxl_to_jpeg(data)?,
+        (Format::Jpeg | Format::Jxl, _) => return Err(Error::UnsupportedFormat(format)),
+        _ => return Err(Error::UnsupportedFormat(source)),
+    };
+    let jpeg = if source == Format::Jpeg {
+        data
+    } else {
+        &encoded
+    };
+    let (width, height) = jpeg_dimensions(jpeg)?;
 
     Ok(PipelineResult {
         data: encoded,
         format,
-        width: image.width,
-        height: image.height,
+        width,
+        height,
+        quality: None,
+        score: None,
+        rejected: Vec::new(),
+        kept_original: false,
     })
 }
 
+/// Width and height from a JPEG header.
+fn jpeg_dimensions(data: &[u8]) -> Result<(u32, u32)> {
+    ImageReader::with_format(Cursor::new(data), ImageFormat::Jpeg)
+        .into_dimensions()
+        .map_err(|e| Error::Decode(format!("jpeg header: {e}")))
+}
+
 /// Derive an output path for the converted image.
 ///
 /// - If `output` is `None`, uses the input directory with the new extension.
@@ -143,6 +658,11 @@ pub fn output_path(input: &Path, format: Format, output: Option<&Path>) -> PathB
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::animation::Frame;
+    use crate::auto::Rejection;
+    use crate::limits::Limit;
+    use crate::metadata::{Metadata, exif_orientation, sample_exif};
+    use crate::orientation::Orientation;
     use std::path::Path;
 
     #[test]
@@ -164,15 +684,702 @@ mod tests {
     #[test]
     fn jxl_encode_succeeds() {
         let image = ImageData::new(2, 2, vec![128u8; 16]);
+        let options = PipelineOptions::new(Format::Jxl);
+        let result = convert(&image, &options);
+        assert!(result.is_ok(), "converting to JXL should succeed");
+    }
+
+    #[test]
+    fn convert_passes_codec_options_to_encoder() {
+        let image = ImageData::new(16, 16, vec![200u8; 16 * 16 * 4]);
+        let mut options = PipelineOptions::new(Format::Jpeg);
+        options.codec.jpeg.progressive = false;
+
+        let result = convert(&image, &options).expect("convert failed");
+
+        // Baseline JPEGs use SOF0 (0xFFC0), progressive ones SOF2 (0xFFC2).
+        assert!(result.data.windows(2).any(|w| w == [0xFF, 0xC0]));
+        assert!(!result.data.windows(2).any(|w| w == [0xFF, 0xC2]));
+    }
+
+    #[test]
+    fn convert_carries_metadata_through_transforms() {
+        let exif = b"MM\0*\0\0\0\x08\0\x01\x82\x98\0\x02\0\0\0\x04abc\0\0\0\0\0".to_vec();
+        let image = ImageData::new(8, 8, vec![200u8; 8 * 8 * 4]).with_metadata(Metadata {
+            exif: Some(exif.clone()),
+            xmp: Some(b"<x:xmpmeta/>".to_vec()),
+            icc: None,
+            ..Default::default()
+        });
         let options = PipelineOptions {
-            format: Format::Jxl,
-            quality: 80,
-            resize: None,
-            crop: None,
-            extend: None,
-            fill_color: None,
+            resize: Some(ResizeMode::Width(4)),
+            metadata: MetadataPolicy::Keep,
+            ..PipelineOptions::new(Format::Png)
+        };
+
+        let result = convert(&image, &options).expect("convert failed");
+        let (decoded, _) = decode(&result.data).expect("decode failed");
+
+        assert_eq!(decoded.metadata.exif, Some(exif));
+        assert_eq!(decoded.metadata.xmp.as_deref(), Some(&b"<x:xmpmeta/>"[..]));
+    }
+
+    #[test]
+    fn strip_policy_drops_metadata() {
+        let image = ImageData::new(8, 8, vec![200u8; 8 * 8 * 4]).with_metadata(Metadata {
+            xmp: Some(b"<x:xmpmeta/>".to_vec()),
+            ..Default::default()
+        });
+        let options = PipelineOptions {
+            metadata: MetadataPolicy::Strip,
+            ..PipelineOptions::new(Format::Jpeg)
+        };
+
+        let result = convert(&image, &options).expect("convert failed");
+        let (decoded, _) = decode(&result.data).expect("decode failed");
+
+        assert!(decoded.metadata.is_empty());
+    }
+
+    #[test]
+    fn icc_profile_is_kept_or_converted_per_format() {
+        let p3 = moxcms::ColorProfile::new_display_p3().encode().unwrap();
+        let image = ImageData::new(4, 4, [40, 160, 90, 255].repeat(16)).with_metadata(Metadata {
+            icc: Some(p3.clone()),
+            ..Default::default()
+        });
+
+        let png = convert(&image, &PipelineOptions::new(Format::Png)).expect("convert failed");
+        let (decoded, _) = decode(&png.data).expect("decode failed");
+        assert_eq!(decoded.metadata.icc, Some(p3));
+        assert_eq!(decoded.data, image.data);
+
+        // QOI cannot carry a profile, so the pixels are moved into sRGB.
+        let qoi = convert(&image, &PipelineOptions::new(Format::Qoi)).expect("convert failed");
+        let (decoded, _) = decode(&qoi.data).expect("decode failed");
+        assert_eq!(decoded.metadata.icc, None);
+        assert_eq!(decoded.data, crate::color::to_srgb(&image).data);
+        assert_ne!(decoded.data, image.data);
+    }
+
+    /// An 8x4 PNG whose EXIF says it must be rotated 90 degrees clockwise.
+    fn sideways_png() -> Vec<u8> {
+        let exif = b"MM\0*\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0\x06\0\0\0\0\0\0".to_vec();
+        let image = ImageData::new(8, 4, vec![200u8; 8 * 4 * 4]).with_metadata(Metadata {
+            exif: Some(exif),
+            ..Default::default()
+        });
+        let options = PipelineOptions {
+            metadata: MetadataPolicy::Keep,
+            ..PipelineOptions::new(Format::Png)
+        };
+        convert(&image, &options).expect("convert failed").data
+    }
+
+    #[test]
+    fn decode_applies_exif_orientation() {
+        let (image, _) = decode(&sideways_png()).expect("decode failed");
+
+        assert_eq!((image.width, image.height), (4, 8));
+        assert_eq!(image.metadata.orientation, Orientation::Normal);
+        // The tag is reset so viewers don't rotate the upright pixels again.
+        let exif = image.metadata.exif.as_deref().expect("exif kept");
+        assert_eq!(exif_orientation(exif), Some(Orientation::Normal));
+    }
+
+    #[test]
+    fn decode_without_auto_orient_keeps_stored_pixels() {
+        let options = DecodeOptions {
+            auto_orient: false,
+            ..Default::default()
+        };
+        let (image, _) = decode_with(&sideways_png(), &options).expect("decode failed");
+
+        assert_eq!((image.width, image.height), (8, 4));
+        assert_eq!(image.metadata.orientation, Orientation::Rotate90);
+    }
+
+    #[test]
+    fn probe_matches_the_stored_image() {
+        let exif = b"MM\0*\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0\x06\0\0\0\0\0\0".to_vec();
+        let image = ImageData::new(8, 4, vec![200u8; 8 * 4 * 4]).with_metadata(Metadata {
+            exif: Some(exif),
+            ..Default::default()
+        });
+        for format in [Format::Png, Format::Jpeg, Format::WebP] {
+            let options = PipelineOptions {
+                metadata: MetadataPolicy::Keep,
+                ..PipelineOptions::new(format)
+            };
+            let data = convert(&image, &options).expect("convert failed").data;
+
+            let info = probe(&data).expect("probe failed");
+            assert_eq!(info.format, format);
+            assert_eq!((info.width, info.height), (8, 4), "{format:?}");
+            assert_eq!(info.display_dimensions(), (4, 8), "{format:?}");
+            assert_eq!(info.orientation, Orientation::Rotate90, "{format:?}");
+            assert_eq!(info.bit_depth, 8, "{format:?}");
+            assert_eq!(info.frame_count, 1, "{format:?}");
+            assert!(info.metadata.exif_bytes.is_some(), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn probe_counts_animation_frames() {
+        for format in [Format::Gif, Format::Png, Format::WebP] {
+            let options = PipelineOptions::new(format);
+            let result =
+                convert_animation(&two_frame_animation(), &options).expect("convert failed");
+
+            let info = probe(&result.data).expect("probe failed");
+            assert_eq!((info.width, info.height), (8, 6), "{format:?}");
+            assert_eq!(info.frame_count, 2, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn probe_rejects_unknown_data() {
+        assert!(matches!(
+            probe(b"not an image"),
+            Err(Error::UnknownFormat(_))
+        ));
+    }
+
+    #[test]
+    fn default_limits_reject_a_bomb_before_decoding() {
+        // A QOI header declaring 60000x60000 RGBA, with no pixels behind it.
+        let mut bomb = b"qoif".to_vec();
+        bomb.extend_from_slice(&60_000u32.to_be_bytes());
+        bomb.extend_from_slice(&60_000u32.to_be_bytes());
+        bomb.extend_from_slice(&[4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+
+        assert!(matches!(
+            decode(&bomb),
+            Err(Error::LimitExceeded {
+                limit: Limit::Alloc,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn custom_limits_apply_to_every_entry_point() {
+        let image = ImageData::new(64, 48, vec![120u8; 64 * 48 * 4]);
+        let png = convert(&image, &PipelineOptions::new(Format::Png))
+            .expect("convert failed")
+            .data;
+        let limits = DecodeLimits {
+            max_width: Some(32),
+            ..DecodeLimits::none()
+        };
+        let too_wide = |result: Result<_>| {
+            matches!(
+                result,
+                Err(Error::LimitExceeded {
+                    limit: Limit::Width,
+                    actual: 64,
+                    max: 32
+                })
+            )
+        };
+
+        let options = DecodeOptions {
+            limits,
+            ..Default::default()
+        };
+        assert!(too_wide(decode_with(&png, &options).map(|_| ())));
+        let options = OptimizeOptions {
+            limits,
+            ..Default::default()
+        };
+        assert!(too_wide(optimize_with(&png, &options).map(|_| ())));
+
+        let gif = convert_animation(&two_frame_animation(), &PipelineOptions::new(Format::Gif))
+            .expect("convert failed")
+            .data;
+        let options = DecodeOptions {
+            limits: DecodeLimits {
+                max_frames: Some(1),
+                ..DecodeLimits::none()
+            },
+            ..Default::default()
+        };
+        assert!(matches!(
+            decode_animation_with(&gif, &options),
+            Err(Error::LimitExceeded {
+                limit: Limit::Frames,
+                ..
+            })
+        ));
+        // A still decode only reads the first frame.
+        assert!(decode_with(&gif, &options).is_ok());
+    }
+
+    #[test]
+    fn lossless_convert_rejects_lossy_only_format() {
+        let image = ImageData::new(2, 2, vec![128u8; 16]);
+        let options = PipelineOptions {
+            lossless: true,
+            ..PipelineOptions::new(Format::Avif)
         };
+
         let result = convert(&image, &options);
-        assert!(result.is_ok(), "converting to JXL should succeed");
+        assert!(matches!(
+            result,
+            Err(Error::LosslessNotSupported(Format::Avif))
+        ));
+    }
+
+    fn two_frame_animation() -> Animation {
+        let frame = |value: u8, duration_ms| {
+            Frame::new(ImageData::new(8, 6, vec![value; 8 * 6 * 4]), duration_ms)
+        };
+        Animation {
+            frames: vec![frame(40, 120), frame(200, 80)],
+            loop_count: 2,
+        }
+    }
+
+    #[test]
+    fn animation_to_still_only_format_fails() {
+        let options = PipelineOptions::new(Format::Jpeg);
+
+        let result = convert_animation(&two_frame_animation(), &options);
+        assert!(matches!(
+            result,
+            Err(Error::AnimationNotSupported(Format::Jpeg))
+        ));
+    }
+
+    #[test]
+    fn convert_animation_transforms_every_frame() {
+        let options = PipelineOptions {
+            lossless: true,
+            crop: Some(CropMode::Region {
+                x: 0,
+                y: 0,
+                width: 4,
+                height: 6,
+            }),
+            resize: Some(ResizeMode::Width(2)),
+            ..PipelineOptions::new(Format::WebP)
+        };
+
+        let result = convert_animation(&two_frame_animation(), &options).expect("convert failed");
+        assert_eq!((result.width, result.height), (2, 3));
+
+        let (decoded, format) = decode_animation(&result.data).expect("decode failed");
+        assert_eq!(format, Format::WebP);
+        assert_eq!(decoded.loop_count, 2);
+        assert_eq!(decoded.frames.len(), 2);
+        for (frame, value) in decoded.frames.iter().zip([40, 200]) {
+            assert_eq!((frame.image.width, frame.image.height), (2, 3));
+            assert_eq!(frame.image.data[0], value);
+        }
+    }
+
+    #[test]
+    fn smart_crop_places_one_window_for_every_frame() {
+        // Detail on the left of the first frame and the right of the second.
+        let frame = |detail: std::ops::Range<u32>| {
+            let data = (0..24 * 8)
+                .flat_map(|i| {
+                    let (x, y) = (i % 24, i / 24);
+                    match (detail.contains(&x), (x + y) % 2) {
+                        (true, 0) => [0, 0, 0, 255],
+                        (true, _) => [255; 4],
+                        (false, _) => [128, 128, 128, 255],
+                    }
+                })
+                .collect();
+            Frame::new(ImageData::new(24, 8, data), 100)
+        };
+        let animation = Animation {
+            frames: vec![frame(0..8), frame(16..24)],
+            loop_count: 0,
+        };
+        let options = PipelineOptions {
+            lossless: true,
+            crop: Some(CropMode::Smart {
+                width: 1,
+                height: 1,
+            }),
+            ..PipelineOptions::new(Format::WebP)
+        };
+
+        let result = convert_animation(&animation, &options).expect("convert failed");
+        assert_eq!((result.width, result.height), (8, 8));
+        let (decoded, _) = decode_animation(&result.data).expect("decode failed");
+        assert_eq!(
+            &decoded.frames[0].image.data[..8],
+            [0, 0, 0, 255, 255, 255, 255, 255]
+        );
+        assert!(
+            decoded.frames[1]
+                .image
+                .data
+                .iter()
+                .all(|&s| s == 128 || s == 255)
+        );
+    }
+
+    #[test]
+    fn optimize_keeps_every_frame() {
+        let options = PipelineOptions::new(Format::Png);
+        let png = convert_animation(&two_frame_animation(), &options).expect("convert failed");
+
+        let result = optimize(&png.data, &EncodeOptions::default()).expect("optimize failed");
+        let (decoded, _) = decode_animation(&result.data).expect("decode failed");
+
+        assert_eq!(decoded.frames.len(), 2);
+        assert_eq!(decoded.duration_ms(), 200);
+    }
+
+    #[test]
+    fn lossless_jpeg_optimize_never_degrades() {
+        let data = (0..48 * 32)
+            .flat_map(|i| [(i * 7) as u8, (i / 3) as u8, 90, 255])
+            .collect();
+        let image = ImageData::new(48, 32, data);
+        let jpeg = convert(&image, &PipelineOptions::new(Format::Jpeg)).expect("encode failed");
+        let (original, _) = decode(&jpeg.data).expect("decode failed");
+        let options = EncodeOptions {
+            lossless: true,
+            ..Default::default()
+        };
+
+        let mut data = jpeg.data;
+        for _ in 0..3 {
+            let result = optimize(&data, &options).expect("optimize failed");
+            assert_eq!(
+                (result.format, result.width, result.height),
+                (Format::Jpeg, 48, 32)
+            );
+            data = result.data;
+        }
+
+        let (decoded, _) = decode(&data).expect("decode failed");
+        assert_eq!(decoded.data, original.data);
+    }
+
+    #[test]
+    fn never_larger_keeps_input_that_would_grow() {
+        let data = (0..48 * 32)
+            .flat_map(|i| [(i * 7) as u8, (i / 3) as u8, 90, 255])
+            .collect();
+        let image = ImageData::new(48, 32, data);
+        let low = PipelineOptions {
+            quality: 20,
+            ..PipelineOptions::new(Format::Jpeg)
+        };
+        let jpeg = convert(&image, &low).expect("encode failed").data;
+        let mut options = OptimizeOptions {
+            encode: EncodeOptions {
+                quality: 95,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let grown = optimize_with(&jpeg, &options).expect("optimize failed");
+        assert!(grown.data.len() > jpeg.len());
+        assert!(!grown.kept_original);
+
+        options.never_larger = true;
+        let kept = optimize_with(&jpeg, &options).expect("optimize failed");
+        assert!(kept.kept_original);
+        assert_eq!(kept.data, jpeg);
+        assert_eq!(
+            (kept.format, kept.width, kept.height, kept.quality),
+            (Format::Jpeg, 48, 32, None)
+        );
+    }
+
+    #[test]
+    fn never_larger_does_not_hand_back_metadata_the_policy_drops() {
+        let data = (0..48 * 32)
+            .flat_map(|i| [(i * 7) as u8, (i / 3) as u8, 90, 255])
+            .collect();
+        let image = ImageData::new(48, 32, data).with_metadata(Metadata {
+            exif: Some(sample_exif()),
+            ..Default::default()
+        });
+        let low = PipelineOptions {
+            quality: 20,
+            metadata: MetadataPolicy::Keep,
+            ..PipelineOptions::new(Format::Jpeg)
+        };
+        let jpeg = convert(&image, &low).expect("encode failed").data;
+        let optimize_under = |metadata| {
+            let options = OptimizeOptions {
+                encode: EncodeOptions {
+                    quality: 95,
+                    metadata,
+                    ..Default::default()
+                },
+                never_larger: true,
+                ..Default::default()
+            };
+            optimize_with(&jpeg, &options).expect("optimize failed")
+        };
+
+        // The GPS pointer in the original must not survive Safe.
+        let safe = optimize_under(MetadataPolicy::Safe);
+        assert!(!safe.kept_original);
+        let (decoded, _) = decode(&safe.data).expect("decode failed");
+        assert!(decoded.metadata.exif.is_some());
+        assert_eq!(
+            decoded.metadata.filter(MetadataPolicy::Safe).exif,
+            decoded.metadata.exif
+        );
+
+        let kept = optimize_under(MetadataPolicy::Keep);
+        assert!(kept.kept_original);
+        // Orientation 6 displays the 48x32 pixels upright as 32x48.
+        assert_eq!((kept.width, kept.height), (32, 48));
+    }
+
+    #[test]
+    fn lossless_jpeg_over_budget_fails_even_with_never_larger() {
+        let data = (0..48 * 32)
+            .flat_map(|i| [(i * 7) as u8, (i / 3) as u8, 90, 255])
+            .collect();
+        let image = ImageData::new(48, 32, data);
+        let jpeg = convert(&image, &PipelineOptions::new(Format::Jpeg)).expect("encode failed");
+        let options = OptimizeOptions {
+            encode: EncodeOptions {
+                lossless: true,
+                ..Default::default()
+            },
+            max_size: Some(SizeLimit {
+                max_bytes: 100,
+                downscale: false,
+            }),
+            never_larger: true,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            optimize_with(&jpeg.data, &options),
+            Err(Error::TargetSizeUnreachable { max_bytes: 100, .. })
+        ));
+    }
+
+    #[test]
+    fn never_larger_only_swaps_in_a_smaller_original() {
+        let data = (0..48 * 32u32)
+            .flat_map(|i| [(i * 37) as u8, (i * 11 / 7) as u8, (i % 61) as u8, 255])
+            .collect();
+        let image = ImageData::new(48, 32, data);
+        let at = |quality| {
+            let options = PipelineOptions {
+                quality,
+                ..PipelineOptions::new(Format::Jpeg)
+            };
+            convert(&image, &options).expect("encode failed")
+        };
+        let (high, low) = (at(95), at(20));
+
+        let shrunk = never_larger(&high.data, low.clone(), MetadataPolicy::Safe)
+            .expect("never_larger failed");
+        assert!(!shrunk.kept_original);
+        assert_eq!(shrunk.quality, Some(20));
+
+        let kept =
+            never_larger(&low.data, high, MetadataPolicy::Safe).expect("never_larger failed");
+        assert!(kept.kept_original);
+        assert_eq!(
+            (kept.format, kept.width, kept.height),
+            (Format::Jpeg, 48, 32)
+        );
+        assert_eq!(kept.data, low.data);
+    }
+
+    #[test]
+    fn max_size_reports_the_quality_that_fits() {
+        let data = (0..64 * 64u32)
+            .flat_map(|i| [(i * 37) as u8, (i * 11 / 7) as u8, (i % 61) as u8, 255])
+            .collect();
+        let image = ImageData::new(64, 64, data);
+        let mut options = PipelineOptions {
+            quality: 95,
+            ..PipelineOptions::new(Format::WebP)
+        };
+        let full = convert(&image, &options).expect("convert failed");
+        assert_eq!(full.quality, Some(95));
+
+        options.max_size = Some(SizeLimit::new(full.data.len() as u64 / 2));
+        let result = convert(&image, &options).expect("convert failed");
+        assert!(result.data.len() <= full.data.len() / 2);
+        assert!(result.quality.is_some_and(|q| q < 95));
+
+        let optimized = optimize_with(
+            &full.data,
+            &OptimizeOptions {
+                encode: EncodeOptions {
+                    quality: 95,
+                    ..Default::default()
+                },
+                max_size: options.max_size,
+                ..Default::default()
+            },
+        )
+        .expect("optimize failed");
+        assert!(optimized.data.len() <= full.data.len() / 2);
+    }
+
+    #[test]
+    fn quality_target_reports_the_score_reached() {
+        let data = (0..64 * 64u32)
+            .flat_map(|i| [(i % 64 * 4) as u8, (i / 64 * 4) as u8, 128, 255])
+            .collect();
+        let image = ImageData::new(64, 64, data);
+        let options = PipelineOptions {
+            quality_target: Some(QualityTarget::Ssimulacra2(80.0)),
+            ..PipelineOptions::new(Format::Jpeg)
+        };
+
+        let result = convert(&image, &options).expect("convert failed");
+
+        let score = result.score.expect("no score reported");
+        assert!(score >= 80.0, "score {score}");
+        assert!(result.quality.is_some_and(|q| q < 100));
+        let plain = convert(&image, &PipelineOptions::new(Format::Jpeg)).expect("convert failed");
+        assert_eq!(plain.score, None);
+    }
+
+    #[test]
+    fn auto_format_keeps_the_smallest_candidate() {
+        let data = (0..64 * 64u32)
+            .flat_map(|i| [(i % 64 * 4) as u8, (i / 64 * 4) as u8, 128, 255])
+            .collect();
+        let image = ImageData::new(64, 64, data);
+        let options = PipelineOptions {
+            auto_format: Some(AutoFormat {
+                candidates: vec![Format::Png, Format::Jpeg, Format::WebP],
+            }),
+            ..PipelineOptions::new(Format::Png)
+        };
+
+        let result = convert(&image, &options).expect("convert failed");
+
+        assert_eq!(result.rejected.len(), 2);
+        assert!(!result.rejected.iter().any(|r| r.format == result.format));
+        for rejected in &result.rejected {
+            let Rejection::Larger { bytes } = rejected.reason else {
+                panic!("{:?} failed: {}", rejected.format, rejected.reason);
+            };
+            assert!(bytes >= result.data.len() as u64);
+        }
+    }
+
+    #[test]
+    fn auto_format_rejects_candidates_that_cannot_animate() {
+        let options = PipelineOptions {
+            auto_format: Some(AutoFormat {
+                candidates: vec![Format::Jpeg, Format::Gif],
+            }),
+            ..PipelineOptions::new(Format::Jpeg)
+        };
+
+        let result = convert_animation(&two_frame_animation(), &options).expect("convert failed");
+
+        assert_eq!(result.format, Format::Gif);
+        assert_eq!(
+            result.rejected,
+            vec![Rejected {
+                format: Format::Jpeg,
+                reason: Rejection::Failed(Error::AnimationNotSupported(Format::Jpeg).to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn gif_animation_converts_to_webp() {
+        let gif = convert_animation(&two_frame_animation(), &PipelineOptions::new(Format::Gif))
+            .expect("gif encode failed");
+        let (animation, format) = decode_animation(&gif.data).expect("gif decode failed");
+        assert_eq!(format, Format::Gif);
+
+        let webp = convert_animation(&animation, &PipelineOptions::new(Format::WebP))
+            .expect("convert failed");
+        let (decoded, format) = decode_animation(&webp.data).expect("decode failed");
+
+        assert_eq!(format, Format::WebP);
+        assert_eq!(decoded.frames.len(), 2);
+        assert_eq!(decoded.loop_count, 2);
+        assert_eq!(decoded.duration_ms(), 200);
+    }
+
+    #[test]
+    fn bitmap_inputs_convert_to_png() {
+        let image = ImageData::new(4, 3, [30, 60, 90, 255].repeat(12));
+        let options = PipelineOptions::new(Format::Png);
+
+        for format in [
+            Format::Tiff,
+            Format::Bmp,
+            Format::Tga,
+            Format::Ico,
+            Format::Pnm,
+        ] {
+            let source = get_codec(format)
+                .encode(&image, &EncodeOptions::default())
+                .expect("encode failed");
+            let (decoded, detected) = decode(&source).expect("decode failed");
+            assert_eq!(detected, format);
+
+            let result = convert(&decoded, &options).expect("convert failed");
+            let (png, _) = decode(&result.data).expect("png decode failed");
+            assert_eq!(png.data, image.data, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn multi_page_tiff_to_still_only_format_fails() {
+        let tiff = convert_animation(&two_frame_animation(), &PipelineOptions::new(Format::Tiff))
+            .expect("tiff encode failed");
+        let (pages, format) = decode_animation(&tiff.data).expect("tiff decode failed");
+        assert_eq!(format, Format::Tiff);
+        assert_eq!(pages.frames.len(), 2);
+
+        let result = convert_animation(&pages, &PipelineOptions::new(Format::Jpeg));
+        assert!(matches!(
+            result,
+            Err(Error::AnimationNotSupported(Format::Jpeg))
+        ));
+    }
+
+    #[test]
+    fn transcode_jpeg_rejects_other_pairings() {
+        let image = ImageData::new(4, 4, [200, 100, 50, 255].repeat(16));
+        let png = convert(&image, &PipelineOptions::new(Format::Png)).expect("png encode failed");
+        let jpeg =
+            convert(&image, &PipelineOptions::new(Format::Jpeg)).expect("jpeg encode failed");
+        let codec = CodecOptions::default();
+
+        assert!(matches!(
+            transcode_jpeg(&png.data, Format::Jxl, &codec),
+            Err(Error::UnsupportedFormat(Format::Png))
+        ));
+        assert!(matches!(
+            transcode_jpeg(&jpeg.data, Format::WebP, &codec),
+            Err(Error::UnsupportedFormat(Format::WebP))
+        ));
+    }
+
+    #[test]
+    fn transcode_jpeg_roundtrips_through_jxl() {
+        let image = ImageData::new(16, 8, [200, 100, 50, 255].repeat(128));
+        let jpeg =
+            convert(&image, &PipelineOptions::new(Format::Jpeg)).expect("jpeg encode failed");
+        let codec = CodecOptions::default();
+
+        let jxl = transcode_jpeg(&jpeg.data, Format::Jxl, &codec).expect("transcode failed");
+        assert_eq!((jxl.format, jxl.width, jxl.height), (Format::Jxl, 16, 8));
+
+        let back = transcode_jpeg(&jxl.data, Format::Jpeg, &codec).expect("reconstruction failed");
+        assert_eq!((back.width, back.height), (16, 8));
+        assert_eq!(back.data, jpeg.data);
     }
 }
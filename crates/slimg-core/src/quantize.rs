@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+
+use crate::codec::{ImageData, PixelFormat};
+
+/// Options for reducing an image to a colour palette.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuantizeOptions {
+    /// Maximum number of palette entries (2..=256).
+    pub max_colors: u16,
+    /// Floyd–Steinberg error diffusion strength, from 0.0 (off) to 1.0 (full).
+    pub dithering: f32,
+}
+
+impl Default for QuantizeOptions {
+    fn default() -> Self {
+        Self {
+            max_colors: 256,
+            dithering: 1.0,
+        }
+    }
+}
+
+/// An image reduced to a palette of at most 256 RGBA colours.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedImage {
+    pub width: u32,
+    pub height: u32,
+    /// Palette entries in RGBA order.
+    pub palette: Vec<[u8; 4]>,
+    /// One palette index per pixel, row-major.
+    pub indices: Vec<u8>,
+}
+
+impl IndexedImage {
+    /// Expand the palette indices back to RGBA pixels.
+    pub fn to_rgba(&self) -> ImageData {
+        let data = self
+            .indices
+            .iter()
+            .flat_map(|&i| self.palette[i as usize])
+            .collect();
+        ImageData::new(self.width, self.height, data)
+    }
+}
+
+/// Reduce an image to at most `options.max_colors` colours.
+///
+/// Images that already fit in the palette are mapped exactly. Otherwise the
+/// palette is built with median cut, refined with one k-means pass, and the
+/// pixels are remapped with optional error diffusion. Fully transparent
+/// pixels all share a single `[0, 0, 0, 0]` entry. High bit depth images are
+/// narrowed to 8 bits first.
+pub fn quantize(image: &ImageData, options: &QuantizeOptions) -> IndexedImage {
+    let image = image.as_pixel_format(PixelFormat::Rgba8);
+    let max_colors = options.max_colors.clamp(2, 256) as usize;
+    let pixels: Vec<[u8; 4]> = image.data.chunks_exact(4).map(normalize).collect();
+
+    let histogram = histogram(&pixels);
+
+    if histogram.len() <= max_colors {
+        let mut palette: Vec<[u8; 4]> = histogram.keys().copied().collect();
+        palette.sort_unstable();
+        let lookup: HashMap<[u8; 4], u8> = palette
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| (c, i as u8))
+            .collect();
+        let indices = pixels.iter().map(|px| lookup[px]).collect();
+        return IndexedImage {
+            width: image.width,
+            height: image.height,
+            palette,
+            indices,
+        };
+    }
+
+    // Sort so the result does not depend on hash map iteration order.
+    let mut colors: Vec<([u8; 4], u32)> = histogram.into_iter().collect();
+    colors.sort_unstable();
+    let palette = median_cut(&colors, max_colors);
+    let palette = refine(&colors, palette);
+    let indices = remap(
+        &pixels,
+        image.width as usize,
+        &palette,
+        options.dithering.clamp(0.0, 1.0),
+    );
+
+    IndexedImage {
+        width: image.width,
+        height: image.height,
+        palette,
+        indices,
+    }
+}
+
+/// Quantize with `options.max_colors`, then keep halving the palette while
+/// the result still scores at least `target_quality`. Returns the chosen
+/// image and its score.
+pub fn quantize_to_quality(
+    image: &ImageData,
+    options: &QuantizeOptions,
+    target_quality: u8,
+) -> (IndexedImage, u8) {
+    let mut options = *options;
+    let mut best = quantize(image, &options);
+    let mut score = quality_score(image, &best.to_rgba());
+    if score < target_quality {
+        return (best, score);
+    }
+    while best.palette.len() > 2 {
+        options.max_colors = (best.palette.len() / 2) as u16;
+        let candidate = quantize(image, &options);
+        let candidate_score = quality_score(image, &candidate.to_rgba());
+        if candidate_score < target_quality {
+            break;
+        }
+        best = candidate;
+        score = candidate_score;
+    }
+    (best, score)
+}
+
+/// Score (0..=100) how closely `quantized` reproduces `original`.
+///
+/// The score is linear in PSNR: 20 dB or worse scores 0 and 45 dB or better
+/// scores 100. Colour error is weighted by the original pixel's alpha, so
+/// changes hidden under transparency do not count.
+pub fn quality_score(original: &ImageData, quantized: &ImageData) -> u8 {
+    let original = original.as_pixel_format(PixelFormat::Rgba8);
+    let quantized = quantized.as_pixel_format(PixelFormat::Rgba8);
+    let mut sum = 0.0f64;
+    for (a, b) in original
+        .data
+        .chunks_exact(4)
+        .zip(quantized.data.chunks_exact(4))
+    {
+        let weight = a[3] as f64 / 255.0;
+        for c in 0..3 {
+            let d = a[c] as f64 - b[c] as f64;
+            sum += d * d * weight;
+        }
+        let d = a[3] as f64 - b[3] as f64;
+        sum += d * d;
+    }
+
+    let samples = (original.data.len().max(4)) as f64;
+    let mse = sum / samples;
+    if mse == 0.0 {
+        return 100;
+    }
+
+    let psnr = 10.0 * (255.0f64 * 255.0 / mse).log10();
+    ((psnr - 20.0) * 4.0).clamp(0.0, 100.0).round() as u8
+}
+
+/// Fully transparent pixels carry no visible colour; collapse them.
+fn normalize(px: &[u8]) -> [u8; 4] {
+    if px[3] == 0 {
+        [0, 0, 0, 0]
+    } else {
+        [px[0], px[1], px[2], px[3]]
+    }
+}
+
+fn histogram(pixels: &[[u8; 4]]) -> HashMap<[u8; 4], u32> {
+    let mut counts = HashMap::new();
+    for &px in pixels {
+        *counts.entry(px).or_insert(0u32) += 1;
+    }
+    counts
+}
+
+/// A box of colours in RGBA space used by median cut.
+struct ColorBox {
+    colors: Vec<([u8; 4], u32)>,
+}
+
+impl ColorBox {
+    /// The channel with the widest spread and its range.
+    fn widest_channel(&self) -> (usize, u8) {
+        let mut best = (0, 0);
+        for ch in 0..4 {
+            let (lo, hi) = self
+                .colors
+                .iter()
+                .fold((255u8, 0u8), |(lo, hi), (c, _)| (lo.min(c[ch]), hi.max(c[ch])));
+            let range = hi - lo;
+            if range > best.1 {
+                best = (ch, range);
+            }
+        }
+        best
+    }
+
+    fn mean(&self) -> [u8; 4] {
+        weighted_mean(self.colors.iter().map(|&(c, n)| (c, n as u64)))
+    }
+}
+
+fn weighted_mean(colors: impl Iterator<Item = ([u8; 4], u64)>) -> [u8; 4] {
+    let mut sum = [0u64; 4];
+    let mut total = 0u64;
+    for (c, n) in colors {
+        for ch in 0..4 {
+            sum[ch] += c[ch] as u64 * n;
+        }
+        total += n;
+    }
+    let total = total.max(1);
+    let mut out = [0u8; 4];
+    for ch in 0..4 {
+        out[ch] = ((sum[ch] + total / 2) / total) as u8;
+    }
+    out
+}
+
+fn median_cut(colors: &[([u8; 4], u32)], max_colors: usize) -> Vec<[u8; 4]> {
+    let mut boxes = vec![ColorBox {
+        colors: colors.to_vec(),
+    }];
+
+    while boxes.len() < max_colors {
+        // Split the box with the widest channel, weighted by its pixel count.
+        let candidate = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.colors.len() > 1)
+            .map(|(i, b)| {
+                let (ch, range) = b.widest_channel();
+                let pixels: u64 = b.colors.iter().map(|&(_, n)| n as u64).sum();
+                (i, ch, range as u64 * pixels)
+            })
+            .max_by_key(|&(_, _, score)| score);
+
+        let Some((index, channel, _)) = candidate else {
+            break;
+        };
+
+        let mut target = boxes.swap_remove(index);
+        target.colors.sort_unstable_by_key(|(c, _)| c[channel]);
+
+        let total: u64 = target.colors.iter().map(|&(_, n)| n as u64).sum();
+        let mut acc = 0u64;
+        let mut split = 1;
+        for (i, &(_, n)) in target.colors.iter().enumerate() {
+            acc += n as u64;
+            if acc * 2 >= total {
+                split = (i + 1).clamp(1, target.colors.len() - 1);
+                break;
+            }
+        }
+
+        let upper = target.colors.split_off(split);
+        boxes.push(target);
+        boxes.push(ColorBox { colors: upper });
+    }
+
+    boxes.iter().map(ColorBox::mean).collect()
+}
+
+/// Move each palette entry to the mean of the colours nearest to it.
+fn refine(colors: &[([u8; 4], u32)], palette: Vec<[u8; 4]>) -> Vec<[u8; 4]> {
+    let mut sums = vec![([0u64; 4], 0u64); palette.len()];
+    for &(c, n) in colors {
+        let i = nearest(&palette, c);
+        for (sum, &v) in sums[i].0.iter_mut().zip(&c) {
+            *sum += v as u64 * n as u64;
+        }
+        sums[i].1 += n as u64;
+    }
+
+    palette
+        .iter()
+        .zip(sums)
+        .map(|(&old, (sum, total))| {
+            if total == 0 {
+                return old;
+            }
+            let mut out = [0u8; 4];
+            for ch in 0..4 {
+                out[ch] = ((sum[ch] + total / 2) / total) as u8;
+            }
+            out
+        })
+        .collect()
+}
+
+fn nearest(palette: &[[u8; 4]], color: [u8; 4]) -> usize {
+    let mut best = (0, u32::MAX);
+    for (i, p) in palette.iter().enumerate() {
+        let mut dist = 0u32;
+        for ch in 0..4 {
+            let d = p[ch] as i32 - color[ch] as i32;
+            dist += (d * d) as u32;
+        }
+        if dist < best.1 {
+            best = (i, dist);
+            if dist == 0 {
+                break;
+            }
+        }
+    }
+    best.0
+}
+
+fn remap(pixels: &[[u8; 4]], width: usize, palette: &[[u8; 4]], dithering: f32) -> Vec<u8> {
+    let mut cache: HashMap<[u8; 4], u8> = HashMap::new();
+    let mut lookup = |c: [u8; 4]| -> u8 {
+        *cache
+            .entry(c)
+            .or_insert_with(|| nearest(palette, c) as u8)
+    };
+
+    if dithering == 0.0 || width == 0 {
+        return pixels.iter().map(|&px| lookup(px)).collect();
+    }
+
+    // Error rows carry one extra cell on each side to avoid edge checks.
+    let mut current = vec![[0f32; 4]; width + 2];
+    let mut next = vec![[0f32; 4]; width + 2];
+    let mut indices = Vec::with_capacity(pixels.len());
+
+    for row in pixels.chunks_exact(width) {
+        for (x, &px) in row.iter().enumerate() {
+            if px[3] == 0 {
+                indices.push(lookup(px));
+                continue;
+            }
+
+            let mut value = [0u8; 4];
+            for ch in 0..4 {
+                value[ch] = (px[ch] as f32 + current[x + 1][ch]).round().clamp(0.0, 255.0) as u8;
+            }
+
+            let index = lookup(value);
+            indices.push(index);
+
+            let chosen = palette[index as usize];
+            for ch in 0..4 {
+                let err = (value[ch] as f32 - chosen[ch] as f32) * dithering;
+                current[x + 2][ch] += err * 7.0 / 16.0;
+                next[x][ch] += err * 3.0 / 16.0;
+                next[x + 1][ch] += err * 5.0 / 16.0;
+                next[x + 2][ch] += err / 16.0;
+            }
+        }
+
+        std::mem::swap(&mut current, &mut next);
+        next.iter_mut().for_each(|e| *e = [0.0; 4]);
+    }
+
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_image(width: u32, height: u32) -> ImageData {
+        let mut data = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let r = ((x * 255) / width.max(1)) as u8;
+                let g = ((y * 255) / height.max(1)) as u8;
+                data.extend_from_slice(&[r, g, 128, 255]);
+            }
+        }
+        ImageData::new(width, height, data)
+    }
+
+    #[test]
+    fn few_colours_are_mapped_exactly() {
+        let mut data = Vec::new();
+        for i in 0..16u8 {
+            data.extend_from_slice(&[i * 16, 0, 255 - i * 16, 255]);
+        }
+        let image = ImageData::new(4, 4, data);
+
+        let indexed = quantize(&image, &QuantizeOptions::default());
+
+        assert_eq!(indexed.palette.len(), 16);
+        assert_eq!(indexed.to_rgba().data, image.data);
+        assert_eq!(quality_score(&image, &indexed.to_rgba()), 100);
+    }
+
+    #[test]
+    fn palette_respects_max_colors() {
+        let image = create_test_image(64, 64);
+        let options = QuantizeOptions {
+            max_colors: 16,
+            ..Default::default()
+        };
+
+        let indexed = quantize(&image, &options);
+
+        assert!(indexed.palette.len() <= 16);
+        assert_eq!(indexed.indices.len(), 64 * 64);
+        assert!(indexed.indices.iter().all(|&i| (i as usize) < indexed.palette.len()));
+    }
+
+    #[test]
+    fn transparent_pixels_share_one_entry() {
+        let mut image = create_test_image(32, 32);
+        for px in image.data.chunks_exact_mut(4).step_by(2) {
+            px[3] = 0;
+        }
+
+        let indexed = quantize(&image, &QuantizeOptions::default());
+        let rgba = indexed.to_rgba();
+
+        for (orig, out) in image.data.chunks_exact(4).zip(rgba.data.chunks_exact(4)) {
+            if orig[3] == 0 {
+                assert_eq!(out, [0, 0, 0, 0]);
+            }
+        }
+    }
+
+    #[test]
+    fn more_colours_score_higher() {
+        let image = create_test_image(64, 64);
+        let score = |max_colors| {
+            let options = QuantizeOptions {
+                max_colors,
+                dithering: 0.0,
+            };
+            quality_score(&image, &quantize(&image, &options).to_rgba())
+        };
+
+        assert!(score(256) > score(4));
+    }
+
+    #[test]
+    fn quality_score_identical_is_100() {
+        let image = create_test_image(8, 8);
+        assert_eq!(quality_score(&image, &image), 100);
+    }
+}
@@ -1,8 +1,13 @@
-use image::imageops::FilterType;
-use image::{DynamicImage, RgbaImage};
+use std::sync::LazyLock;
 
-use crate::codec::ImageData;
+use fast_image_resize as fir;
+use fast_image_resize::images::{Image, ImageRef};
+use fast_image_resize::{FilterType, PixelComponentMapper, PixelType, ResizeAlg, Resizer};
+
+use crate::codec::{ImageData, PixelFormat};
 use crate::error::{Error, Result};
+use crate::extend::{self, ExtendMode, FillColor};
+use crate::gravity::Gravity;
 
 /// How to resize an image.
 #[derive(Debug, Clone, PartialEq)]
@@ -17,6 +22,101 @@ pub enum ResizeMode {
     Fit(u32, u32),
     /// Scale factor (e.g. 0.5 = half size).
     Scale(f64),
+    /// Fill the exact dimensions, preserving aspect ratio and cropping
+    /// whatever overflows (centered).
+    Cover(u32, u32),
+    /// Fit within the dimensions, then pad to exactly that size with the
+    /// fill color (centered).
+    Contain(u32, u32, FillColor),
+    /// Shrink until the image has at most this many pixels, preserving
+    /// aspect ratio. Smaller images are left alone.
+    MaxPixels(u64),
+}
+
+impl ResizeMode {
+    /// Whether the image content keeps its aspect ratio, so that not
+    /// enlarging it means keeping its original size.
+    fn keeps_aspect_ratio(&self) -> bool {
+        !matches!(self, Self::Exact(..) | Self::Cover(..))
+    }
+}
+
+/// Resampling filter used to compute the resized pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResizeFilter {
+    /// Copy the closest source pixel. Fastest, and keeps pixel art crisp.
+    Nearest,
+    /// Linear interpolation between neighbouring pixels. Soft but cheap.
+    Bilinear,
+    /// Catmull-Rom bicubic. Sharper than bilinear at a moderate cost.
+    CatmullRom,
+    /// Mitchell-Netravali bicubic. Smoother than Catmull-Rom, with less
+    /// ringing around hard edges.
+    Mitchell,
+    /// Three-lobe Lanczos. The sharpest and slowest.
+    #[default]
+    Lanczos3,
+}
+
+impl ResizeFilter {
+    fn algorithm(self) -> ResizeAlg {
+        match self {
+            Self::Nearest => ResizeAlg::Nearest,
+            Self::Bilinear => ResizeAlg::Convolution(FilterType::Bilinear),
+            Self::CatmullRom => ResizeAlg::Convolution(FilterType::CatmullRom),
+            Self::Mitchell => ResizeAlg::Convolution(FilterType::Mitchell),
+            Self::Lanczos3 => ResizeAlg::Convolution(FilterType::Lanczos3),
+        }
+    }
+}
+
+/// Whether to filter in linear light instead of on the stored,
+/// gamma-encoded sample values.
+///
+/// Averaging encoded values darkens fine detail and high-contrast edges
+/// when shrinking, so large downscales look best in linear light. It is
+/// not free: 8-bit images are filtered as 16-bit samples to keep the
+/// shadows apart, which makes a resize about five times slower. Slight
+/// downscales and enlargements change little and skip it by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LinearLight {
+    /// Linear light when the image is scaled to half its size or less
+    /// along either axis.
+    #[default]
+    Auto,
+    /// Always convert to linear light.
+    Always,
+    /// Filter the stored values as they are.
+    Never,
+}
+
+/// How [`resize_with`] resamples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResizeOptions {
+    /// Resampling filter.
+    pub filter: ResizeFilter,
+    /// Never scale the image up. Width, Height, Fit and Scale keep an image
+    /// that is already small enough at its size, and Contain pads it
+    /// without enlarging it. Exact and Cover always produce their size.
+    pub without_enlargement: bool,
+    /// When to filter in linear light. Samples are assumed to be
+    /// sRGB-encoded; HDR images and the nearest filter are never converted.
+    pub linear_light: LinearLight,
+    /// Weight colour by alpha while filtering, so fully transparent pixels
+    /// do not bleed their (usually black) colour into the edges of opaque
+    /// ones.
+    pub premultiply_alpha: bool,
+}
+
+impl Default for ResizeOptions {
+    fn default() -> Self {
+        Self {
+            filter: ResizeFilter::default(),
+            without_enlargement: false,
+            linear_light: LinearLight::default(),
+            premultiply_alpha: true,
+        }
+    }
 }
 
 /// Calculate the target dimensions for a resize operation.
@@ -49,6 +149,19 @@ pub fn calculate_dimensions(orig_w: u32, orig_h: u32, mode: &ResizeMode) -> Resu
             let target_h = (orig_h as f64 * factor).round() as u32;
             (target_w, target_h)
         }
+        ResizeMode::Cover(w, h) | ResizeMode::Contain(w, h, _) => (w, h),
+        ResizeMode::MaxPixels(max) => {
+            let pixels = orig_w as f64 * orig_h as f64;
+            if pixels <= max as f64 {
+                (orig_w, orig_h)
+            } else {
+                // Round down so the result stays within the budget.
+                let ratio = (max as f64 / pixels).sqrt();
+                let target_w = (orig_w as f64 * ratio).floor() as u32;
+                let target_h = (orig_h as f64 * ratio).floor() as u32;
+                (target_w, target_h)
+            }
+        }
     };
 
     if w == 0 || h == 0 {
@@ -60,38 +173,211 @@ pub fn calculate_dimensions(orig_w: u32, orig_h: u32, mode: &ResizeMode) -> Resu
     Ok((w, h))
 }
 
-/// Resize an image according to the given mode.
+/// Resize an image according to the given mode with default options.
 pub fn resize(image: &ImageData, mode: &ResizeMode) -> Result<ImageData> {
+    resize_with(image, mode, &ResizeOptions::default())
+}
+
+/// Resize an image according to the given mode and options.
+///
+/// The samples are resampled straight from `image.data` into the output
+/// buffer in their own pixel format, using SIMD where the CPU has it.
+pub fn resize_with(
+    image: &ImageData,
+    mode: &ResizeMode,
+    options: &ResizeOptions,
+) -> Result<ImageData> {
     let (target_w, target_h) = calculate_dimensions(image.width, image.height, mode)?;
 
-    let rgba =
-        RgbaImage::from_raw(image.width, image.height, image.data.clone()).ok_or_else(|| {
-            Error::Resize(format!(
-                "failed to create RgbaImage from {}x{} data ({} bytes)",
+    // Contain resamples to the fitted size and pads the rest.
+    let (mut scaled_w, mut scaled_h) = match *mode {
+        ResizeMode::Contain(w, h, _) => {
+            calculate_dimensions(image.width, image.height, &ResizeMode::Fit(w, h))?
+        }
+        _ => (target_w, target_h),
+    };
+    if options.without_enlargement && mode.keeps_aspect_ratio() && scaled_w > image.width {
+        (scaled_w, scaled_h) = (image.width, image.height);
+    }
+
+    let scaled = if (scaled_w, scaled_h) == (image.width, image.height) {
+        image.clone()
+    } else {
+        resample(image, scaled_w, scaled_h, options, mode)?
+    };
+
+    match *mode {
+        ResizeMode::Contain(w, h, fill) => {
+            let size = ExtendMode::Size {
+                width: w,
+                height: h,
+                gravity: Gravity::Center,
+            };
+            extend::extend(&scaled, &size, &fill)
+        }
+        _ => Ok(scaled),
+    }
+}
+
+/// Table-driven sRGB to linear conversion and back for integer samples.
+static SRGB_MAPPER: LazyLock<PixelComponentMapper> = LazyLock::new(fir::create_srgb_mapper);
+
+/// Resample `image` to `width`x`height`. Cover crops the source to the
+/// target aspect ratio on the way, so no intermediate image is made.
+fn resample(
+    image: &ImageData,
+    width: u32,
+    height: u32,
+    options: &ResizeOptions,
+    mode: &ResizeMode,
+) -> Result<ImageData> {
+    // Nearest copies pixels rather than blending them, and premultiplying
+    // an opaque image changes nothing but costs a pass.
+    let blends = options.filter != ResizeFilter::Nearest;
+    let mut fir_options = fir::ResizeOptions::new()
+        .resize_alg(options.filter.algorithm())
+        .use_alpha(blends && options.premultiply_alpha && !is_opaque(image));
+    if let ResizeMode::Cover(..) = mode {
+        fir_options = fir_options.fit_into_destination(None);
+    }
+
+    // Cover samples a cropped source, which only shrinks if both sides do.
+    let halving = |to: u32, from: u32| u64::from(to) * 2 <= u64::from(from);
+    let large_shrink = match mode {
+        ResizeMode::Cover(..) => halving(width, image.width) && halving(height, image.height),
+        _ => halving(width, image.width) || halving(height, image.height),
+    };
+    let linear = match options.linear_light {
+        LinearLight::Auto => large_shrink,
+        LinearLight::Always => true,
+        LinearLight::Never => false,
+    } && blends
+        && image.metadata.hdr.is_none();
+
+    let data = match image.pixel_format {
+        _ if !linear => resample_raw(image, width, height, &fir_options)?,
+        // 8-bit linear would merge the darkest levels into a few bands, so
+        // 8-bit samples are filtered as 16-bit linear.
+        PixelFormat::Rgba8 => {
+            let mut linear = Image::new(image.width, image.height, PixelType::U16x4);
+            SRGB_MAPPER
+                .forward_map(&image_ref(image)?, &mut linear)
+                .map_err(|e| Error::Resize(e.to_string()))?;
+            let linear = ImageData::from_raw(
                 image.width,
                 image.height,
-                image.data.len(),
-            ))
-        })?;
+                PixelFormat::Rgba16,
+                linear.into_vec(),
+            );
+            let resized = resample_raw(&linear, width, height, &fir_options)?;
+            let resized = Image::from_vec_u8(width, height, resized, PixelType::U16x4)
+                .map_err(|e| Error::Resize(e.to_string()))?;
+            let mut encoded = Image::new(width, height, PixelType::U8x4);
+            SRGB_MAPPER
+                .backward_map(&resized, &mut encoded)
+                .map_err(|e| Error::Resize(e.to_string()))?;
+            encoded.into_vec()
+        }
+        // 16-bit linear would crush the shadows of 16-bit input, so
+        // deeper formats go through float.
+        PixelFormat::Rgba16 | PixelFormat::Rgba32F => {
+            let mut float = image.to_pixel_format(PixelFormat::Rgba32F);
+            map_colour(&mut float.data, srgb_eotf);
+            let mut data = resample_raw(&float, width, height, &fir_options)?;
+            map_colour(&mut data, srgb_oetf);
+            ImageData::from_raw(width, height, PixelFormat::Rgba32F, data)
+                .to_pixel_format(image.pixel_format)
+                .data
+        }
+    };
+
+    Ok(ImageData::from_raw(width, height, image.pixel_format, data)
+        .with_metadata(image.metadata.clone()))
+}
+
+/// Resample the samples of `image` as they are.
+fn resample_raw(
+    image: &ImageData,
+    width: u32,
+    height: u32,
+    options: &fir::ResizeOptions,
+) -> Result<Vec<u8>> {
+    let src = image_ref(image)?;
+    let mut dst = Image::new(width, height, pixel_type(image.pixel_format));
+    Resizer::new()
+        .resize(&src, &mut dst, options)
+        .map_err(|e| Error::Resize(e.to_string()))?;
+    Ok(dst.into_vec())
+}
+
+fn pixel_type(pixel_format: PixelFormat) -> PixelType {
+    match pixel_format {
+        PixelFormat::Rgba8 => PixelType::U8x4,
+        PixelFormat::Rgba16 => PixelType::U16x4,
+        PixelFormat::Rgba32F => PixelType::F32x4,
+    }
+}
+
+fn image_ref(image: &ImageData) -> Result<ImageRef<'_>> {
+    let pixel_type = pixel_type(image.pixel_format);
+    ImageRef::new(image.width, image.height, &image.data, pixel_type).map_err(|e| {
+        Error::Resize(format!(
+            "invalid {:?} buffer for {}x{} data ({} bytes): {e}",
+            image.pixel_format,
+            image.width,
+            image.height,
+            image.data.len(),
+        ))
+    })
+}
 
-    let dynamic = DynamicImage::ImageRgba8(rgba);
+fn is_opaque(image: &ImageData) -> bool {
+    match image.pixel_format {
+        PixelFormat::Rgba8 => image.data.chunks_exact(4).all(|p| p[3] == u8::MAX),
+        PixelFormat::Rgba16 => image.data.chunks_exact(8).all(|p| p[6..] == [u8::MAX; 2]),
+        PixelFormat::Rgba32F => image
+            .data
+            .chunks_exact(16)
+            .all(|p| f32::from_ne_bytes([p[12], p[13], p[14], p[15]]) >= 1.0),
+    }
+}
 
-    let resized = match mode {
-        ResizeMode::Exact(_, _) => dynamic.resize_exact(target_w, target_h, FilterType::Lanczos3),
-        _ => dynamic.resize(target_w, target_h, FilterType::Lanczos3),
+/// Apply `f` to the colour samples of RGBA f32 data, leaving alpha alone.
+fn map_colour(data: &mut [u8], f: fn(f32) -> f32) {
+    for pixel in data.chunks_exact_mut(16) {
+        for sample in pixel[..12].chunks_exact_mut(4) {
+            let value = f32::from_ne_bytes([sample[0], sample[1], sample[2], sample[3]]);
+            sample.copy_from_slice(&f(value).to_ne_bytes());
+        }
+    }
+}
+
+/// sRGB to linear, mirrored below zero for extended-range floats.
+fn srgb_eotf(encoded: f32) -> f32 {
+    let magnitude = encoded.abs();
+    let linear = if magnitude <= 0.040_45 {
+        magnitude / 12.92
+    } else {
+        ((magnitude + 0.055) / 1.055).powf(2.4)
     };
+    linear.copysign(encoded)
+}
 
-    let output = resized.to_rgba8();
-    Ok(ImageData::new(
-        output.width(),
-        output.height(),
-        output.into_raw(),
-    ))
+/// Linear to sRGB, mirrored below zero for extended-range floats.
+fn srgb_oetf(linear: f32) -> f32 {
+    let magnitude = linear.abs();
+    let encoded = if magnitude <= 0.003_130_8 {
+        magnitude * 12.92
+    } else {
+        1.055 * magnitude.powf(1.0 / 2.4) - 0.055
+    };
+    encoded.copysign(linear)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codec::PixelFormat;
 
     fn create_test_image(width: u32, height: u32) -> ImageData {
         let data = vec![128u8; (width * height * 4) as usize];
@@ -137,4 +423,237 @@ mod tests {
         assert_eq!(result.width, 50);
         assert_eq!(result.height, 50);
     }
+
+    #[test]
+    fn resize_keeps_high_bit_depth() {
+        let img = ImageData::from_rgba16(20, 10, vec![40_000u16; 20 * 10 * 4]);
+        let result = resize(&img, &ResizeMode::Width(10)).unwrap();
+        assert_eq!(result.pixel_format, PixelFormat::Rgba16);
+        assert_eq!((result.width, result.height), (10, 5));
+        assert!(result.rgba16_samples().iter().all(|&s| s == 40_000));
+    }
+
+    #[test]
+    fn every_filter_keeps_a_flat_colour() {
+        let img = ImageData::from_rgba32f(20, 10, vec![0.25; 20 * 10 * 4]);
+        for filter in [
+            ResizeFilter::Nearest,
+            ResizeFilter::Bilinear,
+            ResizeFilter::CatmullRom,
+            ResizeFilter::Mitchell,
+            ResizeFilter::Lanczos3,
+        ] {
+            let options = ResizeOptions {
+                filter,
+                ..Default::default()
+            };
+            let result = resize_with(&img, &ResizeMode::Exact(7, 13), &options).unwrap();
+            assert_eq!(result.pixel_format, PixelFormat::Rgba32F);
+            assert_eq!((result.width, result.height), (7, 13));
+            assert!(
+                result
+                    .rgba32f_samples()
+                    .iter()
+                    .all(|&s| (s - 0.25).abs() < 1e-4),
+                "{filter:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn nearest_copies_source_pixels() {
+        // Left half black, right half white: upscaling must not blend them.
+        let data = (0..4 * 2)
+            .flat_map(|i| if i % 4 < 2 { [0, 0, 0, 255] } else { [255; 4] })
+            .collect();
+        let img = ImageData::new(4, 2, data);
+        let options = ResizeOptions {
+            filter: ResizeFilter::Nearest,
+            ..Default::default()
+        };
+        let result = resize_with(&img, &ResizeMode::Scale(3.0), &options).unwrap();
+        let row = &result.data[..12 * 4];
+        assert!(row[..6 * 4].chunks(4).all(|p| p == [0, 0, 0, 255]));
+        assert!(row[6 * 4..].chunks(4).all(|p| p == [255; 4]));
+    }
+
+    #[test]
+    fn cover_fills_the_size_and_crops_the_sides() {
+        // Red and blue outer quarters around a white middle half.
+        let data = (0..200 * 100)
+            .flat_map(|i| match i % 200 {
+                0..50 => [255, 0, 0, 255],
+                150.. => [0, 0, 255, 255],
+                _ => [255; 4],
+            })
+            .collect();
+        let img = ImageData::new(200, 100, data);
+        let result = resize(&img, &ResizeMode::Cover(50, 50)).unwrap();
+        assert_eq!((result.width, result.height), (50, 50));
+        // The filter reaches a pixel past the crop, so the edges may tint.
+        assert!(result.data.iter().all(|&s| s >= 230));
+        assert_eq!(&result.data[25 * 4..][..4], [255; 4]);
+    }
+
+    #[test]
+    fn contain_pads_to_the_size() {
+        let img = ImageData::new(200, 100, [255, 0, 0, 255].repeat(200 * 100));
+        let blue = FillColor::Solid([0, 0, 255, 255]);
+        let result = resize(&img, &ResizeMode::Contain(100, 100, blue)).unwrap();
+        assert_eq!((result.width, result.height), (100, 100));
+        let pixel = |x: usize, y: usize| &result.data[(y * 100 + x) * 4..][..4];
+        assert_eq!(pixel(50, 0), [0, 0, 255, 255]);
+        assert_eq!(pixel(50, 50), [255, 0, 0, 255]);
+        assert_eq!(pixel(50, 99), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn max_pixels_only_shrinks() {
+        let (w, h) = calculate_dimensions(4000, 3000, &ResizeMode::MaxPixels(2_000_000)).unwrap();
+        assert!(w * h <= 2_000_000);
+        assert_eq!((w, h), (1632, 1224));
+        assert_eq!(
+            calculate_dimensions(400, 300, &ResizeMode::MaxPixels(2_000_000)).unwrap(),
+            (400, 300)
+        );
+    }
+
+    #[test]
+    fn without_enlargement_keeps_small_images() {
+        let img = create_test_image(100, 50);
+        let options = ResizeOptions {
+            without_enlargement: true,
+            ..Default::default()
+        };
+        let size = |mode| {
+            let result = resize_with(&img, &mode, &options).unwrap();
+            (result.width, result.height)
+        };
+        assert_eq!(size(ResizeMode::Width(400)), (100, 50));
+        assert_eq!(size(ResizeMode::Fit(400, 400)), (100, 50));
+        assert_eq!(size(ResizeMode::Scale(2.0)), (100, 50));
+        assert_eq!(size(ResizeMode::Width(50)), (50, 25));
+        assert_eq!(size(ResizeMode::Exact(200, 200)), (200, 200));
+        assert_eq!(size(ResizeMode::Cover(200, 200)), (200, 200));
+
+        // Contain still pads to its size, around the unscaled image.
+        let mode = ResizeMode::Contain(400, 400, FillColor::Transparent);
+        let result = resize_with(&img, &mode, &options).unwrap();
+        assert_eq!((result.width, result.height), (400, 400));
+        let opaque = result.data.chunks(4).filter(|p| p[3] != 0).count();
+        assert_eq!(opaque, 100 * 50);
+    }
+
+    /// A 1px black and white checkerboard.
+    fn checkerboard(size: u32) -> ImageData {
+        let data = (0..size * size)
+            .flat_map(|i| {
+                let (x, y) = (i % size, i / size);
+                if (x + y) % 2 == 0 {
+                    [0, 0, 0, 255]
+                } else {
+                    [255; 4]
+                }
+            })
+            .collect();
+        ImageData::new(size, size, data)
+    }
+
+    #[test]
+    fn downscales_average_in_linear_light() {
+        let img = checkerboard(16);
+        let shrink = |linear_light| {
+            let options = ResizeOptions {
+                filter: ResizeFilter::Bilinear,
+                linear_light,
+                ..Default::default()
+            };
+            let result = resize_with(&img, &ResizeMode::Scale(0.5), &options).unwrap();
+            result.data[(4 * 8 + 4) * 4]
+        };
+        // Half the light of white is sRGB 188, not the 128 halfway value.
+        assert!(shrink(LinearLight::Auto).abs_diff(188) <= 2);
+        assert!(shrink(LinearLight::Always).abs_diff(188) <= 2);
+        assert!(shrink(LinearLight::Never).abs_diff(128) <= 2);
+    }
+
+    #[test]
+    fn enlargements_keep_encoded_values_by_default() {
+        let img = checkerboard(4);
+        let enlarge = |linear_light| {
+            let options = ResizeOptions {
+                linear_light,
+                ..Default::default()
+            };
+            resize_with(&img, &ResizeMode::Scale(2.0), &options)
+                .unwrap()
+                .data
+        };
+        assert_eq!(enlarge(LinearLight::Auto), enlarge(LinearLight::Never));
+        assert_ne!(enlarge(LinearLight::Auto), enlarge(LinearLight::Always));
+    }
+
+    #[test]
+    fn slight_downscales_keep_encoded_values_by_default() {
+        let img = checkerboard(16);
+        let shrink = |linear_light| {
+            let options = ResizeOptions {
+                linear_light,
+                ..Default::default()
+            };
+            resize_with(&img, &ResizeMode::Scale(0.75), &options)
+                .unwrap()
+                .data
+        };
+        assert_eq!(shrink(LinearLight::Auto), shrink(LinearLight::Never));
+        assert_ne!(shrink(LinearLight::Auto), shrink(LinearLight::Always));
+    }
+
+    /// An opaque white square inside a transparent black border.
+    fn sprite() -> ImageData {
+        let data = (0..16 * 16)
+            .flat_map(|i| {
+                let (x, y) = (i % 16, i / 16);
+                if (4..12).contains(&x) && (4..12).contains(&y) {
+                    [255; 4]
+                } else {
+                    [0; 4]
+                }
+            })
+            .collect();
+        ImageData::new(16, 16, data)
+    }
+
+    #[test]
+    fn transparent_edges_do_not_darken_sprites() {
+        for format in [
+            PixelFormat::Rgba8,
+            PixelFormat::Rgba16,
+            PixelFormat::Rgba32F,
+        ] {
+            let img = sprite().to_pixel_format(format);
+            for mode in [ResizeMode::Scale(0.5), ResizeMode::Exact(11, 11)] {
+                let result = resize(&img, &mode).unwrap();
+                let result = result.to_pixel_format(PixelFormat::Rgba8);
+                for pixel in result.data.chunks(4).filter(|p| p[3] > 0) {
+                    assert!(pixel[..3].iter().all(|&c| c >= 254), "{format:?} {pixel:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn straight_alpha_bleeds_transparent_colour() {
+        let options = ResizeOptions {
+            premultiply_alpha: false,
+            ..Default::default()
+        };
+        let result = resize_with(&sprite(), &ResizeMode::Scale(0.5), &options).unwrap();
+        let edge = result
+            .data
+            .chunks(4)
+            .find(|p| p[3] > 0 && p[3] < 255)
+            .unwrap();
+        assert!(edge[0] < 250, "{edge:?}");
+    }
 }
@@ -0,0 +1,534 @@
+use std::borrow::Cow;
+
+use crate::animation::Animation;
+use crate::codec::{Codec, EncodeOptions, ImageData};
+use crate::error::{Error, Result};
+use crate::metrics;
+use crate::resize::{self, ResizeMode, ResizeOptions};
+
+/// Lowest quality a size search will try.
+const MIN_QUALITY: u8 = 0;
+
+/// Highest quality a quality-target search will try.
+const MAX_QUALITY: u8 = 100;
+
+/// An upper bound on the size of the encoded output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeLimit {
+    /// Largest acceptable output in bytes.
+    pub max_bytes: u64,
+    /// Shrink the image when even the lowest quality is too large, instead of
+    /// failing with [`Error::TargetSizeUnreachable`].
+    pub downscale: bool,
+}
+
+impl SizeLimit {
+    /// A limit of `max_bytes` that fails rather than shrinking the image.
+    pub fn new(max_bytes: u64) -> Self {
+        Self {
+            max_bytes,
+            downscale: false,
+        }
+    }
+}
+
+/// A perceptual quality the output should reach, measured by decoding it and
+/// comparing against the source pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QualityTarget {
+    /// Lowest SSIMULACRA2 score to accept. 90 is visually lossless, 70 high
+    /// quality and 50 medium quality.
+    Ssimulacra2(f64),
+    /// Highest DSSIM to accept. 0 is identical; below about 0.001 is hard to
+    /// see.
+    Dssim(f64),
+}
+
+impl QualityTarget {
+    /// Whether `score` is at least as good as the target.
+    fn is_met(&self, score: f64) -> bool {
+        match *self {
+            Self::Ssimulacra2(min) => score >= min,
+            Self::Dssim(max) => score <= max,
+        }
+    }
+}
+
+/// Output of [`encode_within`].
+pub(crate) struct Fitted {
+    pub data: Vec<u8>,
+    /// Quality the output was encoded at, or `None` for lossless output.
+    pub quality: Option<u8>,
+    /// Score under the quality target's metric, when there is a target.
+    pub score: Option<f64>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// An encode together with the quality it was made at.
+struct Candidate {
+    data: Vec<u8>,
+    quality: u8,
+}
+
+/// Outcome of a quality search at one image size.
+enum Search {
+    Fits(Candidate),
+    TooLarge { smallest: u64 },
+}
+
+/// Encode `animation` at the quality `target` calls for, or at
+/// `options.quality` without one, then lower the quality until the output
+/// fits in `limit`. Lossless encodes, and codecs whose size does not follow
+/// the quality, have no quality to search, so only the downscale step
+/// applies to them.
+///
+/// When nothing fits and `limit.downscale` is set, the frames are shrunk
+/// with `resize_options` by the square root of the size overshoot and the
+/// search runs again.
+pub(crate) fn encode_within(
+    codec: &dyn Codec,
+    animation: &Animation,
+    options: &EncodeOptions,
+    target: Option<&QualityTarget>,
+    limit: Option<&SizeLimit>,
+    resize_options: &ResizeOptions,
+) -> Result<Fitted> {
+    let mut scaled = Cow::Borrowed(animation);
+    let mut scale = 1.0;
+    loop {
+        let (ceiling, top_score) = match target {
+            Some(target) if !options.lossless => {
+                let (candidate, score) = aim(codec, &scaled, options, target)?;
+                (candidate, Some(score))
+            }
+            _ => {
+                let data = codec.encode_animation(&scaled, options)?;
+                let candidate = Candidate {
+                    data,
+                    quality: options.quality,
+                };
+                (candidate, None)
+            }
+        };
+        let Some(limit) = limit else {
+            return finish(codec, &scaled, options, target, ceiling, top_score);
+        };
+
+        let ceiling_quality = ceiling.quality;
+        let smallest = match search(codec, &scaled, options, limit.max_bytes, ceiling)? {
+            Search::Fits(candidate) => {
+                let score = top_score.filter(|_| candidate.quality == ceiling_quality);
+                return finish(codec, &scaled, options, target, candidate, score);
+            }
+            Search::TooLarge { smallest } => smallest,
+        };
+        let unreachable = Error::TargetSizeUnreachable {
+            max_bytes: limit.max_bytes,
+            smallest,
+        };
+        if !limit.downscale {
+            return Err(unreachable);
+        }
+
+        // Size tracks the pixel count, so shrink each side by the square root
+        // of the overshoot, aiming a little low to save another round.
+        scale *= ((limit.max_bytes as f64 / smallest as f64).sqrt() * 0.9).min(0.9);
+        let mode = ResizeMode::Scale(scale);
+        match resize::calculate_dimensions(animation.width(), animation.height(), &mode) {
+            Ok(size) if size != (scaled.width(), scaled.height()) => {}
+            _ => return Err(unreachable),
+        }
+        scaled = Cow::Owned(
+            animation.try_map_frames(|image| resize::resize_with(image, &mode, resize_options))?,
+        );
+    }
+}
+
+/// Package the chosen encode, scoring it against `target` unless `score`
+/// already holds its score. Lossless output is not scored.
+fn finish(
+    codec: &dyn Codec,
+    animation: &Animation,
+    options: &EncodeOptions,
+    target: Option<&QualityTarget>,
+    candidate: Candidate,
+    score: Option<f64>,
+) -> Result<Fitted> {
+    let score = match (target, score) {
+        (Some(target), None) if !options.lossless => {
+            Some(measure(codec, target, animation, &candidate.data)?)
+        }
+        _ => score,
+    };
+    Ok(Fitted {
+        data: candidate.data,
+        quality: (!options.lossless).then_some(candidate.quality),
+        score,
+        width: animation.width(),
+        height: animation.height(),
+    })
+}
+
+/// Find the lowest quality whose output reaches `target`, by bisection on
+/// the assumption that the score improves with quality. When even
+/// [`MAX_QUALITY`] falls short, that is the closest the codec gets.
+fn aim(
+    codec: &dyn Codec,
+    animation: &Animation,
+    options: &EncodeOptions,
+    target: &QualityTarget,
+) -> Result<(Candidate, f64)> {
+    let attempt = |quality: u8| -> Result<(Candidate, f64)> {
+        let options = EncodeOptions {
+            quality,
+            ..*options
+        };
+        let data = codec.encode_animation(animation, &options)?;
+        let score = measure(codec, target, animation, &data)?;
+        Ok((Candidate { data, quality }, score))
+    };
+
+    let mut best = attempt(MAX_QUALITY)?;
+    if !target.is_met(best.1) {
+        return Ok(best);
+    }
+
+    // `meets` always reaches the target and `misses` never does; the quality
+    // below the lowest one is taken to miss without trying it.
+    let (mut misses, mut meets) = (i32::from(MIN_QUALITY) - 1, i32::from(MAX_QUALITY));
+    while meets - misses > 1 {
+        let mid = (misses + meets) / 2;
+        let (candidate, score) = attempt(mid as u8)?;
+        if target.is_met(score) {
+            meets = mid;
+            best = (candidate, score);
+        } else {
+            misses = mid;
+        }
+    }
+    Ok(best)
+}
+
+/// Decode `data` and score it against `reference` with the target's metric.
+fn measure(
+    codec: &dyn Codec,
+    target: &QualityTarget,
+    reference: &Animation,
+    data: &[u8],
+) -> Result<f64> {
+    match target {
+        QualityTarget::Ssimulacra2(_) => {
+            score_frames(codec, reference, data, metrics::ssimulacra2, f64::min)
+        }
+        QualityTarget::Dssim(_) => score_frames(codec, reference, data, metrics::dssim, f64::max),
+    }
+}
+
+/// Decode `data` and score every frame against `reference` with `metric`,
+/// returning the score `worse` picks across frames.
+pub(crate) fn score_frames(
+    codec: &dyn Codec,
+    reference: &Animation,
+    data: &[u8],
+    metric: fn(&ImageData, &ImageData) -> Result<f64>,
+    worse: fn(f64, f64) -> f64,
+) -> Result<f64> {
+    let decoded = codec.decode_animation(data)?;
+    if decoded.frames.len() != reference.frames.len() {
+        return Err(Error::Encode(format!(
+            "cannot score {:?} output: it decodes to {} frame(s), not {}",
+            codec.format(),
+            decoded.frames.len(),
+            reference.frames.len()
+        )));
+    }
+    let mut worst = None;
+    for (reference, decoded) in reference.frames.iter().zip(&decoded.frames) {
+        let score = metric(&reference.image, &decoded.image)?;
+        worst = Some(worst.map_or(score, |worst| worse(worst, score)));
+    }
+    worst.ok_or_else(|| Error::Encode("animation has no frames".to_string()))
+}
+
+/// Find the highest quality up to the `ceiling` candidate's whose output is
+/// at most `max_bytes`, by bisection on the assumption that size grows with
+/// quality.
+fn search(
+    codec: &dyn Codec,
+    animation: &Animation,
+    options: &EncodeOptions,
+    max_bytes: u64,
+    ceiling: Candidate,
+) -> Result<Search> {
+    let encode = |quality: u8| {
+        let options = EncodeOptions {
+            quality,
+            ..*options
+        };
+        codec.encode_animation(animation, &options)
+    };
+    let fits = |data: &[u8]| data.len() as u64 <= max_bytes;
+
+    if fits(&ceiling.data) {
+        return Ok(Search::Fits(ceiling));
+    }
+    if !codec.quality_affects_size(options) || ceiling.quality == MIN_QUALITY {
+        return Ok(Search::TooLarge {
+            smallest: ceiling.data.len() as u64,
+        });
+    }
+
+    let bottom = encode(MIN_QUALITY)?;
+    if !fits(&bottom) {
+        return Ok(Search::TooLarge {
+            smallest: ceiling.data.len().min(bottom.len()) as u64,
+        });
+    }
+
+    // `low` always fits and `high` never does.
+    let (mut low, mut high) = (MIN_QUALITY, ceiling.quality);
+    let mut best = bottom;
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        let data = encode(mid)?;
+        if fits(&data) {
+            low = mid;
+            best = data;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(Search::Fits(Candidate {
+        data: best,
+        quality: low,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{ImageData, get_codec};
+    use crate::format::Format;
+    use crate::resize::ResizeFilter;
+
+    fn noise(width: u32, height: u32) -> Animation {
+        let mut state = 0x2545_f491u32;
+        let data = (0..width * height * 4)
+            .map(|i| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                if i % 4 == 3 { 255 } else { state as u8 }
+            })
+            .collect();
+        Animation::from_still(ImageData::new(width, height, data))
+    }
+
+    #[test]
+    fn quality_is_lowered_until_output_fits() {
+        let codec = get_codec(Format::Jpeg);
+        let animation = noise(64, 64);
+        let options = EncodeOptions {
+            quality: 95,
+            ..Default::default()
+        };
+        let full = codec.encode_animation(&animation, &options).unwrap().len() as u64;
+        let limit = SizeLimit::new(full / 2);
+
+        let fitted = encode_within(
+            codec.as_ref(),
+            &animation,
+            &options,
+            None,
+            Some(&limit),
+            &ResizeOptions::default(),
+        )
+        .unwrap();
+
+        assert!(fitted.data.len() as u64 <= limit.max_bytes);
+        let quality = fitted.quality.unwrap();
+        assert!(quality < 95);
+        // The next quality up would not have fitted.
+        let above = EncodeOptions {
+            quality: quality + 1,
+            ..options
+        };
+        let above = codec.encode_animation(&animation, &above).unwrap();
+        assert!(above.len() as u64 > limit.max_bytes);
+    }
+
+    #[test]
+    fn output_under_the_limit_keeps_the_requested_quality() {
+        let codec = get_codec(Format::Jpeg);
+        let animation = noise(16, 16);
+        let options = EncodeOptions::default();
+
+        let fitted = encode_within(
+            codec.as_ref(),
+            &animation,
+            &options,
+            None,
+            Some(&SizeLimit::new(1 << 20)),
+            &ResizeOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(fitted.quality, Some(80));
+    }
+
+    #[test]
+    fn unreachable_limit_fails_or_downscales() {
+        let codec = get_codec(Format::Png);
+        let animation = noise(64, 64);
+        let options = EncodeOptions {
+            lossless: true,
+            ..Default::default()
+        };
+        let limit = SizeLimit::new(4096);
+
+        let err = encode_within(
+            codec.as_ref(),
+            &animation,
+            &options,
+            None,
+            Some(&limit),
+            &ResizeOptions::default(),
+        );
+        assert!(matches!(
+            err,
+            Err(Error::TargetSizeUnreachable {
+                max_bytes: 4096,
+                ..
+            })
+        ));
+
+        let limit = SizeLimit {
+            downscale: true,
+            ..limit
+        };
+        let fitted = encode_within(
+            codec.as_ref(),
+            &animation,
+            &options,
+            None,
+            Some(&limit),
+            &ResizeOptions::default(),
+        )
+        .unwrap();
+        assert!(fitted.data.len() <= 4096);
+        assert!(fitted.width < 64 && fitted.width == fitted.height);
+        assert_eq!(fitted.quality, None);
+    }
+
+    #[test]
+    fn png_without_quantize_downscales_instead_of_searching_quality() {
+        let codec = get_codec(Format::Png);
+        let mut options = EncodeOptions::default();
+        assert!(!codec.quality_affects_size(&options));
+        options.codec.png.quantize = Some(Default::default());
+        assert!(codec.quality_affects_size(&options));
+
+        let options = EncodeOptions::default();
+        let limit = SizeLimit {
+            downscale: true,
+            ..SizeLimit::new(4096)
+        };
+        let fitted = encode_within(
+            codec.as_ref(),
+            &noise(64, 64),
+            &options,
+            None,
+            Some(&limit),
+            &ResizeOptions::default(),
+        )
+        .unwrap();
+        assert!(fitted.data.len() <= 4096);
+        assert!(fitted.width < 64);
+        assert_eq!(fitted.quality, Some(options.quality));
+    }
+
+    #[test]
+    fn downscale_uses_the_callers_resize_options() {
+        let codec = get_codec(Format::Png);
+        let mut state = 0x2545_f491u32;
+        let data = (0..64 * 64)
+            .flat_map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                let v = if state & 1 == 0 { 0 } else { 255 };
+                [v, v, v, 255]
+            })
+            .collect();
+        let animation = Animation::from_still(ImageData::new(64, 64, data));
+        let options = EncodeOptions {
+            lossless: true,
+            ..Default::default()
+        };
+        let limit = SizeLimit {
+            downscale: true,
+            ..SizeLimit::new(256)
+        };
+        let resize_options = ResizeOptions {
+            filter: ResizeFilter::Nearest,
+            ..Default::default()
+        };
+
+        let fitted = encode_within(
+            codec.as_ref(),
+            &animation,
+            &options,
+            None,
+            Some(&limit),
+            &resize_options,
+        )
+        .unwrap();
+
+        assert!(fitted.width < 64);
+        let decoded = codec.decode(&fitted.data).unwrap();
+        // Nearest copies source pixels, so no grey appears.
+        assert!(decoded.data.iter().all(|&v| v == 0 || v == 255));
+    }
+
+    fn gradient(width: u32, height: u32) -> Animation {
+        let data = (0..width * height)
+            .flat_map(|i| {
+                let (x, y) = (i % width, i / width);
+                [(x * 4) as u8, (y * 4) as u8, ((x + y) * 2) as u8, 255]
+            })
+            .collect();
+        Animation::from_still(ImageData::new(width, height, data))
+    }
+
+    #[test]
+    fn quality_target_picks_the_lowest_quality_that_meets_it() {
+        let codec = get_codec(Format::Jpeg);
+        let animation = gradient(64, 64);
+        let target = QualityTarget::Dssim(0.005);
+        let options = EncodeOptions::default();
+
+        let fitted = encode_within(
+            codec.as_ref(),
+            &animation,
+            &options,
+            Some(&target),
+            None,
+            &ResizeOptions::default(),
+        )
+        .unwrap();
+
+        let quality = fitted.quality.unwrap();
+        assert!(target.is_met(fitted.score.unwrap()));
+        // One step lower would have missed.
+        if quality > MIN_QUALITY {
+            let below = EncodeOptions {
+                quality: quality - 1,
+                ..options
+            };
+            let data = codec.encode_animation(&animation, &below).unwrap();
+            let score = measure(codec.as_ref(), &target, &animation, &data).unwrap();
+            assert!(!target.is_met(score));
+        }
+    }
+}
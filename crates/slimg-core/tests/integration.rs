@@ -21,12 +21,8 @@ fn convert_jpeg_to_webp() {
 
     // Encode as JPEG first
     let jpeg_options = PipelineOptions {
-        format: Format::Jpeg,
         quality: 90,
-        resize: None,
-        crop: None,
-        extend: None,
-        fill_color: None,
+        ..PipelineOptions::new(Format::Jpeg)
     };
     let jpeg_result = convert(&image, &jpeg_options).expect("JPEG encode failed");
     assert!(!jpeg_result.data.is_empty());
@@ -39,14 +35,7 @@ fn convert_jpeg_to_webp() {
     assert_eq!(decoded.height, 80);
 
     // Convert the decoded image to WebP
-    let webp_options = PipelineOptions {
-        format: Format::WebP,
-        quality: 80,
-        resize: None,
-        crop: None,
-        extend: None,
-        fill_color: None,
-    };
+    let webp_options = PipelineOptions::new(Format::WebP);
     let webp_result = convert(&decoded, &webp_options).expect("WebP encode failed");
     assert!(!webp_result.data.is_empty());
     assert_eq!(webp_result.format, Format::WebP);
@@ -61,12 +50,8 @@ fn convert_with_resize() {
     let image = create_test_image();
 
     let options = PipelineOptions {
-        format: Format::Png,
-        quality: 80,
         resize: Some(ResizeMode::Width(50)),
-        crop: None,
-        extend: None,
-        fill_color: None,
+        ..PipelineOptions::new(Format::Png)
     };
     let result = convert(&image, &options).expect("PNG encode with resize failed");
     assert!(!result.data.is_empty());
@@ -86,14 +71,7 @@ fn roundtrip_all_encodable_formats() {
     let formats = vec![Format::Jpeg, Format::Png, Format::WebP, Format::Qoi, Format::Avif];
 
     for fmt in formats {
-        let options = PipelineOptions {
-            format: fmt,
-            quality: 80,
-            resize: None,
-            crop: None,
-            extend: None,
-            fill_color: None,
-        };
+        let options = PipelineOptions::new(fmt);
 
         // Encode
         let result = convert(&image, &options).unwrap_or_else(|e| {
@@ -119,12 +97,8 @@ fn convert_with_crop_region() {
     let image = create_test_image(); // 100x80
 
     let options = PipelineOptions {
-        format: Format::Png,
-        quality: 80,
-        resize: None,
         crop: Some(CropMode::Region { x: 10, y: 10, width: 50, height: 40 }),
-        extend: None,
-        fill_color: None,
+        ..PipelineOptions::new(Format::Png)
     };
     let result = convert(&image, &options).expect("PNG encode with crop failed");
     assert!(!result.data.is_empty());
@@ -139,12 +113,12 @@ fn convert_with_crop_aspect_ratio() {
     let image = create_test_image(); // 100x80
 
     let options = PipelineOptions {
-        format: Format::WebP,
-        quality: 80,
-        resize: None,
-        crop: Some(CropMode::AspectRatio { width: 1, height: 1 }),
-        extend: None,
-        fill_color: None,
+        crop: Some(CropMode::AspectRatio {
+            width: 1,
+            height: 1,
+            gravity: Gravity::Center,
+        }),
+        ..PipelineOptions::new(Format::WebP)
     };
     let result = convert(&image, &options).expect("WebP encode with crop failed");
     assert!(!result.data.is_empty());
@@ -159,12 +133,13 @@ fn extend_aspect_ratio_jpeg() {
     let image = create_test_image(); // 100x80
 
     let options = PipelineOptions {
-        format: Format::Jpeg,
-        quality: 80,
-        resize: None,
-        crop: None,
-        extend: Some(ExtendMode::AspectRatio { width: 1, height: 1 }),
+        extend: Some(ExtendMode::AspectRatio {
+            width: 1,
+            height: 1,
+            gravity: Gravity::Center,
+        }),
         fill_color: Some(FillColor::Solid([255, 255, 255, 255])),
+        ..PipelineOptions::new(Format::Jpeg)
     };
 
     let result = convert(&image, &options).expect("JPEG encode with extend failed");
@@ -186,12 +161,13 @@ fn extend_size_png() {
     let target_h = image.height + 200;
 
     let options = PipelineOptions {
-        format: Format::Png,
-        quality: 80,
-        resize: None,
-        crop: None,
-        extend: Some(ExtendMode::Size { width: target_w, height: target_h }),
+        extend: Some(ExtendMode::Size {
+            width: target_w,
+            height: target_h,
+            gravity: Gravity::Center,
+        }),
         fill_color: Some(FillColor::Transparent),
+        ..PipelineOptions::new(Format::Png)
     };
 
     let result = convert(&image, &options).expect("PNG encode with extend failed");
@@ -202,3 +178,116 @@ fn extend_size_png() {
     assert_eq!(decoded.width, target_w);
     assert_eq!(decoded.height, target_h);
 }
+
+#[test]
+fn lossless_roundtrip_is_bit_exact() {
+    let mut image = create_test_image();
+    // Include fully transparent pixels that still carry colour.
+    for px in image.data.chunks_exact_mut(4).step_by(5) {
+        px[3] = 0;
+    }
+
+    for fmt in [
+        Format::Png,
+        Format::WebP,
+        Format::Qoi,
+        Format::Tiff,
+        Format::Bmp,
+        Format::Tga,
+        Format::Ico,
+        Format::Pnm,
+    ] {
+        let options = PipelineOptions {
+            lossless: true,
+            ..PipelineOptions::new(fmt)
+        };
+
+        let result = convert(&image, &options).unwrap_or_else(|e| {
+            panic!("lossless encoding to {fmt:?} failed: {e}");
+        });
+        let (decoded, _) = decode(&result.data).expect("decode failed");
+
+        assert_eq!(decoded.data, image.data, "{fmt:?}: pixels differ");
+    }
+
+    // A profile the target cannot keep is dropped, not converted into.
+    let p3 = moxcms::ColorProfile::new_display_p3()
+        .encode()
+        .expect("encode profile");
+    let tagged = image.clone().with_metadata(Metadata {
+        icc: Some(p3),
+        ..Default::default()
+    });
+    for (fmt, metadata) in [
+        (Format::Qoi, MetadataPolicy::Safe),
+        (Format::Png, MetadataPolicy::Strip),
+    ] {
+        let options = PipelineOptions {
+            lossless: true,
+            metadata,
+            ..PipelineOptions::new(fmt)
+        };
+
+        let result = convert(&tagged, &options).expect("lossless encode failed");
+        let (decoded, _) = decode(&result.data).expect("decode failed");
+
+        assert_eq!(decoded.metadata.icc, None, "{fmt:?}");
+        assert_eq!(decoded.data, image.data, "{fmt:?}: pixels differ");
+    }
+}
+
+#[test]
+fn default_policy_keeps_icc_and_copyright_but_drops_gps() {
+    // Big-endian EXIF with Copyright ("abc") and a GPS IFD pointer.
+    let exif = b"MM\0*\0\0\0\x08\0\x02\x82\x98\0\x02\0\0\0\x04abc\0\x88\x25\0\x04\0\0\0\x01\0\0\0\x26\0\0\0\0".to_vec();
+    let icc = vec![0x42u8; 200];
+    let image = create_test_image().with_metadata(Metadata {
+        exif: Some(exif),
+        xmp: Some(b"<x:xmpmeta/>".to_vec()),
+        icc: Some(icc.clone()),
+        ..Default::default()
+    });
+    let jpeg = convert(
+        &image,
+        &PipelineOptions {
+            metadata: MetadataPolicy::Keep,
+            ..PipelineOptions::new(Format::Jpeg)
+        },
+    )
+    .expect("JPEG encode failed");
+
+    let optimized = optimize(&jpeg.data, &EncodeOptions::default()).expect("optimize failed");
+    let (decoded, _) = decode(&optimized.data).expect("decode failed");
+
+    assert_eq!(decoded.metadata.icc, Some(icc));
+    assert_eq!(decoded.metadata.xmp, None);
+    let exif = decoded.metadata.exif.expect("copyright should survive");
+    assert!(exif.windows(4).any(|w| w == b"abc\0"));
+    assert!(!exif.windows(2).any(|w| w == [0x88, 0x25]), "GPS pointer should be dropped");
+}
+
+#[test]
+fn sideways_jpeg_is_upright_after_convert() {
+    // Big-endian EXIF with Orientation = 6 (rotate 90 degrees clockwise).
+    let exif = b"MM\0*\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0\x06\0\0\0\0\0\0".to_vec();
+    let image = create_test_image().with_metadata(Metadata {
+        exif: Some(exif),
+        ..Default::default()
+    });
+    let jpeg = convert(&image, &PipelineOptions::new(Format::Jpeg)).expect("JPEG encode failed");
+
+    let (upright, _) = decode(&jpeg.data).expect("decode failed");
+    assert_eq!((upright.width, upright.height), (80, 100));
+
+    let webp = convert(&upright, &PipelineOptions::new(Format::WebP)).expect("WebP encode failed");
+    let (decoded, _) = decode(&webp.data).expect("decode failed");
+    assert_eq!((decoded.width, decoded.height), (80, 100));
+
+    let raw_options = DecodeOptions {
+        auto_orient: false,
+        ..Default::default()
+    };
+    let (raw, _) = decode_with(&jpeg.data, &raw_options).expect("decode failed");
+    assert_eq!((raw.width, raw.height), (100, 80));
+    assert_eq!(raw.metadata.orientation, Orientation::Rotate90);
+}
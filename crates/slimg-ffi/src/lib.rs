@@ -11,6 +11,12 @@ pub enum Format {
     Avif,
     Jxl,
     Qoi,
+    Gif,
+    Tiff,
+    Bmp,
+    Tga,
+    Ico,
+    Pnm,
 }
 
 impl Format {
@@ -22,6 +28,12 @@ impl Format {
             Format::Avif => slimg_core::Format::Avif,
             Format::Jxl => slimg_core::Format::Jxl,
             Format::Qoi => slimg_core::Format::Qoi,
+            Format::Gif => slimg_core::Format::Gif,
+            Format::Tiff => slimg_core::Format::Tiff,
+            Format::Bmp => slimg_core::Format::Bmp,
+            Format::Tga => slimg_core::Format::Tga,
+            Format::Ico => slimg_core::Format::Ico,
+            Format::Pnm => slimg_core::Format::Pnm,
         }
     }
 
@@ -33,6 +45,12 @@ impl Format {
             slimg_core::Format::Avif => Format::Avif,
             slimg_core::Format::Jxl => Format::Jxl,
             slimg_core::Format::Qoi => Format::Qoi,
+            slimg_core::Format::Gif => Format::Gif,
+            slimg_core::Format::Tiff => Format::Tiff,
+            slimg_core::Format::Bmp => Format::Bmp,
+            slimg_core::Format::Tga => Format::Tga,
+            slimg_core::Format::Ico => Format::Ico,
+            slimg_core::Format::Pnm => Format::Pnm,
         }
     }
 }
@@ -50,6 +68,16 @@ pub enum ResizeMode {
     Fit { max_width: u32, max_height: u32 },
     /// Scale factor (e.g. 0.5 = half size).
     Scale { factor: f64 },
+    /// Fill the exact dimensions and crop the overflow (centered).
+    Cover { width: u32, height: u32 },
+    /// Fit within the dimensions, then pad to exactly that size.
+    Contain {
+        width: u32,
+        height: u32,
+        fill: FillColor,
+    },
+    /// Shrink to at most this many pixels, preserving aspect ratio.
+    MaxPixels { max_pixels: u64 },
 }
 
 impl ResizeMode {
@@ -63,17 +91,129 @@ impl ResizeMode {
                 max_height,
             } => slimg_core::ResizeMode::Fit(*max_width, *max_height),
             ResizeMode::Scale { factor } => slimg_core::ResizeMode::Scale(*factor),
+            ResizeMode::Cover { width, height } => slimg_core::ResizeMode::Cover(*width, *height),
+            ResizeMode::Contain {
+                width,
+                height,
+                fill,
+            } => slimg_core::ResizeMode::Contain(*width, *height, fill.to_core()),
+            ResizeMode::MaxPixels { max_pixels } => slimg_core::ResizeMode::MaxPixels(*max_pixels),
         }
     }
 }
 
+/// Resampling filter used when resizing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum ResizeFilter {
+    /// Copy the closest source pixel; keeps pixel art crisp.
+    Nearest,
+    /// Linear interpolation between neighbouring pixels.
+    Bilinear,
+    /// Catmull-Rom bicubic.
+    CatmullRom,
+    /// Mitchell-Netravali bicubic, with less ringing than Catmull-Rom.
+    Mitchell,
+    /// Three-lobe Lanczos, the sharpest.
+    Lanczos3,
+}
+
+impl ResizeFilter {
+    fn to_core(self) -> slimg_core::ResizeFilter {
+        match self {
+            ResizeFilter::Nearest => slimg_core::ResizeFilter::Nearest,
+            ResizeFilter::Bilinear => slimg_core::ResizeFilter::Bilinear,
+            ResizeFilter::CatmullRom => slimg_core::ResizeFilter::CatmullRom,
+            ResizeFilter::Mitchell => slimg_core::ResizeFilter::Mitchell,
+            ResizeFilter::Lanczos3 => slimg_core::ResizeFilter::Lanczos3,
+        }
+    }
+}
+
+/// When to resize in linear light instead of on the gamma-encoded values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum LinearLight {
+    /// Linear light when scaling to half size or less.
+    Auto,
+    /// Always convert to linear light.
+    Always,
+    /// Filter the stored values as they are.
+    Never,
+}
+
+impl LinearLight {
+    fn to_core(self) -> slimg_core::LinearLight {
+        match self {
+            LinearLight::Auto => slimg_core::LinearLight::Auto,
+            LinearLight::Always => slimg_core::LinearLight::Always,
+            LinearLight::Never => slimg_core::LinearLight::Never,
+        }
+    }
+}
+
+/// Which part of an image to keep when cropping, or where to put it when
+/// extending the canvas.
+#[derive(Debug, Clone, Copy, PartialEq, uniffi::Enum)]
+pub enum Gravity {
+    /// The middle.
+    Center,
+    /// The top edge.
+    North,
+    /// The top-right corner.
+    NorthEast,
+    /// The right edge.
+    East,
+    /// The bottom-right corner.
+    SouthEast,
+    /// The bottom edge.
+    South,
+    /// The bottom-left corner.
+    SouthWest,
+    /// The left edge.
+    West,
+    /// The top-left corner.
+    NorthWest,
+    /// A point of the image, as fractions of its width and height
+    /// (`0.0..=1.0`), kept as close to the middle as possible.
+    Focal { x: f64, y: f64 },
+}
+
+impl Gravity {
+    fn to_core(self) -> slimg_core::Gravity {
+        match self {
+            Gravity::Center => slimg_core::Gravity::Center,
+            Gravity::North => slimg_core::Gravity::North,
+            Gravity::NorthEast => slimg_core::Gravity::NorthEast,
+            Gravity::East => slimg_core::Gravity::East,
+            Gravity::SouthEast => slimg_core::Gravity::SouthEast,
+            Gravity::South => slimg_core::Gravity::South,
+            Gravity::SouthWest => slimg_core::Gravity::SouthWest,
+            Gravity::West => slimg_core::Gravity::West,
+            Gravity::NorthWest => slimg_core::Gravity::NorthWest,
+            Gravity::Focal { x, y } => slimg_core::Gravity::Focal { x, y },
+        }
+    }
+}
+
+fn gravity_to_core(gravity: Option<Gravity>) -> slimg_core::Gravity {
+    gravity.map(Gravity::to_core).unwrap_or_default()
+}
+
 /// How to crop an image.
 #[derive(Debug, Clone, uniffi::Enum)]
 pub enum CropMode {
     /// Extract a specific region.
     Region { x: u32, y: u32, width: u32, height: u32 },
-    /// Crop to an aspect ratio (centered).
-    AspectRatio { width: u32, height: u32 },
+    /// Crop to an aspect ratio, keeping the part picked by `gravity`
+    /// (centered when unset).
+    AspectRatio {
+        width: u32,
+        height: u32,
+        #[uniffi(default = None)]
+        gravity: Option<Gravity>,
+    },
+    /// Crop to an aspect ratio, placed over the most detailed part of the
+    /// image. `crop_region` reports where.
+    Smart { width: u32, height: u32 },
 }
 
 impl CropMode {
@@ -82,32 +222,66 @@ impl CropMode {
             CropMode::Region { x, y, width, height } => slimg_core::CropMode::Region {
                 x: *x, y: *y, width: *width, height: *height,
             },
-            CropMode::AspectRatio { width, height } => slimg_core::CropMode::AspectRatio {
+            CropMode::AspectRatio { width, height, gravity } => slimg_core::CropMode::AspectRatio {
+                width: *width, height: *height, gravity: gravity_to_core(*gravity),
+            },
+            CropMode::Smart { width, height } => slimg_core::CropMode::Smart {
                 width: *width, height: *height,
             },
         }
     }
 }
 
+/// A rectangle within an image, in pixels.
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct CropRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
 /// How to extend (add padding to) an image.
 #[derive(Debug, Clone, uniffi::Enum)]
 pub enum ExtendMode {
-    /// Extend the canvas so the image fits the given aspect ratio (centered).
-    AspectRatio { width: u32, height: u32 },
-    /// Extend the canvas to an exact pixel size (centered).
-    Size { width: u32, height: u32 },
+    /// Extend the canvas so the image fits the given aspect ratio, placing
+    /// the image by `gravity` (centered when unset).
+    AspectRatio {
+        width: u32,
+        height: u32,
+        #[uniffi(default = None)]
+        gravity: Option<Gravity>,
+    },
+    /// Extend the canvas to an exact pixel size, placing the image by
+    /// `gravity` (centered when unset).
+    Size {
+        width: u32,
+        height: u32,
+        #[uniffi(default = None)]
+        gravity: Option<Gravity>,
+    },
 }
 
 impl ExtendMode {
     fn to_core(&self) -> slimg_core::ExtendMode {
         match self {
-            ExtendMode::AspectRatio { width, height } => slimg_core::ExtendMode::AspectRatio {
+            ExtendMode::AspectRatio {
+                width,
+                height,
+                gravity,
+            } => slimg_core::ExtendMode::AspectRatio {
                 width: *width,
                 height: *height,
+                gravity: gravity_to_core(*gravity),
             },
-            ExtendMode::Size { width, height } => slimg_core::ExtendMode::Size {
+            ExtendMode::Size {
+                width,
+                height,
+                gravity,
+            } => slimg_core::ExtendMode::Size {
                 width: *width,
                 height: *height,
+                gravity: gravity_to_core(*gravity),
             },
         }
     }
@@ -131,17 +305,289 @@ impl FillColor {
     }
 }
 
-/// Decoded image data in RGBA format (4 bytes per pixel).
+/// EXIF, XMP and ICC data read from a source image.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct Metadata {
+    /// EXIF as a TIFF structure (no `Exif\0\0` prefix).
+    #[uniffi(default = None)]
+    pub exif: Option<Vec<u8>>,
+    /// XMP packet (UTF-8 XML).
+    #[uniffi(default = None)]
+    pub xmp: Option<Vec<u8>>,
+    /// ICC colour profile.
+    #[uniffi(default = None)]
+    pub icc: Option<Vec<u8>>,
+    /// Orientation the pixels still need, when decoded without auto-orient.
+    #[uniffi(default = None)]
+    pub orientation: Option<Orientation>,
+    /// Transfer function, primaries and light levels of HDR pixels.
+    #[uniffi(default = None)]
+    pub hdr: Option<HdrMetadata>,
+}
+
+impl Metadata {
+    fn to_core(&self) -> slimg_core::Metadata {
+        slimg_core::Metadata {
+            exif: self.exif.clone(),
+            xmp: self.xmp.clone(),
+            icc: self.icc.clone(),
+            orientation: self.orientation.map(Orientation::to_core).unwrap_or_default(),
+            hdr: self.hdr.as_ref().map(HdrMetadata::to_core),
+        }
+    }
+
+    fn from_core(metadata: slimg_core::Metadata) -> Option<Self> {
+        if metadata.is_empty() {
+            return None;
+        }
+        let orientation = match metadata.orientation {
+            slimg_core::Orientation::Normal => None,
+            other => Some(Orientation::from_core(other)),
+        };
+        Some(Self {
+            exif: metadata.exif,
+            xmp: metadata.xmp,
+            icc: metadata.icc,
+            orientation,
+            hdr: metadata.hdr.map(HdrMetadata::from_core),
+        })
+    }
+}
+
+/// How stored pixels must be rotated or mirrored to display upright
+/// (EXIF Orientation values 1 to 8).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum Orientation {
+    Normal,
+    FlipHorizontal,
+    Rotate180,
+    FlipVertical,
+    Transpose,
+    Rotate90,
+    Transverse,
+    Rotate270,
+}
+
+impl Orientation {
+    fn to_core(self) -> slimg_core::Orientation {
+        match self {
+            Orientation::Normal => slimg_core::Orientation::Normal,
+            Orientation::FlipHorizontal => slimg_core::Orientation::FlipHorizontal,
+            Orientation::Rotate180 => slimg_core::Orientation::Rotate180,
+            Orientation::FlipVertical => slimg_core::Orientation::FlipVertical,
+            Orientation::Transpose => slimg_core::Orientation::Transpose,
+            Orientation::Rotate90 => slimg_core::Orientation::Rotate90,
+            Orientation::Transverse => slimg_core::Orientation::Transverse,
+            Orientation::Rotate270 => slimg_core::Orientation::Rotate270,
+        }
+    }
+
+    fn from_core(orientation: slimg_core::Orientation) -> Self {
+        match orientation {
+            slimg_core::Orientation::Normal => Orientation::Normal,
+            slimg_core::Orientation::FlipHorizontal => Orientation::FlipHorizontal,
+            slimg_core::Orientation::Rotate180 => Orientation::Rotate180,
+            slimg_core::Orientation::FlipVertical => Orientation::FlipVertical,
+            slimg_core::Orientation::Transpose => Orientation::Transpose,
+            slimg_core::Orientation::Rotate90 => Orientation::Rotate90,
+            slimg_core::Orientation::Transverse => Orientation::Transverse,
+            slimg_core::Orientation::Rotate270 => Orientation::Rotate270,
+        }
+    }
+}
+
+/// Which source metadata to carry into the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum MetadataPolicy {
+    /// Keep EXIF, XMP and ICC as decoded.
+    Keep,
+    /// Drop all metadata.
+    Strip,
+    /// Keep ICC and EXIF copyright/artist/orientation; drop GPS and XMP.
+    Safe,
+}
+
+impl MetadataPolicy {
+    fn to_core(self) -> slimg_core::MetadataPolicy {
+        match self {
+            MetadataPolicy::Keep => slimg_core::MetadataPolicy::Keep,
+            MetadataPolicy::Strip => slimg_core::MetadataPolicy::Strip,
+            MetadataPolicy::Safe => slimg_core::MetadataPolicy::Safe,
+        }
+    }
+}
+
+/// What to do with the source ICC profile when encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum ColorPolicy {
+    /// Embed the source profile; convert to sRGB only when it would be lost.
+    Preserve,
+    /// Convert pixels to sRGB and drop the source profile.
+    Srgb,
+}
+
+impl ColorPolicy {
+    fn to_core(self) -> slimg_core::ColorPolicy {
+        match self {
+            ColorPolicy::Preserve => slimg_core::ColorPolicy::Preserve,
+            ColorPolicy::Srgb => slimg_core::ColorPolicy::Srgb,
+        }
+    }
+}
+
+/// HDR transfer function of the stored pixel values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum TransferFunction {
+    /// SMPTE ST 2084 perceptual quantizer.
+    Pq,
+    /// ARIB STD-B67 hybrid log-gamma.
+    Hlg,
+}
+
+/// Colour primaries of an HDR image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum ColorPrimaries {
+    Bt709,
+    DisplayP3,
+    Bt2020,
+}
+
+/// Maximum content and frame-average light levels, in nits (0 = unknown).
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct ContentLightLevel {
+    pub max_cll: u16,
+    pub max_fall: u16,
+}
+
+/// How the pixels of an HDR image are encoded.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct HdrMetadata {
+    pub transfer: TransferFunction,
+    pub primaries: ColorPrimaries,
+    #[uniffi(default = None)]
+    pub content_light_level: Option<ContentLightLevel>,
+}
+
+impl HdrMetadata {
+    fn to_core(&self) -> slimg_core::HdrMetadata {
+        slimg_core::HdrMetadata {
+            transfer: match self.transfer {
+                TransferFunction::Pq => slimg_core::TransferFunction::Pq,
+                TransferFunction::Hlg => slimg_core::TransferFunction::Hlg,
+            },
+            primaries: match self.primaries {
+                ColorPrimaries::Bt709 => slimg_core::ColorPrimaries::Bt709,
+                ColorPrimaries::DisplayP3 => slimg_core::ColorPrimaries::DisplayP3,
+                ColorPrimaries::Bt2020 => slimg_core::ColorPrimaries::Bt2020,
+            },
+            content_light_level: self.content_light_level.as_ref().map(|level| {
+                slimg_core::ContentLightLevel {
+                    max_cll: level.max_cll,
+                    max_fall: level.max_fall,
+                }
+            }),
+        }
+    }
+
+    fn from_core(hdr: slimg_core::HdrMetadata) -> Self {
+        Self {
+            transfer: match hdr.transfer {
+                slimg_core::TransferFunction::Pq => TransferFunction::Pq,
+                slimg_core::TransferFunction::Hlg => TransferFunction::Hlg,
+            },
+            primaries: match hdr.primaries {
+                slimg_core::ColorPrimaries::Bt709 => ColorPrimaries::Bt709,
+                slimg_core::ColorPrimaries::DisplayP3 => ColorPrimaries::DisplayP3,
+                slimg_core::ColorPrimaries::Bt2020 => ColorPrimaries::Bt2020,
+            },
+            content_light_level: hdr.content_light_level.map(|level| ContentLightLevel {
+                max_cll: level.max_cll,
+                max_fall: level.max_fall,
+            }),
+        }
+    }
+}
+
+/// Operator used to bring HDR pixels into SDR range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum ToneMapOperator {
+    /// Hard-clip everything brighter than SDR white.
+    Clip,
+    /// Extended Reinhard on luminance.
+    Reinhard,
+    /// Hable's filmic curve.
+    Hable,
+}
+
+impl ToneMapOperator {
+    fn to_core(self) -> slimg_core::ToneMapOperator {
+        match self {
+            ToneMapOperator::Clip => slimg_core::ToneMapOperator::Clip,
+            ToneMapOperator::Reinhard => slimg_core::ToneMapOperator::Reinhard,
+            ToneMapOperator::Hable => slimg_core::ToneMapOperator::Hable,
+        }
+    }
+}
+
+/// Sample layout of [`ImageData::data`]. Wide samples are native-endian.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum PixelFormat {
+    /// 8 bits per channel (4 bytes per pixel).
+    Rgba8,
+    /// 16 bits per channel (8 bytes per pixel).
+    Rgba16,
+    /// 32-bit float per channel (16 bytes per pixel).
+    Rgba32F,
+}
+
+impl PixelFormat {
+    fn to_core(self) -> slimg_core::PixelFormat {
+        match self {
+            PixelFormat::Rgba8 => slimg_core::PixelFormat::Rgba8,
+            PixelFormat::Rgba16 => slimg_core::PixelFormat::Rgba16,
+            PixelFormat::Rgba32F => slimg_core::PixelFormat::Rgba32F,
+        }
+    }
+
+    fn from_core(format: slimg_core::PixelFormat) -> Self {
+        match format {
+            slimg_core::PixelFormat::Rgba8 => PixelFormat::Rgba8,
+            slimg_core::PixelFormat::Rgba16 => PixelFormat::Rgba16,
+            slimg_core::PixelFormat::Rgba32F => PixelFormat::Rgba32F,
+        }
+    }
+}
+
+/// Decoded image data as interleaved RGBA pixels.
 #[derive(Debug, Clone, uniffi::Record)]
 pub struct ImageData {
     pub width: u32,
     pub height: u32,
     pub data: Vec<u8>,
+    /// Metadata read from the source file, if any.
+    #[uniffi(default = None)]
+    pub metadata: Option<Metadata>,
+    /// Layout of `data` (defaults to RGBA8).
+    #[uniffi(default = None)]
+    pub pixel_format: Option<PixelFormat>,
 }
 
 impl ImageData {
     fn to_core(&self) -> slimg_core::ImageData {
-        slimg_core::ImageData::new(self.width, self.height, self.data.clone())
+        let pixel_format = self
+            .pixel_format
+            .map(PixelFormat::to_core)
+            .unwrap_or_default();
+        let image = slimg_core::ImageData::from_raw(
+            self.width,
+            self.height,
+            pixel_format,
+            self.data.clone(),
+        );
+        match &self.metadata {
+            Some(metadata) => image.with_metadata(metadata.to_core()),
+            None => image,
+        }
     }
 
     fn from_core(img: slimg_core::ImageData) -> Self {
@@ -149,6 +595,447 @@ impl ImageData {
             width: img.width,
             height: img.height,
             data: img.data,
+            metadata: Metadata::from_core(img.metadata),
+            pixel_format: Some(PixelFormat::from_core(img.pixel_format)),
+        }
+    }
+}
+
+/// What a player does with a frame once its duration is over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum Disposal {
+    /// Leave the frame on the canvas.
+    None,
+    /// Clear the frame's area to transparent.
+    Background,
+    /// Restore the canvas to its state before the frame.
+    Previous,
+}
+
+impl Disposal {
+    fn to_core(self) -> slimg_core::Disposal {
+        match self {
+            Disposal::None => slimg_core::Disposal::None,
+            Disposal::Background => slimg_core::Disposal::Background,
+            Disposal::Previous => slimg_core::Disposal::Previous,
+        }
+    }
+
+    fn from_core(disposal: slimg_core::Disposal) -> Self {
+        match disposal {
+            slimg_core::Disposal::None => Disposal::None,
+            slimg_core::Disposal::Background => Disposal::Background,
+            slimg_core::Disposal::Previous => Disposal::Previous,
+        }
+    }
+}
+
+/// One full-canvas frame of an [`Animation`].
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct Frame {
+    pub image: ImageData,
+    /// How long the frame is shown, in milliseconds.
+    pub duration_ms: u32,
+    /// Disposal requested by the source file (defaults to `None`).
+    #[uniffi(default = None)]
+    pub disposal: Option<Disposal>,
+}
+
+/// A sequence of equally sized frames.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct Animation {
+    /// The frames in display order.
+    pub frames: Vec<Frame>,
+    /// How many times the animation plays. `0` loops forever.
+    pub loop_count: u32,
+}
+
+impl Animation {
+    fn to_core(&self) -> slimg_core::Animation {
+        slimg_core::Animation {
+            frames: self
+                .frames
+                .iter()
+                .map(|frame| slimg_core::Frame {
+                    image: frame.image.to_core(),
+                    duration_ms: frame.duration_ms,
+                    disposal: frame.disposal.map(Disposal::to_core).unwrap_or_default(),
+                })
+                .collect(),
+            loop_count: self.loop_count,
+        }
+    }
+
+    fn from_core(animation: slimg_core::Animation) -> Self {
+        Self {
+            frames: animation
+                .frames
+                .into_iter()
+                .map(|frame| Frame {
+                    image: ImageData::from_core(frame.image),
+                    duration_ms: frame.duration_ms,
+                    disposal: Some(Disposal::from_core(frame.disposal)),
+                })
+                .collect(),
+            loop_count: animation.loop_count,
+        }
+    }
+}
+
+/// JPEG chroma subsampling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum ChromaSubsampling {
+    /// 4:2:0 — chroma halved in both directions.
+    Yuv420,
+    /// 4:2:2 — chroma halved horizontally.
+    Yuv422,
+    /// 4:4:4 — full-resolution chroma.
+    Yuv444,
+}
+
+impl ChromaSubsampling {
+    fn to_core(self) -> slimg_core::ChromaSubsampling {
+        match self {
+            ChromaSubsampling::Yuv420 => slimg_core::ChromaSubsampling::Yuv420,
+            ChromaSubsampling::Yuv422 => slimg_core::ChromaSubsampling::Yuv422,
+            ChromaSubsampling::Yuv444 => slimg_core::ChromaSubsampling::Yuv444,
+        }
+    }
+}
+
+/// JPEG encoder settings.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct JpegOptions {
+    /// Write a progressive JPEG.
+    #[uniffi(default = true)]
+    pub progressive: bool,
+    /// Compute optimal Huffman tables.
+    #[uniffi(default = true)]
+    pub optimize_coding: bool,
+    /// Chroma subsampling (defaults to 4:2:0).
+    #[uniffi(default = None)]
+    pub chroma_subsampling: Option<ChromaSubsampling>,
+    /// Smoothing factor (0-100).
+    #[uniffi(default = 0)]
+    pub smoothing: u8,
+}
+
+impl JpegOptions {
+    fn to_core(&self) -> slimg_core::JpegOptions {
+        slimg_core::JpegOptions {
+            progressive: self.progressive,
+            optimize_coding: self.optimize_coding,
+            chroma_subsampling: self
+                .chroma_subsampling
+                .map(|c| c.to_core())
+                .unwrap_or_default(),
+            smoothing: self.smoothing,
+        }
+    }
+}
+
+/// PNG encoder settings.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct PngOptions {
+    /// OxiPNG optimization preset (0-6). Derived from quality when unset.
+    #[uniffi(default = None)]
+    pub optimization_level: Option<u8>,
+    /// Write an Adam7-interlaced PNG.
+    #[uniffi(default = false)]
+    pub interlace: bool,
+    /// Allow rewriting the colour of fully transparent pixels.
+    #[uniffi(default = false)]
+    pub optimize_alpha: bool,
+    /// Quantize to a palette before optimizing (lossy).
+    #[uniffi(default = None)]
+    pub quantize: Option<PngQuantizeOptions>,
+}
+
+impl PngOptions {
+    fn to_core(&self) -> slimg_core::PngOptions {
+        slimg_core::PngOptions {
+            optimization_level: self.optimization_level,
+            interlace: self.interlace,
+            optimize_alpha: self.optimize_alpha,
+            quantize: self.quantize.as_ref().map(|q| q.to_core()),
+        }
+    }
+}
+
+/// Lossy palette settings for PNG.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct PngQuantizeOptions {
+    /// Maximum palette size (2-256).
+    #[uniffi(default = 256)]
+    pub max_colors: u16,
+    /// Dithering strength (0.0-1.0).
+    #[uniffi(default = 1.0)]
+    pub dithering: f32,
+    /// Fall back to lossless output below this quality score (0-100).
+    #[uniffi(default = 0)]
+    pub min_quality: u8,
+}
+
+impl PngQuantizeOptions {
+    fn to_core(&self) -> slimg_core::PngQuantizeOptions {
+        slimg_core::PngQuantizeOptions {
+            max_colors: self.max_colors,
+            dithering: self.dithering,
+            min_quality: self.min_quality,
+        }
+    }
+}
+
+/// WebP encoder settings.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct WebPOptions {
+    /// Compression method (0-6).
+    #[uniffi(default = 4)]
+    pub method: u8,
+    /// Quality of the alpha plane (0-100).
+    #[uniffi(default = 100)]
+    pub alpha_quality: u8,
+    /// Use the sharper RGB to YUV conversion.
+    #[uniffi(default = false)]
+    pub sharp_yuv: bool,
+}
+
+impl WebPOptions {
+    fn to_core(&self) -> slimg_core::WebPOptions {
+        slimg_core::WebPOptions {
+            method: self.method,
+            alpha_quality: self.alpha_quality,
+            sharp_yuv: self.sharp_yuv,
+        }
+    }
+}
+
+/// AVIF encoder settings.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct AvifOptions {
+    /// Encoder speed (1-10).
+    #[uniffi(default = 6)]
+    pub speed: u8,
+    /// Quality of the alpha plane (1-100).
+    #[uniffi(default = None)]
+    pub alpha_quality: Option<u8>,
+}
+
+impl AvifOptions {
+    fn to_core(&self) -> slimg_core::AvifOptions {
+        slimg_core::AvifOptions {
+            speed: self.speed,
+            alpha_quality: self.alpha_quality,
+        }
+    }
+}
+
+/// JPEG XL encoder settings.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct JxlOptions {
+    /// Encoder effort (1-10).
+    #[uniffi(default = 7)]
+    pub effort: u8,
+    /// Decoding speed tier (0-4).
+    #[uniffi(default = 0)]
+    pub decoding_speed: u8,
+}
+
+impl JxlOptions {
+    fn to_core(&self) -> slimg_core::JxlOptions {
+        slimg_core::JxlOptions {
+            effort: self.effort,
+            decoding_speed: self.decoding_speed,
+        }
+    }
+}
+
+/// QOI encoder settings.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct QoiOptions {
+    /// Tag the output as linear instead of sRGB.
+    #[uniffi(default = false)]
+    pub linear: bool,
+}
+
+impl QoiOptions {
+    fn to_core(&self) -> slimg_core::QoiOptions {
+        slimg_core::QoiOptions {
+            colorspace: if self.linear {
+                slimg_core::QoiColorspace::Linear
+            } else {
+                slimg_core::QoiColorspace::Srgb
+            },
+        }
+    }
+}
+
+/// GIF encoder settings.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct GifOptions {
+    /// Maximum size of the shared palette (2-256).
+    #[uniffi(default = 256)]
+    pub max_colors: u16,
+    /// Dithering strength (0.0-1.0).
+    #[uniffi(default = 1.0)]
+    pub dithering: f32,
+}
+
+impl GifOptions {
+    fn to_core(&self) -> slimg_core::GifOptions {
+        slimg_core::GifOptions {
+            max_colors: self.max_colors,
+            dithering: self.dithering,
+        }
+    }
+}
+
+/// Per-format encoder settings. Unset formats use their defaults.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct CodecOptions {
+    #[uniffi(default = None)]
+    pub jpeg: Option<JpegOptions>,
+    #[uniffi(default = None)]
+    pub png: Option<PngOptions>,
+    #[uniffi(default = None)]
+    pub webp: Option<WebPOptions>,
+    #[uniffi(default = None)]
+    pub avif: Option<AvifOptions>,
+    #[uniffi(default = None)]
+    pub jxl: Option<JxlOptions>,
+    #[uniffi(default = None)]
+    pub qoi: Option<QoiOptions>,
+    #[uniffi(default = None)]
+    pub gif: Option<GifOptions>,
+}
+
+impl CodecOptions {
+    fn to_core(&self) -> slimg_core::CodecOptions {
+        let mut opts = slimg_core::CodecOptions::default();
+        if let Some(jpeg) = &self.jpeg {
+            opts.jpeg = jpeg.to_core();
+        }
+        if let Some(png) = &self.png {
+            opts.png = png.to_core();
+        }
+        if let Some(webp) = &self.webp {
+            opts.webp = webp.to_core();
+        }
+        if let Some(avif) = &self.avif {
+            opts.avif = avif.to_core();
+        }
+        if let Some(jxl) = &self.jxl {
+            opts.jxl = jxl.to_core();
+        }
+        if let Some(qoi) = &self.qoi {
+            opts.qoi = qoi.to_core();
+        }
+        if let Some(gif) = &self.gif {
+            opts.gif = gif.to_core();
+        }
+        opts
+    }
+}
+
+fn codec_to_core(codec: Option<&CodecOptions>) -> slimg_core::CodecOptions {
+    codec.map(|c| c.to_core()).unwrap_or_default()
+}
+
+fn metadata_to_core(policy: Option<MetadataPolicy>) -> slimg_core::MetadataPolicy {
+    policy.map(MetadataPolicy::to_core).unwrap_or_default()
+}
+
+fn color_to_core(policy: Option<ColorPolicy>) -> slimg_core::ColorPolicy {
+    policy.map(ColorPolicy::to_core).unwrap_or_default()
+}
+
+fn tone_map_to_core(operator: Option<ToneMapOperator>) -> slimg_core::ToneMapOperator {
+    operator.map(ToneMapOperator::to_core).unwrap_or_default()
+}
+
+fn resize_options_to_core(
+    filter: Option<ResizeFilter>,
+    without_enlargement: bool,
+    linear_light: Option<LinearLight>,
+    premultiply_alpha: bool,
+) -> slimg_core::ResizeOptions {
+    slimg_core::ResizeOptions {
+        filter: filter.map(ResizeFilter::to_core).unwrap_or_default(),
+        without_enlargement,
+        linear_light: linear_light.map(LinearLight::to_core).unwrap_or_default(),
+        premultiply_alpha,
+    }
+}
+
+fn limits_to_core(limits: Option<&DecodeLimits>) -> slimg_core::DecodeLimits {
+    limits.map(DecodeLimits::to_core).unwrap_or_default()
+}
+
+/// Caps on what a file may make the decoder allocate, checked against its
+/// headers before decoding. Unset fields are not limited; passing no limits
+/// at all keeps the default 4 GiB cap on the decoded size.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct DecodeLimits {
+    #[uniffi(default = None)]
+    pub max_width: Option<u32>,
+    #[uniffi(default = None)]
+    pub max_height: Option<u32>,
+    /// Most pixels in one frame.
+    #[uniffi(default = None)]
+    pub max_pixels: Option<u64>,
+    /// Most bytes the decoded frames may take together.
+    #[uniffi(default = None)]
+    pub max_alloc: Option<u64>,
+    #[uniffi(default = None)]
+    pub max_frames: Option<u32>,
+}
+
+impl DecodeLimits {
+    fn to_core(&self) -> slimg_core::DecodeLimits {
+        slimg_core::DecodeLimits {
+            max_width: self.max_width,
+            max_height: self.max_height,
+            max_pixels: self.max_pixels,
+            max_alloc: self.max_alloc,
+            max_frames: self.max_frames,
+        }
+    }
+}
+
+/// An upper bound on the size of the encoded output.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct SizeLimit {
+    /// Largest acceptable output in bytes.
+    pub max_bytes: u64,
+    /// Shrink the image when even the lowest quality is too large, instead
+    /// of failing.
+    #[uniffi(default = false)]
+    pub downscale: bool,
+}
+
+impl SizeLimit {
+    fn to_core(&self) -> slimg_core::SizeLimit {
+        slimg_core::SizeLimit {
+            max_bytes: self.max_bytes,
+            downscale: self.downscale,
+        }
+    }
+}
+
+/// A perceptual quality the output should reach.
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum QualityTarget {
+    /// Lowest SSIMULACRA2 score to accept (90 is visually lossless).
+    Ssimulacra2 { score: f64 },
+    /// Highest DSSIM to accept (0 is identical).
+    Dssim { value: f64 },
+}
+
+impl QualityTarget {
+    fn to_core(&self) -> slimg_core::QualityTarget {
+        match self {
+            QualityTarget::Ssimulacra2 { score } => slimg_core::QualityTarget::Ssimulacra2(*score),
+            QualityTarget::Dssim { value } => slimg_core::QualityTarget::Dssim(*value),
         }
     }
 }
@@ -168,6 +1055,75 @@ pub struct PipelineOptions {
     pub extend: Option<ExtendMode>,
     /// Fill color for the extended region (defaults to opaque white).
     pub fill_color: Option<FillColor>,
+    /// Resampling filter used by `resize` (defaults to `Lanczos3`).
+    #[uniffi(default = None)]
+    pub resize_filter: Option<ResizeFilter>,
+    /// Never scale small images up when resizing.
+    #[uniffi(default = false)]
+    pub without_enlargement: bool,
+    /// When `resize` filters in linear light (defaults to `Auto`).
+    #[uniffi(default = None)]
+    pub linear_light: Option<LinearLight>,
+    /// Weight colour by alpha while resizing, so transparent pixels do not
+    /// darken the edges of opaque ones.
+    #[uniffi(default = true)]
+    pub premultiply_alpha: bool,
+    /// Per-format encoder settings (defaults when unset).
+    #[uniffi(default = None)]
+    pub codec: Option<CodecOptions>,
+    /// Encode losslessly (PNG, WebP, JXL and QOI only).
+    #[uniffi(default = false)]
+    pub lossless: bool,
+    /// Metadata to carry over (defaults to `Safe`).
+    #[uniffi(default = None)]
+    pub metadata: Option<MetadataPolicy>,
+    /// Colour profile handling (defaults to `Preserve`).
+    #[uniffi(default = None)]
+    pub color: Option<ColorPolicy>,
+    /// Tone-mapping operator for HDR sources written to SDR formats
+    /// (defaults to `Reinhard`).
+    #[uniffi(default = None)]
+    pub tone_map: Option<ToneMapOperator>,
+    /// Byte budget for the output. The quality is lowered from `quality`
+    /// until the output fits.
+    #[uniffi(default = None)]
+    pub max_size: Option<SizeLimit>,
+    /// Perceptual quality to aim for. When set, `quality` is ignored.
+    #[uniffi(default = None)]
+    pub quality_target: Option<QualityTarget>,
+    /// Encode to each of these formats at the same perceived quality and
+    /// keep the smallest, instead of encoding to `format`.
+    #[uniffi(default = None)]
+    pub candidates: Option<Vec<Format>>,
+}
+
+impl PipelineOptions {
+    fn to_core(&self) -> slimg_core::PipelineOptions {
+        slimg_core::PipelineOptions {
+            format: self.format.to_core(),
+            quality: self.quality,
+            lossless: self.lossless,
+            resize: self.resize.as_ref().map(|r| r.to_core()),
+            resize_options: resize_options_to_core(
+                self.resize_filter,
+                self.without_enlargement,
+                self.linear_light,
+                self.premultiply_alpha,
+            ),
+            crop: self.crop.as_ref().map(|c| c.to_core()),
+            extend: self.extend.as_ref().map(|e| e.to_core()),
+            fill_color: self.fill_color.as_ref().map(|f| f.to_core()),
+            codec: codec_to_core(self.codec.as_ref()),
+            metadata: metadata_to_core(self.metadata),
+            color: color_to_core(self.color),
+            tone_map: tone_map_to_core(self.tone_map),
+            max_size: self.max_size.as_ref().map(|l| l.to_core()),
+            quality_target: self.quality_target.as_ref().map(|t| t.to_core()),
+            auto_format: self.candidates.as_ref().map(|c| slimg_core::AutoFormat {
+                candidates: c.iter().map(|f| f.to_core()).collect(),
+            }),
+        }
+    }
 }
 
 /// Result of a pipeline conversion.
@@ -181,6 +1137,143 @@ pub struct PipelineResult {
     pub width: u32,
     /// Height of the output image in pixels.
     pub height: u32,
+    /// Quality the output was encoded at. Unset for lossless output.
+    pub quality: Option<u8>,
+    /// Score reached under the quality target's metric. Unset without a
+    /// target.
+    pub score: Option<f64>,
+    /// With `candidates`, the formats that lost and why.
+    pub rejected: Vec<Rejected>,
+    /// The input was handed back unchanged because re-encoding would not
+    /// have made it smaller.
+    pub kept_original: bool,
+}
+
+impl PipelineResult {
+    fn from_core(result: slimg_core::PipelineResult) -> Self {
+        Self {
+            data: result.data,
+            format: Format::from_core(result.format),
+            width: result.width,
+            height: result.height,
+            quality: result.quality,
+            score: result.score,
+            rejected: result
+                .rejected
+                .into_iter()
+                .map(Rejected::from_core)
+                .collect(),
+            kept_original: result.kept_original,
+        }
+    }
+}
+
+/// Why a candidate format lost.
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum Rejection {
+    /// It encoded, but to more bytes than the winner.
+    Larger { bytes: u64 },
+    /// It could not be encoded.
+    Failed { message: String },
+}
+
+/// A candidate format that lost to the chosen one.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct Rejected {
+    pub format: Format,
+    pub reason: Rejection,
+}
+
+impl Rejected {
+    fn from_core(rejected: slimg_core::Rejected) -> Self {
+        let reason = match rejected.reason {
+            slimg_core::Rejection::Larger { bytes } => Rejection::Larger { bytes },
+            slimg_core::Rejection::Failed(message) => Rejection::Failed { message },
+        };
+        Self {
+            format: Format::from_core(rejected.format),
+            reason,
+        }
+    }
+}
+
+/// Scores for how far an image has drifted from a reference.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct Scores {
+    /// Peak signal-to-noise ratio in dB (infinite for identical images).
+    pub psnr: f64,
+    /// Structural similarity of the luma (1 is identical).
+    pub ssim: f64,
+    /// DSSIM (0 is identical).
+    pub dssim: f64,
+    /// SSIMULACRA2 score (100 is identical, 90 visually lossless).
+    pub ssimulacra2: f64,
+    /// Largest per-channel difference in 8-bit steps.
+    pub max_error: u8,
+}
+
+impl Scores {
+    fn from_core(scores: slimg_core::metrics::Scores) -> Self {
+        Self {
+            psnr: scores.psnr,
+            ssim: scores.ssim,
+            dssim: scores.dssim,
+            ssimulacra2: scores.ssimulacra2,
+            max_error: scores.max_error,
+        }
+    }
+}
+
+/// Which metadata a file carries, by size in bytes.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct MetadataSummary {
+    pub exif_bytes: Option<u64>,
+    pub xmp_bytes: Option<u64>,
+    pub icc_bytes: Option<u64>,
+    pub hdr: Option<HdrMetadata>,
+}
+
+impl MetadataSummary {
+    fn from_core(summary: slimg_core::MetadataSummary) -> Self {
+        Self {
+            exif_bytes: summary.exif_bytes.map(|n| n as u64),
+            xmp_bytes: summary.xmp_bytes.map(|n| n as u64),
+            icc_bytes: summary.icc_bytes.map(|n| n as u64),
+            hdr: summary.hdr.map(HdrMetadata::from_core),
+        }
+    }
+}
+
+/// What an image file's headers say about it, read without decoding pixels.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct ImageInfo {
+    pub format: Format,
+    /// Width of the stored pixels, before the orientation is applied.
+    pub width: u32,
+    /// Height of the stored pixels, before the orientation is applied.
+    pub height: u32,
+    /// Bits per channel sample as stored.
+    pub bit_depth: u8,
+    pub has_alpha: bool,
+    /// Number of frames; 1 for still images.
+    pub frame_count: u32,
+    pub orientation: Orientation,
+    pub metadata: MetadataSummary,
+}
+
+impl ImageInfo {
+    fn from_core(info: slimg_core::ImageInfo) -> Self {
+        Self {
+            format: Format::from_core(info.format),
+            width: info.width,
+            height: info.height,
+            bit_depth: info.bit_depth,
+            has_alpha: info.has_alpha,
+            frame_count: info.frame_count,
+            orientation: Orientation::from_core(info.orientation),
+            metadata: MetadataSummary::from_core(info.metadata),
+        }
+    }
 }
 
 /// Result of a decode operation.
@@ -192,6 +1285,15 @@ pub struct DecodeResult {
     pub format: Format,
 }
 
+/// Result of decoding every frame.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct AnimationDecodeResult {
+    /// Decoded frames. Still images have one.
+    pub animation: Animation,
+    /// Detected format of the input.
+    pub format: Format,
+}
+
 /// Errors from slimg operations.
 #[derive(Debug, uniffi::Error, thiserror::Error)]
 pub enum SlimgError {
@@ -204,6 +1306,12 @@ pub enum SlimgError {
     #[error("encoding not supported: {format}")]
     EncodingNotSupported { format: String },
 
+    #[error("lossless encoding not supported: {format}")]
+    LosslessNotSupported { format: String },
+
+    #[error("animation not supported: {format}")]
+    AnimationNotSupported { format: String },
+
     #[error("decode error: {message}")]
     Decode { message: String },
 
@@ -219,6 +1327,26 @@ pub enum SlimgError {
     #[error("extend error: {message}")]
     Extend { message: String },
 
+    #[error("output cannot fit in {max_bytes} bytes (smallest was {smallest} bytes)")]
+    TargetSizeUnreachable { max_bytes: u64, smallest: u64 },
+
+    #[error(
+        "image dimensions differ: expected {expected_width}x{expected_height}, got {actual_width}x{actual_height}"
+    )]
+    DimensionMismatch {
+        expected_width: u32,
+        expected_height: u32,
+        actual_width: u32,
+        actual_height: u32,
+    },
+
+    #[error("{limit} {actual} is over the decode limit of {max}")]
+    LimitExceeded {
+        limit: String,
+        actual: u64,
+        max: u64,
+    },
+
     #[error("I/O error: {message}")]
     Io { message: String },
 
@@ -236,11 +1364,40 @@ impl From<slimg_core::Error> for SlimgError {
             slimg_core::Error::EncodingNotSupported(f) => SlimgError::EncodingNotSupported {
                 format: format!("{f:?}"),
             },
+            slimg_core::Error::LosslessNotSupported(f) => SlimgError::LosslessNotSupported {
+                format: format!("{f:?}"),
+            },
+            slimg_core::Error::AnimationNotSupported(f) => SlimgError::AnimationNotSupported {
+                format: format!("{f:?}"),
+            },
             slimg_core::Error::Decode(s) => SlimgError::Decode { message: s },
             slimg_core::Error::Encode(s) => SlimgError::Encode { message: s },
             slimg_core::Error::Resize(s) => SlimgError::Resize { message: s },
             slimg_core::Error::Crop(s) => SlimgError::Crop { message: s },
             slimg_core::Error::Extend(s) => SlimgError::Extend { message: s },
+            slimg_core::Error::TargetSizeUnreachable {
+                max_bytes,
+                smallest,
+            } => SlimgError::TargetSizeUnreachable {
+                max_bytes,
+                smallest,
+            },
+            slimg_core::Error::DimensionMismatch {
+                expected_width,
+                expected_height,
+                actual_width,
+                actual_height,
+            } => SlimgError::DimensionMismatch {
+                expected_width,
+                expected_height,
+                actual_width,
+                actual_height,
+            },
+            slimg_core::Error::LimitExceeded { limit, actual, max } => SlimgError::LimitExceeded {
+                limit: limit.to_string(),
+                actual,
+                max,
+            },
             slimg_core::Error::Io(e) => SlimgError::Io {
                 message: e.to_string(),
             },
@@ -265,6 +1422,18 @@ fn format_can_encode(format: Format) -> bool {
     format.to_core().can_encode()
 }
 
+/// Whether the given format can be encoded losslessly.
+#[uniffi::export]
+fn format_supports_lossless(format: Format) -> bool {
+    format.to_core().supports_lossless()
+}
+
+/// Whether the given format can hold more than one frame.
+#[uniffi::export]
+fn format_supports_animation(format: Format) -> bool {
+    format.to_core().supports_animation()
+}
+
 /// Detect format from file extension (case-insensitive).
 #[uniffi::export]
 fn format_from_extension(path: String) -> Option<Format> {
@@ -278,9 +1447,19 @@ fn format_from_magic_bytes(data: Vec<u8>) -> Option<Format> {
 }
 
 /// Detect the format from magic bytes and decode raw image data.
-#[uniffi::export]
-fn decode(data: Vec<u8>) -> Result<DecodeResult, SlimgError> {
-    let (image, format) = slimg_core::decode(&data)?;
+///
+/// With `auto_orient` (the default) the pixels are rotated to display upright.
+#[uniffi::export(default(auto_orient = true, limits = None))]
+fn decode(
+    data: Vec<u8>,
+    auto_orient: bool,
+    limits: Option<DecodeLimits>,
+) -> Result<DecodeResult, SlimgError> {
+    let options = slimg_core::DecodeOptions {
+        auto_orient,
+        limits: limits_to_core(limits.as_ref()),
+    };
+    let (image, format) = slimg_core::decode_with(&data, &options)?;
     Ok(DecodeResult {
         image: ImageData::from_core(image),
         format: Format::from_core(format),
@@ -288,33 +1467,97 @@ fn decode(data: Vec<u8>) -> Result<DecodeResult, SlimgError> {
 }
 
 /// Read a file from disk, detect its format, and decode it.
-#[uniffi::export]
-fn decode_file(path: String) -> Result<DecodeResult, SlimgError> {
-    let (image, format) = slimg_core::decode_file(Path::new(&path))?;
+///
+/// With `auto_orient` (the default) the pixels are rotated to display upright.
+#[uniffi::export(default(auto_orient = true, limits = None))]
+fn decode_file(
+    path: String,
+    auto_orient: bool,
+    limits: Option<DecodeLimits>,
+) -> Result<DecodeResult, SlimgError> {
+    let options = slimg_core::DecodeOptions {
+        auto_orient,
+        limits: limits_to_core(limits.as_ref()),
+    };
+    let (image, format) = slimg_core::decode_file_with(Path::new(&path), &options)?;
     Ok(DecodeResult {
         image: ImageData::from_core(image),
         format: Format::from_core(format),
     })
 }
 
+/// Detect the format from magic bytes and decode every frame.
+///
+/// Still images come back as a single frame. With `auto_orient` (the
+/// default) the pixels are rotated to display upright.
+#[uniffi::export(default(auto_orient = true, limits = None))]
+fn decode_animation(
+    data: Vec<u8>,
+    auto_orient: bool,
+    limits: Option<DecodeLimits>,
+) -> Result<AnimationDecodeResult, SlimgError> {
+    let options = slimg_core::DecodeOptions {
+        auto_orient,
+        limits: limits_to_core(limits.as_ref()),
+    };
+    let (animation, format) = slimg_core::decode_animation_with(&data, &options)?;
+    Ok(AnimationDecodeResult {
+        animation: Animation::from_core(animation),
+        format: Format::from_core(format),
+    })
+}
+
+/// Read a file from disk, detect its format, and decode every frame.
+///
+/// With `auto_orient` (the default) the pixels are rotated to display upright.
+#[uniffi::export(default(auto_orient = true, limits = None))]
+fn decode_animation_file(
+    path: String,
+    auto_orient: bool,
+    limits: Option<DecodeLimits>,
+) -> Result<AnimationDecodeResult, SlimgError> {
+    let options = slimg_core::DecodeOptions {
+        auto_orient,
+        limits: limits_to_core(limits.as_ref()),
+    };
+    let (animation, format) = slimg_core::decode_animation_file_with(Path::new(&path), &options)?;
+    Ok(AnimationDecodeResult {
+        animation: Animation::from_core(animation),
+        format: Format::from_core(format),
+    })
+}
+
+/// Detect the format from magic bytes and read the image's headers without
+/// decoding its pixels.
+#[uniffi::export]
+fn probe(data: Vec<u8>) -> Result<ImageInfo, SlimgError> {
+    Ok(ImageInfo::from_core(slimg_core::probe(&data)?))
+}
+
+/// Read a file from disk, detect its format, and probe its headers.
+#[uniffi::export]
+fn probe_file(path: String) -> Result<ImageInfo, SlimgError> {
+    Ok(ImageInfo::from_core(slimg_core::probe_file(Path::new(
+        &path,
+    ))?))
+}
+
 /// Convert an image to the specified format, optionally resizing first.
 #[uniffi::export]
 fn convert(image: &ImageData, options: &PipelineOptions) -> Result<PipelineResult, SlimgError> {
-    let core_options = slimg_core::PipelineOptions {
-        format: options.format.to_core(),
-        quality: options.quality,
-        resize: options.resize.as_ref().map(|r| r.to_core()),
-        crop: options.crop.as_ref().map(|c| c.to_core()),
-        extend: options.extend.as_ref().map(|e| e.to_core()),
-        fill_color: options.fill_color.as_ref().map(|f| f.to_core()),
-    };
-    let result = slimg_core::convert(&image.to_core(), &core_options)?;
-    Ok(PipelineResult {
-        data: result.data,
-        format: Format::from_core(result.format),
-        width: result.width,
-        height: result.height,
-    })
+    let result = slimg_core::convert(&image.to_core(), &options.to_core())?;
+    Ok(PipelineResult::from_core(result))
+}
+
+/// Convert every frame of an animation, applying crop, extend and resize to
+/// each. Fails for still-only formats when there is more than one frame.
+#[uniffi::export]
+fn convert_animation(
+    animation: &Animation,
+    options: &PipelineOptions,
+) -> Result<PipelineResult, SlimgError> {
+    let result = slimg_core::convert_animation(&animation.to_core(), &options.to_core())?;
+    Ok(PipelineResult::from_core(result))
 }
 
 /// Crop an image according to the given mode.
@@ -324,6 +1567,18 @@ fn crop(image: &ImageData, mode: &CropMode) -> Result<ImageData, SlimgError> {
     Ok(ImageData::from_core(result))
 }
 
+/// The region `crop` would take from `image`.
+#[uniffi::export]
+fn crop_region(image: &ImageData, mode: &CropMode) -> Result<CropRegion, SlimgError> {
+    let (x, y, width, height) = slimg_core::crop::crop_region(&image.to_core(), &mode.to_core())?;
+    Ok(CropRegion {
+        x,
+        y,
+        width,
+        height,
+    })
+}
+
 /// Extend an image by adding padding around it.
 #[uniffi::export]
 fn extend(image: &ImageData, mode: &ExtendMode, fill: &FillColor) -> Result<ImageData, SlimgError> {
@@ -331,23 +1586,89 @@ fn extend(image: &ImageData, mode: &ExtendMode, fill: &FillColor) -> Result<Imag
     Ok(ImageData::from_core(result))
 }
 
-/// Resize an image according to the given mode.
+/// Resize an image according to the given mode, with `Lanczos3` unless
+/// `filter` says otherwise. Downscales are filtered in linear light and
+/// with premultiplied alpha by default.
+#[uniffi::export(default(
+    filter = None,
+    without_enlargement = false,
+    linear_light = None,
+    premultiply_alpha = true
+))]
+fn resize(
+    image: &ImageData,
+    mode: &ResizeMode,
+    filter: Option<ResizeFilter>,
+    without_enlargement: bool,
+    linear_light: Option<LinearLight>,
+    premultiply_alpha: bool,
+) -> Result<ImageData, SlimgError> {
+    let options =
+        resize_options_to_core(filter, without_enlargement, linear_light, premultiply_alpha);
+    let result = slimg_core::resize::resize_with(&image.to_core(), &mode.to_core(), &options)?;
+    Ok(ImageData::from_core(result))
+}
+
+/// Score `distorted` against `reference`, which must be the same size.
 #[uniffi::export]
-fn resize(image: &ImageData, mode: &ResizeMode) -> Result<ImageData, SlimgError> {
-    let result = slimg_core::resize::resize(&image.to_core(), &mode.to_core())?;
+fn compare(reference: &ImageData, distorted: &ImageData) -> Result<Scores, SlimgError> {
+    let scores = slimg_core::metrics::compare(&reference.to_core(), &distorted.to_core())?;
+    Ok(Scores::from_core(scores))
+}
+
+/// An opaque image highlighting where `distorted` differs from `reference`.
+#[uniffi::export]
+fn heatmap(reference: &ImageData, distorted: &ImageData) -> Result<ImageData, SlimgError> {
+    let result = slimg_core::metrics::heatmap(&reference.to_core(), &distorted.to_core())?;
     Ok(ImageData::from_core(result))
 }
 
 /// Decode the data and re-encode in the same format at the given quality.
-#[uniffi::export]
-fn optimize(data: Vec<u8>, quality: u8) -> Result<PipelineResult, SlimgError> {
-    let result = slimg_core::optimize(&data, quality)?;
-    Ok(PipelineResult {
-        data: result.data,
-        format: Format::from_core(result.format),
-        width: result.width,
-        height: result.height,
-    })
+/// Every frame of an animation is kept. With `quality_target`, the quality
+/// is searched for instead; with `max_size`, it is lowered until the output
+/// fits. With `never_larger`, input that would grow is returned unchanged,
+/// unless it holds metadata that `metadata` drops.
+#[uniffi::export(default(
+    codec = None,
+    lossless = false,
+    metadata = None,
+    color = None,
+    tone_map = None,
+    max_size = None,
+    quality_target = None,
+    never_larger = false,
+    limits = None
+))]
+#[allow(clippy::too_many_arguments)]
+fn optimize(
+    data: Vec<u8>,
+    quality: u8,
+    codec: Option<CodecOptions>,
+    lossless: bool,
+    metadata: Option<MetadataPolicy>,
+    color: Option<ColorPolicy>,
+    tone_map: Option<ToneMapOperator>,
+    max_size: Option<SizeLimit>,
+    quality_target: Option<QualityTarget>,
+    never_larger: bool,
+    limits: Option<DecodeLimits>,
+) -> Result<PipelineResult, SlimgError> {
+    let options = slimg_core::OptimizeOptions {
+        encode: slimg_core::EncodeOptions {
+            quality,
+            lossless,
+            codec: codec_to_core(codec.as_ref()),
+            metadata: metadata_to_core(metadata),
+            color: color_to_core(color),
+            tone_map: tone_map_to_core(tone_map),
+        },
+        max_size: max_size.as_ref().map(SizeLimit::to_core),
+        quality_target: quality_target.as_ref().map(QualityTarget::to_core),
+        never_larger,
+        limits: limits_to_core(limits.as_ref()),
+    };
+    let result = slimg_core::optimize_with(&data, &options)?;
+    Ok(PipelineResult::from_core(result))
 }
 
 /// Derive an output path for the converted image.
@@ -5,7 +5,9 @@ use base64::engine::general_purpose::STANDARD as BASE64;
 use serde::{Deserialize, Serialize};
 use tauri::Emitter;
 use slimg_core::{
-    CropMode, EncodeOptions, ExtendMode, FillColor, Format, ImageData, PipelineOptions, ResizeMode,
+    ChromaSubsampling, CodecOptions, ColorPolicy, CropMode, EncodeOptions, ExtendMode, FillColor,
+    Format, Gravity, ImageData, MetadataPolicy, OptimizeOptions, PipelineOptions,
+    PngQuantizeOptions, QoiColorspace, ResizeMode, ResizeOptions, ToneMapOperator,
     codec::get_codec,
 };
 
@@ -23,7 +25,6 @@ pub struct ImageInfo {
     pub height: u32,
     pub format: String,
     pub size_bytes: u64,
-    pub thumbnail_base64: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -41,15 +42,61 @@ pub struct ProcessOptions {
     pub operation: Operation,
     pub format: Option<String>,
     pub quality: u8,
+    #[serde(default)]
+    pub lossless: bool,
     pub width: Option<u32>,
     pub height: Option<u32>,
     pub x: Option<u32>,
     pub y: Option<u32>,
     pub crop_mode: Option<String>,
+    /// Part of the image kept by aspect crops, or where extend places it:
+    /// "center" (default), a compass direction such as "north" or
+    /// "southwest", or a focal point "x,y" in fractions of the image.
+    pub gravity: Option<String>,
     pub fill_color: Option<String>,
     pub resize_mode: Option<String>,
+    /// Pixel budget for the "max_pixels" resize mode, in megapixels.
+    pub max_megapixels: Option<f64>,
+    /// Never scale small images up when resizing.
+    #[serde(default)]
+    pub without_enlargement: bool,
     pub output_dir: Option<String>,
     pub overwrite: bool,
+    #[serde(default)]
+    pub codec: CodecSettings,
+    /// "keep", "strip" or "safe" (default).
+    pub metadata: Option<String>,
+    /// "preserve" (default) or "srgb".
+    pub color: Option<String>,
+    /// "clip", "reinhard" (default) or "hable".
+    pub tone_map: Option<String>,
+    /// Skip outputs no smaller than the input. Always on for optimize.
+    #[serde(default)]
+    pub never_larger: bool,
+}
+
+/// Per-format encoder overrides. Unset fields keep the core defaults.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CodecSettings {
+    pub jpeg_progressive: Option<bool>,
+    pub jpeg_subsampling: Option<String>,
+    pub png_level: Option<u8>,
+    pub png_interlace: Option<bool>,
+    pub png_lossy: Option<bool>,
+    pub png_colors: Option<u16>,
+    pub png_dithering: Option<f32>,
+    pub png_min_quality: Option<u8>,
+    pub webp_method: Option<u8>,
+    pub webp_alpha_quality: Option<u8>,
+    pub webp_sharp_yuv: Option<bool>,
+    pub avif_speed: Option<u8>,
+    pub avif_alpha_quality: Option<u8>,
+    pub jxl_effort: Option<u8>,
+    pub jxl_decoding_speed: Option<u8>,
+    pub qoi_linear: Option<bool>,
+    pub gif_colors: Option<u16>,
+    pub gif_dithering: Option<f32>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -60,6 +107,7 @@ pub struct ProcessResult {
     pub width: u32,
     pub height: u32,
     pub format: String,
+    pub skipped: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -124,8 +172,27 @@ pub async fn load_image(path: String) -> Result<ImageInfo, String> {
         let raw_bytes = std::fs::read(file_path).map_err(|e| e.to_string())?;
         let size_bytes = raw_bytes.len() as u64;
 
-        let (image, format) = slimg_core::decode(&raw_bytes).map_err(|e| e.to_string())?;
+        // Headers only; the thumbnail is decoded separately, on demand.
+        let info = slimg_core::probe(&raw_bytes).map_err(|e| e.to_string())?;
+        let (width, height) = info.display_dimensions();
 
+        Ok(ImageInfo {
+            width,
+            height,
+            format: info.format.extension().to_string(),
+            size_bytes,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Decode the image and return a small PNG of it, base64-encoded.
+#[tauri::command]
+pub async fn load_thumbnail(path: String) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let raw_bytes = std::fs::read(&path).map_err(|e| e.to_string())?;
+        let (image, _) = slimg_core::decode(&raw_bytes).map_err(|e| e.to_string())?;
         let thumbnail = slimg_core::resize::resize(
             &image,
             &ResizeMode::Fit(THUMBNAIL_MAX_DIMENSION, THUMBNAIL_MAX_DIMENSION),
@@ -133,15 +200,7 @@ pub async fn load_image(path: String) -> Result<ImageInfo, String> {
         .map_err(|e| e.to_string())?;
 
         let png_bytes = encode_as_png(&thumbnail)?;
-        let thumbnail_base64 = BASE64.encode(&png_bytes);
-
-        Ok(ImageInfo {
-            width: image.width,
-            height: image.height,
-            format: format.extension().to_string(),
-            size_bytes,
-            thumbnail_base64,
-        })
+        Ok(BASE64.encode(&png_bytes))
     })
     .await
     .map_err(|e| format!("Task failed: {}", e))?
@@ -160,13 +219,16 @@ pub async fn preview_image(input: String, options: ProcessOptions) -> Result<Pre
         let input_path = Path::new(&input);
 
         let raw_bytes = std::fs::read(input_path).map_err(|e| e.to_string())?;
-        let (image, source_format) = slimg_core::decode(&raw_bytes).map_err(|e| e.to_string())?;
+        let (animation, source_format) =
+            slimg_core::decode_animation(&raw_bytes).map_err(|e| e.to_string())?;
 
         let pipeline_result = if matches!(options.operation, Operation::Optimize) {
-            slimg_core::optimize(&raw_bytes, options.quality).map_err(|e| e.to_string())?
+            let encode_options = build_encode_options(&options)?;
+            slimg_core::optimize(&raw_bytes, &encode_options).map_err(|e| e.to_string())?
         } else {
             let pipeline_options = build_pipeline_options(&options, source_format)?;
-            slimg_core::convert(&image, &pipeline_options).map_err(|e| e.to_string())?
+            slimg_core::convert_animation(&animation, &pipeline_options)
+                .map_err(|e| e.to_string())?
         };
 
         let data_base64 = BASE64.encode(&pipeline_result.data);
@@ -245,15 +307,41 @@ fn process_single_file(input: &str, options: &ProcessOptions) -> Result<ProcessR
     let raw_bytes = std::fs::read(input_path).map_err(|e| e.to_string())?;
     let original_size = raw_bytes.len() as u64;
 
-    let (image, source_format) = slimg_core::decode(&raw_bytes).map_err(|e| e.to_string())?;
+    let (animation, source_format) =
+        slimg_core::decode_animation(&raw_bytes).map_err(|e| e.to_string())?;
 
     let pipeline_result = if matches!(options.operation, Operation::Optimize) {
-        slimg_core::optimize(&raw_bytes, options.quality).map_err(|e| e.to_string())?
+        let optimize_options = OptimizeOptions {
+            encode: build_encode_options(options)?,
+            never_larger: true,
+            ..Default::default()
+        };
+        slimg_core::optimize_with(&raw_bytes, &optimize_options).map_err(|e| e.to_string())?
     } else {
         let pipeline_options = build_pipeline_options(options, source_format)?;
-        slimg_core::convert(&image, &pipeline_options).map_err(|e| e.to_string())?
+        slimg_core::convert_animation(&animation, &pipeline_options)
+            .and_then(|result| {
+                if options.never_larger {
+                    slimg_core::never_larger(&raw_bytes, result, pipeline_options.metadata)
+                } else {
+                    Ok(result)
+                }
+            })
+            .map_err(|e| e.to_string())?
     };
 
+    if pipeline_result.kept_original {
+        return Ok(ProcessResult {
+            output_path: input.to_string(),
+            original_size,
+            new_size: original_size,
+            width: pipeline_result.width,
+            height: pipeline_result.height,
+            format: pipeline_result.format.extension().to_string(),
+            skipped: true,
+        });
+    }
+
     let output_dir = options.output_dir.as_deref().map(Path::new);
     let mut out_path = slimg_core::output_path(input_path, pipeline_result.format, output_dir);
 
@@ -272,6 +360,7 @@ fn process_single_file(input: &str, options: &ProcessOptions) -> Result<ProcessR
         width: pipeline_result.width,
         height: pipeline_result.height,
         format: pipeline_result.format.extension().to_string(),
+        skipped: false,
     })
 }
 
@@ -284,6 +373,12 @@ fn build_pipeline_options(
         None => source_format,
     };
 
+    let fill_color = options
+        .fill_color
+        .as_ref()
+        .map(|hex| parse_hex_color(hex))
+        .transpose()?;
+
     let resize = match options.operation {
         Operation::Resize => match options.resize_mode.as_deref() {
             Some("width") => options.width.map(ResizeMode::Width),
@@ -296,11 +391,25 @@ fn build_pipeline_options(
                 options.width.unwrap_or(0),
                 options.height.unwrap_or(0),
             )),
+            Some("cover") => Some(ResizeMode::Cover(
+                options.width.unwrap_or(0),
+                options.height.unwrap_or(0),
+            )),
+            Some("contain") => Some(ResizeMode::Contain(
+                options.width.unwrap_or(0),
+                options.height.unwrap_or(0),
+                fill_color.unwrap_or(FillColor::Solid([255, 255, 255, 255])),
+            )),
+            Some("max_pixels") => options
+                .max_megapixels
+                .map(|megapixels| ResizeMode::MaxPixels((megapixels * 1_000_000.0) as u64)),
             _ => options.width.map(ResizeMode::Width),
         },
         _ => None,
     };
 
+    let gravity = parse_gravity(options.gravity.as_deref())?;
+
     let crop = match options.operation {
         Operation::Crop => match options.crop_mode.as_deref() {
             Some("region") => Some(CropMode::Region {
@@ -312,6 +421,7 @@ fn build_pipeline_options(
             _ => Some(CropMode::AspectRatio {
                 width: options.width.unwrap_or(1),
                 height: options.height.unwrap_or(1),
+                gravity,
             }),
         },
         _ => None,
@@ -321,26 +431,108 @@ fn build_pipeline_options(
         Operation::Extend => Some(ExtendMode::AspectRatio {
             width: options.width.unwrap_or(1),
             height: options.height.unwrap_or(1),
+            gravity,
         }),
         _ => None,
     };
 
-    let fill_color = options
-        .fill_color
-        .as_ref()
-        .map(|hex| parse_hex_color(hex))
-        .transpose()?;
-
     Ok(PipelineOptions {
         format,
         quality: options.quality,
+        lossless: options.lossless,
         resize,
+        resize_options: ResizeOptions {
+            without_enlargement: options.without_enlargement,
+            ..Default::default()
+        },
         crop,
         extend,
         fill_color,
+        codec: build_codec_options(&options.codec)?,
+        metadata: parse_metadata_policy(options.metadata.as_deref())?,
+        color: parse_color_policy(options.color.as_deref())?,
+        tone_map: parse_tone_map(options.tone_map.as_deref())?,
+        max_size: None,
+        quality_target: None,
+        auto_format: None,
     })
 }
 
+fn build_encode_options(options: &ProcessOptions) -> Result<EncodeOptions, String> {
+    Ok(EncodeOptions {
+        quality: options.quality,
+        lossless: options.lossless,
+        codec: build_codec_options(&options.codec)?,
+        metadata: parse_metadata_policy(options.metadata.as_deref())?,
+        color: parse_color_policy(options.color.as_deref())?,
+        tone_map: parse_tone_map(options.tone_map.as_deref())?,
+    })
+}
+
+fn build_codec_options(settings: &CodecSettings) -> Result<CodecOptions, String> {
+    let mut codec = CodecOptions::default();
+
+    if let Some(progressive) = settings.jpeg_progressive {
+        codec.jpeg.progressive = progressive;
+    }
+    if let Some(subsampling) = &settings.jpeg_subsampling {
+        codec.jpeg.chroma_subsampling = match subsampling.as_str() {
+            "420" => ChromaSubsampling::Yuv420,
+            "422" => ChromaSubsampling::Yuv422,
+            "444" => ChromaSubsampling::Yuv444,
+            other => return Err(format!("Unknown chroma subsampling: {}", other)),
+        };
+    }
+
+    codec.png.optimization_level = settings.png_level;
+    if let Some(interlace) = settings.png_interlace {
+        codec.png.interlace = interlace;
+    }
+    if settings.png_lossy == Some(true) {
+        let defaults = PngQuantizeOptions::default();
+        codec.png.quantize = Some(PngQuantizeOptions {
+            max_colors: settings.png_colors.unwrap_or(defaults.max_colors),
+            dithering: settings.png_dithering.unwrap_or(defaults.dithering),
+            min_quality: settings.png_min_quality.unwrap_or(defaults.min_quality),
+        });
+    }
+
+    if let Some(method) = settings.webp_method {
+        codec.webp.method = method;
+    }
+    if let Some(alpha_quality) = settings.webp_alpha_quality {
+        codec.webp.alpha_quality = alpha_quality;
+    }
+    if let Some(sharp_yuv) = settings.webp_sharp_yuv {
+        codec.webp.sharp_yuv = sharp_yuv;
+    }
+
+    if let Some(speed) = settings.avif_speed {
+        codec.avif.speed = speed;
+    }
+    codec.avif.alpha_quality = settings.avif_alpha_quality;
+
+    if let Some(effort) = settings.jxl_effort {
+        codec.jxl.effort = effort;
+    }
+    if let Some(decoding_speed) = settings.jxl_decoding_speed {
+        codec.jxl.decoding_speed = decoding_speed;
+    }
+
+    if settings.qoi_linear == Some(true) {
+        codec.qoi.colorspace = QoiColorspace::Linear;
+    }
+
+    if let Some(colors) = settings.gif_colors {
+        codec.gif.max_colors = colors;
+    }
+    if let Some(dithering) = settings.gif_dithering {
+        codec.gif.dithering = dithering;
+    }
+
+    Ok(codec)
+}
+
 fn parse_format(s: &str) -> Result<Format, String> {
     match s.to_lowercase().as_str() {
         "jpeg" | "jpg" => Ok(Format::Jpeg),
@@ -349,10 +541,65 @@ fn parse_format(s: &str) -> Result<Format, String> {
         "avif" => Ok(Format::Avif),
         "jxl" => Ok(Format::Jxl),
         "qoi" => Ok(Format::Qoi),
+        "gif" => Ok(Format::Gif),
+        "tiff" | "tif" => Ok(Format::Tiff),
+        "bmp" => Ok(Format::Bmp),
+        "tga" => Ok(Format::Tga),
+        "ico" => Ok(Format::Ico),
+        "pnm" => Ok(Format::Pnm),
         _ => Err(format!("Unknown format: {}", s)),
     }
 }
 
+fn parse_metadata_policy(s: Option<&str>) -> Result<MetadataPolicy, String> {
+    match s.map(str::to_lowercase).as_deref() {
+        None | Some("safe") => Ok(MetadataPolicy::Safe),
+        Some("keep") => Ok(MetadataPolicy::Keep),
+        Some("strip") => Ok(MetadataPolicy::Strip),
+        Some(other) => Err(format!("Unknown metadata policy: {}", other)),
+    }
+}
+
+fn parse_color_policy(s: Option<&str>) -> Result<ColorPolicy, String> {
+    match s.map(str::to_lowercase).as_deref() {
+        None | Some("preserve") => Ok(ColorPolicy::Preserve),
+        Some("srgb") => Ok(ColorPolicy::Srgb),
+        Some(other) => Err(format!("Unknown color policy: {}", other)),
+    }
+}
+
+fn parse_tone_map(s: Option<&str>) -> Result<ToneMapOperator, String> {
+    match s.map(str::to_lowercase).as_deref() {
+        None | Some("reinhard") => Ok(ToneMapOperator::Reinhard),
+        Some("clip") => Ok(ToneMapOperator::Clip),
+        Some("hable") => Ok(ToneMapOperator::Hable),
+        Some(other) => Err(format!("Unknown tone map operator: {}", other)),
+    }
+}
+
+fn parse_gravity(s: Option<&str>) -> Result<Gravity, String> {
+    match s.map(str::to_lowercase).as_deref() {
+        None | Some("center") => Ok(Gravity::Center),
+        Some("north") => Ok(Gravity::North),
+        Some("northeast") => Ok(Gravity::NorthEast),
+        Some("east") => Ok(Gravity::East),
+        Some("southeast") => Ok(Gravity::SouthEast),
+        Some("south") => Ok(Gravity::South),
+        Some("southwest") => Ok(Gravity::SouthWest),
+        Some("west") => Ok(Gravity::West),
+        Some("northwest") => Ok(Gravity::NorthWest),
+        Some(other) => {
+            let point = other
+                .split_once(',')
+                .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)));
+            match point {
+                Some((x, y)) => Ok(Gravity::Focal { x, y }),
+                None => Err(format!("Unknown gravity: {}", other)),
+            }
+        }
+    }
+}
+
 fn parse_hex_color(hex: &str) -> Result<FillColor, String> {
     let hex = hex.trim_start_matches('#');
     if hex.len() != 6 && hex.len() != 8 {
@@ -373,6 +620,9 @@ fn encode_as_png(image: &ImageData) -> Result<Vec<u8>, String> {
     let codec = get_codec(Format::Png);
     let opts = EncodeOptions {
         quality: THUMBNAIL_PNG_COMPRESSION,
+        metadata: MetadataPolicy::Strip,
+        color: ColorPolicy::Srgb,
+        ..Default::default()
     };
     codec.encode(image, &opts).map_err(|e| e.to_string())
 }
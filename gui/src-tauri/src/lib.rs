@@ -7,6 +7,7 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             commands::scan_directory,
             commands::load_image,
+            commands::load_thumbnail,
             commands::process_image,
             commands::preview_image,
             commands::process_batch,
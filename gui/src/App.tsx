@@ -6,6 +6,7 @@ import { OptionsPanel } from "@/components/OptionsPanel";
 import { ProcessResultCard } from "@/components/ProcessResultCard";
 import { BatchList } from "@/components/BatchList";
 import { Settings } from "@/components/Settings";
+import { Thumbnail } from "@/components/Thumbnail";
 import { Button } from "@/components/ui/button";
 import { Checkbox } from "@/components/ui/checkbox";
 import { Label } from "@/components/ui/label";
@@ -369,11 +370,7 @@ function FileListWithOptions({
             className="overflow-hidden rounded-xl border bg-card"
           >
             <div className="flex aspect-video items-center justify-center bg-muted">
-              <img
-                src={`data:image/png;base64,${file.info.thumbnail_base64}`}
-                alt={basename(file.path)}
-                className="max-h-full max-w-full object-contain"
-              />
+              <Thumbnail path={file.path} alt={basename(file.path)} />
             </div>
             <div className="p-4">
               <p className="truncate text-sm font-medium">
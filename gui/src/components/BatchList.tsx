@@ -104,12 +104,18 @@ export function BatchList({
                 <p className="truncate text-sm font-medium">
                   {basename(item.path)}
                 </p>
-                {item.status === "completed" && item.result && (
-                  <SizeChange
-                    originalSize={item.result.original_size}
-                    newSize={item.result.new_size}
-                  />
-                )}
+                {item.status === "completed" &&
+                  item.result &&
+                  (item.result.skipped ? (
+                    <span className="text-xs text-amber-600 dark:text-amber-400">
+                      skipped (would grow)
+                    </span>
+                  ) : (
+                    <SizeChange
+                      originalSize={item.result.original_size}
+                      newSize={item.result.new_size}
+                    />
+                  ))}
                 {item.status === "error" && item.error && (
                   <p className="truncate text-xs text-destructive">
                     {item.error}
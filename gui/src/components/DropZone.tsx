@@ -14,6 +14,19 @@ const SUPPORTED_EXTENSIONS = [
   "avif",
   "jxl",
   "qoi",
+  "gif",
+  "tif",
+  "tiff",
+  "bmp",
+  "dib",
+  "tga",
+  "ico",
+  "cur",
+  "pnm",
+  "pbm",
+  "pgm",
+  "ppm",
+  "pam",
 ];
 
 function isSupportedImage(path: string): boolean {
@@ -128,7 +141,7 @@ export function DropZone({ onFilesSelected, disabled = false }: DropZoneProps) {
           Drop images or folders here
         </p>
         <p className="mt-1 text-sm text-muted-foreground">
-          Supports JPG, PNG, WebP, AVIF, JXL, QOI
+          Supports JPG, PNG, WebP, AVIF, JXL, QOI, GIF, TIFF, BMP, TGA, ICO, PNM
         </p>
       </div>
 
@@ -1,6 +1,7 @@
 import type { ImageInfo, ProcessResult } from "@/lib/tauri";
 import { formatBytes, calcSavingsPercent } from "@/lib/format";
 import { basename } from "@/lib/path";
+import { Thumbnail } from "@/components/Thumbnail";
 
 interface ImagePreviewProps {
   original: { path: string; info: ImageInfo };
@@ -10,7 +11,7 @@ interface ImagePreviewProps {
 
 function ImagePanel({
   label,
-  thumbnailBase64,
+  thumbnailPath,
   fileName,
   sizeBytes,
   width,
@@ -18,7 +19,7 @@ function ImagePanel({
   format,
 }: {
   label: string;
-  thumbnailBase64?: string;
+  thumbnailPath?: string;
   fileName: string;
   sizeBytes: number;
   width: number;
@@ -31,17 +32,7 @@ function ImagePanel({
         <span className="text-sm font-bold">{label}</span>
       </div>
       <div className="flex aspect-video items-center justify-center bg-muted">
-        {thumbnailBase64 ? (
-          <img
-            src={`data:image/png;base64,${thumbnailBase64}`}
-            alt={`${label} - ${fileName}`}
-            className="max-h-full max-w-full object-contain"
-          />
-        ) : (
-          <div className="flex items-center justify-center text-sm text-muted-foreground">
-            Loading...
-          </div>
-        )}
+        <Thumbnail path={thumbnailPath} alt={`${label} - ${fileName}`} />
       </div>
       <div className="space-y-1 p-4">
         <p className="truncate text-sm font-medium" title={fileName}>
@@ -94,7 +85,7 @@ export function ImagePreview({
     <div className="flex items-stretch gap-4">
       <ImagePanel
         label="Before"
-        thumbnailBase64={original.info.thumbnail_base64}
+        thumbnailPath={original.path}
         fileName={basename(original.path)}
         sizeBytes={original.info.size_bytes}
         width={original.info.width}
@@ -109,7 +100,7 @@ export function ImagePreview({
 
       <ImagePanel
         label="After"
-        thumbnailBase64={resultInfo?.thumbnail_base64}
+        thumbnailPath={resultInfo ? result.output_path : undefined}
         fileName={basename(result.output_path)}
         sizeBytes={result.new_size}
         width={result.width}
@@ -24,20 +24,26 @@ export function ProcessResultCard({ result }: { result: ProcessResult }) {
         </div>
         <div className="flex justify-between">
           <span className="text-muted-foreground">Size</span>
-          <span>
-            {formatBytes(result.original_size)} &rarr;{" "}
-            {formatBytes(result.new_size)}{" "}
-            <span
-              className={
-                isSizeReduced
-                  ? "text-green-600 dark:text-green-400"
-                  : "text-amber-600 dark:text-amber-400"
-              }
-            >
-              ({isSizeReduced ? "-" : "+"}
-              {Math.abs(savingsPercent).toFixed(1)}%)
+          {result.skipped ? (
+            <span className="text-amber-600 dark:text-amber-400">
+              Skipped (would grow)
             </span>
-          </span>
+          ) : (
+            <span>
+              {formatBytes(result.original_size)} &rarr;{" "}
+              {formatBytes(result.new_size)}{" "}
+              <span
+                className={
+                  isSizeReduced
+                    ? "text-green-600 dark:text-green-400"
+                    : "text-amber-600 dark:text-amber-400"
+                }
+              >
+                ({isSizeReduced ? "-" : "+"}
+                {Math.abs(savingsPercent).toFixed(1)}%)
+              </span>
+            </span>
+          )}
         </div>
         <div className="flex justify-between">
           <span className="text-muted-foreground">Resolution</span>
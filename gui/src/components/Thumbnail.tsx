@@ -0,0 +1,17 @@
+import { useThumbnail } from "@/hooks/useThumbnail";
+
+export function Thumbnail({ path, alt }: { path?: string; alt: string }) {
+  const thumbnail = useThumbnail(path);
+
+  return thumbnail ? (
+    <img
+      src={`data:image/png;base64,${thumbnail}`}
+      alt={alt}
+      className="max-h-full max-w-full object-contain"
+    />
+  ) : (
+    <div className="flex items-center justify-center text-sm text-muted-foreground">
+      Loading...
+    </div>
+  );
+}
@@ -1,9 +1,10 @@
 import { useState, useCallback, useEffect } from "react";
-import type { ProcessOptions } from "@/lib/tauri";
+import type { Gravity, ProcessOptions } from "@/lib/tauri";
 import { Label } from "@/components/ui/label";
 import { Input } from "@/components/ui/input";
 import { RadioGroup, RadioGroupItem } from "@/components/ui/radio-group";
 import { FormatSelect } from "./FormatSelect";
+import { GravitySelect } from "./GravitySelect";
 import { QualitySlider } from "./QualitySlider";
 
 type CropMode = "region" | "aspect";
@@ -19,6 +20,7 @@ export function CropOptions({ defaultQuality, onChange }: CropOptionsProps) {
   const [y, setY] = useState<number | undefined>(undefined);
   const [width, setWidth] = useState<number | undefined>(undefined);
   const [height, setHeight] = useState<number | undefined>(undefined);
+  const [gravity, setGravity] = useState<Gravity>("center");
   const [format, setFormat] = useState("same");
   const [quality, setQuality] = useState(defaultQuality);
 
@@ -29,6 +31,7 @@ export function CropOptions({ defaultQuality, onChange }: CropOptionsProps) {
       y?: number;
       width?: number;
       height?: number;
+      gravity: Gravity;
       format: string;
       quality: number;
     }) => {
@@ -40,6 +43,8 @@ export function CropOptions({ defaultQuality, onChange }: CropOptionsProps) {
       if (state.cropMode === "region") {
         if (state.x !== undefined) opts.x = state.x;
         if (state.y !== undefined) opts.y = state.y;
+      } else {
+        opts.gravity = state.gravity;
       }
       if (state.width) opts.width = state.width;
       if (state.height) opts.height = state.height;
@@ -50,14 +55,14 @@ export function CropOptions({ defaultQuality, onChange }: CropOptionsProps) {
   );
 
   useEffect(() => {
-    emitChange({ cropMode, x, y, width, height, format, quality });
+    emitChange({ cropMode, x, y, width, height, gravity, format, quality });
     // eslint-disable-next-line react-hooks/exhaustive-deps -- emit initial values on mount only
   }, []);
 
   const handleCropModeChange = (value: string) => {
     const mode = value as CropMode;
     setCropMode(mode);
-    emitChange({ cropMode: mode, x, y, width, height, format, quality });
+    emitChange({ cropMode: mode, x, y, width, height, gravity, format, quality });
   };
 
   const parseNum = (value: string) =>
@@ -68,7 +73,7 @@ export function CropOptions({ defaultQuality, onChange }: CropOptionsProps) {
     value: string
   ) => {
     const num = parseNum(value);
-    const next = { cropMode, x, y, width, height, format, quality };
+    const next = { cropMode, x, y, width, height, gravity, format, quality };
     next[field] = num;
 
     if (field === "x") setX(num);
@@ -79,14 +84,19 @@ export function CropOptions({ defaultQuality, onChange }: CropOptionsProps) {
     emitChange(next);
   };
 
+  const handleGravityChange = (value: Gravity) => {
+    setGravity(value);
+    emitChange({ cropMode, x, y, width, height, gravity: value, format, quality });
+  };
+
   const handleFormatChange = (value: string) => {
     setFormat(value);
-    emitChange({ cropMode, x, y, width, height, format: value, quality });
+    emitChange({ cropMode, x, y, width, height, gravity, format: value, quality });
   };
 
   const handleQualityChange = (value: number) => {
     setQuality(value);
-    emitChange({ cropMode, x, y, width, height, format, quality: value });
+    emitChange({ cropMode, x, y, width, height, gravity, format, quality: value });
   };
 
   return (
@@ -157,6 +167,14 @@ export function CropOptions({ defaultQuality, onChange }: CropOptionsProps) {
         </div>
       </div>
 
+      {cropMode === "aspect" && (
+        <GravitySelect
+          value={gravity}
+          onChange={handleGravityChange}
+          label="Keep"
+        />
+      )}
+
       <FormatSelect
         value={format}
         onChange={handleFormatChange}
@@ -1,8 +1,9 @@
 import { useState, useCallback, useEffect } from "react";
-import type { ProcessOptions } from "@/lib/tauri";
+import type { Gravity, ProcessOptions } from "@/lib/tauri";
 import { Label } from "@/components/ui/label";
 import { Input } from "@/components/ui/input";
 import { FormatSelect } from "./FormatSelect";
+import { GravitySelect } from "./GravitySelect";
 import { QualitySlider } from "./QualitySlider";
 
 const DEFAULT_FILL_COLOR = "#FFFFFF";
@@ -16,6 +17,7 @@ export function ExtendOptions({ defaultQuality, onChange }: ExtendOptionsProps)
   const [width, setWidth] = useState<number | undefined>(undefined);
   const [height, setHeight] = useState<number | undefined>(undefined);
   const [fillColor, setFillColor] = useState(DEFAULT_FILL_COLOR);
+  const [gravity, setGravity] = useState<Gravity>("center");
   const [format, setFormat] = useState("same");
   const [quality, setQuality] = useState(defaultQuality);
 
@@ -24,12 +26,14 @@ export function ExtendOptions({ defaultQuality, onChange }: ExtendOptionsProps)
       width?: number;
       height?: number;
       fillColor: string;
+      gravity: Gravity;
       format: string;
       quality: number;
     }) => {
       const opts: Partial<ProcessOptions> = {
         operation: "extend",
         fill_color: state.fillColor,
+        gravity: state.gravity,
         quality: state.quality,
       };
       if (state.width) opts.width = state.width;
@@ -41,35 +45,40 @@ export function ExtendOptions({ defaultQuality, onChange }: ExtendOptionsProps)
   );
 
   useEffect(() => {
-    emitChange({ width, height, fillColor, format, quality });
+    emitChange({ width, height, fillColor, gravity, format, quality });
     // eslint-disable-next-line react-hooks/exhaustive-deps -- emit initial values on mount only
   }, []);
 
   const handleWidthChange = (value: string) => {
     const w = value ? parseInt(value, 10) : undefined;
     setWidth(w);
-    emitChange({ width: w, height, fillColor, format, quality });
+    emitChange({ width: w, height, fillColor, gravity, format, quality });
   };
 
   const handleHeightChange = (value: string) => {
     const h = value ? parseInt(value, 10) : undefined;
     setHeight(h);
-    emitChange({ width, height: h, fillColor, format, quality });
+    emitChange({ width, height: h, fillColor, gravity, format, quality });
   };
 
   const handleFillColorChange = (value: string) => {
     setFillColor(value);
-    emitChange({ width, height, fillColor: value, format, quality });
+    emitChange({ width, height, fillColor: value, gravity, format, quality });
+  };
+
+  const handleGravityChange = (value: Gravity) => {
+    setGravity(value);
+    emitChange({ width, height, fillColor, gravity: value, format, quality });
   };
 
   const handleFormatChange = (value: string) => {
     setFormat(value);
-    emitChange({ width, height, fillColor, format: value, quality });
+    emitChange({ width, height, fillColor, gravity, format: value, quality });
   };
 
   const handleQualityChange = (value: number) => {
     setQuality(value);
-    emitChange({ width, height, fillColor, format, quality: value });
+    emitChange({ width, height, fillColor, gravity, format, quality: value });
   };
 
   const isValidHex = /^#[0-9A-Fa-f]{6}$/.test(fillColor);
@@ -120,6 +129,12 @@ export function ExtendOptions({ defaultQuality, onChange }: ExtendOptionsProps)
         </div>
       </div>
 
+      <GravitySelect
+        value={gravity}
+        onChange={handleGravityChange}
+        label="Position"
+      />
+
       <FormatSelect
         value={format}
         onChange={handleFormatChange}
@@ -14,6 +14,7 @@ const FORMATS = [
   { value: "avif", label: "AVIF" },
   { value: "qoi", label: "QOI" },
   { value: "jxl", label: "JXL" },
+  { value: "gif", label: "GIF" },
 ] as const;
 
 interface FormatSelectProps {
@@ -0,0 +1,47 @@
+import type { Gravity } from "@/lib/tauri";
+import { Label } from "@/components/ui/label";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+
+const GRAVITIES = [
+  { value: "center", label: "Center" },
+  { value: "north", label: "Top" },
+  { value: "northeast", label: "Top right" },
+  { value: "east", label: "Right" },
+  { value: "southeast", label: "Bottom right" },
+  { value: "south", label: "Bottom" },
+  { value: "southwest", label: "Bottom left" },
+  { value: "west", label: "Left" },
+  { value: "northwest", label: "Top left" },
+] as const;
+
+interface GravitySelectProps {
+  value: Gravity;
+  onChange: (value: Gravity) => void;
+  label: string;
+}
+
+export function GravitySelect({ value, onChange, label }: GravitySelectProps) {
+  return (
+    <div className="space-y-2">
+      <Label>{label}</Label>
+      <Select value={value} onValueChange={(v) => onChange(v as Gravity)}>
+        <SelectTrigger className="w-full">
+          <SelectValue />
+        </SelectTrigger>
+        <SelectContent>
+          {GRAVITIES.map((g) => (
+            <SelectItem key={g.value} value={g.value}>
+              {g.label}
+            </SelectItem>
+          ))}
+        </SelectContent>
+      </Select>
+    </div>
+  );
+}
@@ -1,20 +1,37 @@
 import { useState, useCallback, useEffect } from "react";
 import type { ProcessOptions, ImageInfo } from "@/lib/tauri";
+import { Checkbox } from "@/components/ui/checkbox";
 import { Label } from "@/components/ui/label";
 import { Input } from "@/components/ui/input";
 import { RadioGroup, RadioGroupItem } from "@/components/ui/radio-group";
 import { FormatSelect } from "./FormatSelect";
 import { QualitySlider } from "./QualitySlider";
 
-type ResizeMode = "width" | "height" | "exact" | "fit";
+type ResizeMode = "width" | "height" | "exact" | "fit" | "cover" | "contain" | "max_pixels";
 
 const RESIZE_MODES: { value: ResizeMode; label: string }[] = [
   { value: "width", label: "By Width" },
   { value: "height", label: "By Height" },
   { value: "exact", label: "Exact" },
   { value: "fit", label: "Fit" },
+  { value: "cover", label: "Cover (crop)" },
+  { value: "contain", label: "Contain (pad)" },
+  { value: "max_pixels", label: "Max Megapixels" },
 ];
 
+const DEFAULT_FILL_COLOR = "#FFFFFF";
+
+interface ResizeState {
+  resizeMode: ResizeMode;
+  width?: number;
+  height?: number;
+  megapixels?: number;
+  fillColor: string;
+  withoutEnlargement: boolean;
+  format: string;
+  quality: number;
+}
+
 interface ResizeOptionsProps {
   defaultQuality: number;
   onChange: (options: Partial<ProcessOptions>) => void;
@@ -25,24 +42,35 @@ export function ResizeOptions({ defaultQuality, onChange, imageInfo }: ResizeOpt
   const [resizeMode, setResizeMode] = useState<ResizeMode>("width");
   const [width, setWidth] = useState<number | undefined>(undefined);
   const [height, setHeight] = useState<number | undefined>(undefined);
+  const [megapixels, setMegapixels] = useState<number | undefined>(undefined);
+  const [fillColor, setFillColor] = useState(DEFAULT_FILL_COLOR);
+  const [withoutEnlargement, setWithoutEnlargement] = useState(false);
   const [format, setFormat] = useState("same");
   const [quality, setQuality] = useState(defaultQuality);
 
+  const current: ResizeState = {
+    resizeMode,
+    width,
+    height,
+    megapixels,
+    fillColor,
+    withoutEnlargement,
+    format,
+    quality,
+  };
+
   const emitChange = useCallback(
-    (state: {
-      resizeMode: ResizeMode;
-      width?: number;
-      height?: number;
-      format: string;
-      quality: number;
-    }) => {
+    (state: ResizeState) => {
       const opts: Partial<ProcessOptions> = {
         operation: "resize",
         resize_mode: state.resizeMode,
+        without_enlargement: state.withoutEnlargement,
         quality: state.quality,
       };
       if (state.width) opts.width = state.width;
       if (state.height) opts.height = state.height;
+      if (state.megapixels) opts.max_megapixels = state.megapixels;
+      if (state.resizeMode === "contain") opts.fill_color = state.fillColor;
       if (state.format !== "same") opts.format = state.format;
       onChange(opts);
     },
@@ -50,40 +78,58 @@ export function ResizeOptions({ defaultQuality, onChange, imageInfo }: ResizeOpt
   );
 
   useEffect(() => {
-    emitChange({ resizeMode, width, height, format, quality });
+    emitChange(current);
     // eslint-disable-next-line react-hooks/exhaustive-deps -- emit initial values on mount only
   }, []);
 
   const handleResizeModeChange = (value: string) => {
     const mode = value as ResizeMode;
     setResizeMode(mode);
-    emitChange({ resizeMode: mode, width, height, format, quality });
+    emitChange({ ...current, resizeMode: mode });
   };
 
   const handleWidthChange = (value: string) => {
     const w = value ? parseInt(value, 10) : undefined;
     setWidth(w);
-    emitChange({ resizeMode, width: w, height, format, quality });
+    emitChange({ ...current, width: w });
   };
 
   const handleHeightChange = (value: string) => {
     const h = value ? parseInt(value, 10) : undefined;
     setHeight(h);
-    emitChange({ resizeMode, width, height: h, format, quality });
+    emitChange({ ...current, height: h });
+  };
+
+  const handleMegapixelsChange = (value: string) => {
+    const mp = value ? parseFloat(value) : undefined;
+    setMegapixels(mp);
+    emitChange({ ...current, megapixels: mp });
+  };
+
+  const handleFillColorChange = (value: string) => {
+    setFillColor(value);
+    emitChange({ ...current, fillColor: value });
+  };
+
+  const handleWithoutEnlargementChange = (value: boolean) => {
+    setWithoutEnlargement(value);
+    emitChange({ ...current, withoutEnlargement: value });
   };
 
   const handleFormatChange = (value: string) => {
     setFormat(value);
-    emitChange({ resizeMode, width, height, format: value, quality });
+    emitChange({ ...current, format: value });
   };
 
   const handleQualityChange = (value: number) => {
     setQuality(value);
-    emitChange({ resizeMode, width, height, format, quality: value });
+    emitChange({ ...current, quality: value });
   };
 
+  const isMaxPixels = resizeMode === "max_pixels";
   const isWidthDisabled = resizeMode === "height";
   const isHeightDisabled = resizeMode === "width";
+  const isValidHex = /^#[0-9A-Fa-f]{6}$/.test(fillColor);
 
   return (
     <div className="space-y-4">
@@ -106,29 +152,74 @@ export function ResizeOptions({ defaultQuality, onChange, imageInfo }: ResizeOpt
         </RadioGroup>
       </div>
 
-      <div className="grid grid-cols-2 gap-4">
+      {isMaxPixels ? (
         <div className="space-y-2">
-          <Label>Width</Label>
+          <Label>Megapixels</Label>
           <Input
             type="number"
-            min={1}
-            value={width ?? ""}
-            onChange={(e) => handleWidthChange(e.target.value)}
-            placeholder={imageInfo ? String(imageInfo.width) : "Width"}
-            disabled={isWidthDisabled}
+            min={0.1}
+            step={0.1}
+            value={megapixels ?? ""}
+            onChange={(e) => handleMegapixelsChange(e.target.value)}
+            placeholder="e.g. 12"
           />
         </div>
+      ) : (
+        <div className="grid grid-cols-2 gap-4">
+          <div className="space-y-2">
+            <Label>Width</Label>
+            <Input
+              type="number"
+              min={1}
+              value={width ?? ""}
+              onChange={(e) => handleWidthChange(e.target.value)}
+              placeholder={imageInfo ? String(imageInfo.width) : "Width"}
+              disabled={isWidthDisabled}
+            />
+          </div>
+          <div className="space-y-2">
+            <Label>Height</Label>
+            <Input
+              type="number"
+              min={1}
+              value={height ?? ""}
+              onChange={(e) => handleHeightChange(e.target.value)}
+              placeholder={imageInfo ? String(imageInfo.height) : "Height"}
+              disabled={isHeightDisabled}
+            />
+          </div>
+        </div>
+      )}
+
+      {resizeMode === "contain" && (
         <div className="space-y-2">
-          <Label>Height</Label>
-          <Input
-            type="number"
-            min={1}
-            value={height ?? ""}
-            onChange={(e) => handleHeightChange(e.target.value)}
-            placeholder={imageInfo ? String(imageInfo.height) : "Height"}
-            disabled={isHeightDisabled}
-          />
+          <Label>Padding Color</Label>
+          <div className="flex items-center gap-2">
+            <div
+              className="h-9 w-9 shrink-0 rounded-md border"
+              style={{
+                backgroundColor: isValidHex ? fillColor : "#FFFFFF",
+              }}
+            />
+            <Input
+              value={fillColor}
+              onChange={(e) => handleFillColorChange(e.target.value)}
+              placeholder="#FFFFFF"
+              maxLength={7}
+            />
+          </div>
         </div>
+      )}
+
+      <div className="flex items-center gap-3">
+        <Checkbox
+          id="without-enlargement"
+          checked={withoutEnlargement}
+          onCheckedChange={(checked) => handleWithoutEnlargementChange(checked === true)}
+        />
+        <Label htmlFor="without-enlargement" className="cursor-pointer">
+          Don&apos;t enlarge smaller images
+        </Label>
       </div>
 
       <FormatSelect
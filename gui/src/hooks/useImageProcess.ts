@@ -31,7 +31,7 @@ export function useImageProcess(): UseImageProcessReturn {
         const res = await api.processImage(path, options);
         setResult(res);
 
-        // Load result file info (thumbnail) for before/after comparison
+        // Load result file info for before/after comparison
         try {
           const info = await api.loadImage(res.output_path);
           setResultInfo(info);
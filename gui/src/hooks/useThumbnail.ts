@@ -0,0 +1,25 @@
+import { useEffect, useState } from "react";
+import { api } from "@/lib/tauri";
+
+/** Base64 PNG thumbnail of the image at `path`, loaded once it is shown. */
+export function useThumbnail(path?: string): string | undefined {
+  const [thumbnail, setThumbnail] = useState<string>();
+
+  useEffect(() => {
+    setThumbnail(undefined);
+    if (!path) return;
+
+    let cancelled = false;
+    api
+      .loadThumbnail(path)
+      .then((data) => {
+        if (!cancelled) setThumbnail(data);
+      })
+      .catch((err) => console.warn("Failed to load thumbnail:", err));
+    return () => {
+      cancelled = true;
+    };
+  }, [path]);
+
+  return thumbnail;
+}
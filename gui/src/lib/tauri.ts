@@ -5,22 +5,64 @@ export interface ImageInfo {
   height: number;
   format: string;
   size_bytes: number;
-  thumbnail_base64: string;
 }
 
+/** Compass anchor, or a focal point "x,y" in fractions of the image. */
+export type Gravity =
+  | "center"
+  | "north"
+  | "northeast"
+  | "east"
+  | "southeast"
+  | "south"
+  | "southwest"
+  | "west"
+  | "northwest"
+  | `${number},${number}`;
+
 export interface ProcessOptions {
   operation: "convert" | "optimize" | "resize" | "crop" | "extend";
   format?: string;
   quality: number;
+  lossless?: boolean;
   width?: number;
   height?: number;
   x?: number;
   y?: number;
   crop_mode?: "region" | "aspect";
+  gravity?: Gravity;
   fill_color?: string;
-  resize_mode?: "width" | "height" | "exact" | "fit";
+  resize_mode?: "width" | "height" | "exact" | "fit" | "cover" | "contain" | "max_pixels";
+  max_megapixels?: number;
+  without_enlargement?: boolean;
   output_dir?: string;
   overwrite: boolean;
+  codec?: CodecSettings;
+  metadata?: "keep" | "strip" | "safe";
+  color?: "preserve" | "srgb";
+  tone_map?: "clip" | "reinhard" | "hable";
+  never_larger?: boolean;
+}
+
+export interface CodecSettings {
+  jpeg_progressive?: boolean;
+  jpeg_subsampling?: "420" | "422" | "444";
+  png_level?: number;
+  png_interlace?: boolean;
+  png_lossy?: boolean;
+  png_colors?: number;
+  png_dithering?: number;
+  png_min_quality?: number;
+  webp_method?: number;
+  webp_alpha_quality?: number;
+  webp_sharp_yuv?: boolean;
+  avif_speed?: number;
+  avif_alpha_quality?: number;
+  jxl_effort?: number;
+  jxl_decoding_speed?: number;
+  qoi_linear?: boolean;
+  gif_colors?: number;
+  gif_dithering?: number;
 }
 
 export interface ProcessResult {
@@ -30,6 +72,7 @@ export interface ProcessResult {
   width: number;
   height: number;
   format: string;
+  skipped: boolean;
 }
 
 export interface PreviewResult {
@@ -52,6 +95,9 @@ export interface BatchProgress {
 export const api = {
   scanDirectory: (path: string) => invoke<string[]>("scan_directory", { path }),
   loadImage: (path: string) => invoke<ImageInfo>("load_image", { path }),
+  /** Base64 PNG thumbnail; decodes the image, so load it only when shown. */
+  loadThumbnail: (path: string) =>
+    invoke<string>("load_thumbnail", { path }),
   processImage: (input: string, options: ProcessOptions) =>
     invoke<ProcessResult>("process_image", { input, options }),
   previewImage: (input: string, options: ProcessOptions) =>